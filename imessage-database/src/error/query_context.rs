@@ -15,7 +15,7 @@ impl Display for QueryContextError {
         match self {
             QueryContextError::InvalidDate(date) => write!(
                 fmt,
-                "Invalid date provided: {date}! Must be in format YYYY-MM-DD."
+                "Invalid date provided: {date}! Must be in format YYYY-MM-DD or a phrase like `last week`, `january 2023`, or `6 months ago`."
             ),
         }
     }
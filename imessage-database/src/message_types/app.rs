@@ -405,4 +405,31 @@ mod tests {
 
         assert_eq!(balloon, expected);
     }
+
+    #[test]
+    fn test_parse_journal() {
+        let plist_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/app_message/Journal.plist");
+        let plist_data = File::open(plist_path).unwrap();
+        let plist = Value::from_reader(plist_data).unwrap();
+        let parsed = parse_plist(&plist).unwrap();
+
+        let balloon = AppMessage::from_map(&parsed).unwrap();
+        let expected = AppMessage {
+            image: None,
+            url: Some("?suggestionType=workout&suggestionID=FAKE_SUGGESTION_ID"),
+            title: Some("Morning Walk"),
+            subtitle: None,
+            caption: Some("Journal Suggestion"),
+            subcaption: Some("Workout"),
+            trailing_caption: None,
+            trailing_subcaption: None,
+            app_name: Some("Journal"),
+            ldtext: Some("Shared a Journal suggestion"),
+        };
+
+        assert_eq!(balloon, expected);
+    }
 }
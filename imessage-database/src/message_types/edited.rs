@@ -28,6 +28,15 @@ pub enum EditStatus {
     Original,
 }
 
+/// When an unsent message was retracted, relative to its delivery
+#[derive(Debug, PartialEq, Eq)]
+pub enum UnsentTiming {
+    /// The message was retracted before it was delivered, so the recipient never saw it
+    BeforeDelivery,
+    /// The message was unsent after it was delivered to the recipient
+    AfterDelivery,
+}
+
 /// Represents a single edit event for a message part
 #[derive(Debug, PartialEq, Eq)]
 pub struct EditedEvent {
@@ -201,6 +210,17 @@ impl EditedMessage {
     pub fn items(&self) -> usize {
         self.parts.len()
     }
+
+    /// Gets the total number of edits made across all message parts
+    ///
+    /// The first item in each part's edit history is the original content, so it is not counted as an edit.
+    pub fn edit_count(&self) -> usize {
+        self.parts
+            .iter()
+            .filter(|part| matches!(part.status, EditStatus::Edited))
+            .map(|part| part.edit_history.len().saturating_sub(1))
+            .sum()
+    }
 }
 
 #[cfg(test)]
@@ -237,6 +257,7 @@ mod tests {
 
         let expected_item = Some(expected.parts.first().unwrap());
         assert_eq!(parsed.part(0), expected_item);
+        assert_eq!(parsed.edit_count(), 3);
     }
 
     #[test]
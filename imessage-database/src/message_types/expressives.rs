@@ -2,6 +2,8 @@
  [Expressives](https://support.apple.com/en-us/HT206894) are effects that you can select by tapping and holding the send button.
 */
 
+use std::fmt::Display;
+
 /// Bubble effects are effects that alter the display of the chat bubble.
 ///
 /// Read more [here](https://www.imore.com/how-to-use-bubble-and-screen-effects-imessage-iphone-ipad).
@@ -60,3 +62,23 @@ pub enum Expressive<'a> {
     /// Message is not an expressive
     None,
 }
+
+/// Represents what the recipient did with an expressive after it was delivered
+///
+/// This is best-effort: it is only known when the database records when the message was played.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExpressiveState {
+    /// The effect was replayed
+    Replayed,
+    /// The message hidden by Invisible Ink was revealed
+    Revealed,
+}
+
+impl Display for ExpressiveState {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpressiveState::Replayed => write!(fmt, "replayed"),
+            ExpressiveState::Revealed => write!(fmt, "revealed"),
+        }
+    }
+}
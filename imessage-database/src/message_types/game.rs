@@ -0,0 +1,119 @@
+/*!
+ Games are [app messages](crate::message_types::app) that store the state of a turn-based game in their balloons.
+*/
+
+use crate::message_types::app::AppMessage;
+
+/// Bundle IDs of iMessage game apps, mapped to the name of the app
+pub const GAME_APPS: [(&str, &str); 1] = [("com.gamerdelights.gamepigeon.ext", "GamePigeon")];
+
+/// Get the name of the game app that uses a Bundle ID, if it is a known game
+pub fn game_app_name(bundle_id: &str) -> Option<&'static str> {
+    GAME_APPS
+        .iter()
+        .find(|(id, _)| *id == bundle_id)
+        .map(|(_, name)| *name)
+}
+
+/// Represents a turn in a game played in an iMessage app, i.e. GamePigeon
+#[derive(Debug, PartialEq, Eq)]
+pub struct GameMessage<'a> {
+    /// The name of the app the game is played in
+    pub app_name: &'a str,
+    /// The name of the game, i.e. `Dots & Boxes`
+    pub game: Option<&'a str>,
+    /// A description of the state of the game, i.e. `Your move.`
+    pub state: Option<&'a str>,
+    /// The score, if the app includes one
+    pub score: Option<&'a str>,
+}
+
+impl<'a> GameMessage<'a> {
+    /// Build a game message from the template layout of its balloon
+    ///
+    /// Games put the name of the game in the center of the balloon and describe the turn in its caption.
+    pub fn from_app_message(balloon: &AppMessage<'a>, bundle_id: &'a str) -> Self {
+        Self {
+            app_name: balloon
+                .app_name
+                .or(game_app_name(bundle_id))
+                .unwrap_or(bundle_id),
+            game: balloon.ldtext.or(balloon.title),
+            state: balloon.caption.or(balloon.subcaption),
+            score: balloon.trailing_caption,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        message_types::{
+            app::AppMessage,
+            game::{game_app_name, GameMessage},
+            variants::BalloonProvider,
+        },
+        util::plist::parse_plist,
+    };
+    use plist::Value;
+    use std::{env::current_dir, fs::File};
+
+    #[test]
+    fn test_parse_game_pigeon() {
+        let plist_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/app_message/Game.plist");
+        let plist_data = File::open(plist_path).unwrap();
+        let plist = Value::from_reader(plist_data).unwrap();
+        let parsed = parse_plist(&plist).unwrap();
+
+        let balloon = AppMessage::from_map(&parsed).unwrap();
+        let game = GameMessage::from_app_message(&balloon, "com.gamerdelights.gamepigeon.ext");
+        let expected = GameMessage {
+            app_name: "GamePigeon",
+            game: Some("Dots & Boxes"),
+            state: Some("Your move."),
+            score: None,
+        };
+
+        assert_eq!(game, expected);
+    }
+
+    #[test]
+    fn test_game_app_name_fallback() {
+        let balloon = AppMessage {
+            image: None,
+            url: None,
+            title: Some("8 Ball"),
+            subtitle: None,
+            caption: None,
+            subcaption: Some("Your turn"),
+            trailing_caption: Some("3 - 5"),
+            trailing_subcaption: None,
+            app_name: None,
+            ldtext: None,
+        };
+        let game = GameMessage::from_app_message(&balloon, "com.gamerdelights.gamepigeon.ext");
+        let expected = GameMessage {
+            app_name: "GamePigeon",
+            game: Some("8 Ball"),
+            state: Some("Your turn"),
+            score: Some("3 - 5"),
+        };
+
+        assert_eq!(game, expected);
+    }
+
+    #[test]
+    fn can_get_game_app_name() {
+        assert_eq!(
+            game_app_name("com.gamerdelights.gamepigeon.ext"),
+            Some("GamePigeon")
+        );
+        assert_eq!(
+            game_app_name("com.contextoptional.OpenTable.Messages"),
+            None
+        );
+    }
+}
@@ -7,6 +7,7 @@ pub mod app_store;
 pub mod collaboration;
 pub mod edited;
 pub mod expressives;
+pub mod game;
 pub mod handwriting;
 pub mod music;
 pub mod placemark;
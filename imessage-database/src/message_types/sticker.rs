@@ -4,6 +4,10 @@ These are [sticker messages](https://support.apple.com/guide/iphone/send-sticker
 
 use std::fmt::Display;
 
+use plist::Value;
+
+use crate::util::plist::get_string_from_dict;
+
 /// Bytes for `stickerEffect:type="`
 const STICKER_EFFECT_PREFIX: [u8; 20] = [
     115, 116, 105, 99, 107, 101, 114, 69, 102, 102, 101, 99, 116, 58, 116, 121, 112, 101, 61, 34,
@@ -57,6 +61,54 @@ impl Default for StickerEffect {
     }
 }
 
+/// Represents the app a sticker was sent from, i.e. a third-party sticker pack
+///
+/// This data is parsed from the attachment's `attribution_info` BLOB.
+#[derive(Debug, PartialEq, Eq)]
+pub struct StickerSource {
+    /// The display name of the app, i.e. `GIPHY`
+    pub app_name: Option<String>,
+    /// The bundle identifier of the app's Messages extension
+    pub bundle_id: Option<String>,
+}
+
+impl StickerSource {
+    /// Build a `StickerSource` from an attachment's `attribution_info` plist
+    ///
+    /// Returns `None` if the plist does not name the originating app.
+    pub fn from_attribution_info(plist: &Value) -> Option<Self> {
+        let app_name = get_string_from_dict(plist, "name").map(String::from);
+        let bundle_id = get_string_from_dict(plist, "bundle-id").map(String::from);
+
+        if app_name.is_none() && bundle_id.is_none() {
+            return None;
+        }
+        Some(Self {
+            app_name,
+            bundle_id,
+        })
+    }
+
+    /// Build a `StickerSource` from the bundle identifier of the app that sent a sticker's message
+    ///
+    /// Used when a downloaded sticker has no `attribution_info`.
+    pub fn from_bundle_id(bundle_id: &str) -> Self {
+        Self {
+            app_name: None,
+            bundle_id: Some(bundle_id.to_string()),
+        }
+    }
+}
+
+impl Display for StickerSource {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.app_name, &self.bundle_id) {
+            (Some(name), _) | (None, Some(name)) => write!(fmt, "{name}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
 /// Parse the sticker effect type from the EXIF data of a HEIC blob
 pub fn get_sticker_effect(mut heic_data: Vec<u8>) -> StickerEffect {
     // Find the start index and drain
@@ -95,7 +147,9 @@ mod tests {
     use std::fs::File;
     use std::io::Read;
 
-    use crate::message_types::sticker::{get_sticker_effect, StickerEffect};
+    use plist::{Dictionary, Value};
+
+    use crate::message_types::sticker::{get_sticker_effect, StickerEffect, StickerSource};
 
     #[test]
     fn test_parse_sticker_normal() {
@@ -171,4 +225,52 @@ mod tests {
 
         assert_eq!(effect, StickerEffect::Shiny);
     }
+
+    #[test]
+    fn test_parse_sticker_source() {
+        let plist_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/stickers/AttributionInfo.plist");
+        let plist_data = File::open(plist_path).unwrap();
+        let plist = Value::from_reader(plist_data).unwrap();
+
+        let source = StickerSource::from_attribution_info(&plist).unwrap();
+        let expected = StickerSource {
+            app_name: Some("GIPHY".to_string()),
+            bundle_id: Some("com.giphy.giphyformessenger.StickerPackExtension".to_string()),
+        };
+
+        assert_eq!(source, expected);
+        assert_eq!(source.to_string(), "GIPHY");
+    }
+
+    #[test]
+    fn test_parse_sticker_source_bundle_id_only() {
+        let mut dict = Dictionary::new();
+        dict.insert(
+            "bundle-id".to_string(),
+            Value::String("com.example.stickers".to_string()),
+        );
+
+        let source = StickerSource::from_attribution_info(&Value::Dictionary(dict)).unwrap();
+
+        assert_eq!(source.app_name, None);
+        assert_eq!(source.to_string(), "com.example.stickers");
+    }
+
+    #[test]
+    fn test_sticker_source_from_bundle_id() {
+        let source = StickerSource::from_bundle_id("com.example.stickers");
+
+        assert_eq!(source.app_name, None);
+        assert_eq!(source.to_string(), "com.example.stickers");
+    }
+
+    #[test]
+    fn test_parse_sticker_source_missing() {
+        let plist = Value::Dictionary(Dictionary::new());
+
+        assert!(StickerSource::from_attribution_info(&plist).is_none());
+    }
 }
@@ -50,6 +50,8 @@ pub enum Reaction {
     Emphasized,
     /// Question marks
     Questioned,
+    /// A sticker used as a tapback, where the sticker image is the reaction's attachment
+    Sticker,
 }
 
 /// Application Messages
@@ -64,6 +66,8 @@ pub enum CustomBalloon<'a> {
     URL,
     /// Handwritten animated messages
     Handwriting,
+    /// Digital Touch messages (sketches, taps, heartbeats, and similar)
+    DigitalTouch,
     /// Apple Pay (one of Sent, Requested, Received)
     ApplePay,
     /// Fitness.app messages
@@ -73,7 +77,11 @@ pub enum CustomBalloon<'a> {
     /// [Check In](https://support.apple.com/guide/iphone/use-check-in-iphc143bb7e9/ios) messages
     CheckIn,
     /// Find My messages
-    FindMy
+    FindMy,
+    /// Journal.app suggestion messages
+    Journal,
+    /// Turn-based [games](crate::message_types::game) played in a third party application
+    Game(&'a str),
 }
 
 /// URL Message Types
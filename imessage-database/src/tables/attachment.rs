@@ -2,9 +2,11 @@
  This module represents common (but not all) columns in the `attachment` table.
 */
 
+use plist::Value;
 use rusqlite::{Connection, Error, Result, Row, Statement};
 use sha1::{Digest, Sha1};
 use std::{
+    collections::HashMap,
     fs::File,
     io::Read,
     path::{Path, PathBuf},
@@ -12,14 +14,14 @@ use std::{
 
 use crate::{
     error::{attachment::AttachmentError, table::TableError},
-    message_types::sticker::{get_sticker_effect, StickerEffect},
+    message_types::sticker::{get_sticker_effect, StickerEffect, StickerSource},
     tables::{
         messages::Message,
-        table::{Table, ATTACHMENT},
+        table::{Table, ATTACHMENT, ATTACHMENT_ATTRIBUTION_INFO},
     },
     util::{
         dates::TIMESTAMP_FACTOR,
-        dirs::home,
+        dirs::{home, ios_backup_path},
         output::{done_processing, processing},
         platform::Platform,
         query_context::QueryContext,
@@ -36,6 +38,8 @@ pub const DEFAULT_ATTACHMENT_ROOT: &str = "~/Library/Messages/Attachments";
 #[derive(Debug, PartialEq, Eq)]
 pub enum MediaType<'a> {
     Image(&'a str),
+    /// An image format that may contain multiple frames, i.e. `gif` or `heics`
+    AnimatedImage(&'a str),
     Video(&'a str),
     Audio(&'a str),
     Text(&'a str),
@@ -44,6 +48,88 @@ pub enum MediaType<'a> {
     Unknown,
 }
 
+impl<'a> MediaType<'a> {
+    /// Classify an attachment given its MIME type string and [Uniform Type Identifier](https://developer.apple.com/library/archive/documentation/FileManagement/Conceptual/understanding_utis/understand_utis_intro/understand_utis_intro.html)
+    ///
+    /// The MIME type takes precedence; the UTI is used as a fallback if the MIME type is missing.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use imessage_database::tables::attachment::MediaType;
+    ///
+    /// assert_eq!(MediaType::classify(Some("image/gif"), None), MediaType::AnimatedImage("gif"));
+    /// assert_eq!(MediaType::classify(None, Some("public.heic")), MediaType::Image("heic"));
+    /// ```
+    pub fn classify(mime_type: Option<&'a str>, uti: Option<&'a str>) -> MediaType<'a> {
+        match mime_type {
+            Some(mime) => {
+                let mut mime_parts = mime.split('/');
+                if let (Some(category), Some(subtype)) = (mime_parts.next(), mime_parts.next()) {
+                    match category {
+                        "image" if Self::is_animated_subtype(subtype) => {
+                            MediaType::AnimatedImage(subtype)
+                        }
+                        "image" => MediaType::Image(subtype),
+                        "video" => MediaType::Video(subtype),
+                        "audio" => MediaType::Audio(subtype),
+                        "text" => MediaType::Text(subtype),
+                        "application" => MediaType::Application(subtype),
+                        _ => MediaType::Other(mime),
+                    }
+                } else {
+                    MediaType::Other(mime)
+                }
+            }
+            // Fallback to `uti` if the MIME type cannot be inferred
+            None => match uti {
+                // This type is for audio messages, which are sent in `caf` format
+                // https://developer.apple.com/library/archive/documentation/MusicAudio/Reference/CAFSpec/CAF_overview/CAF_overview.html
+                Some("com.apple.coreaudio-format") => MediaType::Audio("x-caf; codecs=opus"),
+                Some("com.compuserve.gif") => MediaType::AnimatedImage("gif"),
+                Some("public.heics") => MediaType::AnimatedImage("heics"),
+                Some("public.heic") => MediaType::Image("heic"),
+                _ => MediaType::Unknown,
+            },
+        }
+    }
+
+    /// `true` if the media may contain multiple frames, else `false`
+    pub fn is_animated(&self) -> bool {
+        matches!(self, MediaType::AnimatedImage(_))
+    }
+
+    /// Determine if an `image/*` subtype is a format that supports animation
+    fn is_animated_subtype(subtype: &str) -> bool {
+        matches!(
+            subtype,
+            "gif" | "GIF" | "heics" | "HEICS" | "heic-sequence" | "apng"
+        )
+    }
+}
+
+/// The `transfer_state` of an attachment whose transfer completed
+const TRANSFER_STATE_FINISHED: i32 = 5;
+/// The `transfer_state` of an attachment whose transfer failed
+const TRANSFER_STATE_FAILED: i32 = 6;
+
+/// Represents the direction and outcome of an attachment's transfer
+///
+/// This is driven by the `transfer_state` column of the `attachment` table, which tracks the
+/// progress of the upload or download: `5` when it finished and `6` when it failed. Any other value
+/// means the transfer never completed. The direction comes from the `is_outgoing` column.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TransferState {
+    /// The attachment was uploaded by the database owner
+    Sent,
+    /// The attachment was downloaded by the database owner
+    Received,
+    /// The attachment failed to upload or download
+    Failed,
+    /// The attachment has not finished uploading or downloading
+    Pending,
+}
+
 /// Represents a single row in the `attachment` table.
 #[derive(Debug)]
 pub struct Attachment {
@@ -61,8 +147,14 @@ pub struct Attachment {
     /// `true` if the attachment was a sticker, else `false`
     pub is_sticker: bool,
     pub hide_attachment: i32,
+    /// The raw `transfer_state` column, see [`TransferState`]
+    pub transfer_state: i32,
+    /// `true` if the attachment was sent by the database owner, else `false`
+    pub is_outgoing: bool,
     /// Auxiliary data to denote that an attachment has been copied
     pub copied_path: Option<PathBuf>,
+    /// A text description of the image, i.e. the prompt used to create a Genmoji
+    pub description: Option<String>,
 }
 
 impl Table for Attachment {
@@ -76,7 +168,10 @@ impl Table for Attachment {
             total_bytes: row.get("total_bytes").unwrap_or_default(),
             is_sticker: row.get("is_sticker").unwrap_or(false),
             hide_attachment: row.get("hide_attachment").unwrap_or(0),
+            transfer_state: row.get("transfer_state").unwrap_or(TRANSFER_STATE_FINISHED),
+            is_outgoing: row.get("is_outgoing").unwrap_or(false),
             copied_path: None,
+            description: row.get("emoji_image_short_description").unwrap_or(None),
         })
     }
 
@@ -121,38 +216,44 @@ impl Attachment {
         Ok(out_l)
     }
 
+    /// `true` if the attachment is a recorded audio message, else `false`
+    ///
+    /// Audio messages are sent in `caf` format and expire after they are played unless the recipient keeps them.
+    pub fn is_audio_message(&self) -> bool {
+        self.uti.as_deref() == Some("com.apple.coreaudio-format")
+    }
+
+    /// Get the transfer state of an attachment
+    ///
+    /// See [`TransferState`] for details.
+    pub fn transfer_state(&self) -> TransferState {
+        match self.transfer_state {
+            TRANSFER_STATE_FINISHED if self.is_outgoing => TransferState::Sent,
+            TRANSFER_STATE_FINISHED => TransferState::Received,
+            TRANSFER_STATE_FAILED => TransferState::Failed,
+            _ => TransferState::Pending,
+        }
+    }
+
     /// Get the media type of an attachment
+    ///
+    /// See [`MediaType::classify`] for details.
     pub fn mime_type(&'_ self) -> MediaType<'_> {
-        match &self.mime_type {
-            Some(mime) => {
-                let mut mime_parts = mime.split('/');
-                if let (Some(category), Some(subtype)) = (mime_parts.next(), mime_parts.next()) {
-                    match category {
-                        "image" => MediaType::Image(subtype),
-                        "video" => MediaType::Video(subtype),
-                        "audio" => MediaType::Audio(subtype),
-                        "text" => MediaType::Text(subtype),
-                        "application" => MediaType::Application(subtype),
-                        _ => MediaType::Other(mime),
-                    }
-                } else {
-                    MediaType::Other(mime)
-                }
-            }
-            None => {
-                // Fallback to `uti` if the MIME type cannot be inferred
-                if let Some(uti) = &self.uti {
-                    match uti.as_str() {
-                        // This type is for audio messages, which are sent in `caf` format
-                        // https://developer.apple.com/library/archive/documentation/MusicAudio/Reference/CAFSpec/CAF_overview/CAF_overview.html
-                        "com.apple.coreaudio-format" => MediaType::Audio("x-caf; codecs=opus"),
-                        _ => MediaType::Unknown,
-                    }
-                } else {
-                    MediaType::Unknown
-                }
-            }
-        }
+        MediaType::classify(self.mime_type.as_deref(), self.uti.as_deref())
+    }
+
+    /// Get the media type of an attachment, preferring the MIME type mapped to its file extension in `overrides`
+    ///
+    /// Keys in `overrides` are lowercase extensions without the leading `.`, i.e. `mov`. This corrects attachments
+    /// that [`MediaType::classify`] misclassifies because their MIME type or UTI is missing or wrong.
+    pub fn mime_type_with_overrides<'b>(
+        &'b self,
+        overrides: &'b HashMap<String, String>,
+    ) -> MediaType<'b> {
+        self.extension()
+            .and_then(|extension| overrides.get(&extension.to_lowercase()))
+            .map(|mime| MediaType::classify(Some(mime), None))
+            .unwrap_or_else(|| self.mime_type())
     }
 
     /// Read the attachment from the disk into a vector of bytes in memory
@@ -203,6 +304,28 @@ impl Attachment {
         Ok(Some(StickerEffect::default()))
     }
 
+    /// Determine the [`StickerSource`] app a sticker was sent from, if it came from a sticker pack app
+    ///
+    /// Calling this hits the database, so it is expensive and should
+    /// only get invoked when needed.
+    pub fn get_sticker_source(&self, db: &Connection) -> Option<StickerSource> {
+        // Handle the non-sticker case
+        if !self.is_sticker {
+            return None;
+        }
+
+        let blob = db
+            .blob_open(
+                rusqlite::DatabaseName::Main,
+                ATTACHMENT,
+                ATTACHMENT_ATTRIBUTION_INFO,
+                self.rowid as i64,
+                true,
+            )
+            .ok()?;
+        StickerSource::from_attribution_info(&Value::from_reader(blob).ok()?)
+    }
+
     /// Get the path to an attachment, if it exists
     pub fn path(&self) -> Option<&Path> {
         match &self.filename {
@@ -235,6 +358,14 @@ impl Attachment {
         "Attachment missing name metadata!"
     }
 
+    /// Get the text that describes the attachment to screen readers, falling back to its [`filename()`](Attachment::filename) if it has no description
+    pub fn alt_text(&self) -> &str {
+        self.description
+            .as_deref()
+            .filter(|description| !description.trim().is_empty())
+            .unwrap_or_else(|| self.filename())
+    }
+
     /// Get a human readable file size for an attachment
     pub fn file_size(&self) -> String {
         format_file_size(self.total_bytes)
@@ -431,18 +562,27 @@ impl Attachment {
         );
         let directory = filename.get(0..2)?;
 
-        Some(format!("{}/{directory}/{filename}", db_path.display()))
+        ios_backup_path(db_path, &format!("{directory}/{filename}"))
+            .to_str()
+            .map(String::from)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        tables::attachment::{Attachment, MediaType, DEFAULT_ATTACHMENT_ROOT},
+        tables::{
+            attachment::{Attachment, MediaType, TransferState, DEFAULT_ATTACHMENT_ROOT},
+            table::{Table, ATTACHMENT},
+        },
         util::platform::Platform,
     };
 
-    use std::path::{Path, PathBuf};
+    use rusqlite::Connection;
+    use std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    };
 
     fn sample_attachment() -> Attachment {
         Attachment {
@@ -454,7 +594,10 @@ mod tests {
             total_bytes: 100,
             is_sticker: false,
             hide_attachment: 0,
+            transfer_state: 5,
+            is_outgoing: false,
             copied_path: None,
+            description: None,
         }
     }
 
@@ -484,6 +627,22 @@ mod tests {
         assert_eq!(attachment.extension(), None);
     }
 
+    #[test]
+    fn can_get_audio_message() {
+        let mut attachment = sample_attachment();
+        attachment.uti = Some("com.apple.coreaudio-format".to_string());
+        attachment.mime_type = None;
+        assert!(attachment.is_audio_message());
+    }
+
+    #[test]
+    fn cant_get_audio_message_other_audio() {
+        let mut attachment = sample_attachment();
+        attachment.uti = Some("public.mp3".to_string());
+        attachment.mime_type = Some("audio/mpeg".to_string());
+        assert!(!attachment.is_audio_message());
+    }
+
     #[test]
     fn can_get_mime_type_png() {
         let attachment = sample_attachment();
@@ -504,6 +663,31 @@ mod tests {
         assert_eq!(attachment.mime_type(), MediaType::Other("fake/bloop"));
     }
 
+    #[test]
+    fn can_get_mime_type_override() {
+        let mut attachment = sample_attachment();
+        attachment.filename = Some("a/b/c.MOV".to_string());
+        attachment.mime_type = None;
+        attachment.uti = Some("com.apple.quicktime-movie".to_string());
+        assert_eq!(attachment.mime_type(), MediaType::Unknown);
+
+        let overrides = HashMap::from([("mov".to_string(), "video/quicktime".to_string())]);
+        assert_eq!(
+            attachment.mime_type_with_overrides(&overrides),
+            MediaType::Video("quicktime")
+        );
+    }
+
+    #[test]
+    fn can_get_mime_type_override_other_extension() {
+        let attachment = sample_attachment();
+        let overrides = HashMap::from([("mov".to_string(), "video/quicktime".to_string())]);
+        assert_eq!(
+            attachment.mime_type_with_overrides(&overrides),
+            MediaType::Image("png")
+        );
+    }
+
     #[test]
     fn can_get_mime_type_missing() {
         let mut attachment = sample_attachment();
@@ -511,6 +695,48 @@ mod tests {
         assert_eq!(attachment.mime_type(), MediaType::Unknown);
     }
 
+    #[test]
+    fn can_get_mime_type_gif() {
+        let mut attachment = sample_attachment();
+        attachment.mime_type = Some("image/gif".to_string());
+        assert_eq!(attachment.mime_type(), MediaType::AnimatedImage("gif"));
+        assert!(attachment.mime_type().is_animated());
+    }
+
+    #[test]
+    fn can_get_mime_type_gif_uti() {
+        let mut attachment = sample_attachment();
+        attachment.mime_type = None;
+        attachment.uti = Some("com.compuserve.gif".to_string());
+        assert_eq!(attachment.mime_type(), MediaType::AnimatedImage("gif"));
+        assert!(attachment.mime_type().is_animated());
+    }
+
+    #[test]
+    fn can_get_mime_type_heic_uti() {
+        let mut attachment = sample_attachment();
+        attachment.mime_type = None;
+        attachment.uti = Some("public.heic".to_string());
+        assert_eq!(attachment.mime_type(), MediaType::Image("heic"));
+        assert!(!attachment.mime_type().is_animated());
+    }
+
+    #[test]
+    fn can_get_mime_type_heics() {
+        let mut attachment = sample_attachment();
+        attachment.mime_type = Some("image/heics".to_string());
+        assert_eq!(attachment.mime_type(), MediaType::AnimatedImage("heics"));
+        assert!(attachment.mime_type().is_animated());
+    }
+
+    #[test]
+    fn can_get_mime_type_missing_unknown_uti() {
+        let mut attachment = sample_attachment();
+        attachment.mime_type = None;
+        attachment.uti = Some("fake.uti".to_string());
+        assert_eq!(attachment.mime_type(), MediaType::Unknown);
+    }
+
     #[test]
     fn can_get_filename() {
         let attachment = sample_attachment();
@@ -539,6 +765,22 @@ mod tests {
         assert_eq!(attachment.filename(), "Attachment missing name metadata!");
     }
 
+    #[test]
+    fn can_get_alt_text() {
+        let mut attachment = sample_attachment();
+        attachment.description = Some("Cat wearing a party hat".to_string());
+        assert_eq!(attachment.alt_text(), "Cat wearing a party hat");
+    }
+
+    #[test]
+    fn can_get_alt_text_no_description() {
+        let mut attachment = sample_attachment();
+        assert_eq!(attachment.alt_text(), "c.png");
+
+        attachment.description = Some(" ".to_string());
+        assert_eq!(attachment.alt_text(), "c.png");
+    }
+
     #[test]
     fn can_get_resolved_path_macos() {
         let db_path = PathBuf::from("fake_root");
@@ -597,7 +839,11 @@ mod tests {
 
         assert_eq!(
             attachment.resolved_attachment_path(&Platform::iOS, &db_path, None),
-            Some("fake_root/41/41746ffc65924078eae42725c979305626f57cca".to_string())
+            db_path
+                .join("41")
+                .join("41746ffc65924078eae42725c979305626f57cca")
+                .to_str()
+                .map(String::from)
         );
     }
 
@@ -610,7 +856,11 @@ mod tests {
         // is intact, the custom root is not relevant
         assert_eq!(
             attachment.resolved_attachment_path(&Platform::iOS, &db_path, Some("custom/root")),
-            Some("fake_root/41/41746ffc65924078eae42725c979305626f57cca".to_string())
+            db_path
+                .join("41")
+                .join("41746ffc65924078eae42725c979305626f57cca")
+                .to_str()
+                .map(String::from)
         );
     }
 
@@ -676,4 +926,47 @@ mod tests {
 
         assert_eq!(attachment.file_size(), String::from("16777216.00 TB"));
     }
+
+    #[test]
+    fn can_get_transfer_state_received() {
+        let attachment = sample_attachment();
+        assert_eq!(attachment.transfer_state(), TransferState::Received);
+    }
+
+    #[test]
+    fn can_get_transfer_state_sent() {
+        let mut attachment = sample_attachment();
+        attachment.is_outgoing = true;
+        assert_eq!(attachment.transfer_state(), TransferState::Sent);
+    }
+
+    #[test]
+    fn can_get_transfer_state_pending() {
+        let mut attachment = sample_attachment();
+        attachment.transfer_state = 0;
+        assert_eq!(attachment.transfer_state(), TransferState::Pending);
+    }
+
+    #[test]
+    fn can_get_transfer_state_failed_row() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {ATTACHMENT} (ROWID INTEGER PRIMARY KEY, filename TEXT, transfer_state INTEGER, is_outgoing INTEGER);
+             INSERT INTO {ATTACHMENT} VALUES (1, 'a/b/c.png', 6, 1);"
+        ))
+        .unwrap();
+
+        let mut statement = Attachment::get(&db).unwrap();
+        let attachment = statement
+            .query_map([], |row| Ok(Attachment::from_row(row)))
+            .unwrap()
+            .map(Attachment::extract)
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(attachment.transfer_state, 6);
+        assert!(attachment.is_outgoing);
+        assert_eq!(attachment.transfer_state(), TransferState::Failed);
+    }
 }
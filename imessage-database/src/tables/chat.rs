@@ -2,15 +2,58 @@
  This module represents common (but not all) columns in the `chat` table.
 */
 
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display};
 
+use chrono::{DateTime, Local};
 use rusqlite::{Connection, Error, Result, Row, Statement};
 
 use crate::{
-    error::table::TableError,
-    tables::table::{Cacheable, Table, CHAT},
+    error::{message::MessageError, table::TableError},
+    tables::{
+        schema::Schema,
+        table::{Cacheable, Table, CHAT, CHAT_MESSAGE_JOIN, MESSAGE},
+    },
+    util::dates::get_local_time,
 };
 
+/// Represents which services the messages in a conversation were sent with
+///
+/// Group chats with SMS participants fall back to SMS for everyone, so a chat can contain both.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ServiceMix {
+    /// Every message was sent as an iMessage
+    #[allow(non_camel_case_types)]
+    iMessage,
+    /// Every message was sent as an SMS
+    SMS,
+    /// Messages were sent as both iMessages and SMS
+    Mixed,
+}
+
+impl ServiceMix {
+    /// Classify a conversation given whether it contains any iMessages and any SMS
+    ///
+    /// Returns `None` if it contains neither, i.e. if it has no messages.
+    pub fn from_services(has_imessage: bool, has_sms: bool) -> Option<Self> {
+        match (has_imessage, has_sms) {
+            (true, true) => Some(ServiceMix::Mixed),
+            (true, false) => Some(ServiceMix::iMessage),
+            (false, true) => Some(ServiceMix::SMS),
+            (false, false) => None,
+        }
+    }
+}
+
+impl Display for ServiceMix {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceMix::iMessage => write!(fmt, "iMessage"),
+            ServiceMix::SMS => write!(fmt, "SMS"),
+            ServiceMix::Mixed => write!(fmt, "iMessage and SMS"),
+        }
+    }
+}
+
 /// Represents a single row in the `chat` table.
 #[derive(Debug)]
 pub struct Chat {
@@ -20,6 +63,14 @@ pub struct Chat {
     pub service_name: Option<String>,
     /// Optional custom name created created for the chat
     pub display_name: Option<String>,
+    /// The date of the earliest message in the chat, used as the chat's creation date
+    pub first_message_date: Option<i64>,
+    /// The date of the most recent message in the chat
+    pub last_message_date: Option<i64>,
+    /// `true` if any message in the chat was sent as an iMessage, else `false`
+    pub has_imessage: bool,
+    /// `true` if any message in the chat was sent as an SMS, else `false`
+    pub has_sms: bool,
 }
 
 impl Table for Chat {
@@ -29,12 +80,26 @@ impl Table for Chat {
             chat_identifier: row.get("chat_identifier")?,
             service_name: row.get("service_name")?,
             display_name: row.get("display_name").unwrap_or(None),
+            first_message_date: row.get("first_message_date").unwrap_or(None),
+            last_message_date: row.get("last_message_date").unwrap_or(None),
+            has_imessage: row.get("has_imessage").unwrap_or(false),
+            has_sms: row.get("has_sms").unwrap_or(false),
         })
     }
 
     fn get(db: &Connection) -> Result<Statement, TableError> {
-        db.prepare(&format!("SELECT * from {CHAT}"))
-            .map_err(TableError::Chat)
+        // Activity dates and services come from the messages in each chat, which requires `chat_message_join`
+        db.prepare(&format!(
+            "SELECT
+                 c.*,
+                 (SELECT MIN(m.date) FROM {CHAT_MESSAGE_JOIN} j JOIN {MESSAGE} m ON m.ROWID = j.message_id WHERE j.chat_id = c.ROWID) AS first_message_date,
+                 (SELECT MAX(m.date) FROM {CHAT_MESSAGE_JOIN} j JOIN {MESSAGE} m ON m.ROWID = j.message_id WHERE j.chat_id = c.ROWID) AS last_message_date,
+                 EXISTS (SELECT 1 FROM {CHAT_MESSAGE_JOIN} j JOIN {MESSAGE} m ON m.ROWID = j.message_id WHERE j.chat_id = c.ROWID AND m.service = 'iMessage') AS has_imessage,
+                 EXISTS (SELECT 1 FROM {CHAT_MESSAGE_JOIN} j JOIN {MESSAGE} m ON m.ROWID = j.message_id WHERE j.chat_id = c.ROWID AND m.service = 'SMS') AS has_sms
+             FROM {CHAT} c"
+        ))
+        .or_else(|_| db.prepare(&format!("SELECT * FROM {CHAT}")))
+        .map_err(TableError::Chat)
     }
 
     fn extract(chat: Result<Result<Self, Error>, Error>) -> Result<Self, TableError> {
@@ -102,4 +167,199 @@ impl Chat {
             None => None,
         }
     }
+
+    /// Get the date the chat was created, approximated by the date of its first message
+    ///
+    /// Returns `None` if the chat has no messages.
+    pub fn created_date(&self, offset: &i64) -> Option<Result<DateTime<Local>, MessageError>> {
+        self.first_message_date
+            .map(|date| get_local_time(&date, offset))
+    }
+
+    /// Get the date of the most recent message in the chat
+    ///
+    /// Returns `None` if the chat has no messages.
+    pub fn last_activity_date(
+        &self,
+        offset: &i64,
+    ) -> Option<Result<DateTime<Local>, MessageError>> {
+        self.last_message_date
+            .map(|date| get_local_time(&date, offset))
+    }
+
+    /// Get which services the messages in the chat were sent with
+    ///
+    /// Returns `None` if the chat has no iMessages or SMS.
+    pub fn service_mix(&self) -> Option<ServiceMix> {
+        ServiceMix::from_services(self.has_imessage, self.has_sms)
+    }
+
+    /// Generate a hashmap containing each chatroom's ID pointing to the number of messages in it.
+    ///
+    /// Chats without any messages are not included.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use imessage_database::util::dirs::default_db_path;
+    /// use imessage_database::tables::table::get_connection;
+    /// use imessage_database::tables::chat::Chat;
+    ///
+    /// let db_path = default_db_path();
+    /// let conn = get_connection(&db_path).unwrap();
+    /// let message_counts = Chat::message_counts(&conn);
+    /// ```
+    pub fn message_counts(db: &Connection) -> Result<HashMap<i32, u64>, TableError> {
+        Self::message_counts_with_schema(db, &Schema::detect(db)?)
+    }
+
+    /// Count the messages in each chat as described in [`Chat::message_counts`] for a database whose [`Schema`] was already detected
+    pub fn message_counts_with_schema(
+        db: &Connection,
+        schema: &Schema,
+    ) -> Result<HashMap<i32, u64>, TableError> {
+        // Messages cannot be counted per chat if they are not linked to chats
+        if !schema.supports_chat_join() {
+            return Ok(HashMap::new());
+        }
+
+        let mut statement = db
+            .prepare(&format!(
+                "SELECT chat_id, COUNT(*) FROM {CHAT_MESSAGE_JOIN} GROUP BY chat_id"
+            ))
+            .map_err(TableError::Chat)?;
+
+        let counts = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(TableError::Chat)?;
+
+        let mut map = HashMap::new();
+        for count in counts {
+            let (chat_id, count): (i32, u64) = count.map_err(TableError::Chat)?;
+            map.insert(chat_id, count);
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use crate::tables::{
+        chat::{Chat, ServiceMix},
+        table::{Cacheable, CHAT, CHAT_MESSAGE_JOIN, MESSAGE},
+    };
+
+    fn fake_db() -> Connection {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {CHAT} (ROWID INTEGER PRIMARY KEY, chat_identifier TEXT, service_name TEXT, display_name TEXT);
+             CREATE TABLE {MESSAGE} (ROWID INTEGER PRIMARY KEY, date INTEGER, service TEXT);
+             CREATE TABLE {CHAT_MESSAGE_JOIN} (chat_id INTEGER, message_id INTEGER);
+             INSERT INTO {CHAT} VALUES (1, 'chat1', 'iMessage', NULL), (2, 'chat2', 'SMS', 'Empty'), (3, 'chat3', 'iMessage', NULL);
+             INSERT INTO {MESSAGE} VALUES (1, 674526582885055488, 'iMessage'), (2, 674530231992568192, 'SMS'), (3, 674526000000000000, 'iMessage'), (4, 674526000000000000, 'iMessage');
+             INSERT INTO {CHAT_MESSAGE_JOIN} VALUES (1, 1), (1, 2), (1, 3), (3, 4);"
+        ))
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn can_get_activity_dates() {
+        let db = fake_db();
+        let chats = Chat::cache(&db).unwrap();
+
+        let chat = chats.get(&1).unwrap();
+        assert_eq!(chat.first_message_date, Some(674526000000000000));
+        assert_eq!(chat.last_message_date, Some(674530231992568192));
+    }
+
+    #[test]
+    fn can_get_activity_dates_empty_chat() {
+        let db = fake_db();
+        let chats = Chat::cache(&db).unwrap();
+
+        let chat = chats.get(&2).unwrap();
+        assert_eq!(chat.first_message_date, None);
+        assert_eq!(chat.last_message_date, None);
+        assert!(chat.created_date(&0).is_none());
+        assert!(chat.last_activity_date(&0).is_none());
+    }
+
+    #[test]
+    fn can_get_created_date() {
+        let db = fake_db();
+        let chats = Chat::cache(&db).unwrap();
+
+        let chat = chats.get(&1).unwrap();
+        assert!(chat.created_date(&0).unwrap().is_ok());
+        assert!(
+            chat.last_activity_date(&0).unwrap().unwrap() > chat.created_date(&0).unwrap().unwrap()
+        );
+    }
+
+    #[test]
+    fn can_get_message_counts() {
+        let db = fake_db();
+        let counts = Chat::message_counts(&db).unwrap();
+
+        assert_eq!(counts.get(&1), Some(&3));
+        assert_eq!(counts.get(&2), None);
+    }
+
+    #[test]
+    fn can_get_service_mix_mixed() {
+        let db = fake_db();
+        let chats = Chat::cache(&db).unwrap();
+
+        let chat = chats.get(&1).unwrap();
+        assert!(chat.has_imessage);
+        assert!(chat.has_sms);
+        assert_eq!(chat.service_mix(), Some(ServiceMix::Mixed));
+    }
+
+    #[test]
+    fn can_get_service_mix_imessage() {
+        let db = fake_db();
+        let chats = Chat::cache(&db).unwrap();
+
+        let chat = chats.get(&3).unwrap();
+        assert_eq!(chat.service_mix(), Some(ServiceMix::iMessage));
+    }
+
+    #[test]
+    fn cant_get_service_mix_empty_chat() {
+        let db = fake_db();
+        let chats = Chat::cache(&db).unwrap();
+
+        let chat = chats.get(&2).unwrap();
+        assert_eq!(chat.service_mix(), None);
+    }
+
+    #[test]
+    fn can_display_service_mix() {
+        assert_eq!(
+            ServiceMix::from_services(false, true),
+            Some(ServiceMix::SMS)
+        );
+        assert_eq!(ServiceMix::Mixed.to_string(), "iMessage and SMS");
+    }
+
+    #[test]
+    fn can_cache_without_chat_message_join() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {CHAT} (ROWID INTEGER PRIMARY KEY, chat_identifier TEXT, service_name TEXT, display_name TEXT);
+             CREATE TABLE {MESSAGE} (ROWID INTEGER PRIMARY KEY, date INTEGER, service TEXT);
+             INSERT INTO {CHAT} VALUES (1, 'chat1', 'iMessage', NULL);"
+        ))
+        .unwrap();
+
+        let chats = Chat::cache(&db).unwrap();
+        let chat = chats.get(&1).unwrap();
+        assert_eq!(chat.chat_identifier, "chat1");
+        assert_eq!(chat.first_message_date, None);
+        assert!(Chat::message_counts(&db).unwrap().is_empty());
+    }
 }
@@ -4,7 +4,7 @@ use crate::{
         text_effects::{TextEffect, Unit},
     },
     tables::messages::{
-        models::{BubbleComponent, TextAttributes},
+        models::{BubbleComponent, TextAttributes, WritingDirection},
         Message,
     },
     util::typedstream::models::{Archivable, OutputData},
@@ -16,6 +16,8 @@ const ATTACHMENT_CHAR: char = '\u{FFFC}';
 const APP_CHAR: char = '\u{FFFD}';
 /// A collection of characters that represent non-text content within body text
 const REPLACEMENT_CHARS: [char; 2] = [ATTACHMENT_CHAR, APP_CHAR];
+/// Attribute key for the base writing direction of a range of text
+const WRITING_DIRECTION_KEY: &str = "__kIMBaseWritingDirectionAttributeName";
 
 pub enum BubbleResult<'a> {
     New(BubbleComponent<'a>),
@@ -88,6 +90,12 @@ pub(crate) fn parse_body_typedstream(message: &Message) -> Option<Vec<BubbleComp
         }
     }
 
+    // If no ranges were parsed, i.e. the typedstream data was missing or malformed but the text was recovered,
+    // render the text with the default effect so it is not dropped when retracted parts are added below
+    if out_v.is_empty() {
+        out_v = parse_body_legacy(message);
+    }
+
     // Add retracted components into the body
     if let Some(edited_message) = &message.edited_parts {
         for (idx, edited_message_part) in edited_message.parts.iter().enumerate() {
@@ -149,6 +157,19 @@ fn get_n_dict_objects(components: &[Archivable], idx: usize, num_objects: usize)
     components.get(idx..final_idx + 1).unwrap_or(&[])
 }
 
+/// Get the target of the link attribute whose key is at `idx`
+///
+/// The target is stored separately from the text it is applied to, so it can differ from the displayed text.
+/// It is usually an `NSURL` followed by the URL string, but may be a bare `NSString`.
+fn get_link_target(components: &[Archivable], idx: usize) -> Option<&str> {
+    match components.get(idx + 1)? {
+        Archivable::Object(class, _) if class.name == "NSURL" => {
+            components.get(idx + 2)?.deserialize_as_nsstring()
+        }
+        value => value.deserialize_as_nsstring(),
+    }
+}
+
 /// Determine the type of bubble the current range represents
 ///
 /// App messages are handled in [`Message::body()`]; they are detected by the presence of data in the `balloon_bundle_id` column.
@@ -184,12 +205,7 @@ fn get_bubble_type<'a>(
                     return Some(BubbleResult::Continuation(TextAttributes::new(
                         range_start,
                         range_end,
-                        TextEffect::Link(
-                            components
-                                .get(idx + 2)?
-                                .deserialize_as_nsstring()
-                                .unwrap_or("#"),
-                        ),
+                        TextEffect::Link(get_link_target(components, idx).unwrap_or("#")),
                     )));
                 }
                 "__kIMOneTimeCodeAttributeName" => {
@@ -217,6 +233,35 @@ fn get_bubble_type<'a>(
     )))
 }
 
+/// Determine the base writing direction of the message text from deserialized typedstream data
+///
+/// Each range of text may carry its own direction; the first explicit direction is used for the whole message.
+pub(crate) fn parse_writing_direction(message: &Message) -> WritingDirection {
+    message
+        .components
+        .as_ref()
+        .and_then(|components| {
+            components
+                .windows(2)
+                .filter(|pair| pair[0].deserialize_as_nsstring() == Some(WRITING_DIRECTION_KEY))
+                .find_map(|pair| match &pair[1] {
+                    Archivable::Object(class, data) if class.name == "NSNumber" => {
+                        match data.first() {
+                            Some(OutputData::SignedInteger(0)) => {
+                                Some(WritingDirection::LeftToRight)
+                            }
+                            Some(OutputData::SignedInteger(1)) => {
+                                Some(WritingDirection::RightToLeft)
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                })
+        })
+        .unwrap_or(WritingDirection::Natural)
+}
+
 /// Fallback logic to parse the body from the message string content
 pub(crate) fn parse_body_legacy(message: &Message) -> Vec<BubbleComponent> {
     let mut out_v = vec![];
@@ -272,11 +317,14 @@ mod typedstream_tests {
             text_effects::{TextEffect, Unit},
         },
         tables::messages::{
-            body::parse_body_typedstream,
-            models::{BubbleComponent, TextAttributes},
+            body::{get_link_target, parse_body_typedstream, parse_writing_direction},
+            models::{BubbleComponent, TextAttributes, WritingDirection},
             Message,
         },
-        util::typedstream::parser::TypedStreamReader,
+        util::typedstream::{
+            models::{Archivable, Class, OutputData},
+            parser::TypedStreamReader,
+        },
     };
 
     pub(super) fn blank() -> Message {
@@ -291,8 +339,12 @@ mod typedstream_tests {
             date: i64::default(),
             date_read: i64::default(),
             date_delivered: i64::default(),
+            date_played: 0,
             is_from_me: false,
             is_read: false,
+            is_forward: false,
+            is_spam: false,
+            was_delivered_quietly: false,
             item_type: 0,
             other_handle: 0,
             share_status: false,
@@ -306,6 +358,7 @@ mod typedstream_tests {
             thread_originator_guid: None,
             thread_originator_part: None,
             date_edited: 0,
+            schedule_type: 0,
             chat_id: None,
             num_attachments: 0,
             deleted_from: None,
@@ -341,6 +394,65 @@ mod typedstream_tests {
         );
     }
 
+    #[test]
+    fn can_get_writing_direction_natural() {
+        let mut m = blank();
+        m.text = Some("Test 3".to_string());
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/AttributedBodyTextOnly2");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        m.components = parser.parse().ok();
+
+        assert_eq!(parse_writing_direction(&m), WritingDirection::Natural);
+    }
+
+    #[test]
+    fn can_get_writing_direction_rtl() {
+        let mut m = blank();
+        m.text = Some("Test 3".to_string());
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/AttributedBodyTextOnly2");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        let mut components = parser.parse().unwrap();
+
+        // Change the direction from natural (-1) to right-to-left (1)
+        if let Some(Archivable::Object(_, data)) = components.get_mut(4) {
+            data[0] = OutputData::SignedInteger(1);
+        }
+        m.components = Some(components);
+
+        assert_eq!(parse_writing_direction(&m), WritingDirection::RightToLeft);
+        assert_eq!(
+            parse_body_typedstream(&m).unwrap(),
+            vec![BubbleComponent::Text(vec![TextAttributes::new(
+                0,
+                6,
+                TextEffect::Default
+            )])]
+        );
+    }
+
+    #[test]
+    fn can_get_writing_direction_missing() {
+        let m = blank();
+
+        assert_eq!(parse_writing_direction(&m), WritingDirection::Natural);
+    }
+
     #[test]
     fn can_get_message_body_app() {
         let mut m = blank();
@@ -586,6 +698,65 @@ mod typedstream_tests {
         );
     }
 
+    #[test]
+    fn can_get_message_body_url_different_target() {
+        let mut m = blank();
+        m.text = Some("https://twitter.com/xxxxxxxxx/status/0000223300009216128".to_string());
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/URLMessage");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        let mut components = parser.parse().unwrap();
+
+        // Point the link attribute somewhere other than the displayed text
+        let url_idx = components
+            .iter()
+            .position(|item| matches!(item, Archivable::Object(class, _) if class.name == "NSURL"))
+            .unwrap();
+        if let Some(Archivable::Object(_, data)) = components.get_mut(url_idx + 1) {
+            data[0] = OutputData::String("https://example.com/target".to_string());
+        }
+        m.components = Some(components);
+
+        assert_eq!(
+            parse_body_typedstream(&m).unwrap(),
+            vec![BubbleComponent::Text(vec![TextAttributes::new(
+                0,
+                56,
+                TextEffect::Link("https://example.com/target")
+            )]),]
+        );
+    }
+
+    #[test]
+    fn can_get_link_target_string() {
+        let nsstring = |text: &str| {
+            Archivable::Object(
+                Class {
+                    name: "NSString".to_string(),
+                    version: 1,
+                },
+                vec![OutputData::String(text.to_string())],
+            )
+        };
+        let components = vec![
+            nsstring("__kIMLinkAttributeName"),
+            nsstring("https://example.com/target"),
+        ];
+
+        assert_eq!(
+            get_link_target(&components, 0),
+            Some("https://example.com/target")
+        );
+        assert_eq!(get_link_target(&components, 1), None);
+    }
+
     #[test]
     fn can_get_message_body_mention() {
         let mut m = blank();
@@ -763,6 +934,32 @@ mod typedstream_tests {
             vec![BubbleComponent::Retracted,]
         );
     }
+
+    #[test]
+    fn can_get_message_body_no_components() {
+        let mut m = blank();
+        m.text = Some("Hello world".to_string());
+        m.edited_parts = Some(EditedMessage {
+            parts: vec![
+                EditedMessagePart {
+                    status: EditStatus::Original,
+                    edit_history: vec![],
+                },
+                EditedMessagePart {
+                    status: EditStatus::Unsent,
+                    edit_history: vec![],
+                },
+            ],
+        });
+
+        assert_eq!(
+            parse_body_typedstream(&m).unwrap(),
+            vec![
+                BubbleComponent::Text(vec![TextAttributes::new(0, 11, TextEffect::Default)]),
+                BubbleComponent::Retracted,
+            ]
+        );
+    }
 }
 
 #[cfg(test)]
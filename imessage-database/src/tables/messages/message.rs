@@ -6,27 +6,31 @@ use std::{collections::HashMap, io::Read};
 
 use chrono::{offset::Local, DateTime};
 use plist::Value;
-use rusqlite::{blob::Blob, Connection, Error, Result, Row, Statement};
+use rusqlite::{blob::Blob, Connection, Error, OptionalExtension, Result, Row, Statement};
 
 use crate::{
     error::{message::MessageError, table::TableError},
     message_types::{
-        edited::{EditStatus, EditedMessage},
-        expressives::{BubbleEffect, Expressive, ScreenEffect},
+        edited::{EditStatus, EditedMessage, UnsentTiming},
+        expressives::{BubbleEffect, Expressive, ExpressiveState, ScreenEffect},
+        game::game_app_name,
+        text_effects::TextEffect,
         variants::{Announcement, BalloonProvider, CustomBalloon, Reaction, Variant},
     },
     tables::{
         messages::{
-            body::{parse_body_legacy, parse_body_typedstream},
-            models::{BubbleComponent, Service},
+            body::{parse_body_legacy, parse_body_typedstream, parse_writing_direction},
+            models::{BubbleComponent, ReadReceiptState, Service, WritingDirection},
         },
+        schema::Schema,
         table::{
-            Cacheable, Diagnostic, Table, ATTRIBUTED_BODY, CHAT_MESSAGE_JOIN, MESSAGE,
-            MESSAGE_ATTACHMENT_JOIN, MESSAGE_PAYLOAD, MESSAGE_SUMMARY_INFO, RECENTLY_DELETED,
+            Cacheable, Diagnostic, Table, ATTRIBUTED_BODY, CHAT_MESSAGE_JOIN, HANDLE, MESSAGE,
+            MESSAGE_ATTACHMENT_JOIN, MESSAGE_PAYLOAD, MESSAGE_SUMMARY_INFO,
         },
     },
     util::{
         dates::{get_local_time, readable_diff},
+        emoji::count_emoji,
         output::{done_processing, processing},
         query_context::QueryContext,
         streamtyped,
@@ -34,8 +38,11 @@ use crate::{
     },
 };
 
+/// The most emoji a message can contain and still be displayed enlarged
+const BIG_EMOJI_LIMIT: usize = 3;
+
 /// The required columns, interpolated into the most recent schema due to performance considerations
-const COLS: &str = "rowid, guid, text, service, handle_id, destination_caller_id, subject, date, date_read, date_delivered, is_from_me, is_read, item_type, other_handle, share_status, share_direction, group_title, group_action_type, associated_message_guid, associated_message_type, balloon_bundle_id, expressive_send_style_id, thread_originator_guid, thread_originator_part, date_edited, chat_id";
+const COLS: &str = "rowid, guid, text, service, handle_id, destination_caller_id, subject, date, date_read, date_delivered, date_played, is_from_me, is_read, is_forward, item_type, other_handle, share_status, share_direction, group_title, group_action_type, associated_message_guid, associated_message_type, balloon_bundle_id, expressive_send_style_id, thread_originator_guid, thread_originator_part, date_edited";
 
 /// Represents a single row in the `message` table.
 #[derive(Debug)]
@@ -58,10 +65,22 @@ pub struct Message {
     pub date_read: i64,
     /// The date a message was delivered
     pub date_delivered: i64,
+    /// The date a message's expressive was replayed or its Invisible Ink was revealed, if recorded
+    pub date_played: i64,
     /// `true` if the database owner sent the message, else `false`
     pub is_from_me: bool,
     /// `true` if the message was read by the recipient, else `false`
     pub is_read: bool,
+    /// `true` if the message was forwarded from another conversation, else `false`
+    pub is_forward: bool,
+    /// `true` if the message was reported as junk, else `false`
+    ///
+    /// Read from the `is_spam` column, which is set when the database owner taps "Report Junk" on a message from an unknown sender.
+    pub is_spam: bool,
+    /// `true` if the message was delivered while the recipient silenced notifications, else `false`
+    ///
+    /// Read from the `was_delivered_quietly` column when the schema has it; older databases always report `false`.
+    pub was_delivered_quietly: bool,
     /// Intermediate data for determining the [`variant`](crate::message_types::variants) of a message
     pub item_type: i32,
     /// Optional handle for the recipient of a message that includes shared content
@@ -88,6 +107,8 @@ pub struct Message {
     pub thread_originator_part: Option<String>,
     /// The date the message was most recently edited
     pub date_edited: i64,
+    /// Intermediate data for determining if a message was scheduled with Send Later, see [`is_scheduled()`](Self::is_scheduled)
+    pub schedule_type: i32,
     /// The [`identifier`](crate::tables::chat::Chat::chat_identifier) of the chat the message belongs to
     pub chat_id: Option<i32>,
     /// The number of attached files included in the message
@@ -115,8 +136,12 @@ impl Table for Message {
             date: row.get("date")?,
             date_read: row.get("date_read").unwrap_or(0),
             date_delivered: row.get("date_delivered").unwrap_or(0),
+            date_played: row.get("date_played").unwrap_or(0),
             is_from_me: row.get("is_from_me")?,
             is_read: row.get("is_read")?,
+            is_forward: row.get("is_forward").unwrap_or(false),
+            is_spam: row.get("is_spam").unwrap_or(false),
+            was_delivered_quietly: row.get("was_delivered_quietly").unwrap_or(false),
             item_type: row.get("item_type").unwrap_or_default(),
             other_handle: row.get("other_handle").unwrap_or_default(),
             share_status: row.get("share_status").unwrap_or(false),
@@ -130,6 +155,7 @@ impl Table for Message {
             thread_originator_guid: row.get("thread_originator_guid").unwrap_or(None),
             thread_originator_part: row.get("thread_originator_part").unwrap_or(None),
             date_edited: row.get("date_edited").unwrap_or(0),
+            schedule_type: row.get("schedule_type").unwrap_or(0),
             chat_id: row.get("chat_id").unwrap_or(None),
             num_attachments: row.get("num_attachments")?,
             deleted_from: row.get("deleted_from").unwrap_or(None),
@@ -142,53 +168,7 @@ impl Table for Message {
     /// Convert data from the messages table to native Rust data structures, falling back to
     /// more compatible queries to ensure compatibility with older database schemas
     fn get(db: &Connection) -> Result<Statement, TableError> {
-        // If the database has `chat_recoverable_message_join`, we can restore some deleted messages.
-        // If database has `thread_originator_guid`, we can parse replies, otherwise default to 0
-        Ok(db.prepare(&format!(
-            // macOS Ventura+ and i0S 16+ schema, interpolated with required columns for performance
-            "SELECT
-                 {COLS},
-                 c.chat_id,
-                 (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
-                 (SELECT b.chat_id FROM {RECENTLY_DELETED} b WHERE m.ROWID = b.message_id) as deleted_from,
-                 (SELECT COUNT(*) FROM {MESSAGE} m2 WHERE m2.thread_originator_guid = m.guid) as num_replies
-             FROM
-                 message as m
-                 LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
-             ORDER BY
-                 m.date;
-            "
-        )).or(db.prepare(&format!(
-            // macOS Big Sur to Monterey, iOS 14 to iOS 15 schema
-            "SELECT
-                 *,
-                 c.chat_id,
-                 (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
-                 NULL as deleted_from,
-                 (SELECT COUNT(*) FROM {MESSAGE} m2 WHERE m2.thread_originator_guid = m.guid) as num_replies
-             FROM
-                 message as m
-                 LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
-             ORDER BY
-                 m.date;
-            "
-        )))
-        .unwrap_or(db.prepare(&format!(
-            // macOS Catalina, iOS 13 and older 
-            "SELECT
-                 *,
-                 c.chat_id,
-                 (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
-                 NULL as deleted_from,
-                 0 as num_replies
-             FROM
-                 message as m
-                 LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
-             ORDER BY
-                 m.date;
-            "
-        )).map_err(TableError::Messages)?)
-    )
+        Self::select_messages(db, &Schema::detect(db)?, "")
     }
 
     fn extract(message: Result<Result<Self, Error>, Error>) -> Result<Self, TableError> {
@@ -303,19 +283,32 @@ impl Cacheable for Message {
     ///
     /// Where the `0` and `1` are the reaction indexes in the body of the message mapped by `message_guid`
     fn cache(db: &Connection) -> Result<HashMap<Self::K, Self::V>, TableError> {
+        Self::cache_with_schema(db, &Schema::detect(db)?)
+    }
+}
+
+impl Message {
+    /// Build the map of reactions described in [`Message::cache`] for a database whose [`Schema`] was already detected
+    pub fn cache_with_schema(
+        db: &Connection,
+        schema: &Schema,
+    ) -> Result<HashMap<String, HashMap<usize, Vec<Self>>>, TableError> {
         // Create cache for user IDs
-        let mut map: HashMap<Self::K, Self::V> = HashMap::new();
+        let mut map: HashMap<String, HashMap<usize, Vec<Self>>> = HashMap::new();
 
         // Create query, independent of table schema
+        let num_replies = schema.num_replies_column();
+        let chat_id = schema.chat_id_column();
+        let chat_join = schema.chat_join_clause();
         let statement = db.prepare(&format!(
             "SELECT 
                  *, 
-                 c.chat_id, 
+                 {chat_id} as chat_id, 
                  (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
-                 (SELECT COUNT(*) FROM {MESSAGE} m2 WHERE m2.thread_originator_guid = m.guid) as num_replies
+                 {num_replies} as num_replies
              FROM 
                  message as m 
-                 LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
+                 {chat_join}
              WHERE m.associated_message_guid NOT NULL
             "
         ));
@@ -354,9 +347,7 @@ impl Cacheable for Message {
 
         Ok(map)
     }
-}
 
-impl Message {
     /// Generate the text of a message, deserializing it as [`typedstream`](crate::util::typedstream) (and falling back to [`streamtyped`]) data if necessary.
     pub fn generate_text<'a>(&'a mut self, db: &'a Connection) -> Result<&'a str, MessageError> {
         if self.text.is_none() {
@@ -448,6 +439,43 @@ impl Message {
         parse_body_legacy(self)
     }
 
+    /// Get the base writing direction of the message text
+    ///
+    /// This requires the `typedstream` body data to be deserialized into [`Message::components`];
+    /// otherwise, the direction is [`WritingDirection::Natural`].
+    pub fn writing_direction(&self) -> WritingDirection {
+        parse_writing_direction(self)
+    }
+
+    /// `true` if the message text is only a few emoji, which iMessage displays enlarged, else `false`
+    pub fn is_big_emoji(&self) -> bool {
+        self.text
+            .as_deref()
+            .and_then(count_emoji)
+            .is_some_and(|count| (1..=BIG_EMOJI_LIMIT).contains(&count))
+    }
+
+    /// Get the one-time passcodes detected in the message body
+    ///
+    /// These are the ranges of text annotated with [`TextEffect::OTP`],
+    /// so this requires the `typedstream` body data to be deserialized into [`Message::components`].
+    pub fn one_time_codes(&self) -> Vec<&str> {
+        let Some(text) = &self.text else {
+            return vec![];
+        };
+
+        self.body()
+            .into_iter()
+            .filter_map(|component| match component {
+                BubbleComponent::Text(attributes) => Some(attributes),
+                _ => None,
+            })
+            .flatten()
+            .filter(|attribute| matches!(attribute.effect, TextEffect::OTP))
+            .filter_map(|attribute| text.get(attribute.start..attribute.end))
+            .collect()
+    }
+
     /// Calculates the date a message was written to the database.
     ///
     /// This field is stored as a unix timestamp with an epoch of `2001-01-01 00:00:00` in the local time zone
@@ -481,24 +509,51 @@ impl Message {
     /// - You received a message, then waited to read it
     /// - You sent a message, and the recipient waited to read it
     ///
-    /// In the former case, this subtracts the date read column (`date_read`) from the date received column (`date`).
-    /// In the latter case, this subtracts the date delivered column (`date_delivered`) from the date received column (`date`).
+    /// In both cases, this subtracts the date received column (`date`) from the date read column (`date_read`).
     ///
     /// Not all messages get tagged with the read properties.
     /// If more than one message has been sent in a thread before getting read,
-    /// only the most recent message will get the tag.
+    /// only the most recent message will get the tag. Recipients with read receipts turned off
+    /// never tag messages as read, see [`Self::read_receipt_state()`].
     pub fn time_until_read(&self, offset: &i64) -> Option<String> {
-        // Message we received
-        if !self.is_from_me && self.date_read != 0 && self.date != 0 {
+        if self.date_read != 0 && self.date != 0 {
             return readable_diff(self.date(offset), self.date_read(offset));
         }
-        // Message we sent
-        else if self.is_from_me && self.date_delivered != 0 && self.date != 0 {
+        None
+    }
+
+    /// Gets the time until a message you sent was delivered.
+    ///
+    /// This subtracts the date received column (`date`) from the date delivered column (`date_delivered`).
+    pub fn time_until_delivered(&self, offset: &i64) -> Option<String> {
+        if self.is_from_me && self.date_delivered != 0 && self.date != 0 {
             return readable_diff(self.date(offset), self.date_delivered(offset));
         }
         None
     }
 
+    /// Determine what is known about whether the message was read
+    ///
+    /// A sent message without a read receipt is [`ReadReceiptState::Delivered`], not [`ReadReceiptState::Unread`],
+    /// because the database cannot distinguish an unread message from a recipient that has read receipts turned off.
+    pub fn read_receipt_state(&self) -> ReadReceiptState {
+        if self.date_read != 0 {
+            return ReadReceiptState::Read;
+        }
+
+        if self.is_from_me {
+            if self.date_delivered != 0 {
+                return ReadReceiptState::Delivered;
+            }
+            return ReadReceiptState::Unknown;
+        }
+
+        if self.is_read {
+            return ReadReceiptState::Read;
+        }
+        ReadReceiptState::Unread
+    }
+
     /// `true` if the message is a response to a thread, else `false`
     pub fn is_reply(&self) -> bool {
         self.thread_originator_guid.is_some()
@@ -530,6 +585,13 @@ impl Message {
         matches!(self.variant(), Variant::App(CustomBalloon::URL))
     }
 
+    /// `true` if the message was scheduled with Send Later, else `false`
+    ///
+    /// Scheduled messages have a `schedule_type` of `2`. Their [`date`](Self::date) is the time they were scheduled to send.
+    pub fn is_scheduled(&self) -> bool {
+        self.schedule_type == 2
+    }
+
     /// `true` if the message was edited, else `false`
     pub fn is_edited(&self) -> bool {
         self.date_edited != 0
@@ -545,6 +607,13 @@ impl Message {
         false
     }
 
+    /// Get the number of times the message's components were edited
+    pub fn edit_count(&self) -> usize {
+        self.edited_parts
+            .as_ref()
+            .map_or(0, |edited_parts| edited_parts.edit_count())
+    }
+
     /// `true` if all message components were unsent, else `false`
     pub fn is_fully_unsent(&self) -> bool {
         self.edited_parts.as_ref().map_or(false, |ep| {
@@ -554,6 +623,31 @@ impl Message {
         })
     }
 
+    /// Determine whether an unsent message was retracted before or after it was delivered
+    ///
+    /// Returns `None` if no part of the message was unsent. Only sent messages can be retracted
+    /// before delivery; a received message was necessarily delivered.
+    pub fn unsent_timing(&self) -> Option<UnsentTiming> {
+        let is_unsent = self.edited_parts.as_ref().is_some_and(|ep| {
+            ep.parts
+                .iter()
+                .any(|part| matches!(part.status, EditStatus::Unsent))
+        });
+        if !is_unsent {
+            return None;
+        }
+
+        let retracted_before_delivery = self.is_from_me()
+            && (self.date_delivered == 0
+                || (self.date_edited != 0 && self.date_edited < self.date_delivered));
+
+        if retracted_before_delivery {
+            Some(UnsentTiming::BeforeDelivery)
+        } else {
+            Some(UnsentTiming::AfterDelivery)
+        }
+    }
+
     /// `true` if the message has attachments, else `false`
     pub fn has_attachments(&self) -> bool {
         self.num_attachments > 0
@@ -584,6 +678,25 @@ impl Message {
         self.item_type == 4 && self.group_action_type == 0 && self.share_status
     }
 
+    /// `true` if the message was forwarded from another conversation, else `false`
+    ///
+    /// The database does not record who sent the original message.
+    pub fn is_forwarded(&self) -> bool {
+        self.is_forward
+    }
+
+    /// `true` if the message was reported as junk, else `false`
+    pub fn is_reported_junk(&self) -> bool {
+        self.is_spam
+    }
+
+    /// `true` if the message was delivered quietly because the recipient had notifications silenced, else `false`
+    ///
+    /// This is best-effort: the flag is only present on newer schemas, and is `false` when the database does not record it.
+    pub fn was_delivered_quietly(&self) -> bool {
+        self.was_delivered_quietly
+    }
+
     /// `true` if the message was deleted and is recoverable, else `false`
     ///
     /// Messages removed by deleting an entire conversation or by deleting a single message
@@ -641,6 +754,38 @@ impl Message {
         Ok(count)
     }
 
+    /// Get the date of the newest message in the database, or `None` if there are no messages
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use imessage_database::util::dirs::default_db_path;
+    /// use imessage_database::tables::table::get_connection;
+    /// use imessage_database::tables::messages::Message;
+    /// use imessage_database::util::query_context::QueryContext;
+    ///
+    /// let db_path = default_db_path();
+    /// let conn = get_connection(&db_path).unwrap();
+    /// let context = QueryContext::default();
+    /// Message::get_max_date(&conn, &context);
+    /// ```
+    pub fn get_max_date(
+        db: &Connection,
+        context: &QueryContext,
+    ) -> Result<Option<i64>, TableError> {
+        let mut statement = db
+            .prepare(&format!(
+                "SELECT MAX(m.date) FROM {MESSAGE} as m {}",
+                context.generate_filter_statement("m.date")
+            ))
+            .map_err(TableError::Messages)?;
+        statement
+            .query_row([], |r| r.get(0))
+            .optional()
+            .map(Option::flatten)
+            .map_err(TableError::Messages)
+    }
+
     /// Stream messages from the database with optional filters
     ///
     /// # Example:
@@ -660,43 +805,93 @@ impl Message {
         db: &'a Connection,
         context: &'a QueryContext,
     ) -> Result<Statement<'a>, TableError> {
-        if !context.has_filters() {
-            return Self::get(db);
-        }
+        Self::stream_rows_with_schema(db, &Schema::detect(db)?, context)
+    }
 
-        let filters = context.generate_filter_statement("m.date");
+    /// Stream messages as described in [`Message::stream_rows`] from a database whose [`Schema`] was already detected
+    pub fn stream_rows_with_schema<'a>(
+        db: &'a Connection,
+        schema: &Schema,
+        context: &'a QueryContext,
+    ) -> Result<Statement<'a>, TableError> {
+        let filters = if context.has_filters() {
+            context.generate_filter_statement("m.date")
+        } else {
+            String::new()
+        };
+        Self::select_messages(db, schema, &filters)
+    }
 
-        // If database has `thread_originator_guid`, we can parse replies, otherwise default to 0
-        Ok(db.prepare(&format!(
-                "SELECT
-                     *,
-                     c.chat_id,
-                     (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
-                     (SELECT b.chat_id FROM {RECENTLY_DELETED} b WHERE m.ROWID = b.message_id) as deleted_from,
-                     (SELECT COUNT(*) FROM {MESSAGE} m2 WHERE m2.thread_originator_guid = m.guid) as num_replies
-                 FROM
-                     message as m
-                     LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
-                 {filters}
-                 ORDER BY
-                     m.date;
-                "
-            ))
-            .unwrap_or(db.prepare(&format!(
+    /// Get a single message by its GUID
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use imessage_database::util::dirs::default_db_path;
+    /// use imessage_database::tables::table::get_connection;
+    /// use imessage_database::tables::messages::Message;
+    ///
+    /// let db_path = default_db_path();
+    /// let conn = get_connection(&db_path).unwrap();
+    /// let message = Message::from_guid("example-guid", &conn);
+    /// ```
+    pub fn from_guid(guid: &str, db: &Connection) -> Result<Self, TableError> {
+        Self::from_guid_with_schema(guid, db, &Schema::detect(db)?)
+    }
+
+    /// Get a single message by its GUID from a database whose [`Schema`] was already detected
+    pub fn from_guid_with_schema(
+        guid: &str,
+        db: &Connection,
+        schema: &Schema,
+    ) -> Result<Self, TableError> {
+        let mut statement = Self::select_messages(db, schema, "WHERE m.guid = ?1")?;
+        Self::extract(statement.query_row([guid], |row| Ok(Message::from_row(row))))
+    }
+
+    /// Build a query for messages matching `filters`, selecting only the columns the database's [`Schema`] supports
+    ///
+    /// If the database has `chat_recoverable_message_join`, we can restore some deleted messages.
+    /// If the database has `thread_originator_guid`, we can parse replies, otherwise default to 0.
+    /// If the database has no `chat_message_join`, messages are selected without a chat instead of failing.
+    fn select_messages<'a>(
+        db: &'a Connection,
+        schema: &Schema,
+        filters: &str,
+    ) -> Result<Statement<'a>, TableError> {
+        let deleted_from = schema.deleted_from_column();
+        let num_replies = schema.num_replies_column();
+        let schedule_type = schema.schedule_type_column();
+        let is_spam = schema.is_spam_column();
+        let was_delivered_quietly = schema.was_delivered_quietly_column();
+        let chat_id = schema.chat_id_column();
+        let chat_join = schema.chat_join_clause();
+
+        let query = |columns: &str| {
+            format!(
                 "SELECT
-                     *,
-                     c.chat_id,
+                     {columns},
+                     {chat_id} as chat_id,
                      (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
-                     (SELECT NULL) as deleted_from,
-                     (SELECT 0) as num_replies
+                     {deleted_from} as deleted_from,
+                     {num_replies} as num_replies,
+                     {schedule_type} as schedule_type,
+                     {is_spam} as is_spam,
+                     {was_delivered_quietly} as was_delivered_quietly
                  FROM
                      message as m
-                     LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
+                     {chat_join}
                  {filters}
                  ORDER BY
                      m.date;
                 "
-            )).map_err(TableError::Messages)?))
+            )
+        };
+
+        // The required columns are interpolated for performance, but older schemas may be missing some of them
+        db.prepare(&query(COLS))
+            .or_else(|_| db.prepare(&query("*")))
+            .map_err(TableError::Messages)
     }
 
     /// See [`Reaction`] for details on this data.
@@ -717,6 +912,11 @@ impl Message {
         None
     }
 
+    /// Get the GUID of the message that a reaction or sticker was applied to, parsed from its associated GUID field
+    pub fn associated_target_guid(&self) -> Option<&str> {
+        self.clean_associated_guid().map(|(_, guid)| guid)
+    }
+
     /// Parse the index of a reaction from it's associated GUID field
     fn reaction_index(&self) -> usize {
         match self.clean_associated_guid() {
@@ -730,20 +930,36 @@ impl Message {
         &self,
         db: &Connection,
         reactions: &HashMap<String, Vec<String>>,
+    ) -> Result<HashMap<usize, Vec<Self>>, TableError> {
+        // No need to detect the schema if we know we don't have reactions
+        if !reactions.contains_key(&self.guid) {
+            return Ok(HashMap::new());
+        }
+        self.get_reactions_with_schema(db, &Schema::detect(db)?, reactions)
+    }
+
+    /// Build the reactions described in [`Message::get_reactions`] from a database whose [`Schema`] was already detected
+    pub fn get_reactions_with_schema(
+        &self,
+        db: &Connection,
+        schema: &Schema,
+        reactions: &HashMap<String, Vec<String>>,
     ) -> Result<HashMap<usize, Vec<Self>>, TableError> {
         let mut out_h: HashMap<usize, Vec<Self>> = HashMap::new();
         if let Some(rxs) = reactions.get(&self.guid) {
             let filter: Vec<String> = rxs.iter().map(|guid| format!("\"{guid}\"")).collect();
+            let chat_id = schema.chat_id_column();
+            let chat_join = schema.chat_join_clause();
             // Create query
             let mut statement = db.prepare(&format!(
                 "SELECT 
                         *, 
-                        c.chat_id, 
+                        {chat_id} as chat_id, 
                         (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
                         (SELECT COUNT(*) FROM {MESSAGE} m2 WHERE m2.thread_originator_guid = m.guid) as num_replies
                     FROM 
                         message as m 
-                        LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
+                        {chat_join}
                     WHERE m.guid IN ({})
                     ORDER BY 
                         m.date;
@@ -773,19 +989,34 @@ impl Message {
 
     /// Build a `HashMap` of message component index to messages that reply to that component
     pub fn get_replies(&self, db: &Connection) -> Result<HashMap<usize, Vec<Self>>, TableError> {
+        // No need to detect the schema if we know we don't have replies
+        if !self.has_replies() {
+            return Ok(HashMap::new());
+        }
+        self.get_replies_with_schema(db, &Schema::detect(db)?)
+    }
+
+    /// Build the replies described in [`Message::get_replies`] from a database whose [`Schema`] was already detected
+    pub fn get_replies_with_schema(
+        &self,
+        db: &Connection,
+        schema: &Schema,
+    ) -> Result<HashMap<usize, Vec<Self>>, TableError> {
         let mut out_h: HashMap<usize, Vec<Self>> = HashMap::new();
 
         // No need to hit the DB if we know we don't have replies
         if self.has_replies() {
+            let chat_id = schema.chat_id_column();
+            let chat_join = schema.chat_join_clause();
             let mut statement = db.prepare(&format!(
                 "SELECT 
                      *, 
-                     c.chat_id, 
+                     {chat_id} as chat_id, 
                      (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
                      (SELECT COUNT(*) FROM {MESSAGE} m2 WHERE m2.thread_originator_guid = m.guid) as num_replies
                  FROM 
                      message as m 
-                     LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id 
+                     {chat_join}
                  WHERE m.thread_originator_guid = \"{}\"
                  ORDER BY 
                      m.date;
@@ -816,7 +1047,7 @@ impl Message {
     ///
     /// For example, a Bundle ID like `com.apple.messages.MSMessageExtensionBalloonPlugin:0000000000:com.apple.SafetyMonitorApp.SafetyMonitorMessages`
     /// should get parsed into `com.apple.SafetyMonitorApp.SafetyMonitorMessages`.
-    fn parse_balloon_bundle_id(&self) -> Option<&str> {
+    pub fn parse_balloon_bundle_id(&self) -> Option<&str> {
         if let Some(bundle_id) = &self.balloon_bundle_id {
             let mut parts = bundle_id.split(':');
             let bundle_id = parts.next();
@@ -849,6 +1080,9 @@ impl Message {
                         "com.apple.Handwriting.HandwritingProvider" => {
                             Variant::App(CustomBalloon::Handwriting)
                         }
+                        "com.apple.DigitalTouchBalloonProvider" => {
+                            Variant::App(CustomBalloon::DigitalTouch)
+                        }
                         "com.apple.PassbookUIService.PeerPaymentMessagesExtension" => {
                             Variant::App(CustomBalloon::ApplePay)
                         }
@@ -862,6 +1096,12 @@ impl Message {
                             Variant::App(CustomBalloon::CheckIn)
                         }
                         "com.apple.findmy.FindMyMessagesApp" => Variant::App(CustomBalloon::FindMy),
+                        "com.apple.journal.MessagesExtension" => {
+                            Variant::App(CustomBalloon::Journal)
+                        }
+                        _ if game_app_name(bundle_id).is_some() => {
+                            Variant::App(CustomBalloon::Game(bundle_id))
+                        }
                         _ => Variant::App(CustomBalloon::Application(bundle_id)),
                     },
                     // This is the most common case
@@ -878,12 +1118,14 @@ impl Message {
                 2003 => Variant::Reaction(self.reaction_index(), true, Reaction::Laughed),
                 2004 => Variant::Reaction(self.reaction_index(), true, Reaction::Emphasized),
                 2005 => Variant::Reaction(self.reaction_index(), true, Reaction::Questioned),
+                2007 => Variant::Reaction(self.reaction_index(), true, Reaction::Sticker),
                 3000 => Variant::Reaction(self.reaction_index(), false, Reaction::Loved),
                 3001 => Variant::Reaction(self.reaction_index(), false, Reaction::Liked),
                 3002 => Variant::Reaction(self.reaction_index(), false, Reaction::Disliked),
                 3003 => Variant::Reaction(self.reaction_index(), false, Reaction::Laughed),
                 3004 => Variant::Reaction(self.reaction_index(), false, Reaction::Emphasized),
                 3005 => Variant::Reaction(self.reaction_index(), false, Reaction::Questioned),
+                3007 => Variant::Reaction(self.reaction_index(), false, Reaction::Sticker),
 
                 // Unknown
                 x => Variant::Unknown(x),
@@ -973,6 +1215,46 @@ impl Message {
         Some(body)
     }
 
+    /// Get the raw identifier, i.e. a phone number or email address, of the handle that sent the message
+    ///
+    /// Returns `None` for messages sent by the database owner and messages without a sender handle.
+    /// The handle is not resolved to a contact, so callers can do their own contact resolution.
+    ///
+    /// The lookup statement is cached on the connection, so calling this for many messages does not
+    /// prepare a new query each time. To avoid querying at all, build a map of every handle with
+    /// [`Handle::cache()`](crate::tables::handle::Handle).
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use imessage_database::util::dirs::default_db_path;
+    /// use imessage_database::tables::table::get_connection;
+    /// use imessage_database::tables::messages::Message;
+    ///
+    /// let db_path = default_db_path();
+    /// let conn = get_connection(&db_path).unwrap();
+    /// if let Ok(message) = Message::from_guid("example-guid", &conn) {
+    ///     let sender = message.sender_handle(&conn);
+    /// }
+    /// ```
+    pub fn sender_handle(&self, db: &Connection) -> Result<Option<String>, TableError> {
+        if self.is_from_me() {
+            return Ok(None);
+        }
+        // Handle ID 0 is self in group chats
+        let Some(handle_id) = self.handle_id.filter(|handle_id| *handle_id != 0) else {
+            return Ok(None);
+        };
+
+        let mut statement = db
+            .prepare_cached(&format!("SELECT id FROM {HANDLE} WHERE ROWID = ?1"))
+            .map_err(TableError::Handle)?;
+        statement
+            .query_row([handle_id], |row| row.get(0))
+            .optional()
+            .map_err(TableError::Handle)
+    }
+
     /// Determine which expressive the message was sent with
     pub fn get_expressive(&self) -> Expressive {
         match &self.expressive_send_style_id {
@@ -1017,20 +1299,44 @@ impl Message {
             None => Expressive::None,
         }
     }
+
+    /// Determine if the recipient replayed the message's expressive or revealed its Invisible Ink
+    ///
+    /// Returns `None` if the message has no known expressive or the database did not record it being played.
+    pub fn expressive_state(&self) -> Option<ExpressiveState> {
+        if self.date_played == 0 {
+            return None;
+        }
+        match self.get_expressive() {
+            Expressive::Bubble(BubbleEffect::InvisibleInk) => Some(ExpressiveState::Revealed),
+            Expressive::Screen(_) | Expressive::Bubble(_) => Some(ExpressiveState::Replayed),
+            Expressive::Unknown(_) | Expressive::None => None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{env::current_dir, fs::File, io::Read};
+
     use crate::{
         message_types::{
-            edited::{EditStatus, EditedMessage, EditedMessagePart},
+            edited::{EditStatus, EditedMessage, EditedMessagePart, UnsentTiming},
             expressives,
-            variants::{CustomBalloon, Variant},
+            variants::{CustomBalloon, Reaction, Variant},
+        },
+        tables::{
+            messages::{models::ReadReceiptState, Message},
+            schema::Schema,
+            table::{Table, CHAT_MESSAGE_JOIN, HANDLE, MESSAGE, MESSAGE_ATTACHMENT_JOIN},
+        },
+        util::{
+            dates::get_offset, query_context::QueryContext, typedstream::parser::TypedStreamReader,
         },
-        tables::messages::Message,
-        util::dates::get_offset,
     };
 
+    use rusqlite::Connection;
+
     fn blank() -> Message {
         Message {
             rowid: i32::default(),
@@ -1043,8 +1349,12 @@ mod tests {
             date: i64::default(),
             date_read: i64::default(),
             date_delivered: i64::default(),
+            date_played: 0,
             is_from_me: false,
             is_read: false,
+            is_forward: false,
+            is_spam: false,
+            was_delivered_quietly: false,
             item_type: 0,
             other_handle: 0,
             share_status: false,
@@ -1058,6 +1368,7 @@ mod tests {
             thread_originator_guid: None,
             thread_originator_part: None,
             date_edited: 0,
+            schedule_type: 0,
             chat_id: None,
             num_attachments: 0,
             deleted_from: None,
@@ -1067,6 +1378,107 @@ mod tests {
         }
     }
 
+    #[test]
+    fn can_get_message_from_guid() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {MESSAGE} (ROWID INTEGER PRIMARY KEY, guid TEXT, text TEXT, date INTEGER, is_from_me INTEGER, is_read INTEGER);
+             CREATE TABLE {CHAT_MESSAGE_JOIN} (chat_id INTEGER, message_id INTEGER);
+             CREATE TABLE {MESSAGE_ATTACHMENT_JOIN} (message_id INTEGER, attachment_id INTEGER);
+             INSERT INTO {MESSAGE} VALUES (1, 'first', 'Hello', 1, 0, 1), (2, 'second', 'World', 2, 1, 1);
+             INSERT INTO {CHAT_MESSAGE_JOIN} VALUES (3, 2);"
+        ))
+        .unwrap();
+
+        let message = Message::from_guid("second", &db).unwrap();
+        assert_eq!(message.rowid, 2);
+        assert_eq!(message.text.as_deref(), Some("World"));
+        assert_eq!(message.chat_id, Some(3));
+    }
+
+    #[test]
+    fn can_get_sender_handle() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {HANDLE} (ROWID INTEGER PRIMARY KEY, id TEXT);
+             INSERT INTO {HANDLE} VALUES (1, '+15558675309'), (2, 'test@example.com');"
+        ))
+        .unwrap();
+
+        let mut message = blank();
+        message.handle_id = Some(2);
+        assert_eq!(
+            message.sender_handle(&db).unwrap().as_deref(),
+            Some("test@example.com")
+        );
+
+        message.handle_id = Some(1);
+        assert_eq!(
+            message.sender_handle(&db).unwrap().as_deref(),
+            Some("+15558675309")
+        );
+    }
+
+    #[test]
+    fn cant_get_sender_handle_from_me() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {HANDLE} (ROWID INTEGER PRIMARY KEY, id TEXT);
+             INSERT INTO {HANDLE} VALUES (1, '+15558675309');"
+        ))
+        .unwrap();
+
+        let mut message = blank();
+        message.handle_id = Some(1);
+        message.is_from_me = true;
+        assert_eq!(message.sender_handle(&db).unwrap(), None);
+    }
+
+    #[test]
+    fn cant_get_sender_handle_missing() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {HANDLE} (ROWID INTEGER PRIMARY KEY, id TEXT);"
+        ))
+        .unwrap();
+
+        let mut message = blank();
+        message.handle_id = Some(5);
+        assert_eq!(message.sender_handle(&db).unwrap(), None);
+
+        message.handle_id = Some(0);
+        assert_eq!(message.sender_handle(&db).unwrap(), None);
+    }
+
+    #[test]
+    fn cant_get_message_from_missing_guid() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {MESSAGE} (ROWID INTEGER PRIMARY KEY, guid TEXT, date INTEGER, is_from_me INTEGER, is_read INTEGER);
+             CREATE TABLE {CHAT_MESSAGE_JOIN} (chat_id INTEGER, message_id INTEGER);
+             CREATE TABLE {MESSAGE_ATTACHMENT_JOIN} (message_id INTEGER, attachment_id INTEGER);"
+        ))
+        .unwrap();
+
+        assert!(Message::from_guid("missing", &db).is_err());
+    }
+
+    #[test]
+    fn can_get_message_without_chat_message_join() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {MESSAGE} (ROWID INTEGER PRIMARY KEY, guid TEXT, text TEXT, date INTEGER, is_from_me INTEGER, is_read INTEGER);
+             CREATE TABLE {MESSAGE_ATTACHMENT_JOIN} (message_id INTEGER, attachment_id INTEGER);
+             INSERT INTO {MESSAGE} VALUES (1, 'first', 'Hello', 1, 0, 1);"
+        ))
+        .unwrap();
+
+        // Messages are still read, but they do not belong to a chat
+        let message = Message::from_guid("first", &db).unwrap();
+        assert_eq!(message.text.as_deref(), Some("Hello"));
+        assert_eq!(message.chat_id, None);
+    }
+
     #[test]
     fn can_gen_message() {
         blank();
@@ -1109,6 +1521,21 @@ mod tests {
         assert_eq!(message.time_until_read(&offset), None);
     }
 
+    #[test]
+    fn can_get_scheduled_message() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {MESSAGE} (ROWID INTEGER PRIMARY KEY, guid TEXT, date INTEGER, is_from_me INTEGER, is_read INTEGER, schedule_type INTEGER);
+             CREATE TABLE {CHAT_MESSAGE_JOIN} (chat_id INTEGER, message_id INTEGER);
+             CREATE TABLE {MESSAGE_ATTACHMENT_JOIN} (message_id INTEGER, attachment_id INTEGER);
+             INSERT INTO {MESSAGE} VALUES (1, 'scheduled', 1, 1, 0, 2), (2, 'sent', 2, 1, 0, 0);"
+        ))
+        .unwrap();
+
+        assert!(Message::from_guid("scheduled", &db).unwrap().is_scheduled());
+        assert!(!Message::from_guid("sent", &db).unwrap().is_scheduled());
+    }
+
     #[test]
     fn can_get_message_expression_none() {
         let m = blank();
@@ -1136,6 +1563,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_get_expressive_state_revealed() {
+        let mut m = blank();
+        m.expressive_send_style_id =
+            Some("com.apple.MobileSMS.expressivesend.invisibleink".to_string());
+        m.date_played = 674526582885055488;
+        assert_eq!(
+            m.expressive_state(),
+            Some(expressives::ExpressiveState::Revealed)
+        );
+    }
+
+    #[test]
+    fn can_get_expressive_state_replayed() {
+        let mut m = blank();
+        m.expressive_send_style_id = Some("com.apple.messages.effect.CKConfettiEffect".to_string());
+        m.date_played = 674526582885055488;
+        assert_eq!(
+            m.expressive_state(),
+            Some(expressives::ExpressiveState::Replayed)
+        );
+    }
+
+    #[test]
+    fn cant_get_expressive_state_not_played() {
+        let mut m = blank();
+        m.expressive_send_style_id =
+            Some("com.apple.MobileSMS.expressivesend.invisibleink".to_string());
+        assert_eq!(m.expressive_state(), None);
+    }
+
+    #[test]
+    fn cant_get_expressive_state_no_expressive() {
+        let mut m = blank();
+        m.date_played = 674526582885055488;
+        assert_eq!(m.expressive_state(), None);
+    }
+
     #[test]
     fn can_get_no_balloon_bundle_id() {
         let m = blank();
@@ -1188,6 +1653,73 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn can_get_big_emoji() {
+        let mut m = blank();
+        m.text = Some("🎉🎉".to_string());
+        assert!(m.is_big_emoji());
+
+        // Too many emoji
+        m.text = Some("🎉🎉🎉🎉".to_string());
+        assert!(!m.is_big_emoji());
+
+        // Emoji mixed with text
+        m.text = Some("Party 🎉".to_string());
+        assert!(!m.is_big_emoji());
+
+        // No text
+        m.text = None;
+        assert!(!m.is_big_emoji());
+    }
+
+    #[test]
+    fn can_get_balloon_bundle_id_game() {
+        let mut m = blank();
+        m.balloon_bundle_id = Some("com.apple.messages.MSMessageExtensionBalloonPlugin:0000000000:com.gamerdelights.gamepigeon.ext".to_owned());
+        assert!(matches!(
+            m.variant(),
+            Variant::App(CustomBalloon::Game("com.gamerdelights.gamepigeon.ext"))
+        ));
+    }
+
+    #[test]
+    fn can_get_balloon_bundle_id_digital_touch() {
+        let mut m = blank();
+        m.balloon_bundle_id = Some("com.apple.DigitalTouchBalloonProvider".to_owned());
+        assert_eq!(
+            m.parse_balloon_bundle_id(),
+            Some("com.apple.DigitalTouchBalloonProvider")
+        );
+        assert!(matches!(
+            m.variant(),
+            Variant::App(CustomBalloon::DigitalTouch)
+        ));
+    }
+
+    #[test]
+    fn can_get_sticker_tapback() {
+        let mut m = blank();
+        m.associated_message_guid = Some("p:1/A44CE9D7-AAAA-BBBB-CCCC-23C54E1A9B6A".to_string());
+        m.associated_message_type = Some(2007);
+        assert!(matches!(
+            m.variant(),
+            Variant::Reaction(1, true, Reaction::Sticker)
+        ));
+        assert!(m.is_reaction());
+        assert!(!m.is_sticker());
+    }
+
+    #[test]
+    fn can_get_removed_sticker_tapback() {
+        let mut m = blank();
+        m.associated_message_guid = Some("p:0/A44CE9D7-AAAA-BBBB-CCCC-23C54E1A9B6A".to_string());
+        m.associated_message_type = Some(3007);
+        assert!(matches!(
+            m.variant(),
+            Variant::Reaction(0, false, Reaction::Sticker)
+        ));
+    }
+
     #[test]
     fn can_get_valid_guid() {
         let mut m = blank();
@@ -1237,6 +1769,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_get_associated_target_guid() {
+        let mut m = blank();
+        m.associated_message_guid = Some("p:1/A44CE9D7-AAAA-BBBB-CCCC-23C54E1A9B6A".to_string());
+
+        assert_eq!(
+            Some("A44CE9D7-AAAA-BBBB-CCCC-23C54E1A9B6A"),
+            m.associated_target_guid()
+        );
+    }
+
     #[test]
     fn cant_get_invalid_guid_bp() {
         let mut m = blank();
@@ -1328,6 +1871,12 @@ mod tests {
         assert!(m.is_part_edited(0));
     }
 
+    #[test]
+    fn can_get_edit_count_blank() {
+        let m = blank();
+        assert_eq!(m.edit_count(), 0);
+    }
+
     #[test]
     fn can_get_part_edited_false() {
         let mut m = blank();
@@ -1360,4 +1909,248 @@ mod tests {
 
         assert!(!m.is_fully_unsent());
     }
+
+    #[test]
+    fn can_get_unsent_timing_before_delivery() {
+        let mut m = blank();
+        m.is_from_me = true;
+        m.date = 674526582885055488;
+        m.date_edited = 674526600000000000;
+        m.edited_parts = Some(EditedMessage {
+            parts: vec![EditedMessagePart {
+                status: EditStatus::Unsent,
+                edit_history: vec![],
+            }],
+        });
+
+        assert_eq!(m.unsent_timing(), Some(UnsentTiming::BeforeDelivery));
+    }
+
+    #[test]
+    fn can_get_unsent_timing_after_delivery() {
+        let mut m = blank();
+        m.is_from_me = true;
+        m.date = 674526582885055488;
+        m.date_delivered = 674526590000000000;
+        m.date_edited = 674526600000000000;
+        m.edited_parts = Some(EditedMessage {
+            parts: vec![EditedMessagePart {
+                status: EditStatus::Unsent,
+                edit_history: vec![],
+            }],
+        });
+
+        assert_eq!(m.unsent_timing(), Some(UnsentTiming::AfterDelivery));
+    }
+
+    #[test]
+    fn can_get_unsent_timing_received() {
+        let mut m = blank();
+        m.date = 674526582885055488;
+        m.date_edited = 674526600000000000;
+        m.edited_parts = Some(EditedMessage {
+            parts: vec![EditedMessagePart {
+                status: EditStatus::Unsent,
+                edit_history: vec![],
+            }],
+        });
+
+        assert_eq!(m.unsent_timing(), Some(UnsentTiming::AfterDelivery));
+    }
+
+    #[test]
+    fn can_get_unsent_timing_none() {
+        let mut m = blank();
+        m.is_from_me = true;
+        m.edited_parts = Some(EditedMessage {
+            parts: vec![EditedMessagePart {
+                status: EditStatus::Edited,
+                edit_history: vec![],
+            }],
+        });
+
+        assert_eq!(m.unsent_timing(), None);
+        assert_eq!(blank().unsent_timing(), None);
+    }
+
+    #[test]
+    fn can_get_one_time_codes() {
+        let mut m = blank();
+        m.text = Some("000123 is your security code. Don't share your code.".to_string());
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/Code");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        m.components = parser.parse().ok();
+
+        assert_eq!(m.one_time_codes(), vec!["000123"]);
+    }
+
+    #[test]
+    fn can_get_one_time_codes_none() {
+        let mut m = blank();
+        m.text = Some("Your code is 000123".to_string());
+
+        assert!(m.one_time_codes().is_empty());
+    }
+
+    #[test]
+    fn can_get_forwarded_true() {
+        let mut m = blank();
+        m.is_forward = true;
+
+        assert!(m.is_forwarded());
+    }
+
+    #[test]
+    fn can_get_forwarded_false() {
+        let m = blank();
+
+        assert!(!m.is_forwarded());
+    }
+
+    #[test]
+    fn can_get_time_date_delivered_sent() {
+        // Get offset
+        let offset = get_offset();
+
+        // Create message
+        let mut message = blank();
+        message.is_from_me = true;
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        // May 17, 2022  9:30:31 PM
+        message.date_delivered = 674530231992568192;
+
+        assert_eq!(message.time_until_read(&offset), None);
+        assert_eq!(
+            message.time_until_delivered(&offset),
+            Some("1 hour, 49 seconds".to_string())
+        );
+    }
+
+    #[test]
+    fn can_get_time_date_read_sent() {
+        // Get offset
+        let offset = get_offset();
+
+        // Create message
+        let mut message = blank();
+        message.is_from_me = true;
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        // May 17, 2022  8:29:42 PM
+        message.date_delivered = 674526582885055488;
+        // May 17, 2022  9:30:31 PM
+        message.date_read = 674530231992568192;
+
+        assert_eq!(
+            message.time_until_read(&offset),
+            Some("1 hour, 49 seconds".to_string())
+        );
+    }
+
+    #[test]
+    fn can_get_read_receipt_state_sent() {
+        let mut message = blank();
+        message.is_from_me = true;
+        assert_eq!(message.read_receipt_state(), ReadReceiptState::Unknown);
+
+        message.date_delivered = 674526582885055488;
+        assert_eq!(message.read_receipt_state(), ReadReceiptState::Delivered);
+
+        message.date_read = 674530231992568192;
+        assert_eq!(message.read_receipt_state(), ReadReceiptState::Read);
+    }
+
+    #[test]
+    fn can_get_read_receipt_state_received() {
+        let mut message = blank();
+        assert_eq!(message.read_receipt_state(), ReadReceiptState::Unread);
+
+        message.is_read = true;
+        assert_eq!(message.read_receipt_state(), ReadReceiptState::Read);
+
+        message.is_read = false;
+        message.date_read = 674530231992568192;
+        assert_eq!(message.read_receipt_state(), ReadReceiptState::Read);
+    }
+
+    #[test]
+    fn can_read_older_schema_with_detected_schema() {
+        // Older databases have no `chat_message_join` table and no columns for threaded replies
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {MESSAGE} (ROWID INTEGER PRIMARY KEY, guid TEXT, text TEXT, date INTEGER, is_from_me INTEGER, is_read INTEGER);
+             CREATE TABLE {MESSAGE_ATTACHMENT_JOIN} (message_id INTEGER, attachment_id INTEGER);
+             INSERT INTO {MESSAGE} VALUES (1, 'first', 'Hello', 1, 0, 1);
+             INSERT INTO {MESSAGE} VALUES (2, 'second', 'World', 2, 1, 1);"
+        ))
+        .unwrap();
+        let schema = Schema::detect(&db).unwrap();
+        assert!(!schema.supports_chat_join());
+        assert!(!schema.supports_replies());
+
+        // Queries built from the detected schema fall back instead of failing on the missing table and columns
+        let message = Message::from_guid_with_schema("first", &db, &schema).unwrap();
+        assert_eq!(message.text.as_deref(), Some("Hello"));
+        assert_eq!(message.chat_id, None);
+        assert_eq!(message.num_replies, 0);
+        assert!(message
+            .get_replies_with_schema(&db, &schema)
+            .unwrap()
+            .is_empty());
+
+        let context = QueryContext::default();
+        let mut statement = Message::stream_rows_with_schema(&db, &schema, &context).unwrap();
+        let guids: Vec<String> = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .unwrap()
+            .map(|message| Message::extract(message).unwrap().guid)
+            .collect();
+        assert_eq!(guids, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn can_get_max_date() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {MESSAGE} (ROWID INTEGER PRIMARY KEY, date INTEGER);
+             INSERT INTO {MESSAGE} (date) VALUES (674526582885055488), (674530231992568192);"
+        ))
+        .unwrap();
+
+        let date = Message::get_max_date(&db, &QueryContext::default()).unwrap();
+        assert_eq!(date, Some(674530231992568192));
+    }
+
+    #[test]
+    fn can_get_max_date_empty() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {MESSAGE} (ROWID INTEGER PRIMARY KEY, date INTEGER);"
+        ))
+        .unwrap();
+
+        let date = Message::get_max_date(&db, &QueryContext::default()).unwrap();
+        assert_eq!(date, None);
+    }
+
+    #[test]
+    fn cant_get_max_date_invalid() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(&format!(
+            "CREATE TABLE {MESSAGE} (ROWID INTEGER PRIMARY KEY, date TEXT);
+             INSERT INTO {MESSAGE} (date) VALUES ('yesterday');"
+        ))
+        .unwrap();
+
+        assert!(Message::get_max_date(&db, &QueryContext::default()).is_err());
+    }
 }
@@ -23,6 +23,19 @@ pub enum BubbleComponent<'a> {
     Retracted,
 }
 
+/// Defines the base writing direction of a message's text, from the `__kIMBaseWritingDirectionAttributeName` attribute.
+///
+/// Values match [`NSWritingDirection`](https://developer.apple.com/documentation/uikit/nswritingdirection).
+#[derive(Debug, PartialEq, Eq)]
+pub enum WritingDirection {
+    /// The direction is determined by the text itself (`-1`)
+    Natural,
+    /// Left-to-right text, i.e. English (`0`)
+    LeftToRight,
+    /// Right-to-left text, i.e. Arabic or Hebrew (`1`)
+    RightToLeft,
+}
+
 /// Defines different types of services we can receive messages from.
 #[derive(Debug)]
 pub enum Service<'a> {
@@ -37,6 +50,21 @@ pub enum Service<'a> {
     Unknown,
 }
 
+/// Defines what is known about whether a message was read, based on the read receipt data in the message table.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReadReceiptState {
+    /// The message was read
+    Read,
+    /// A sent message was delivered, but no read receipt was received
+    ///
+    /// The recipient either has not read the message or has read receipts turned off, so it is not possible to tell if it was read.
+    Delivered,
+    /// A received message that was not read
+    Unread,
+    /// There is no delivery or read data for a sent message, i.e. an SMS or a message that failed to send
+    Unknown,
+}
+
 /// Defines ranges of text and associated attributes parsed from [`typedstream`](crate::util::typedstream) `attributedBody` data.
 ///
 /// Ranges specify locations attributes applied to specific portions of a [`Message`](crate::tables::messages::Message)'s [`text`](crate::tables::messages::Message::text). For example, given message text with a [`Mention`](TextEffect::Mention) like:
@@ -10,4 +10,5 @@ pub mod chat;
 pub mod chat_handle;
 pub mod handle;
 pub mod messages;
+pub mod schema;
 pub mod table;
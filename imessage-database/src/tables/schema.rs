@@ -0,0 +1,322 @@
+/*!
+ This module detects which version of the iMessage database schema a database uses.
+
+ Newer versions of macOS and iOS add columns and tables to the database; this data is used to
+ avoid querying for data that does not exist in older databases.
+*/
+
+use std::{collections::HashSet, fmt::Display};
+
+use rusqlite::Connection;
+
+use crate::{
+    error::table::TableError,
+    tables::table::{CHAT_MESSAGE_JOIN, MESSAGE, RECENTLY_DELETED},
+};
+
+/// Represents the generation of iMessage database schema, named for the OS versions that introduced it
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SchemaVersion {
+    /// macOS Catalina, iOS 13 and older: no replies or edits
+    Catalina,
+    /// macOS Big Sur to Monterey, iOS 14 to iOS 15: adds threaded replies
+    BigSur,
+    /// macOS Ventura+ and iOS 16+: adds edited, unsent, and recently deleted messages
+    Ventura,
+}
+
+impl Display for SchemaVersion {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaVersion::Catalina => write!(fmt, "macOS Catalina, iOS 13 or older"),
+            SchemaVersion::BigSur => write!(fmt, "macOS Big Sur to Monterey, iOS 14 to iOS 15"),
+            SchemaVersion::Ventura => write!(fmt, "macOS Ventura+, iOS 16+"),
+        }
+    }
+}
+
+/// Represents the columns and tables present in an iMessage database
+#[derive(Debug, Default)]
+pub struct Schema {
+    /// The names of the columns in the `message` table
+    message_columns: HashSet<String>,
+    /// The names of the tables in the database
+    tables: HashSet<String>,
+}
+
+impl Schema {
+    /// Read the columns and tables present in a database
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use imessage_database::util::dirs::default_db_path;
+    /// use imessage_database::tables::table::get_connection;
+    /// use imessage_database::tables::schema::Schema;
+    ///
+    /// let db_path = default_db_path();
+    /// let conn = get_connection(&db_path).unwrap();
+    /// let schema = Schema::detect(&conn).unwrap();
+    /// println!("{}", schema.version());
+    /// ```
+    pub fn detect(db: &Connection) -> Result<Self, TableError> {
+        let mut statement = db
+            .prepare(&format!("SELECT name FROM pragma_table_info('{MESSAGE}')"))
+            .map_err(TableError::Messages)?;
+        let message_columns = statement
+            .query_map([], |row| row.get(0))
+            .map_err(TableError::Messages)?
+            .collect::<Result<HashSet<String>, _>>()
+            .map_err(TableError::Messages)?;
+
+        let mut statement = db
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table'")
+            .map_err(TableError::Messages)?;
+        let tables = statement
+            .query_map([], |row| row.get(0))
+            .map_err(TableError::Messages)?
+            .collect::<Result<HashSet<String>, _>>()
+            .map_err(TableError::Messages)?;
+
+        Ok(Self {
+            message_columns,
+            tables,
+        })
+    }
+
+    /// `true` if the `message` table has a column with the given name
+    pub fn has_message_column(&self, column: &str) -> bool {
+        self.message_columns.contains(column)
+    }
+
+    /// `true` if the database has a table with the given name
+    pub fn has_table(&self, table: &str) -> bool {
+        self.tables.contains(table)
+    }
+
+    /// `true` if messages can be threaded replies to other messages
+    pub fn supports_replies(&self) -> bool {
+        self.has_message_column("thread_originator_guid")
+            && self.has_message_column("thread_originator_part")
+    }
+
+    /// `true` if messages can be edited or unsent
+    pub fn supports_edits(&self) -> bool {
+        self.has_message_column("date_edited") && self.has_message_column("message_summary_info")
+    }
+
+    /// `true` if deleted messages can be recovered from the recently deleted table
+    pub fn supports_recently_deleted(&self) -> bool {
+        self.has_table(RECENTLY_DELETED)
+    }
+
+    /// `true` if messages are linked to the chats they belong to with `chat_message_join`
+    pub fn supports_chat_join(&self) -> bool {
+        self.has_table(CHAT_MESSAGE_JOIN)
+    }
+
+    /// `true` if messages can be scheduled to send later
+    pub fn supports_scheduling(&self) -> bool {
+        self.has_message_column("schedule_type")
+    }
+
+    /// `true` if messages can be reported as junk
+    pub fn supports_junk_reports(&self) -> bool {
+        self.has_message_column("is_spam")
+    }
+
+    /// `true` if the database records messages delivered while the recipient silenced notifications
+    pub fn supports_quiet_delivery(&self) -> bool {
+        self.has_message_column("was_delivered_quietly")
+    }
+
+    /// Determine the generation of schema from the features it supports
+    pub fn version(&self) -> SchemaVersion {
+        if self.supports_edits() || self.supports_recently_deleted() {
+            SchemaVersion::Ventura
+        } else if self.supports_replies() {
+            SchemaVersion::BigSur
+        } else {
+            SchemaVersion::Catalina
+        }
+    }
+
+    /// SQL that selects the chat a message was deleted from, or `NULL` if the schema cannot recover deleted messages
+    pub(crate) fn deleted_from_column(&self) -> String {
+        if self.supports_recently_deleted() {
+            format!("(SELECT b.chat_id FROM {RECENTLY_DELETED} b WHERE m.ROWID = b.message_id)")
+        } else {
+            String::from("NULL")
+        }
+    }
+
+    /// SQL that selects the number of replies to a message, or `0` if the schema does not support replies
+    pub(crate) fn num_replies_column(&self) -> String {
+        if self.supports_replies() {
+            format!("(SELECT COUNT(*) FROM {MESSAGE} m2 WHERE m2.thread_originator_guid = m.guid)")
+        } else {
+            String::from("0")
+        }
+    }
+
+    /// SQL that selects how a message was scheduled, or `0` if the schema does not support scheduling
+    pub(crate) fn schedule_type_column(&self) -> String {
+        if self.supports_scheduling() {
+            String::from("m.schedule_type")
+        } else {
+            String::from("0")
+        }
+    }
+
+    /// SQL that joins each message `m` to the chat it belongs to as `c`, or nothing if the schema has no `chat_message_join` table
+    pub(crate) fn chat_join_clause(&self) -> String {
+        if self.supports_chat_join() {
+            format!("LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id")
+        } else {
+            String::new()
+        }
+    }
+
+    /// SQL that selects the chat a message belongs to, or `NULL` if the schema has no `chat_message_join` table
+    ///
+    /// Without the join table, messages are still exported, but as orphans that do not belong to a conversation.
+    pub(crate) fn chat_id_column(&self) -> String {
+        if self.supports_chat_join() {
+            String::from("c.chat_id")
+        } else {
+            String::from("NULL")
+        }
+    }
+
+    /// SQL that selects whether a message was reported as junk, or `0` if the schema does not record junk reports
+    pub(crate) fn is_spam_column(&self) -> String {
+        if self.supports_junk_reports() {
+            String::from("m.is_spam")
+        } else {
+            String::from("0")
+        }
+    }
+
+    /// SQL that selects whether a message was delivered quietly, or `0` if the schema does not record quiet delivery
+    pub(crate) fn was_delivered_quietly_column(&self) -> String {
+        if self.supports_quiet_delivery() {
+            String::from("m.was_delivered_quietly")
+        } else {
+            String::from("0")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use crate::tables::schema::{Schema, SchemaVersion};
+
+    fn fake_db(message_columns: &str, extra_tables: &[&str]) -> Connection {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute(&format!("CREATE TABLE message ({message_columns})"), [])
+            .unwrap();
+        for table in extra_tables {
+            db.execute(&format!("CREATE TABLE {table} (message_id INTEGER)"), [])
+                .unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn can_detect_catalina() {
+        let db = fake_db("ROWID INTEGER, guid TEXT, text TEXT", &[]);
+        let schema = Schema::detect(&db).unwrap();
+
+        assert!(!schema.supports_replies());
+        assert!(!schema.supports_edits());
+        assert!(!schema.supports_recently_deleted());
+        assert_eq!(schema.version(), SchemaVersion::Catalina);
+        assert_eq!(schema.num_replies_column(), "0");
+        assert_eq!(schema.deleted_from_column(), "NULL");
+        assert!(!schema.supports_scheduling());
+        assert_eq!(schema.schedule_type_column(), "0");
+        assert!(!schema.supports_junk_reports());
+        assert_eq!(schema.is_spam_column(), "0");
+        assert!(!schema.supports_quiet_delivery());
+        assert_eq!(schema.was_delivered_quietly_column(), "0");
+        assert!(!schema.supports_chat_join());
+        assert_eq!(schema.chat_id_column(), "NULL");
+        assert_eq!(schema.chat_join_clause(), "");
+    }
+
+    #[test]
+    fn can_detect_big_sur() {
+        let db = fake_db(
+            "ROWID INTEGER, guid TEXT, thread_originator_guid TEXT, thread_originator_part TEXT",
+            &[],
+        );
+        let schema = Schema::detect(&db).unwrap();
+
+        assert!(schema.supports_replies());
+        assert!(!schema.supports_edits());
+        assert_eq!(schema.version(), SchemaVersion::BigSur);
+        assert_ne!(schema.num_replies_column(), "0");
+    }
+
+    #[test]
+    fn can_detect_ventura() {
+        let db = fake_db(
+            "ROWID INTEGER, guid TEXT, thread_originator_guid TEXT, thread_originator_part TEXT, date_edited INTEGER, message_summary_info BLOB",
+            &["chat_recoverable_message_join"],
+        );
+        let schema = Schema::detect(&db).unwrap();
+
+        assert!(schema.supports_replies());
+        assert!(schema.supports_edits());
+        assert!(schema.supports_recently_deleted());
+        assert!(schema.has_message_column("date_edited"));
+        assert!(!schema.has_message_column("is_forward"));
+        assert_eq!(schema.version(), SchemaVersion::Ventura);
+        assert_ne!(schema.deleted_from_column(), "NULL");
+    }
+
+    #[test]
+    fn can_detect_scheduling() {
+        let db = fake_db("ROWID INTEGER, guid TEXT, schedule_type INTEGER", &[]);
+        let schema = Schema::detect(&db).unwrap();
+
+        assert!(schema.supports_scheduling());
+        assert_eq!(schema.schedule_type_column(), "m.schedule_type");
+    }
+
+    #[test]
+    fn can_detect_chat_join() {
+        let db = fake_db("ROWID INTEGER, guid TEXT", &["chat_message_join"]);
+        let schema = Schema::detect(&db).unwrap();
+
+        assert!(schema.supports_chat_join());
+        assert_eq!(schema.chat_id_column(), "c.chat_id");
+        assert!(schema.chat_join_clause().contains("chat_message_join"));
+    }
+
+    #[test]
+    fn can_detect_junk_reports() {
+        let db = fake_db("ROWID INTEGER, guid TEXT, is_spam INTEGER", &[]);
+        let schema = Schema::detect(&db).unwrap();
+
+        assert!(schema.supports_junk_reports());
+        assert_eq!(schema.is_spam_column(), "m.is_spam");
+    }
+
+    #[test]
+    fn can_detect_quiet_delivery() {
+        let db = fake_db(
+            "ROWID INTEGER, guid TEXT, was_delivered_quietly INTEGER",
+            &[],
+        );
+        let schema = Schema::detect(&db).unwrap();
+
+        assert!(schema.supports_quiet_delivery());
+        assert_eq!(
+            schema.was_delivered_quietly_column(),
+            "m.was_delivered_quietly"
+        );
+    }
+}
@@ -121,6 +121,8 @@ pub const MESSAGE_PAYLOAD: &str = "payload_data";
 pub const MESSAGE_SUMMARY_INFO: &str = "message_summary_info";
 /// The attributedBody column contains a message's body text with any other attributes
 pub const ATTRIBUTED_BODY: &str = "attributedBody";
+/// The attribution info column contains the app an attachment was sent from
+pub const ATTACHMENT_ATTRIBUTION_INFO: &str = "attribution_info";
 
 // Default information
 /// Name used for messages sent by the database owner in a first-person context
@@ -137,7 +139,9 @@ pub const DEFAULT_PATH_IOS: &str = "3d/3d0d7e5fb2ce288813306e4d4636395e047a3d28"
 pub const ORPHANED: &str = "orphaned";
 /// Maximum length a filename can be
 pub const MAX_LENGTH: usize = 240;
-/// Replacement text sent in Fitness.app messages
+/// Replacement text sent in Fitness.app messages in place of the person who received the message
 pub const FITNESS_RECEIVER: &str = "$(kIMTranscriptPluginBreadcrumbTextReceiverIdentifier)";
+/// Replacement text sent in Fitness.app messages in place of the person who sent the message
+pub const FITNESS_SENDER: &str = "$(kIMTranscriptPluginBreadcrumbTextSenderIdentifier)";
 /// Name for attachments directory in exports
 pub const ATTACHMENTS_DIR: &str = "attachments";
@@ -4,7 +4,7 @@
  Most dates are stored as nanosecond-precision unix timestamps with an epoch of `1/1/2001 00:00:00` in the local time zone.
 */
 
-use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, TimeZone, Utc};
 
 use crate::error::message::MessageError;
 
@@ -125,11 +125,133 @@ pub fn readable_diff(
     Some(out_s)
 }
 
+/// Generate a coarse relative duration between two local timestamps, i.e. `3 years ago`.
+///
+/// Only the largest unit is used; months are 30 days and years are 365 days.
+///
+/// # Example:
+///
+/// ```
+/// use chrono::prelude::*;
+/// use imessage_database::util::dates::readable_relative;
+///
+/// let start = Ok(Local.with_ymd_and_hms(2020, 5, 20, 9, 10, 11).unwrap());
+/// let end = Ok(Local.with_ymd_and_hms(2023, 6, 1, 9, 15, 13).unwrap());
+/// println!("{}", readable_relative(start, end).unwrap()) // "3 years ago"
+/// ```
+pub fn readable_relative(
+    start: Result<DateTime<Local>, MessageError>,
+    end: Result<DateTime<Local>, MessageError>,
+) -> Option<String> {
+    // Calculate diff
+    let diff: Duration = end.ok()? - start.ok()?;
+    let seconds = diff.num_seconds();
+
+    // Early escape for dates in the future
+    if seconds < 0 {
+        return None;
+    }
+
+    let (amount, metric) = match seconds {
+        s if s >= 31_536_000 => (s / 31_536_000, "year"),
+        s if s >= 2_592_000 => (s / 2_592_000, "month"),
+        s if s >= 86400 => (s / 86400, "day"),
+        s if s >= 3600 => (s / 3600, "hour"),
+        s if s >= 60 => (s / 60, "minute"),
+        s => (s, "second"),
+    };
+
+    Some(match amount {
+        1 => format!("{amount} {metric} ago"),
+        _ => format!("{amount} {metric}s ago"),
+    })
+}
+
+/// Generate a coarse relative duration between a local timestamp and the current time, i.e. `3 years ago`.
+///
+/// See [`readable_relative`] for details.
+pub fn readable_relative_to_now(date: Result<DateTime<Local>, MessageError>) -> Option<String> {
+    readable_relative(date, Ok(Local::now()))
+}
+
+/// Month names accepted by [`parse_date_phrase`], in calendar order
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// Parse a human readable date phrase into the range of local dates it describes, relative to `today`.
+///
+/// The range is half-open: it includes the first date and excludes the second. Supported phrases are:
+///
+/// - `today` and `yesterday`
+/// - `last day`, `last week`, `last month`, and `last year`, which end today
+/// - `N days ago`, `N weeks ago`, `N months ago`, and `N years ago`, which cover a single day
+/// - A month and year, i.e. `january 2023`
+/// - A year, i.e. `2023`
+///
+/// # Example:
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use imessage_database::util::dates::parse_date_phrase;
+///
+/// let today = NaiveDate::from_ymd_opt(2023, 6, 15).unwrap();
+/// let (start, end) = parse_date_phrase("january 2023", today).unwrap();
+/// assert_eq!(start, NaiveDate::from_ymd_opt(2023, 1, 1).unwrap());
+/// assert_eq!(end, NaiveDate::from_ymd_opt(2023, 2, 1).unwrap());
+/// ```
+pub fn parse_date_phrase(phrase: &str, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    let phrase = phrase.trim().to_lowercase();
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+
+    match words.as_slice() {
+        ["today"] => Some((today, today.succ_opt()?)),
+        ["yesterday"] => Some((today.pred_opt()?, today)),
+        ["last", unit] => Some((subtract_units(today, 1, unit)?, today)),
+        [amount, unit, "ago"] => {
+            let date = subtract_units(today, amount.parse().ok()?, unit)?;
+            Some((date, date.succ_opt()?))
+        }
+        [month, year] => {
+            let month = MONTHS.iter().position(|name| name == month)? as u32 + 1;
+            let start = NaiveDate::from_ymd_opt(year.parse().ok()?, month, 1)?;
+            Some((start, start.checked_add_months(Months::new(1))?))
+        }
+        [year] if year.len() == 4 => {
+            let start = NaiveDate::from_ymd_opt(year.parse().ok()?, 1, 1)?;
+            Some((start, start.with_year(start.year() + 1)?))
+        }
+        _ => None,
+    }
+}
+
+/// Subtract `amount` of a calendar unit, i.e. `days` or `month`, from a date
+fn subtract_units(date: NaiveDate, amount: u32, unit: &str) -> Option<NaiveDate> {
+    match unit.strip_suffix('s').unwrap_or(unit) {
+        "day" => date.checked_sub_signed(Duration::days(amount.into())),
+        "week" => date.checked_sub_signed(Duration::weeks(amount.into())),
+        "month" => date.checked_sub_months(Months::new(amount)),
+        "year" => date.checked_sub_months(Months::new(amount.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         error::message::MessageError,
-        util::dates::{format, readable_diff},
+        util::dates::{format, parse_date_phrase, readable_diff, readable_relative},
     };
     use chrono::prelude::*;
 
@@ -256,4 +378,129 @@ mod tests {
         let end = Ok(Local.with_ymd_and_hms(2020, 5, 20, 9, 10, 11).unwrap());
         assert_eq!(readable_diff(start, end), Some("".to_owned()));
     }
+
+    #[test]
+    fn can_format_relative_years() {
+        let start = Ok(Local.with_ymd_and_hms(2020, 5, 20, 9, 10, 11).unwrap());
+        let end = Ok(Local.with_ymd_and_hms(2023, 6, 1, 9, 15, 13).unwrap());
+        assert_eq!(
+            readable_relative(start, end),
+            Some("3 years ago".to_owned())
+        );
+    }
+
+    #[test]
+    fn can_format_relative_singular() {
+        let start = Ok(Local.with_ymd_and_hms(2020, 5, 20, 9, 10, 11).unwrap());
+        let end = Ok(Local.with_ymd_and_hms(2020, 6, 20, 9, 10, 11).unwrap());
+        assert_eq!(
+            readable_relative(start, end),
+            Some("1 month ago".to_owned())
+        );
+    }
+
+    #[test]
+    fn can_format_relative_minutes() {
+        let start = Ok(Local.with_ymd_and_hms(2020, 5, 20, 9, 10, 11).unwrap());
+        let end = Ok(Local.with_ymd_and_hms(2020, 5, 20, 9, 15, 13).unwrap());
+        assert_eq!(
+            readable_relative(start, end),
+            Some("5 minutes ago".to_owned())
+        );
+    }
+
+    #[test]
+    fn can_format_relative_no_diff() {
+        let start = Ok(Local.with_ymd_and_hms(2020, 5, 20, 9, 10, 11).unwrap());
+        let end = Ok(Local.with_ymd_and_hms(2020, 5, 20, 9, 10, 11).unwrap());
+        assert_eq!(
+            readable_relative(start, end),
+            Some("0 seconds ago".to_owned())
+        );
+    }
+
+    #[test]
+    fn cant_format_relative_future() {
+        let start = Ok(Local.with_ymd_and_hms(2020, 5, 20, 9, 10, 30).unwrap());
+        let end = Ok(Local.with_ymd_and_hms(2020, 5, 20, 9, 10, 11).unwrap());
+        assert_eq!(readable_relative(start, end), None);
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn can_parse_phrase_today() {
+        let today = date(2023, 6, 15);
+        assert_eq!(
+            parse_date_phrase("today", today),
+            Some((date(2023, 6, 15), date(2023, 6, 16)))
+        );
+        assert_eq!(
+            parse_date_phrase("Yesterday", today),
+            Some((date(2023, 6, 14), date(2023, 6, 15)))
+        );
+    }
+
+    #[test]
+    fn can_parse_phrase_last() {
+        let today = date(2023, 6, 15);
+        assert_eq!(
+            parse_date_phrase("last week", today),
+            Some((date(2023, 6, 8), today))
+        );
+        assert_eq!(
+            parse_date_phrase("last month", today),
+            Some((date(2023, 5, 15), today))
+        );
+        assert_eq!(
+            parse_date_phrase("last year", today),
+            Some((date(2022, 6, 15), today))
+        );
+    }
+
+    #[test]
+    fn can_parse_phrase_ago() {
+        let today = date(2023, 6, 15);
+        assert_eq!(
+            parse_date_phrase("6 months ago", today),
+            Some((date(2022, 12, 15), date(2022, 12, 16)))
+        );
+        assert_eq!(
+            parse_date_phrase("1 day ago", today),
+            Some((date(2023, 6, 14), date(2023, 6, 15)))
+        );
+        assert_eq!(
+            parse_date_phrase("  2 Weeks  ago ", today),
+            Some((date(2023, 6, 1), date(2023, 6, 2)))
+        );
+    }
+
+    #[test]
+    fn can_parse_phrase_month_year() {
+        let today = date(2023, 6, 15);
+        assert_eq!(
+            parse_date_phrase("January 2023", today),
+            Some((date(2023, 1, 1), date(2023, 2, 1)))
+        );
+        assert_eq!(
+            parse_date_phrase("december 2022", today),
+            Some((date(2022, 12, 1), date(2023, 1, 1)))
+        );
+        assert_eq!(
+            parse_date_phrase("2022", today),
+            Some((date(2022, 1, 1), date(2023, 1, 1)))
+        );
+    }
+
+    #[test]
+    fn cant_parse_invalid_phrase() {
+        let today = date(2023, 6, 15);
+        assert_eq!(parse_date_phrase("next week", today), None);
+        assert_eq!(parse_date_phrase("6 fortnights ago", today), None);
+        assert_eq!(parse_date_phrase("smarch 2023", today), None);
+        assert_eq!(parse_date_phrase("", today), None);
+        assert_eq!(parse_date_phrase("20", today), None);
+    }
 }
@@ -2,11 +2,16 @@
  Contains functions that generate the correct path to the default iMessage database location.
 */
 
-use std::{env::var, path::PathBuf};
+use std::{
+    env::var,
+    path::{Path, PathBuf},
+};
 
 use crate::tables::table::DEFAULT_PATH_MACOS;
 
-/// Get the user's home directory (macOS only)
+/// Get the user's home directory
+///
+/// Uses `HOME` on macOS and Linux, falling back to `USERPROFILE` on Windows.
 ///
 /// # Example:
 ///
@@ -17,7 +22,9 @@ use crate::tables::table::DEFAULT_PATH_MACOS;
 /// println!("{path}");
 /// ```
 pub fn home() -> String {
-    var("HOME").unwrap_or_default()
+    var("HOME")
+        .or_else(|_| var("USERPROFILE"))
+        .unwrap_or_default()
 }
 
 /// Get the default path the macOS iMessage database is located at (macOS only)
@@ -31,5 +38,86 @@ pub fn home() -> String {
 /// println!("{path:?}");
 /// ```
 pub fn default_db_path() -> PathBuf {
-    PathBuf::from(format!("{}/{DEFAULT_PATH_MACOS}", home()))
+    join_relative(Path::new(&home()), DEFAULT_PATH_MACOS)
+}
+
+/// Get the path to a file inside the root of an unencrypted iOS backup
+///
+/// Backups store files in folders named for the first two characters of their hashed names. If the backup
+/// was copied through a case-insensitive filesystem or network share that changed the case of those names,
+/// the uppercase path is used instead. If neither path exists, the lowercase path is returned.
+///
+/// # Example:
+///
+/// ```
+/// use std::path::Path;
+/// use imessage_database::{tables::table::DEFAULT_PATH_IOS, util::dirs::ios_backup_path};
+///
+/// let path = ios_backup_path(Path::new("backup"), DEFAULT_PATH_IOS);
+/// println!("{path:?}");
+/// ```
+pub fn ios_backup_path(backup_root: &Path, relative_path: &str) -> PathBuf {
+    let path = join_relative(backup_root, relative_path);
+    if !path.exists() {
+        let uppercase = join_relative(backup_root, &relative_path.to_uppercase());
+        if uppercase.exists() {
+            return uppercase;
+        }
+    }
+    path
+}
+
+/// Join a `/`-separated relative path onto a root using the platform's path separator
+fn join_relative(root: &Path, relative_path: &str) -> PathBuf {
+    relative_path
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .fold(root.to_path_buf(), |path, component| path.join(component))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env::temp_dir,
+        fs::{create_dir_all, remove_dir_all, File},
+        path::Path,
+    };
+
+    use crate::util::dirs::{ios_backup_path, join_relative};
+
+    #[test]
+    fn can_join_relative() {
+        let expected = Path::new("root").join("3d").join("3d0d");
+        assert_eq!(join_relative(Path::new("root"), "3d/3d0d"), expected);
+    }
+
+    #[test]
+    fn can_join_relative_ignores_extra_separators() {
+        let expected = Path::new("root").join("a").join("b");
+        assert_eq!(join_relative(Path::new("root"), "/a//b/"), expected);
+    }
+
+    #[test]
+    fn can_get_missing_ios_backup_path() {
+        let root = Path::new("fake_root");
+        assert_eq!(
+            ios_backup_path(root, "3d/3d0d"),
+            root.join("3d").join("3d0d")
+        );
+    }
+
+    #[test]
+    fn can_get_uppercase_ios_backup_path() {
+        let root = temp_dir().join("imessage-database-uppercase-backup");
+        let folder = root.join("3D");
+        create_dir_all(&folder).unwrap();
+        File::create(folder.join("3D0D")).unwrap();
+
+        let path = ios_backup_path(&root, "3d/3d0d");
+        let exists = path.exists();
+        remove_dir_all(&root).unwrap();
+
+        // Case-insensitive filesystems resolve either name, so only require that the file is found
+        assert!(exists);
+    }
 }
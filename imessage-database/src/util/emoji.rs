@@ -0,0 +1,431 @@
+/*!
+Contains logic for detecting text made up only of emoji and converting emoji to shortcodes.
+*/
+
+use std::borrow::Cow;
+
+/// Joins two emoji into a single glyph, i.e. 👨‍👩‍👧
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+/// Requests the emoji presentation of the preceding character
+const VARIATION_SELECTOR_EMOJI: char = '\u{FE0F}';
+/// Turns the preceding digit, `#`, or `*` into a keycap, i.e. 1️⃣
+const COMBINING_KEYCAP: char = '\u{20E3}';
+
+/// Common emoji mapped to their shortcodes, as used by GitHub and Slack
+///
+/// Skin tone modifiers are written as their own shortcode after the emoji they modify, i.e. `:+1::skin-tone-4:`.
+const SHORTCODES: [(char, &str); 236] = [
+    ('😀', "grinning"),
+    ('😃', "smiley"),
+    ('😄', "smile"),
+    ('😁', "grin"),
+    ('😆', "laughing"),
+    ('😅', "sweat_smile"),
+    ('🤣', "rofl"),
+    ('😂', "joy"),
+    ('🙂', "slightly_smiling_face"),
+    ('🙃', "upside_down_face"),
+    ('😉', "wink"),
+    ('😊', "blush"),
+    ('😇', "innocent"),
+    ('🥰', "smiling_face_with_three_hearts"),
+    ('😍', "heart_eyes"),
+    ('🤩', "star_struck"),
+    ('😘', "kissing_heart"),
+    ('😋', "yum"),
+    ('😛', "stuck_out_tongue"),
+    ('😜', "stuck_out_tongue_winking_eye"),
+    ('🤪', "zany_face"),
+    ('🤗', "hugs"),
+    ('🤔', "thinking"),
+    ('🤐', "zipper_mouth_face"),
+    ('😐', "neutral_face"),
+    ('😑', "expressionless"),
+    ('😶', "no_mouth"),
+    ('😏', "smirk"),
+    ('😒', "unamused"),
+    ('🙄', "roll_eyes"),
+    ('😬', "grimacing"),
+    ('😌', "relieved"),
+    ('😔', "pensive"),
+    ('😪', "sleepy"),
+    ('😴', "sleeping"),
+    ('😷', "mask"),
+    ('🤒', "face_with_thermometer"),
+    ('🤢', "nauseated_face"),
+    ('🤮', "vomiting_face"),
+    ('🥵', "hot_face"),
+    ('🥶', "cold_face"),
+    ('😵', "dizzy_face"),
+    ('🤯', "exploding_head"),
+    ('🥳', "partying_face"),
+    ('😎', "sunglasses"),
+    ('🤓', "nerd_face"),
+    ('😕', "confused"),
+    ('😟', "worried"),
+    ('🙁', "slightly_frowning_face"),
+    ('😮', "open_mouth"),
+    ('😯', "hushed"),
+    ('😲', "astonished"),
+    ('😳', "flushed"),
+    ('🥺', "pleading_face"),
+    ('😦', "frowning"),
+    ('😧', "anguished"),
+    ('😨', "fearful"),
+    ('😰', "cold_sweat"),
+    ('😥', "disappointed_relieved"),
+    ('😢', "cry"),
+    ('😭', "sob"),
+    ('😱', "scream"),
+    ('😖', "confounded"),
+    ('😣', "persevere"),
+    ('😞', "disappointed"),
+    ('😓', "sweat"),
+    ('😩', "weary"),
+    ('😫', "tired_face"),
+    ('🥱', "yawning_face"),
+    ('😤', "triumph"),
+    ('😡', "rage"),
+    ('😠', "angry"),
+    ('🤬', "cursing_face"),
+    ('😈', "smiling_imp"),
+    ('💀', "skull"),
+    ('💩', "hankey"),
+    ('🤡', "clown_face"),
+    ('👻', "ghost"),
+    ('👽', "alien"),
+    ('🤖', "robot"),
+    ('😺', "smiley_cat"),
+    ('🙈', "see_no_evil"),
+    ('🙉', "hear_no_evil"),
+    ('🙊', "speak_no_evil"),
+    ('💋', "kiss"),
+    ('💯', "100"),
+    ('💢', "anger"),
+    ('💥', "boom"),
+    ('💫', "dizzy"),
+    ('💦', "sweat_drops"),
+    ('💤', "zzz"),
+    ('👋', "wave"),
+    ('🤚', "raised_back_of_hand"),
+    ('✋', "hand"),
+    ('🖖', "vulcan_salute"),
+    ('👌', "ok_hand"),
+    ('🤌', "pinched_fingers"),
+    ('🤏', "pinching_hand"),
+    ('✌', "v"),
+    ('🤞', "crossed_fingers"),
+    ('🤟', "love_you_gesture"),
+    ('🤘', "metal"),
+    ('🤙', "call_me_hand"),
+    ('👈', "point_left"),
+    ('👉', "point_right"),
+    ('👆', "point_up_2"),
+    ('👇', "point_down"),
+    ('☝', "point_up"),
+    ('👍', "+1"),
+    ('👎', "-1"),
+    ('✊', "fist_raised"),
+    ('👊', "fist_oncoming"),
+    ('👏', "clap"),
+    ('🙌', "raised_hands"),
+    ('👐', "open_hands"),
+    ('🤲', "palms_up_together"),
+    ('🤝', "handshake"),
+    ('🙏', "pray"),
+    ('💪', "muscle"),
+    ('👀', "eyes"),
+    ('🧠', "brain"),
+    ('👶', "baby"),
+    ('👦', "boy"),
+    ('👧', "girl"),
+    ('👨', "man"),
+    ('👩', "woman"),
+    ('🤷', "shrug"),
+    ('🤦', "facepalm"),
+    ('🙋', "raising_hand"),
+    ('🙇', "bow"),
+    ('💃', "dancer"),
+    ('🕺', "man_dancing"),
+    ('❤', "heart"),
+    ('🧡', "orange_heart"),
+    ('💛', "yellow_heart"),
+    ('💚', "green_heart"),
+    ('💙', "blue_heart"),
+    ('💜', "purple_heart"),
+    ('🖤', "black_heart"),
+    ('🤍', "white_heart"),
+    ('🤎', "brown_heart"),
+    ('💔', "broken_heart"),
+    ('❣', "heavy_heart_exclamation"),
+    ('💕', "two_hearts"),
+    ('💞', "revolving_hearts"),
+    ('💓', "heartbeat"),
+    ('💗', "heartpulse"),
+    ('💖', "sparkling_heart"),
+    ('💘', "cupid"),
+    ('💝', "gift_heart"),
+    ('🔥', "fire"),
+    ('✨', "sparkles"),
+    ('⭐', "star"),
+    ('🌟', "star2"),
+    ('⚡', "zap"),
+    ('☀', "sunny"),
+    ('🌈', "rainbow"),
+    ('☔', "umbrella"),
+    ('❄', "snowflake"),
+    ('🌙', "crescent_moon"),
+    ('🌹', "rose"),
+    ('🌸', "cherry_blossom"),
+    ('🌻', "sunflower"),
+    ('🍀', "four_leaf_clover"),
+    ('🎉', "tada"),
+    ('🎊', "confetti_ball"),
+    ('🎈', "balloon"),
+    ('🎁', "gift"),
+    ('🎂', "birthday"),
+    ('🎄', "christmas_tree"),
+    ('🏆', "trophy"),
+    ('⚽', "soccer"),
+    ('🏀', "basketball"),
+    ('🎵', "musical_note"),
+    ('🎶', "notes"),
+    ('☕', "coffee"),
+    ('🍺', "beer"),
+    ('🍻', "beers"),
+    ('🍷', "wine_glass"),
+    ('🥂', "clinking_glasses"),
+    ('🍕', "pizza"),
+    ('🍔', "hamburger"),
+    ('🍟', "fries"),
+    ('🌮', "taco"),
+    ('🍰', "cake"),
+    ('🍩', "doughnut"),
+    ('🍎', "apple"),
+    ('🐶', "dog"),
+    ('🐱', "cat"),
+    ('🐭', "mouse"),
+    ('🐻', "bear"),
+    ('🐼', "panda_face"),
+    ('🐸', "frog"),
+    ('🐵', "monkey_face"),
+    ('🦄', "unicorn"),
+    ('🐝', "bee"),
+    ('🦋', "butterfly"),
+    ('🐍', "snake"),
+    ('🚗', "car"),
+    ('✈', "airplane"),
+    ('🚀', "rocket"),
+    ('🏠', "house"),
+    ('📱', "iphone"),
+    ('💻', "computer"),
+    ('📷', "camera"),
+    ('💡', "bulb"),
+    ('💰', "moneybag"),
+    ('💸', "money_with_wings"),
+    ('📅', "date"),
+    ('📌', "pushpin"),
+    ('📍', "round_pushpin"),
+    ('🔒', "lock"),
+    ('🔑', "key"),
+    ('⏰', "alarm_clock"),
+    ('⌛', "hourglass"),
+    ('✅', "white_check_mark"),
+    ('✔', "heavy_check_mark"),
+    ('❌', "x"),
+    ('❓', "question"),
+    ('❗', "exclamation"),
+    ('‼', "bangbang"),
+    ('⁉', "interrobang"),
+    ('⚠', "warning"),
+    ('🚫', "no_entry_sign"),
+    ('🆗', "ok"),
+    ('🆒', "cool"),
+    ('🆕', "new"),
+    ('🆘', "sos"),
+    ('👑', "crown"),
+    ('💍', "ring"),
+    ('💎', "gem"),
+    ('🏻', "skin-tone-2"),
+    ('🏼', "skin-tone-3"),
+    ('🏽', "skin-tone-4"),
+    ('🏾', "skin-tone-5"),
+    ('🏿', "skin-tone-6"),
+];
+
+/// Characters that start an emoji on their own
+fn is_emoji_base(c: char) -> bool {
+    matches!(c,
+        '\u{1F000}'..='\u{1F0FF}' // Mahjong, domino, and playing cards
+        | '\u{1F170}'..='\u{1F1E5}' // Enclosed alphanumerics
+        | '\u{1F200}'..='\u{1F2FF}' // Enclosed ideographs
+        | '\u{1F300}'..='\u{1F5FF}' // Symbols and pictographs
+        | '\u{1F600}'..='\u{1F64F}' // Emoticons
+        | '\u{1F680}'..='\u{1F6FF}' // Transport and map symbols
+        | '\u{1F900}'..='\u{1F9FF}' // Supplemental symbols and pictographs
+        | '\u{1FA70}'..='\u{1FAFF}' // Symbols and pictographs extended
+        | '\u{2600}'..='\u{27BF}' // Miscellaneous symbols and dingbats
+        | '\u{2190}'..='\u{21FF}' // Arrows
+        | '\u{2300}'..='\u{23FF}' // Miscellaneous technical
+        | '\u{2B00}'..='\u{2BFF}' // Miscellaneous symbols and arrows
+        | '\u{3030}' | '\u{303D}' | '\u{3297}' | '\u{3299}'
+        | '\u{00A9}' | '\u{00AE}' | '\u{203C}' | '\u{2049}' | '\u{2122}' | '\u{2139}'
+    )
+}
+
+/// Characters that only form an emoji as part of a keycap sequence
+fn is_keycap_base(c: char) -> bool {
+    c.is_ascii_digit() || c == '#' || c == '*'
+}
+
+/// Characters that modify the emoji before them instead of starting a new one
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(c,
+        VARIATION_SELECTOR_EMOJI
+        | COMBINING_KEYCAP
+        | '\u{1F3FB}'..='\u{1F3FF}' // Skin tones
+        | '\u{E0020}'..='\u{E007F}' // Tags, used by subdivision flags
+    )
+}
+
+/// Count the emoji in some text, or return `None` if the text contains anything else
+///
+/// Whitespace between emoji is ignored. Sequences joined with a zero width joiner,
+/// modified with a skin tone, or paired into a flag count as a single emoji.
+///
+/// # Example:
+///
+/// ```
+/// use imessage_database::util::emoji::count_emoji;
+///
+/// assert_eq!(count_emoji("👍🏽 🇺🇸"), Some(2));
+/// assert_eq!(count_emoji("Hi 👋"), None);
+/// ```
+pub fn count_emoji(text: &str) -> Option<usize> {
+    let mut count = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        if is_keycap_base(c) {
+            // Digits are only emoji when they are part of a keycap, i.e. `1` `U+FE0F` `U+20E3`
+            chars.next_if_eq(&VARIATION_SELECTOR_EMOJI);
+            chars.next_if_eq(&COMBINING_KEYCAP)?;
+        } else if ('\u{1F1E6}'..='\u{1F1FF}').contains(&c) {
+            // Flags are made of two regional indicators
+            chars.next_if(|next| ('\u{1F1E6}'..='\u{1F1FF}').contains(next));
+        } else if !is_emoji_base(c) {
+            return None;
+        }
+
+        // Consume the rest of the sequence
+        loop {
+            if chars.next_if(|next| is_emoji_modifier(*next)).is_some() {
+                continue;
+            }
+            if chars.next_if_eq(&ZERO_WIDTH_JOINER).is_some() {
+                chars.next_if(|next| is_emoji_base(*next))?;
+                continue;
+            }
+            break;
+        }
+        count += 1;
+    }
+
+    Some(count)
+}
+
+/// Get the shortcode for an emoji, without the surrounding colons
+fn shortcode(c: char) -> Option<&'static str> {
+    if !is_emoji_base(c) {
+        return None;
+    }
+    SHORTCODES
+        .iter()
+        .find(|(emoji, _)| *emoji == c)
+        .map(|(_, code)| *code)
+}
+
+/// Replace the emoji in some text with their `:shortcode:`, borrowing the input if it has none
+///
+/// Emoji without a known shortcode are left unchanged.
+///
+/// # Example:
+///
+/// ```
+/// use imessage_database::util::emoji::replace_shortcodes;
+///
+/// assert_eq!(replace_shortcodes("Nice 👍"), "Nice :+1:");
+/// ```
+pub fn replace_shortcodes(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|c| shortcode(c).is_some()) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match shortcode(c) {
+            Some(code) => {
+                out.push(':');
+                out.push_str(code);
+                out.push(':');
+                // The emoji presentation selector has no meaning once the emoji is replaced
+                chars.next_if_eq(&VARIATION_SELECTOR_EMOJI);
+            }
+            None => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::util::emoji::{count_emoji, replace_shortcodes};
+
+    #[test]
+    fn can_count_single_emoji() {
+        assert_eq!(count_emoji("😀"), Some(1));
+    }
+
+    #[test]
+    fn can_count_multiple_emoji() {
+        assert_eq!(count_emoji("😀😂🎉"), Some(3));
+        assert_eq!(count_emoji(" ❤️ 🔥 "), Some(2));
+    }
+
+    #[test]
+    fn can_count_sequences() {
+        // Skin tone
+        assert_eq!(count_emoji("👍🏽"), Some(1));
+        // Zero width joiner
+        assert_eq!(count_emoji("👨‍👩‍👧‍👦"), Some(1));
+        // Flag
+        assert_eq!(count_emoji("🇺🇸🇨🇦"), Some(2));
+        // Keycap
+        assert_eq!(count_emoji("1️⃣#️⃣"), Some(2));
+    }
+
+    #[test]
+    fn cant_count_text() {
+        assert_eq!(count_emoji("Hello 😀"), None);
+        assert_eq!(count_emoji("123"), None);
+        assert_eq!(count_emoji(""), Some(0));
+    }
+
+    #[test]
+    fn can_replace_shortcodes() {
+        assert_eq!(replace_shortcodes("I ❤️ this 😂"), "I :heart: this :joy:");
+        assert_eq!(replace_shortcodes("👍🏽"), ":+1::skin-tone-4:");
+    }
+
+    #[test]
+    fn can_replace_shortcodes_unknown() {
+        assert_eq!(replace_shortcodes("🪿 honk"), "🪿 honk");
+        assert!(matches!(replace_shortcodes("Hello"), Cow::Borrowed(_)));
+    }
+}
@@ -4,7 +4,7 @@
 
 use std::{fmt::Display, path::Path};
 
-use crate::tables::table::DEFAULT_PATH_IOS;
+use crate::{tables::table::DEFAULT_PATH_IOS, util::dirs::ios_backup_path};
 
 /// Represents the platform that created the database this library connects to
 #[derive(PartialEq, Eq, Debug)]
@@ -20,7 +20,7 @@ pub enum Platform {
 impl Platform {
     /// Try to determine the current platform, defaulting to macOS.
     pub fn determine(db_path: &Path) -> Self {
-        if db_path.join(DEFAULT_PATH_IOS).exists() {
+        if ios_backup_path(db_path, DEFAULT_PATH_IOS).exists() {
             return Self::iOS;
         } else if db_path.is_file() {
             return Self::macOS;
@@ -5,7 +5,7 @@ use chrono::prelude::*;
 
 use crate::{
     error::query_context::QueryContextError,
-    util::dates::{get_offset, TIMESTAMP_FACTOR},
+    util::dates::{format, get_local_time, get_offset, parse_date_phrase, TIMESTAMP_FACTOR},
 };
 
 #[derive(Debug, Default, PartialEq, Eq)]
@@ -19,6 +19,10 @@ pub struct QueryContext {
 
 impl QueryContext {
     /// Generate a `QueryContext` with a start date
+    ///
+    /// The date can be in `YYYY-MM-DD` format or a phrase supported by [`parse_date_phrase`], in which case
+    /// the start of the range it describes is used.
+    ///
     /// # Example:
     ///
     /// ```
@@ -29,12 +33,20 @@ impl QueryContext {
     /// ```
     pub fn set_start(&mut self, start: &str) -> Result<(), QueryContextError> {
         let timestamp = QueryContext::sanitize_date(start)
+            .or_else(|| {
+                let (start, _) = parse_date_phrase(start, Local::now().date_naive())?;
+                QueryContext::date_timestamp(start)
+            })
             .ok_or(QueryContextError::InvalidDate(start.to_string()))?;
         self.start = Some(timestamp);
         Ok(())
     }
 
     /// Generate a `QueryContext` with an end date
+    ///
+    /// The date can be in `YYYY-MM-DD` format or a phrase supported by [`parse_date_phrase`], in which case
+    /// the end of the range it describes is used.
+    ///
     /// # Example:
     ///
     /// ```
@@ -45,6 +57,10 @@ impl QueryContext {
     /// ```
     pub fn set_end(&mut self, end: &str) -> Result<(), QueryContextError> {
         let timestamp = QueryContext::sanitize_date(end)
+            .or_else(|| {
+                let (_, end) = parse_date_phrase(end, Local::now().date_naive())?;
+                QueryContext::date_timestamp(end)
+            })
             .ok_or(QueryContextError::InvalidDate(end.to_string()))?;
         self.end = Some(timestamp);
         Ok(())
@@ -76,12 +92,47 @@ impl QueryContext {
             return None;
         }
 
-        let local = Local.with_ymd_and_hms(year, month, day, 0, 0, 0).single()?;
+        QueryContext::date_timestamp(NaiveDate::from_ymd_opt(year, month, day)?)
+    }
+
+    /// Convert the start of a local date to a timestamp with the iMessage epoch
+    fn date_timestamp(date: NaiveDate) -> Option<i64> {
+        let local = Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .single()?;
         let stamp = local.timestamp_nanos_opt().unwrap_or(0);
 
         Some(stamp - (get_offset() * TIMESTAMP_FACTOR))
     }
 
+    /// Describe the range of dates this `QueryContext` filters to, if any
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use imessage_database::util::query_context::QueryContext;
+    ///
+    /// let mut context = QueryContext::default();
+    /// context.set_start("2023-01-01");
+    /// println!("{}", context.readable_range().unwrap()); // "Jan 01, 2023 12:00:00 AM to now"
+    /// ```
+    pub fn readable_range(&self) -> Option<String> {
+        if !self.has_filters() {
+            return None;
+        }
+
+        let offset = get_offset();
+        let describe = |bound: Option<i64>, default: &str| match bound {
+            Some(stamp) => format(&get_local_time(&stamp, &offset)),
+            None => default.to_string(),
+        };
+        Some(format!(
+            "{} to {}",
+            describe(self.start, "the beginning"),
+            describe(self.end, "now")
+        ))
+    }
+
     /// Determine if the current `QueryContext` has any filters present
     ///
     /// # Example:
@@ -241,6 +292,51 @@ mod use_tests {
         assert_eq!(context.generate_filter_statement("m.date"), "");
     }
 
+    #[test]
+    fn can_create_phrase() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let mut context = QueryContext::default();
+        context.set_start("january 2020").unwrap();
+        context.set_end("january 2020").unwrap();
+
+        assert_eq!(
+            context.readable_range().unwrap(),
+            "Jan 01, 2020 12:00:00 AM to Feb 01, 2020 12:00:00 AM"
+        );
+    }
+
+    #[test]
+    fn can_describe_open_range() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let mut context = QueryContext::default();
+        assert!(context.readable_range().is_none());
+
+        context.set_start("2020-01-01").unwrap();
+        assert_eq!(
+            context.readable_range().unwrap(),
+            "Jan 01, 2020 12:00:00 AM to now"
+        );
+
+        let mut context = QueryContext::default();
+        context.set_end("2020-01-01").unwrap();
+        assert_eq!(
+            context.readable_range().unwrap(),
+            "the beginning to Jan 01, 2020 12:00:00 AM"
+        );
+    }
+
+    #[test]
+    fn can_create_invalid_phrase() {
+        let mut context = QueryContext::default();
+        assert!(context.set_start("next tuesday").is_err());
+        assert!(context.set_end("next tuesday").is_err());
+        assert!(!context.has_filters());
+    }
+
     #[test]
     fn can_create_invalid_end() {
         let mut context = QueryContext::default();
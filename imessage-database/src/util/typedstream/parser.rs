@@ -54,6 +54,8 @@ pub struct TypedStreamReader<'a> {
 impl<'a> TypedStreamReader<'a> {
     /// Given a stream, construct a reader instance to parse it.
     ///
+    /// This is the only constructor; the [`From`] implementation delegates to it.
+    ///
     /// # Example:
     ///
     /// ```
@@ -72,6 +74,17 @@ impl<'a> TypedStreamReader<'a> {
         }
     }
 
+    /// Clear the parser state so the stream can be read again from the beginning
+    ///
+    /// The [`Type`] and [`Archivable`] tables are built while parsing and referenced by index,
+    /// so entries left over from a previous parse would resolve references to the wrong data.
+    fn reset(&mut self) {
+        self.idx = 0;
+        self.types_table.clear();
+        self.object_table.clear();
+        self.placeholder = None;
+    }
+
     /// Read a signed integer from the stream. Because we don't know the size of the integer ahead of time,
     /// we store it in the largest possible value.
     fn read_signed_int(&mut self) -> Result<i64, TypedStreamError> {
@@ -183,15 +196,15 @@ impl<'a> TypedStreamReader<'a> {
     }
 
     /// Read exactly `n` bytes from the stream
+    ///
+    /// Lengths are read from the stream, so a corrupt length that runs past the end of the stream is an error rather than an overflow.
     fn read_exact_bytes(&mut self, n: usize) -> Result<&[u8], TypedStreamError> {
-        let range =
-            self.stream
-                .get(self.idx..self.idx + n)
-                .ok_or(TypedStreamError::OutOfBounds(
-                    self.idx + n,
-                    self.stream.len(),
-                ))?;
-        self.idx += n;
+        let end = self.idx.saturating_add(n);
+        let range = self
+            .stream
+            .get(self.idx..end)
+            .ok_or(TypedStreamError::OutOfBounds(end, self.stream.len()))?;
+        self.idx = end;
         Ok(range)
     }
 
@@ -233,8 +246,10 @@ impl<'a> TypedStreamReader<'a> {
     /// Determine the current types
     fn read_type(&mut self) -> Result<Vec<Type>, TypedStreamError> {
         let length = self.read_unsigned_int()?;
+        let length = usize::try_from(length)
+            .map_err(|_| TypedStreamError::OutOfBounds(usize::MAX, self.stream.len()))?;
 
-        let types = self.read_exact_bytes(length as usize)?;
+        let types = self.read_exact_bytes(length)?;
 
         // Handle array size
         if types.first() == Some(&0x5b) {
@@ -502,7 +517,10 @@ impl<'a> TypedStreamReader<'a> {
     /// Object(Class { name: "NSString", version: 1 }, [String("__kIMMessagePartAttributeName")])  // The first key in the `NSDictionary`
     /// Object(Class { name: "NSNumber", version: 0 }, [Integer(0)])  // The first value in the `NSDictionary`
     /// ```
+    ///
+    /// Each call parses the whole stream from the beginning, so calling this more than once yields the same data.
     pub fn parse(&mut self) -> Result<Vec<Archivable>, TypedStreamError> {
+        self.reset();
         let mut out_v = vec![];
 
         self.validate_header()?;
@@ -513,10 +531,8 @@ impl<'a> TypedStreamReader<'a> {
                 continue;
             }
 
-
             // First, get the current type
             if let Some(found_types) = self.get_type(false)? {
-
                 let result = self.read_types(found_types);
                 if let Ok(Some(res)) = result {
                     out_v.push(res);
@@ -527,3 +543,9 @@ impl<'a> TypedStreamReader<'a> {
         Ok(out_v)
     }
 }
+
+impl<'a> From<&'a [u8]> for TypedStreamReader<'a> {
+    fn from(stream: &'a [u8]) -> Self {
+        TypedStreamReader::from(stream)
+    }
+}
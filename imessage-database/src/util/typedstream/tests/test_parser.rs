@@ -5,9 +5,12 @@ mod parser_tests {
     use std::io::Read;
     use std::vec;
 
-    use crate::util::typedstream::{
-        models::{Archivable, Class, OutputData},
-        parser::TypedStreamReader,
+    use crate::{
+        error::typedstream::TypedStreamError,
+        util::typedstream::{
+            models::{Archivable, Class, OutputData},
+            parser::TypedStreamReader,
+        },
     };
 
     #[test]
@@ -26,6 +29,52 @@ mod parser_tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_parse_twice() {
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/Mention");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        let first = parser.parse().unwrap();
+        let second = parser.parse().unwrap();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_parse_type_length_out_of_bounds() {
+        // Valid header, then a type whose length runs past the end of the stream
+        let mut bytes = vec![0x04, 0x0b];
+        bytes.extend_from_slice(b"streamtyped");
+        bytes.extend_from_slice(&[0x81, 0xe8, 0x03, 0x84, 0x82, 0xff, 0xff, 0xff, 0xff]);
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        let result = parser.parse();
+
+        assert!(matches!(result, Err(TypedStreamError::OutOfBounds(_, 22))));
+    }
+
+    #[test]
+    fn test_parse_from_trait() {
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/Mention");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser: TypedStreamReader = bytes.as_slice().into();
+        let expected = TypedStreamReader::from(&bytes).parse().unwrap();
+
+        assert_eq!(parser.parse().unwrap(), expected);
+    }
+
     #[test]
     fn test_parse_text_mention() {
         let typedstream_path = current_dir()
@@ -0,0 +1,70 @@
+/*!
+ Reads the notes passed with `--annotations` so they can be rendered beneath the messages they describe.
+*/
+
+use std::{collections::HashMap, fs::read_to_string, path::Path};
+
+use crate::app::error::RuntimeError;
+
+/// Read the annotations file at `path` into a map of message GUID to note
+pub fn read_annotations(path: &Path) -> Result<HashMap<String, String>, RuntimeError> {
+    read_to_string(path)
+        .map(|contents| parse_annotations(&contents))
+        .map_err(RuntimeError::DiskError)
+}
+
+/// Parse annotations, one per line, as a message GUID followed by whitespace and the note
+///
+/// Blank lines, lines starting with `#`, and lines without a note are skipped. If a GUID is listed more
+/// than once, its notes are joined with a space.
+pub fn parse_annotations(contents: &str) -> HashMap<String, String> {
+    let mut annotations: HashMap<String, String> = HashMap::new();
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .map(|(guid, note)| (guid, note.trim()))
+        .filter(|(_, note)| !note.is_empty())
+        .for_each(|(guid, note)| {
+            annotations
+                .entry(guid.to_string())
+                .and_modify(|existing| {
+                    existing.push(' ');
+                    existing.push_str(note);
+                })
+                .or_insert_with(|| note.to_string());
+        });
+    annotations
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::app::annotations::parse_annotations;
+
+    #[test]
+    fn can_parse_annotations() {
+        let contents = "\
+# Evidence for case 42
+ABCD-1234 First mention of the meeting
+
+EFGH-5678\tConfirms the time
+ABCD-1234   Sent from the office
+";
+        let expected = HashMap::from([
+            (
+                "ABCD-1234".to_string(),
+                "First mention of the meeting Sent from the office".to_string(),
+            ),
+            ("EFGH-5678".to_string(), "Confirms the time".to_string()),
+        ]);
+        assert_eq!(parse_annotations(contents), expected);
+    }
+
+    #[test]
+    fn can_skip_annotations_without_notes() {
+        assert!(parse_annotations("ABCD-1234\nEFGH-5678   \n").is_empty());
+    }
+}
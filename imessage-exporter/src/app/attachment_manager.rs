@@ -1,7 +1,10 @@
 use std::{
     fmt::Display,
     fs::{copy, create_dir_all, metadata},
+    io::{Error, ErrorKind},
     path::{Path, PathBuf},
+    thread::sleep,
+    time::Duration,
 };
 
 use filetime::{set_file_times, FileTime};
@@ -13,17 +16,34 @@ use uuid::Uuid;
 
 use crate::app::{
     converter::{convert_heic, Converter, ImageType},
+    exif::ImageMetadata,
     runtime::Config,
 };
 
+/// Text rendered in place of an attachment when attachments are skipped with `--no-attachments`
+pub const ATTACHMENT_PLACEHOLDER: &str = "[attachment]";
+/// Default number of times to retry copying an attachment after a transient failure
+pub const DEFAULT_COPY_RETRIES: u32 = 3;
+/// Delay before the first retry of a failed copy, doubled after each subsequent attempt
+const RETRY_BASE_DELAY_MS: u64 = 100;
+
 /// Represents different ways the app can interact with attachment data
 #[derive(Debug, PartialEq, Eq)]
 pub enum AttachmentManager {
-    /// Do not copy attachments
+    /// Do not copy attachments; exports reference files in their original location
+    ///
+    /// Also accepted as `none` on the command line
     Disabled,
     /// Copy and convert attachments to more compatible formats using a [`Converter`]
+    ///
+    /// Also accepted as `convert` on the command line
     Compatible,
     /// Copy attachments without converting; preserves quality but may not display correctly in all browsers
+    ///
+    /// On APFS volumes, [`copy`] creates a clone that shares storage with the original until either is modified,
+    /// falling back to a normal copy on other file systems or across volumes.
+    ///
+    /// Also accepted as `clone`, `clone-on-write`, or `cow` on the command line
     Efficient,
 }
 
@@ -31,9 +51,9 @@ impl AttachmentManager {
     /// Create an instance of the enum given user input
     pub fn from_cli(copy_state: &str) -> Option<Self> {
         match copy_state.to_lowercase().as_str() {
-            "compatible" => Some(Self::Compatible),
-            "efficient" => Some(Self::Efficient),
-            "disabled" => Some(Self::Disabled),
+            "compatible" | "convert" => Some(Self::Compatible),
+            "efficient" | "clone" | "clone-on-write" | "cow" => Some(Self::Efficient),
+            "disabled" | "none" => Some(Self::Disabled),
             _ => None,
         }
     }
@@ -48,11 +68,7 @@ impl AttachmentManager {
         config: &Config,
     ) -> Option<()> {
         // Resolve the path to the attachment
-        let attachment_path = attachment.resolved_attachment_path(
-            &config.options.platform,
-            &config.options.db_path,
-            config.options.attachment_root.as_deref(),
-        )?;
+        let attachment_path = config.resolved_attachment_path(attachment)?;
 
         if !matches!(self, AttachmentManager::Disabled) {
             let from = Path::new(&attachment_path);
@@ -76,6 +92,7 @@ impl AttachmentManager {
             // Set the new file's extension to the original one
             to.set_extension(attachment.extension()?);
 
+            let retries = config.options.copy_retries;
             match self {
                 AttachmentManager::Compatible => match &config.converter {
                     Some(converter) => {
@@ -84,12 +101,13 @@ impl AttachmentManager {
                             &mut to,
                             converter,
                             attachment.is_sticker,
-                            attachment.mime_type(),
+                            config.media_type(attachment),
+                            retries,
                         );
                     }
-                    None => Self::copy_raw(from, &to),
+                    None => Self::copy_raw(from, &to, retries),
                 },
-                AttachmentManager::Efficient => Self::copy_raw(from, &to),
+                AttachmentManager::Efficient => Self::copy_raw(from, &to, retries),
                 AttachmentManager::Disabled => unreachable!(),
             };
 
@@ -108,13 +126,24 @@ impl AttachmentManager {
                     eprintln!("Unable to update {to:?} metadata: {why}");
                 }
             }
+
+            // Write the image's metadata next to the copy
+            if config.options.exif_sidecars
+                && matches!(config.media_type(attachment), MediaType::Image(_))
+            {
+                if let Some(image_metadata) = ImageMetadata::from_path(&to) {
+                    if let Err(why) = image_metadata.write_sidecar(&to) {
+                        eprintln!("Unable to write {to:?} metadata sidecar: {why}");
+                    }
+                }
+            }
             attachment.copied_path = Some(to);
         }
         Some(())
     }
 
     /// Copy a file without altering it
-    fn copy_raw(from: &Path, to: &Path) {
+    fn copy_raw(from: &Path, to: &Path, retries: u32) {
         // Ensure the directory tree exists
         if let Some(folder) = to.parent() {
             if !folder.exists() {
@@ -123,11 +152,51 @@ impl AttachmentManager {
                 }
             }
         }
-        if let Err(why) = copy(from, to) {
+        if let Err(why) = Self::copy_with_retry(from, to, retries) {
             eprintln!("Unable to copy {from:?} to {to:?}: {why}");
         };
     }
 
+    /// Copy a file, retrying transient failures with exponential backoff
+    ///
+    /// Permanent failures, i.e. a missing source file, are returned immediately.
+    fn copy_with_retry(from: &Path, to: &Path, retries: u32) -> Result<u64, Error> {
+        let mut attempt = 0;
+        loop {
+            match copy(from, to) {
+                Ok(bytes) => return Ok(bytes),
+                Err(why) if attempt < retries && Self::is_transient(&why) => {
+                    let delay = Duration::from_millis(
+                        RETRY_BASE_DELAY_MS.saturating_mul(2_u64.saturating_pow(attempt)),
+                    );
+                    attempt += 1;
+                    eprintln!(
+                        "Unable to copy {from:?} to {to:?}: {why}; retrying in {}ms ({attempt}/{retries})",
+                        delay.as_millis()
+                    );
+                    sleep(delay);
+                }
+                Err(why) => return Err(why),
+            }
+        }
+    }
+
+    /// Determine if an IO error may succeed if the operation is retried
+    ///
+    /// Only errors caused by a slow or interrupted connection are retried; anything else, i.e. a full disk, fails fast.
+    fn is_transient(error: &Error) -> bool {
+        matches!(
+            error.kind(),
+            ErrorKind::Interrupted
+                | ErrorKind::TimedOut
+                | ErrorKind::WouldBlock
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe
+                | ErrorKind::UnexpectedEof
+        )
+    }
+
     /// Copy a file, converting if possible
     ///
     /// - Sticker `HEIC` files convert to `PNG`
@@ -140,6 +209,7 @@ impl AttachmentManager {
         converter: &Converter,
         is_sticker: bool,
         mime_type: MediaType,
+        retries: u32,
     ) {
         // Handle sticker attachments
         if is_sticker {
@@ -147,7 +217,9 @@ impl AttachmentManager {
             let output_type: Option<ImageType> = match mime_type {
                 // Normal stickers get converted to png
                 MediaType::Image("heic") | MediaType::Image("HEIC") => Some(ImageType::Png),
-                MediaType::Image("heics") | MediaType::Image("HEICS") => Some(ImageType::Gif),
+                MediaType::AnimatedImage("heics") | MediaType::AnimatedImage("HEICS") => {
+                    Some(ImageType::Gif)
+                }
                 _ => None,
             };
 
@@ -158,7 +230,7 @@ impl AttachmentManager {
                         eprintln!("Unable to convert {from:?}");
                     }
                 }
-                None => Self::copy_raw(from, to),
+                None => Self::copy_raw(from, to, retries),
             }
         }
         // Normal attachments always get converted to jpeg
@@ -173,7 +245,7 @@ impl AttachmentManager {
                 eprintln!("Unable to convert {from:?}");
             }
         } else {
-            Self::copy_raw(from, to);
+            Self::copy_raw(from, to, retries);
         }
     }
 }
@@ -193,3 +265,121 @@ impl Display for AttachmentManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{read, remove_file, write},
+        io::{Error, ErrorKind},
+        path::PathBuf,
+    };
+
+    use crate::app::attachment_manager::AttachmentManager;
+
+    #[test]
+    fn can_parse_disabled_any_case() {
+        assert_eq!(
+            AttachmentManager::from_cli("disabled"),
+            Some(AttachmentManager::Disabled)
+        );
+        assert_eq!(
+            AttachmentManager::from_cli("DiSaBlEd"),
+            Some(AttachmentManager::Disabled)
+        );
+    }
+
+    #[test]
+    fn can_parse_none_alias() {
+        assert_eq!(
+            AttachmentManager::from_cli("none"),
+            Some(AttachmentManager::Disabled)
+        );
+    }
+
+    #[test]
+    fn can_parse_clone_alias() {
+        assert_eq!(
+            AttachmentManager::from_cli("clone"),
+            Some(AttachmentManager::Efficient)
+        );
+    }
+
+    #[test]
+    fn can_parse_convert_alias() {
+        assert_eq!(
+            AttachmentManager::from_cli("convert"),
+            Some(AttachmentManager::Compatible)
+        );
+    }
+
+    #[test]
+    fn cant_parse_invalid() {
+        assert_eq!(AttachmentManager::from_cli("hardlink"), None);
+    }
+
+    #[test]
+    fn can_detect_transient_errors() {
+        assert!(AttachmentManager::is_transient(&Error::from(
+            ErrorKind::TimedOut
+        )));
+        assert!(AttachmentManager::is_transient(&Error::from(
+            ErrorKind::Interrupted
+        )));
+        assert!(AttachmentManager::is_transient(&Error::from(
+            ErrorKind::ConnectionReset
+        )));
+    }
+
+    #[test]
+    fn can_detect_permanent_errors() {
+        assert!(!AttachmentManager::is_transient(&Error::from(
+            ErrorKind::NotFound
+        )));
+        assert!(!AttachmentManager::is_transient(&Error::from(
+            ErrorKind::PermissionDenied
+        )));
+        assert!(!AttachmentManager::is_transient(&Error::from(
+            ErrorKind::StorageFull
+        )));
+        assert!(!AttachmentManager::is_transient(&Error::from(
+            ErrorKind::ReadOnlyFilesystem
+        )));
+        assert!(!AttachmentManager::is_transient(&Error::other("EIO")));
+    }
+
+    #[test]
+    fn can_parse_clone_on_write_alias() {
+        assert_eq!(
+            AttachmentManager::from_cli("clone-on-write"),
+            Some(AttachmentManager::Efficient)
+        );
+        assert_eq!(
+            AttachmentManager::from_cli("COW"),
+            Some(AttachmentManager::Efficient)
+        );
+    }
+
+    #[test]
+    fn can_copy_with_retry() {
+        let from = PathBuf::from("/tmp/imessage-exporter-retry-from.txt");
+        let to = PathBuf::from("/tmp/imessage-exporter-retry-to.txt");
+        write(&from, "attachment").unwrap();
+
+        let copied = AttachmentManager::copy_with_retry(&from, &to, 3);
+        assert_eq!(copied.unwrap(), 10);
+        assert_eq!(read(&to).unwrap(), b"attachment");
+
+        remove_file(&from).unwrap();
+        remove_file(&to).unwrap();
+    }
+
+    #[test]
+    fn cant_copy_with_retry_missing() {
+        let from = PathBuf::from("/tmp/imessage-exporter-retry-missing.txt");
+        let to = PathBuf::from("/tmp/imessage-exporter-retry-missing-to.txt");
+
+        // Missing files fail on the first attempt instead of retrying
+        let copied = AttachmentManager::copy_with_retry(&from, &to, u32::MAX);
+        assert_eq!(copied.unwrap_err().kind(), ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,18 @@
+/*!
+ Describes the conversations in a database so a caller can choose what to export before exporting anything.
+*/
+
+/// Summary of a single conversation, built from every chat that dedupes to the same conversation
+#[derive(Debug, PartialEq, Eq)]
+pub struct Conversation {
+    /// The deduplicated chat ID shared by every chat in the conversation
+    pub id: i32,
+    /// The conversation's display name, falling back to its participants if it does not have one
+    pub display_name: String,
+    /// The handles of the conversation's participants, i.e. phone numbers or email addresses
+    pub participants: Vec<String>,
+    /// The number of messages across every chat in the conversation
+    pub message_count: u64,
+    /// The date of the conversation's most recent message, if it has any messages
+    pub last_activity: Option<i64>,
+}
@@ -0,0 +1,200 @@
+/*!
+ Copies the database to a temporary location before exporting so `--copy-db-first` never reads a file Messages is writing to.
+*/
+
+use std::{
+    env::temp_dir,
+    ffi::OsString,
+    fs::{copy, create_dir_all, metadata, remove_dir_all, remove_file},
+    iter::once,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use rusqlite::Connection;
+use uuid::Uuid;
+
+use imessage_database::error::table::TableError;
+
+use crate::app::error::RuntimeError;
+
+/// Suffixes of the files SQLite keeps beside a database in write-ahead log mode
+const WAL_SUFFIXES: [&str; 2] = ["-wal", "-shm"];
+
+/// How many times to copy the database files before giving up on getting a copy taken while they were not changing
+const MAX_COPY_ATTEMPTS: usize = 3;
+
+/// A copy of the database in a temporary directory, which is removed when this is dropped
+#[derive(Debug)]
+pub struct DatabaseCopy {
+    /// The temporary directory holding the copy
+    dir: PathBuf,
+    /// The path to the copied database
+    path: PathBuf,
+}
+
+impl DatabaseCopy {
+    /// Copy the database at `source`, along with its `-wal` and `-shm` files, to a new temporary directory
+    ///
+    /// The write-ahead log is checkpointed into the copy, so the copy is a single self-contained file that reflects every transaction committed when it was taken.
+    pub fn new(source: &Path) -> Result<Self, RuntimeError> {
+        let dir = temp_dir().join(format!("imessage-exporter-{}", Uuid::new_v4()));
+        create_dir_all(&dir).map_err(|err| RuntimeError::CreateError(err, dir.clone()))?;
+
+        // Own the directory right away so it is removed if copying fails
+        let db_copy = DatabaseCopy {
+            path: dir.join(source.file_name().unwrap_or(source.as_os_str())),
+            dir,
+        };
+
+        // Messages may commit while the files are being copied, so retry until they are unchanged from start to finish
+        for attempt in 1..=MAX_COPY_ATTEMPTS {
+            let before = file_states(source);
+            db_copy.copy_files(source)?;
+            if file_states(source) == before {
+                break;
+            }
+            if attempt == MAX_COPY_ATTEMPTS {
+                eprintln!("Database changed while it was being copied, the copy may not include the newest messages");
+            }
+        }
+
+        db_copy.checkpoint()?;
+        Ok(db_copy)
+    }
+
+    /// The path to the copied database
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Copy the database file, then its write-ahead log files if they exist
+    fn copy_files(&self, source: &Path) -> Result<(), RuntimeError> {
+        copy(source, &self.path)
+            .map_err(|err| RuntimeError::CreateError(err, self.path.clone()))?;
+        for suffix in WAL_SUFFIXES {
+            let from = with_suffix(source, suffix);
+            let to = with_suffix(&self.path, suffix);
+            if from.exists() {
+                copy(&from, &to).map_err(|err| RuntimeError::CreateError(err, to))?;
+            } else if to.exists() {
+                // A previous attempt copied a log that has since been removed
+                let _ = remove_file(&to);
+            }
+        }
+        Ok(())
+    }
+
+    /// Move every transaction in the copied write-ahead log into the copied database, then switch it to rollback journal mode
+    ///
+    /// Only the copy is written to; the source database is never modified.
+    fn checkpoint(&self) -> Result<(), RuntimeError> {
+        let copy_error = |why: String| {
+            RuntimeError::DatabaseError(TableError::CannotConnect(format!(
+                "Unable to checkpoint database copy at {}: {why}",
+                self.path.display()
+            )))
+        };
+
+        let conn = Connection::open(&self.path).map_err(|err| copy_error(err.to_string()))?;
+        let busy: i32 = conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE);", [], |row| row.get(0))
+            .map_err(|err| copy_error(err.to_string()))?;
+        if busy != 0 {
+            return Err(copy_error(String::from("the database is busy")));
+        }
+
+        // Without a log to read, the copy can be opened read-only like any other database file
+        conn.query_row("PRAGMA journal_mode = DELETE;", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .map_err(|err| copy_error(err.to_string()))?;
+        Ok(())
+    }
+}
+
+impl Drop for DatabaseCopy {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(&self.dir);
+    }
+}
+
+/// Append a suffix like `-wal` to the file name of `path`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// The size and modification time of the database and its write-ahead log files, used to detect writes during a copy
+fn file_states(source: &Path) -> Vec<Option<(u64, SystemTime)>> {
+    once(source.to_path_buf())
+        .chain(
+            WAL_SUFFIXES
+                .iter()
+                .map(|suffix| with_suffix(source, suffix)),
+        )
+        .map(|path| {
+            metadata(path)
+                .ok()
+                .and_then(|meta| Some((meta.len(), meta.modified().ok()?)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env::temp_dir,
+        fs::{create_dir_all, remove_dir_all},
+        path::Path,
+    };
+
+    use rusqlite::Connection;
+
+    use crate::app::db_copy::{with_suffix, DatabaseCopy};
+
+    #[test]
+    fn can_append_suffix() {
+        assert_eq!(
+            with_suffix(Path::new("/a/chat.db"), "-wal"),
+            Path::new("/a/chat.db-wal")
+        );
+    }
+
+    #[test]
+    fn can_copy_wal_database() {
+        let dir = temp_dir().join("imessage-exporter-db-copy-test");
+        let _ = remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+        let source = dir.join("chat.db");
+
+        // Keep the writer open so the committed row is only in the write-ahead log
+        let writer = Connection::open(&source).unwrap();
+        writer
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 PRAGMA wal_autocheckpoint = 0;
+                 CREATE TABLE message (ROWID INTEGER PRIMARY KEY, text TEXT);
+                 INSERT INTO message (text) VALUES ('Hello world');",
+            )
+            .unwrap();
+        assert!(with_suffix(&source, "-wal").exists());
+
+        let db_copy = DatabaseCopy::new(&source).unwrap();
+        let copy_dir = db_copy.path().parent().unwrap().to_path_buf();
+        assert!(!with_suffix(db_copy.path(), "-wal").exists());
+
+        let text: String = Connection::open(db_copy.path())
+            .unwrap()
+            .query_row("SELECT text FROM message", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(text, "Hello world");
+
+        drop(db_copy);
+        assert!(!copy_dir.exists());
+
+        drop(writer);
+        remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+/*!
+ Collects non-fatal problems encountered during an export so they can be summarized when the export finishes.
+*/
+
+use std::{cell::RefCell, collections::BTreeMap, fmt::Display, fs::File, io::Write, path::Path};
+
+use crate::app::{error::RuntimeError, sanitizers::sanitize_json};
+
+/// Name of the file the report is written to in the export directory
+pub const ERROR_REPORT_FILENAME: &str = "errors.json";
+
+/// Categories of non-fatal problems that can occur while exporting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueKind {
+    /// A message references an attachment that does not exist in the attachments table
+    MissingAttachment,
+    /// An attachment exists in the table, but the file could not be located or copied
+    UnresolvedAttachment,
+    /// An app message's payload could not be parsed
+    UnparseablePayload,
+}
+
+impl Display for IssueKind {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IssueKind::MissingAttachment => write!(fmt, "missing_attachment"),
+            IssueKind::UnresolvedAttachment => write!(fmt, "unresolved_attachment"),
+            IssueKind::UnparseablePayload => write!(fmt, "unparseable_payload"),
+        }
+    }
+}
+
+/// A single non-fatal problem encountered while exporting a message
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExportIssue {
+    /// The type of problem
+    pub kind: IssueKind,
+    /// The GUID of the affected message
+    pub guid: String,
+    /// Additional context about the problem
+    pub detail: String,
+}
+
+/// Accumulates [`ExportIssue`]s during an export
+#[derive(Debug, Default)]
+pub struct ErrorReport {
+    issues: RefCell<Vec<ExportIssue>>,
+}
+
+impl ErrorReport {
+    /// Record a problem encountered while exporting a message
+    pub fn record(&self, kind: IssueKind, guid: &str, detail: &str) {
+        self.issues.borrow_mut().push(ExportIssue {
+            kind,
+            guid: guid.to_string(),
+            detail: detail.to_string(),
+        });
+    }
+
+    /// Get the number of problems recorded
+    pub fn len(&self) -> usize {
+        self.issues.borrow().len()
+    }
+
+    /// `true` if no problems were recorded, else `false`
+    pub fn is_empty(&self) -> bool {
+        self.issues.borrow().is_empty()
+    }
+
+    /// Count the recorded problems by kind
+    pub fn counts(&self) -> BTreeMap<IssueKind, usize> {
+        let mut counts = BTreeMap::new();
+        self.issues
+            .borrow()
+            .iter()
+            .for_each(|issue| *counts.entry(issue.kind).or_insert(0) += 1);
+        counts
+    }
+
+    /// Serialize the report as a JSON document
+    pub fn to_json(&self) -> String {
+        let counts = self
+            .counts()
+            .iter()
+            .map(|(kind, count)| format!("\"{kind}\": {count}"))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let issues = self
+            .issues
+            .borrow()
+            .iter()
+            .map(|issue| {
+                format!(
+                    "    {{\"kind\": \"{}\", \"guid\": \"{}\", \"detail\": \"{}\"}}",
+                    issue.kind,
+                    sanitize_json(&issue.guid),
+                    sanitize_json(&issue.detail)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",\n");
+
+        format!(
+            "{{\n  \"total\": {},\n  \"counts\": {{{counts}}},\n  \"issues\": [\n{issues}\n  ]\n}}\n",
+            self.len()
+        )
+    }
+
+    /// Write the report to the export directory, if any problems were recorded
+    pub fn write(&self, export_path: &Path) -> Result<(), RuntimeError> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let path = export_path.join(ERROR_REPORT_FILENAME);
+        let mut file = File::create(&path).map_err(|err| RuntimeError::CreateError(err, path))?;
+        file.write_all(self.to_json().as_bytes())
+            .map_err(RuntimeError::DiskError)?;
+
+        eprintln!(
+            "Encountered {} non-fatal issues, see {ERROR_REPORT_FILENAME} for details",
+            self.len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::error_report::{ErrorReport, IssueKind};
+
+    #[test]
+    fn can_create_empty() {
+        let report = ErrorReport::default();
+        assert!(report.is_empty());
+        assert_eq!(report.len(), 0);
+        assert!(report.counts().is_empty());
+    }
+
+    #[test]
+    fn can_count_issues() {
+        let report = ErrorReport::default();
+        report.record(IssueKind::MissingAttachment, "guid_1", "a");
+        report.record(IssueKind::MissingAttachment, "guid_2", "b");
+        report.record(IssueKind::UnparseablePayload, "guid_3", "c");
+
+        assert_eq!(report.len(), 3);
+        assert_eq!(report.counts().get(&IssueKind::MissingAttachment), Some(&2));
+        assert_eq!(
+            report.counts().get(&IssueKind::UnparseablePayload),
+            Some(&1)
+        );
+        assert_eq!(report.counts().get(&IssueKind::UnresolvedAttachment), None);
+    }
+
+    #[test]
+    fn can_serialize_json() {
+        let report = ErrorReport::default();
+        report.record(
+            IssueKind::UnresolvedAttachment,
+            "guid_1",
+            "path/to/\"file\"",
+        );
+
+        let expected = "{\n  \"total\": 1,\n  \"counts\": {\"unresolved_attachment\": 1},\n  \"issues\": [\n    {\"kind\": \"unresolved_attachment\", \"guid\": \"guid_1\", \"detail\": \"path/to/\\\"file\\\"\"}\n  ]\n}\n";
+        assert_eq!(report.to_json(), expected);
+    }
+}
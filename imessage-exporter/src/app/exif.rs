@@ -0,0 +1,559 @@
+/*!
+ Reads basic metadata from image headers so it can be written alongside copied attachments.
+
+ Only the `EXIF` fields useful for reconstructing the context of a photo are read: when it was taken,
+ its dimensions, and where it was taken. `JPEG` and `PNG` files are supported; other formats are skipped.
+*/
+
+use std::{
+    ffi::OsString,
+    fs::{write, File},
+    io::{Error, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::app::sanitizers::sanitize_json;
+
+/// Image headers are read from the start of the file; `EXIF` data must fit in a single 64 KiB segment,
+/// so this leaves room for other segments that precede the frame header
+const HEADER_READ_LIMIT: u64 = 256 * 1024;
+/// The signature at the start of every `PNG` file
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+/// The marker at the start of every `JPEG` file
+const JPEG_SOI: [u8; 2] = [0xFF, 0xD8];
+/// The prefix of a `JPEG` `APP1` segment that contains `EXIF` data
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+/// `IFD0` tag for the date the file was last changed
+const TAG_DATE_TIME: u16 = 0x0132;
+/// `IFD0` tag that points to the `EXIF` sub-IFD
+const TAG_EXIF_IFD: u16 = 0x8769;
+/// `IFD0` tag that points to the GPS sub-IFD
+const TAG_GPS_IFD: u16 = 0x8825;
+/// `EXIF` tag for the date the photo was taken
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+/// `EXIF` tag for the width of the image
+const TAG_PIXEL_X_DIMENSION: u16 = 0xA002;
+/// `EXIF` tag for the height of the image
+const TAG_PIXEL_Y_DIMENSION: u16 = 0xA003;
+/// GPS tag for the hemisphere of the latitude, `N` or `S`
+const TAG_GPS_LATITUDE_REF: u16 = 0x0001;
+/// GPS tag for the latitude as degrees, minutes, and seconds
+const TAG_GPS_LATITUDE: u16 = 0x0002;
+/// GPS tag for the hemisphere of the longitude, `E` or `W`
+const TAG_GPS_LONGITUDE_REF: u16 = 0x0003;
+/// GPS tag for the longitude as degrees, minutes, and seconds
+const TAG_GPS_LONGITUDE: u16 = 0x0004;
+
+/// Metadata read from an image's headers
+#[derive(Debug, Default, PartialEq)]
+pub struct ImageMetadata {
+    /// When the photo was taken, formatted as `YYYY-MM-DD HH:MM:SS` in the camera's local time
+    pub date_taken: Option<String>,
+    /// The width of the image in pixels
+    pub width: Option<u32>,
+    /// The height of the image in pixels
+    pub height: Option<u32>,
+    /// The latitude the photo was taken at, in decimal degrees
+    pub latitude: Option<f64>,
+    /// The longitude the photo was taken at, in decimal degrees
+    pub longitude: Option<f64>,
+}
+
+impl ImageMetadata {
+    /// Read the metadata from the image at `path`
+    ///
+    /// Returns `None` if the file cannot be read, is not a supported image, or contains no metadata.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let mut bytes = vec![];
+        File::open(path)
+            .ok()?
+            .take(HEADER_READ_LIMIT)
+            .read_to_end(&mut bytes)
+            .ok()?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Read the metadata from the start of an image file
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let metadata = if bytes.starts_with(&JPEG_SOI) {
+            Self::from_jpeg(bytes)
+        } else if bytes.starts_with(&PNG_SIGNATURE) {
+            Self::from_png(bytes)
+        } else {
+            return None;
+        };
+        (!metadata.is_empty()).then_some(metadata)
+    }
+
+    /// `true` if no metadata was found, else `false`
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Walk the `JPEG` segments up to the start of the image data
+    fn from_jpeg(bytes: &[u8]) -> Self {
+        let mut metadata = Self::default();
+        let mut idx = JPEG_SOI.len();
+
+        while idx + 4 <= bytes.len() {
+            if bytes[idx] != 0xFF {
+                break;
+            }
+            let marker = bytes[idx + 1];
+            // Fill bytes and markers without a length
+            if marker == 0xFF || marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+                idx += if marker == 0xFF { 1 } else { 2 };
+                continue;
+            }
+            // Start of scan: the compressed image data follows
+            if marker == 0xDA {
+                break;
+            }
+
+            let length = u16::from_be_bytes([bytes[idx + 2], bytes[idx + 3]]) as usize;
+            let segment = match bytes.get(idx + 4..idx + 2 + length) {
+                Some(segment) => segment,
+                None => break,
+            };
+
+            match marker {
+                // APP1
+                0xE1 => {
+                    if let Some(tiff) = segment.strip_prefix(EXIF_HEADER) {
+                        metadata.read_tiff(tiff);
+                    }
+                }
+                // Start of frame; 0xC4, 0xC8, and 0xCC share the range but are not frame headers
+                0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) && segment.len() >= 5 => {
+                    metadata.height = Some(u16::from_be_bytes([segment[1], segment[2]]) as u32);
+                    metadata.width = Some(u16::from_be_bytes([segment[3], segment[4]]) as u32);
+                }
+                _ => {}
+            }
+
+            idx += 2 + length;
+        }
+
+        metadata
+    }
+
+    /// Walk the `PNG` chunks, reading the header and any `eXIf` chunk
+    fn from_png(bytes: &[u8]) -> Self {
+        let mut metadata = Self::default();
+        let mut idx = PNG_SIGNATURE.len();
+
+        while idx + 8 <= bytes.len() {
+            let length =
+                u32::from_be_bytes([bytes[idx], bytes[idx + 1], bytes[idx + 2], bytes[idx + 3]])
+                    as usize;
+            let kind = &bytes[idx + 4..idx + 8];
+            let data = match bytes.get(idx + 8..idx + 8 + length) {
+                Some(data) => data,
+                None => break,
+            };
+
+            match kind {
+                b"IHDR" if data.len() >= 8 => {
+                    metadata.width = Some(u32::from_be_bytes([data[0], data[1], data[2], data[3]]));
+                    metadata.height =
+                        Some(u32::from_be_bytes([data[4], data[5], data[6], data[7]]));
+                }
+                b"eXIf" => metadata.read_tiff(data),
+                b"IDAT" | b"IEND" => break,
+                _ => {}
+            }
+
+            // Skip the chunk's trailing CRC
+            idx += 8 + length + 4;
+        }
+
+        metadata
+    }
+
+    /// Read the fields we care about from `TIFF`-structured `EXIF` data
+    fn read_tiff(&mut self, tiff: &[u8]) {
+        let Some(reader) = TiffReader::new(tiff) else {
+            return;
+        };
+        let Some(ifd0) = reader.u32_at(4) else {
+            return;
+        };
+
+        let mut date_time = None;
+        for entry in reader.entries(ifd0 as usize) {
+            match entry.tag {
+                TAG_DATE_TIME => date_time = reader.ascii(&entry),
+                TAG_EXIF_IFD => {
+                    for exif_entry in reader.entries(entry.value as usize) {
+                        match exif_entry.tag {
+                            TAG_DATE_TIME_ORIGINAL => self.date_taken = reader.ascii(&exif_entry),
+                            // The dimensions in the image's own header take precedence
+                            TAG_PIXEL_X_DIMENSION => {
+                                self.width = self.width.or(reader.integer(&exif_entry));
+                            }
+                            TAG_PIXEL_Y_DIMENSION => {
+                                self.height = self.height.or(reader.integer(&exif_entry));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                TAG_GPS_IFD => self.read_gps(&reader, entry.value as usize),
+                _ => {}
+            }
+        }
+
+        // Fall back to the modification date if the capture date is missing
+        self.date_taken = self
+            .date_taken
+            .take()
+            .or(date_time)
+            .map(|date| format_exif_date(&date));
+    }
+
+    /// Read the coordinates from the GPS sub-IFD
+    fn read_gps(&mut self, reader: &TiffReader, offset: usize) {
+        let mut latitude = None;
+        let mut longitude = None;
+        let mut latitude_ref = None;
+        let mut longitude_ref = None;
+
+        for entry in reader.entries(offset) {
+            match entry.tag {
+                TAG_GPS_LATITUDE_REF => latitude_ref = reader.ascii(&entry),
+                TAG_GPS_LATITUDE => latitude = reader.degrees(&entry),
+                TAG_GPS_LONGITUDE_REF => longitude_ref = reader.ascii(&entry),
+                TAG_GPS_LONGITUDE => longitude = reader.degrees(&entry),
+                _ => {}
+            }
+        }
+
+        self.latitude = latitude.map(|value| match latitude_ref.as_deref() {
+            Some("S") => -value,
+            _ => value,
+        });
+        self.longitude = longitude.map(|value| match longitude_ref.as_deref() {
+            Some("W") => -value,
+            _ => value,
+        });
+    }
+
+    /// Serialize the metadata as a JSON document
+    pub fn to_json(&self) -> String {
+        let date_taken = match &self.date_taken {
+            Some(date) => format!("\"{}\"", sanitize_json(date)),
+            None => String::from("null"),
+        };
+        format!(
+            "{{\n  \"date_taken\": {date_taken},\n  \"width\": {},\n  \"height\": {},\n  \"latitude\": {},\n  \"longitude\": {}\n}}\n",
+            json_number(self.width),
+            json_number(self.height),
+            json_number(self.latitude),
+            json_number(self.longitude),
+        )
+    }
+
+    /// Write the metadata to a JSON file next to the image, i.e. `photo.jpeg.json`
+    pub fn write_sidecar(&self, image: &Path) -> Result<PathBuf, Error> {
+        let path = sidecar_path(image);
+        write(&path, self.to_json())?;
+        Ok(path)
+    }
+}
+
+/// Get the path of the sidecar file for an image
+pub fn sidecar_path(image: &Path) -> PathBuf {
+    let mut path = OsString::from(image.as_os_str());
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+/// Render an optional number as a JSON value
+fn json_number<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| String::from("null"), |value| value.to_string())
+}
+
+/// Convert an `EXIF` date, i.e. `2023:10:14 13:54:29`, to `2023-10-14 13:54:29`
+fn format_exif_date(date: &str) -> String {
+    match date.split_once(' ') {
+        Some((day, time)) => format!("{} {time}", day.replace(':', "-")),
+        None => date.replace(':', "-"),
+    }
+}
+
+/// A single entry in a `TIFF` image file directory
+struct IfdEntry {
+    /// The tag that identifies the field
+    tag: u16,
+    /// The data type of the field
+    kind: u16,
+    /// The number of values in the field
+    count: u32,
+    /// The value itself if it fits in 4 bytes, otherwise the offset to the value
+    value: u32,
+    /// The offset of the value field, used to read values stored inline
+    value_offset: usize,
+}
+
+/// Reads values from `TIFF`-structured data in either byte order
+struct TiffReader<'a> {
+    /// The `TIFF` data, starting at the byte order marker
+    data: &'a [u8],
+    /// `true` if the data is little-endian (`II`), `false` if big-endian (`MM`)
+    little_endian: bool,
+}
+
+impl<'a> TiffReader<'a> {
+    /// `TIFF` type for 8-bit ASCII text
+    const ASCII: u16 = 2;
+    /// `TIFF` type for an unsigned 16-bit integer
+    const SHORT: u16 = 3;
+    /// `TIFF` type for an unsigned 32-bit integer
+    const LONG: u16 = 4;
+    /// `TIFF` type for a fraction of two unsigned 32-bit integers
+    const RATIONAL: u16 = 5;
+
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(0..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        Some(Self {
+            data,
+            little_endian,
+        })
+    }
+
+    fn u16_at(&self, offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    /// Read the entries of the image file directory at `offset`
+    fn entries(&self, offset: usize) -> Vec<IfdEntry> {
+        let count = self.u16_at(offset).unwrap_or(0) as usize;
+        (0..count)
+            .filter_map(|idx| {
+                let start = offset + 2 + idx * 12;
+                Some(IfdEntry {
+                    tag: self.u16_at(start)?,
+                    kind: self.u16_at(start + 2)?,
+                    count: self.u32_at(start + 4)?,
+                    value: self.u32_at(start + 8)?,
+                    value_offset: start + 8,
+                })
+            })
+            .collect()
+    }
+
+    /// Read a text field, which is stored inline if it fits in 4 bytes
+    fn ascii(&self, entry: &IfdEntry) -> Option<String> {
+        if entry.kind != Self::ASCII {
+            return None;
+        }
+        let start = if entry.count <= 4 {
+            entry.value_offset
+        } else {
+            entry.value as usize
+        };
+        let bytes = self.data.get(start..start + entry.count as usize)?;
+        let text = String::from_utf8_lossy(bytes)
+            .trim_end_matches('\0')
+            .trim()
+            .to_string();
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// Read an integer field, which may be stored as either a `SHORT` or a `LONG`
+    fn integer(&self, entry: &IfdEntry) -> Option<u32> {
+        match entry.kind {
+            Self::SHORT => self.u16_at(entry.value_offset).map(u32::from),
+            Self::LONG => Some(entry.value),
+            _ => None,
+        }
+    }
+
+    /// Read a coordinate stored as three rationals, degrees, minutes, and seconds, as decimal degrees
+    fn degrees(&self, entry: &IfdEntry) -> Option<f64> {
+        if entry.kind != Self::RATIONAL || entry.count != 3 {
+            return None;
+        }
+        let offset = entry.value as usize;
+        let mut parts = [0f64; 3];
+        for (idx, part) in parts.iter_mut().enumerate() {
+            let numerator = self.u32_at(offset + idx * 8)?;
+            let denominator = self.u32_at(offset + idx * 8 + 4)?;
+            if denominator == 0 {
+                return None;
+            }
+            *part = f64::from(numerator) / f64::from(denominator);
+        }
+        Some(parts[0] + parts[1] / 60. + parts[2] / 3600.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::app::exif::{sidecar_path, ImageMetadata};
+
+    /// Build big-endian `TIFF` data with a capture date, dimensions, and a location in San Francisco
+    fn fake_tiff() -> Vec<u8> {
+        fn entry(tiff: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: u32) {
+            tiff.extend(tag.to_be_bytes());
+            tiff.extend(kind.to_be_bytes());
+            tiff.extend(count.to_be_bytes());
+            tiff.extend(value.to_be_bytes());
+        }
+
+        // Layout: header (8), IFD0 at 8 (2 + 2 * 12 + 4 = 30), EXIF IFD at 38 (2 + 3 * 12 + 4 = 42),
+        // GPS IFD at 80 (2 + 4 * 12 + 4 = 54), date at 134 (20), latitude at 154 (24), longitude at 178 (24)
+        let mut tiff = vec![b'M', b'M', 0, 42, 0, 0, 0, 8];
+
+        tiff.extend(2u16.to_be_bytes());
+        entry(&mut tiff, 0x8769, 4, 1, 38);
+        entry(&mut tiff, 0x8825, 4, 1, 80);
+        tiff.extend(0u32.to_be_bytes());
+
+        tiff.extend(3u16.to_be_bytes());
+        entry(&mut tiff, 0x9003, 2, 20, 134);
+        entry(&mut tiff, 0xA002, 3, 1, 4032 << 16);
+        entry(&mut tiff, 0xA003, 4, 1, 3024);
+        tiff.extend(0u32.to_be_bytes());
+
+        tiff.extend(4u16.to_be_bytes());
+        entry(&mut tiff, 0x0001, 2, 2, u32::from_be_bytes([b'N', 0, 0, 0]));
+        entry(&mut tiff, 0x0002, 5, 3, 154);
+        entry(&mut tiff, 0x0003, 2, 2, u32::from_be_bytes([b'W', 0, 0, 0]));
+        entry(&mut tiff, 0x0004, 5, 3, 178);
+        tiff.extend(0u32.to_be_bytes());
+
+        tiff.extend(b"2023:10:14 13:54:29\0");
+        for (numerator, denominator) in [(37, 1), (46, 1), (30, 1), (122, 1), (25, 1), (12, 1)] {
+            tiff.extend((numerator as u32).to_be_bytes());
+            tiff.extend((denominator as u32).to_be_bytes());
+        }
+
+        tiff
+    }
+
+    fn fake_jpeg(tiff: &[u8]) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+
+        // APP1 with EXIF data
+        jpeg.extend([0xFF, 0xE1]);
+        jpeg.extend(((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend(b"Exif\0\0");
+        jpeg.extend(tiff);
+
+        // Start of frame: precision, height, width, components
+        jpeg.extend([0xFF, 0xC0, 0, 8, 8, 0x0B, 0xD0, 0x0F, 0xC0, 3]);
+
+        // Start of scan
+        jpeg.extend([0xFF, 0xDA, 0, 2]);
+        jpeg
+    }
+
+    #[test]
+    fn can_read_jpeg_exif() {
+        let metadata = ImageMetadata::from_bytes(&fake_jpeg(&fake_tiff())).unwrap();
+
+        assert_eq!(metadata.date_taken.as_deref(), Some("2023-10-14 13:54:29"));
+        assert_eq!(metadata.width, Some(4032));
+        assert_eq!(metadata.height, Some(3024));
+        assert!((metadata.latitude.unwrap() - 37.775).abs() < 1e-9);
+        assert!((metadata.longitude.unwrap() + 122.42).abs() < 1e-9);
+    }
+
+    #[test]
+    fn can_read_jpeg_dimensions_without_exif() {
+        let metadata = ImageMetadata::from_bytes(&fake_jpeg(b"not exif")).unwrap();
+
+        let expected = ImageMetadata {
+            date_taken: None,
+            width: Some(4032),
+            height: Some(3024),
+            latitude: None,
+            longitude: None,
+        };
+
+        assert_eq!(metadata, expected);
+    }
+
+    #[test]
+    fn can_read_png_dimensions() {
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        png.extend(13u32.to_be_bytes());
+        png.extend(b"IHDR");
+        png.extend(640u32.to_be_bytes());
+        png.extend(480u32.to_be_bytes());
+        png.extend([8, 6, 0, 0, 0]);
+        png.extend([0, 0, 0, 0]);
+
+        let metadata = ImageMetadata::from_bytes(&png).unwrap();
+
+        assert_eq!(metadata.width, Some(640));
+        assert_eq!(metadata.height, Some(480));
+        assert_eq!(metadata.date_taken, None);
+    }
+
+    #[test]
+    fn cant_read_unsupported() {
+        assert_eq!(ImageMetadata::from_bytes(b"GIF89a"), None);
+        assert_eq!(ImageMetadata::from_bytes(&[]), None);
+    }
+
+    #[test]
+    fn cant_read_truncated_jpeg() {
+        let jpeg = fake_jpeg(&fake_tiff());
+        assert!(ImageMetadata::from_bytes(&jpeg[..20]).is_none());
+    }
+
+    #[test]
+    fn cant_read_missing_file() {
+        assert_eq!(
+            ImageMetadata::from_path(Path::new("/fake/path/photo.jpeg")),
+            None
+        );
+    }
+
+    #[test]
+    fn can_serialize_json() {
+        let metadata = ImageMetadata {
+            date_taken: Some(String::from("2023-10-14 13:54:29")),
+            width: Some(4032),
+            height: Some(3024),
+            latitude: None,
+            longitude: None,
+        };
+
+        assert_eq!(
+            metadata.to_json(),
+            "{\n  \"date_taken\": \"2023-10-14 13:54:29\",\n  \"width\": 4032,\n  \"height\": 3024,\n  \"latitude\": null,\n  \"longitude\": null\n}\n"
+        );
+    }
+
+    #[test]
+    fn can_get_sidecar_path() {
+        assert_eq!(
+            sidecar_path(Path::new("/export/attachments/1/photo.jpeg")),
+            Path::new("/export/attachments/1/photo.jpeg.json")
+        );
+    }
+}
@@ -11,6 +11,14 @@ pub enum ExportType {
     Html,
     /// Text file export
     Txt,
+    /// [Day One](https://dayoneapp.com) journal JSON export
+    DayOne,
+    /// IRC-style plain text log export
+    Irc,
+    /// Newline-delimited JSON export, with one record for each row of the messages table
+    Ndjson,
+    /// Compact JSON summary export, with one file of preprocessed messages for each conversation
+    Json,
 }
 
 impl ExportType {
@@ -19,9 +27,25 @@ impl ExportType {
         match platform.to_lowercase().as_str() {
             "txt" => Some(Self::Txt),
             "html" => Some(Self::Html),
+            "dayone" => Some(Self::DayOne),
+            "irc" => Some(Self::Irc),
+            "ndjson" => Some(Self::Ndjson),
+            "json" => Some(Self::Json),
             _ => None,
         }
     }
+
+    /// The file extension used by files of this export type
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportType::Html => "html",
+            ExportType::Txt => "txt",
+            ExportType::DayOne => "json",
+            ExportType::Irc => "log",
+            ExportType::Ndjson => "ndjson",
+            ExportType::Json => "json",
+        }
+    }
 }
 
 impl Display for ExportType {
@@ -29,13 +53,119 @@ impl Display for ExportType {
         match self {
             ExportType::Txt => write!(fmt, "txt"),
             ExportType::Html => write!(fmt, "html"),
+            ExportType::DayOne => write!(fmt, "dayone"),
+            ExportType::Irc => write!(fmt, "irc"),
+            ExportType::Ndjson => write!(fmt, "ndjson"),
+            ExportType::Json => write!(fmt, "json"),
+        }
+    }
+}
+
+/// Represents how messages are grouped into [Day One](https://dayoneapp.com) journal entries
+#[derive(PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub enum DayOneGranularity {
+    /// Create one entry for each message
+    Message,
+    /// Create one entry for each day of messages in a conversation
+    #[default]
+    Day,
+}
+
+impl DayOneGranularity {
+    /// Given user's input, return a variant if the input matches one
+    pub fn from_cli(granularity: &str) -> Option<Self> {
+        match granularity.to_lowercase().as_str() {
+            "message" => Some(Self::Message),
+            "day" => Some(Self::Day),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DayOneGranularity {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DayOneGranularity::Message => write!(fmt, "message"),
+            DayOneGranularity::Day => write!(fmt, "day"),
+        }
+    }
+}
+
+/// Represents how each conversation is split across multiple files
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SplitBy {
+    /// Create one file for each day of messages in a conversation
+    Day,
+    /// Create one file for each month of messages in a conversation
+    Month,
+    /// Create one file for each year of messages in a conversation
+    Year,
+}
+
+impl SplitBy {
+    /// Given user's input, return a variant if the input matches one
+    pub fn from_cli(split: &str) -> Option<Self> {
+        match split.to_lowercase().as_str() {
+            "day" => Some(Self::Day),
+            "month" => Some(Self::Month),
+            "year" => Some(Self::Year),
+            _ => None,
+        }
+    }
+
+    /// The `strftime` format of the period a message falls in, used to name the file it is written to
+    pub fn date_format(&self) -> &'static str {
+        match self {
+            SplitBy::Day => "%Y-%m-%d",
+            SplitBy::Month => "%Y-%m",
+            SplitBy::Year => "%Y",
+        }
+    }
+}
+
+impl Display for SplitBy {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SplitBy::Day => write!(fmt, "day"),
+            SplitBy::Month => write!(fmt, "month"),
+            SplitBy::Year => write!(fmt, "year"),
+        }
+    }
+}
+
+/// Represents how exported files are arranged in the export directory
+#[derive(PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub enum DirectoryLayout {
+    /// Write every file directly into the export directory
+    #[default]
+    Flat,
+    /// Write the files for each year of split conversations into a directory named for that year
+    Year,
+}
+
+impl DirectoryLayout {
+    /// Given user's input, return a variant if the input matches one
+    pub fn from_cli(layout: &str) -> Option<Self> {
+        match layout.to_lowercase().as_str() {
+            "flat" => Some(Self::Flat),
+            "year" => Some(Self::Year),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DirectoryLayout {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DirectoryLayout::Flat => write!(fmt, "flat"),
+            DirectoryLayout::Year => write!(fmt, "year"),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::app::export_type::ExportType;
+    use crate::app::export_type::{DayOneGranularity, DirectoryLayout, ExportType, SplitBy};
 
     #[test]
     fn can_parse_html_any_case() {
@@ -60,10 +190,96 @@ mod tests {
         assert!(matches!(ExportType::from_cli("tXt"), Some(ExportType::Txt)));
     }
 
+    #[test]
+    fn can_parse_dayone_any_case() {
+        assert!(matches!(
+            ExportType::from_cli("dayone"),
+            Some(ExportType::DayOne)
+        ));
+        assert!(matches!(
+            ExportType::from_cli("DayOne"),
+            Some(ExportType::DayOne)
+        ));
+    }
+
+    #[test]
+    fn can_parse_irc_any_case() {
+        assert!(matches!(ExportType::from_cli("irc"), Some(ExportType::Irc)));
+        assert!(matches!(ExportType::from_cli("IRC"), Some(ExportType::Irc)));
+    }
+
+    #[test]
+    fn can_parse_ndjson_any_case() {
+        assert!(matches!(
+            ExportType::from_cli("ndjson"),
+            Some(ExportType::Ndjson)
+        ));
+        assert!(matches!(
+            ExportType::from_cli("NDJSON"),
+            Some(ExportType::Ndjson)
+        ));
+    }
+
+    #[test]
+    fn can_parse_json_any_case() {
+        assert!(matches!(
+            ExportType::from_cli("json"),
+            Some(ExportType::Json)
+        ));
+        assert!(matches!(
+            ExportType::from_cli("JSON"),
+            Some(ExportType::Json)
+        ));
+    }
+
+    #[test]
+    fn can_get_extension() {
+        assert_eq!(ExportType::Html.extension(), "html");
+        assert_eq!(ExportType::Txt.extension(), "txt");
+        assert_eq!(ExportType::DayOne.extension(), "json");
+        assert_eq!(ExportType::Irc.extension(), "log");
+        assert_eq!(ExportType::Ndjson.extension(), "ndjson");
+        assert_eq!(ExportType::Json.extension(), "json");
+    }
+
+    #[test]
+    fn can_parse_granularity() {
+        assert_eq!(
+            DayOneGranularity::from_cli("message"),
+            Some(DayOneGranularity::Message)
+        );
+        assert_eq!(
+            DayOneGranularity::from_cli("DAY"),
+            Some(DayOneGranularity::Day)
+        );
+        assert_eq!(DayOneGranularity::from_cli("week"), None);
+    }
+
+    #[test]
+    fn can_parse_split_by() {
+        assert_eq!(SplitBy::from_cli("day"), Some(SplitBy::Day));
+        assert_eq!(SplitBy::from_cli("Month"), Some(SplitBy::Month));
+        assert_eq!(SplitBy::from_cli("YEAR"), Some(SplitBy::Year));
+        assert_eq!(SplitBy::from_cli("week"), None);
+    }
+
+    #[test]
+    fn can_parse_directory_layout() {
+        assert_eq!(
+            DirectoryLayout::from_cli("flat"),
+            Some(DirectoryLayout::Flat)
+        );
+        assert_eq!(
+            DirectoryLayout::from_cli("Year"),
+            Some(DirectoryLayout::Year)
+        );
+        assert_eq!(DirectoryLayout::from_cli("month"), None);
+    }
+
     #[test]
     fn cant_parse_invalid() {
         assert!(ExportType::from_cli("pdf").is_none());
-        assert!(ExportType::from_cli("json").is_none());
+        assert!(ExportType::from_cli("csv").is_none());
         assert!(ExportType::from_cli("").is_none());
     }
 }
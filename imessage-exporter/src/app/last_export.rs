@@ -0,0 +1,59 @@
+/*!
+ Records the date of the newest exported message so `--since-last-export` can pick up where the previous export stopped.
+*/
+
+use std::{
+    fs::{read_to_string, write},
+    path::Path,
+};
+
+use crate::app::error::RuntimeError;
+
+/// Name of the file the date of the newest exported message is stored in, in the export directory
+pub const LAST_EXPORT_FILENAME: &str = ".imessage-exporter-last-export";
+
+/// Read the date of the newest message written by the previous export, if one was recorded
+pub fn read_last_export(export_path: &Path) -> Option<i64> {
+    read_to_string(export_path.join(LAST_EXPORT_FILENAME))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Record the date of the newest exported message for the next export to start after
+pub fn write_last_export(export_path: &Path, date: i64) -> Result<(), RuntimeError> {
+    let path = export_path.join(LAST_EXPORT_FILENAME);
+    write(&path, format!("{date}\n")).map_err(|err| RuntimeError::CreateError(err, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env::temp_dir,
+        fs::{create_dir_all, remove_dir_all, write},
+    };
+
+    use crate::app::last_export::{read_last_export, write_last_export, LAST_EXPORT_FILENAME};
+
+    #[test]
+    fn can_write_and_read_last_export() {
+        let dir = temp_dir().join("imessage-exporter-last-export-test");
+        let _ = remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+
+        assert_eq!(read_last_export(&dir), None);
+
+        write_last_export(&dir, 674526582885055488).unwrap();
+        assert_eq!(read_last_export(&dir), Some(674526582885055488));
+    }
+
+    #[test]
+    fn cant_read_invalid_last_export() {
+        let dir = temp_dir().join("imessage-exporter-last-export-invalid-test");
+        create_dir_all(&dir).unwrap();
+        write(dir.join(LAST_EXPORT_FILENAME), "yesterday").unwrap();
+
+        assert_eq!(read_last_export(&dir), None);
+    }
+}
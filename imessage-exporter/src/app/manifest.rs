@@ -0,0 +1,124 @@
+/*!
+ Generates a checksum manifest for the files in an export so the archive can be verified later.
+*/
+
+use std::{
+    fs::{read_dir, File},
+    io::{copy, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::app::error::RuntimeError;
+
+/// Name of the file the manifest is written to in the export directory
+pub const MANIFEST_FILENAME: &str = "manifest.sha256";
+
+/// Write a manifest listing the SHA-256 of every file in the export directory
+///
+/// Files are not filtered by whether this export wrote them, so anything else left in the directory is listed too.
+/// The output uses the same format as `sha256sum`, so it can be checked with `sha256sum -c manifest.sha256`.
+/// Returns the number of files listed.
+pub fn write_manifest(export_path: &Path) -> Result<usize, RuntimeError> {
+    let mut files = vec![];
+    collect_files(export_path, &mut files)?;
+    files.sort();
+
+    let manifest_path = export_path.join(MANIFEST_FILENAME);
+    let manifest = File::create(&manifest_path)
+        .map_err(|err| RuntimeError::CreateError(err, manifest_path.clone()))?;
+    let mut manifest = BufWriter::new(manifest);
+
+    let mut listed = 0;
+    for file in files {
+        if file == manifest_path {
+            continue;
+        }
+
+        let relative_path = file
+            .strip_prefix(export_path)
+            .unwrap_or(&file)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        writeln!(manifest, "{}  {relative_path}", hash_file(&file)?)
+            .map_err(RuntimeError::DiskError)?;
+        listed += 1;
+    }
+    manifest.flush().map_err(RuntimeError::DiskError)?;
+
+    Ok(listed)
+}
+
+/// Recursively collect the paths of all files in a directory
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) -> Result<(), RuntimeError> {
+    for entry in read_dir(directory).map_err(RuntimeError::DiskError)? {
+        let path = entry.map_err(RuntimeError::DiskError)?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Compute the hex-encoded SHA-256 of a file's contents
+fn hash_file(path: &Path) -> Result<String, RuntimeError> {
+    let file =
+        File::open(path).map_err(|err| RuntimeError::CreateError(err, path.to_path_buf()))?;
+    let mut hasher = Sha256::new();
+    copy(&mut BufReader::new(file), &mut hasher).map_err(RuntimeError::DiskError)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env::temp_dir,
+        fs::{create_dir_all, read_to_string, remove_dir_all, write},
+    };
+
+    use crate::app::manifest::{hash_file, write_manifest, MANIFEST_FILENAME};
+
+    #[test]
+    fn can_hash_file() {
+        let directory = temp_dir().join("imessage-exporter-manifest-hash");
+        create_dir_all(&directory).unwrap();
+        let path = directory.join("abc.txt");
+        write(&path, "abc").unwrap();
+
+        let hash = hash_file(&path).unwrap();
+        remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            hash,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn can_write_manifest() {
+        let directory = temp_dir().join("imessage-exporter-manifest-write");
+        create_dir_all(directory.join("attachments")).unwrap();
+        write(directory.join("chat.txt"), "abc").unwrap();
+        write(directory.join("attachments").join("empty.jpg"), "").unwrap();
+
+        let listed = write_manifest(&directory).unwrap();
+        let manifest = read_to_string(directory.join(MANIFEST_FILENAME)).unwrap();
+
+        // Writing the manifest again should not list the previous manifest
+        let relisted = write_manifest(&directory).unwrap();
+        remove_dir_all(&directory).unwrap();
+
+        assert_eq!(listed, 2);
+        assert_eq!(relisted, 2);
+        assert_eq!(
+            manifest,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  attachments/empty.jpg\nba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  chat.txt\n"
+        );
+    }
+}
@@ -1,8 +1,20 @@
+pub mod annotations;
 pub mod attachment_manager;
+pub mod conversations;
 pub mod converter;
+pub mod db_copy;
 pub mod error;
+pub mod error_report;
+pub mod exif;
 pub mod export_type;
+pub mod last_export;
+pub mod manifest;
 pub mod options;
+pub mod post_export;
 pub mod progress;
+pub mod report;
 pub mod runtime;
 pub mod sanitizers;
+pub mod timestamp_format;
+pub mod typedstream_dump;
+pub mod warc;
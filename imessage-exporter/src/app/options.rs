@@ -1,22 +1,42 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, fmt::Write, path::PathBuf};
 
 use clap::{crate_version, Arg, ArgAction, ArgMatches, Command};
 
 use imessage_database::{
-    tables::{attachment::DEFAULT_ATTACHMENT_ROOT, table::DEFAULT_PATH_IOS},
+    tables::{
+        attachment::DEFAULT_ATTACHMENT_ROOT,
+        table::{DEFAULT_PATH_IOS, ORPHANED},
+    },
     util::{
-        dirs::{default_db_path, home},
+        dates::get_local_time,
+        dirs::{default_db_path, home, ios_backup_path},
         platform::Platform,
         query_context::QueryContext,
     },
 };
 
-use crate::app::{
-    attachment_manager::AttachmentManager, error::RuntimeError, export_type::ExportType,
+use crate::{
+    app::{
+        attachment_manager::{AttachmentManager, DEFAULT_COPY_RETRIES},
+        error::RuntimeError,
+        export_type::{DayOneGranularity, DirectoryLayout, ExportType, SplitBy},
+        last_export::{read_last_export, LAST_EXPORT_FILENAME},
+        manifest::MANIFEST_FILENAME,
+        post_export::EXPORT_PATH_PLACEHOLDER,
+        report::ReportType,
+        runtime::FLATTENED_GROUPS,
+        timestamp_format::TimestampFormat,
+        warc::WARC_FILENAME,
+    },
+    exporters::txt::{TEMPLATE_SENDER, TEMPLATE_TEXT, TEMPLATE_TIME},
 };
 
 /// Default export directory name
 pub const DEFAULT_OUTPUT_DIR: &str = "imessage_export";
+/// Default language of HTML exports
+pub const DEFAULT_HTML_LANG: &str = "en";
+/// Default `strftime` format of the timestamps in IRC exports
+pub const DEFAULT_IRC_TIMESTAMP_FORMAT: &str = "%H:%M";
 
 // CLI Arg Names
 pub const OPTION_DB_PATH: &str = "db-path";
@@ -27,20 +47,77 @@ pub const OPTION_EXPORT_TYPE: &str = "format";
 pub const OPTION_EXPORT_PATH: &str = "export-path";
 pub const OPTION_START_DATE: &str = "start-date";
 pub const OPTION_END_DATE: &str = "end-date";
+pub const OPTION_FROM_DATE: &str = "from-date";
+pub const OPTION_TO_DATE: &str = "to-date";
 pub const OPTION_DISABLE_LAZY_LOADING: &str = "no-lazy";
 pub const OPTION_CUSTOM_NAME: &str = "custom-name";
 pub const OPTION_PLATFORM: &str = "platform";
 pub const OPTION_BYPASS_FREE_SPACE_CHECK: &str = "ignore-disk-warning";
 pub const OPTION_USE_CALLER_ID: &str = "use-caller-id";
+pub const OPTION_SHOW_MY_NUMBER: &str = "show-my-number";
+pub const OPTION_COPY_RETRIES: &str = "copy-retries";
+pub const OPTION_RELATIVE_TIME: &str = "relative-time";
+pub const OPTION_SHOW_REMOVED_REACTIONS: &str = "show-removed-reactions";
+pub const OPTION_LIMIT: &str = "limit";
+pub const OPTION_LIMIT_PER_CHAT: &str = "limit-per-chat";
+pub const OPTION_MERGE_SERVICES: &str = "merge-services";
+pub const OPTION_DAYONE_GRANULARITY: &str = "dayone-granularity";
+pub const OPTION_REPORT: &str = "report";
+pub const OPTION_TXT_TEMPLATE: &str = "txt-template";
+pub const OPTION_MANIFEST: &str = "manifest";
+pub const OPTION_ANIMATE_EFFECTS: &str = "animate-effects";
+pub const OPTION_PARTICIPANTS_ONLY: &str = "participants-only";
+pub const OPTION_EXCLUDE_ORPHANED: &str = "exclude-orphaned";
+pub const OPTION_ONLY_ORPHANED: &str = "only-orphaned";
+pub const OPTION_MEDIA_GRID: &str = "media-grid";
+pub const OPTION_SHOW_RECEIPTS: &str = "show-receipts";
+pub const OPTION_EXIF_SIDECARS: &str = "exif-sidecars";
+pub const OPTION_POST_EXPORT_COMMAND: &str = "post-export-command";
+pub const OPTION_MASK_TEXT: &str = "mask-text";
+pub const OPTION_COMPACT_HTML: &str = "compact-html";
+pub const OPTION_SPLIT_BY: &str = "split-by";
+pub const OPTION_WARC: &str = "warc";
+pub const OPTION_NO_ATTACHMENTS: &str = "no-attachments";
+pub const OPTION_COLLAPSE_REACTIONS: &str = "collapse-reactions-under-threshold";
+pub const OPTION_MARK_FAILED_TRANSFERS: &str = "mark-failed-transfers";
+pub const OPTION_MESSAGE_GUID: &str = "message-guid";
+pub const OPTION_NORMALIZE_NEWLINES: &str = "normalize-newlines";
+pub const OPTION_HTML_LANG: &str = "html-lang";
+pub const OPTION_CSS: &str = "css";
+pub const OPTION_DIRECTORY_LAYOUT: &str = "directory-layout";
+pub const OPTION_INCLUDE_EMPTY_CHATS: &str = "include-empty-chats";
+pub const OPTION_PRINT_FRIENDLY: &str = "print-friendly";
+pub const OPTION_SINCE_LAST_EXPORT: &str = "since-last-export";
+pub const OPTION_RESET_LAST_EXPORT: &str = "reset-last-export";
+pub const OPTION_ONLY_REPORTED: &str = "only-reported";
+pub const OPTION_NUMBER_MESSAGES: &str = "number-messages";
+pub const OPTION_EMOJI_SHORTCODES: &str = "emoji-shortcodes";
+pub const OPTION_MIME_OVERRIDE: &str = "mime-override";
+pub const OPTION_FLATTEN_GROUPS: &str = "flatten-groups";
+pub const OPTION_EXTRACT_MEDIA_ONLY: &str = "extract-media-only";
+pub const OPTION_ANNOTATIONS: &str = "annotations";
+pub const OPTION_DUMP_TYPEDSTREAM: &str = "dump-typedstream";
+pub const OPTION_IRC_TIMESTAMP_FORMAT: &str = "irc-timestamp-format";
+pub const OPTION_ALT_TEXT: &str = "alt-text";
+pub const OPTION_REACTION_CONTEXT: &str = "reaction-context";
+pub const OPTION_QUIET_DELIVERY: &str = "quiet-delivery";
+pub const OPTION_COPY_DB_FIRST: &str = "copy-db-first";
+pub const OPTION_TOKENIZE: &str = "tokenize";
 
 // Other CLI Text
-pub const SUPPORTED_FILE_TYPES: &str = "txt, html";
+pub const SUPPORTED_FILE_TYPES: &str = "txt, html, dayone, irc, ndjson, json";
+pub const SUPPORTED_DAYONE_GRANULARITIES: &str = "message, day";
+pub const SUPPORTED_SPLITS: &str = "day, month, year";
+pub const SUPPORTED_DIRECTORY_LAYOUTS: &str = "flat, year";
+pub const SUPPORTED_RELATIVE_TIME_MODES: &str = "relative, both";
+pub const SUPPORTED_REPORT_TYPES: &str = "otp, conversations, my-reactions, reaction-stats";
 pub const SUPPORTED_PLATFORMS: &str = "macOS, iOS";
 pub const SUPPORTED_ATTACHMENT_MANAGER_MODES: &str = "compatible, efficient, disabled";
+pub const SUPPORTED_ATTACHMENT_MANAGER_ALIASES: &str = "convert, clone, cow, none";
 pub const ABOUT: &str = concat!(
     "The `imessage-exporter` binary exports iMessage data to\n",
-    "`txt` or `html` formats. It can also run diagnostics\n",
-    "to find problems with the iMessage database."
+    "`txt`, `html`, or Day One journal formats. It can also run diagnostics\n",
+    "to find problems with the iMessage database or generate reports on its contents."
 );
 
 #[derive(Debug, PartialEq, Eq)]
@@ -65,10 +142,108 @@ pub struct Options {
     pub custom_name: Option<String>,
     /// If true, use the database owner's caller ID instead of "Me"
     pub use_caller_id: bool,
+    /// If true, show the caller ID each message from the database owner was sent from next to their name
+    pub show_my_number: bool,
     /// The database source's platform
     pub platform: Platform,
     /// If true, disable the free disk space check
     pub ignore_disk_space: bool,
+    /// If true, include reactions that were later removed in exports
+    pub show_removed_reactions: bool,
+    /// Maximum number of messages to export across all conversations
+    pub limit: Option<u64>,
+    /// Maximum number of messages to export from each conversation
+    pub limit_per_chat: Option<u64>,
+    /// If true, merge conversations with the same contact across iMessage and SMS into a single file
+    pub merge_services: bool,
+    /// How messages are grouped into Day One journal entries
+    pub dayone_granularity: DayOneGranularity,
+    /// Number of times to retry copying an attachment after a transient failure
+    pub copy_retries: u32,
+    /// How message timestamps are rendered in exports
+    pub timestamp_format: TimestampFormat,
+    /// The type of report to generate instead of exporting
+    pub report: Option<ReportType>,
+    /// Single-line format for messages in TXT exports, i.e. `[{time}] {sender}: {text}`
+    pub txt_template: Option<String>,
+    /// If true, write a SHA-256 manifest of the exported files when the export finishes
+    pub manifest: bool,
+    /// If true, play screen effects as CSS animations in HTML exports
+    pub animate_effects: bool,
+    /// Only export conversations that include this handle, i.e. a phone number or email address
+    pub participants_only: Option<String>,
+    /// If true, skip messages that do not belong to a conversation
+    pub exclude_orphaned: bool,
+    /// If true, export only messages that do not belong to a conversation
+    pub only_orphaned: bool,
+    /// If true, group consecutive attachment-only messages from the same sender into a grid in HTML exports
+    pub media_grid: bool,
+    /// If true, show Delivered and Read status under sent messages in HTML exports
+    pub show_receipts: bool,
+    /// If true, write a JSON file with the EXIF metadata of each copied image next to it
+    pub exif_sidecars: bool,
+    /// Shell command to run after a successful export, with the export path substituted in
+    pub post_export_command: Option<String>,
+    /// If true, replace message text with `█` characters while keeping the rest of the message structure
+    pub mask_text: bool,
+    /// If true, omit the newlines between HTML elements to reduce the size of HTML exports
+    pub compact_html: bool,
+    /// If set, split each conversation into one file per day or month
+    pub split_by: Option<SplitBy>,
+    /// If true, bundle the HTML export into a WARC file when the export finishes
+    pub warc: bool,
+    /// If true, skip loading attachments entirely and render placeholders in their place
+    pub no_attachments: bool,
+    /// If set, summarize the reactions to a message part instead of listing them when there are more than this many
+    pub collapse_reactions: Option<u64>,
+    /// If true, render attachments that failed to send or download as a notice instead of a missing file
+    pub mark_failed_transfers: bool,
+    /// If set, print only the message with this GUID, including its reactions and replies, to stdout
+    pub message_guid: Option<String>,
+    /// If true, convert `\r\n` and lone `\r` line endings in TXT exports to `\n`
+    pub normalize_newlines: bool,
+    /// The language tag set on the `<html>` element of HTML exports
+    pub html_lang: String,
+    /// Path to a stylesheet to include after the built-in styles in HTML exports
+    pub css: Option<PathBuf>,
+    /// How exported files are arranged in the export directory
+    pub directory_layout: DirectoryLayout,
+    /// If true, create a file for conversations that have no messages in the export
+    pub include_empty_chats: bool,
+    /// If true, style HTML exports for printing and disable lazy loading
+    pub print_friendly: bool,
+    /// If true, only export messages newer than the ones written by the previous export to the same directory
+    pub since_last_export: bool,
+    /// If true, ignore the date recorded by the previous export and start over
+    pub reset_last_export: bool,
+    /// If true, only export messages that were reported as junk
+    pub only_reported: bool,
+    /// If true, prefix each top-level message with its position in its conversation file
+    pub number_messages: bool,
+    /// If true, convert emoji in message text to `:shortcode:` form in TXT exports
+    pub emoji_shortcodes: bool,
+    /// MIME types to use for attachments with a given file extension, keyed by lowercase extension
+    pub mime_overrides: HashMap<String, String>,
+    /// If true, write the messages from every group chat to a single file in TXT exports
+    pub flatten_groups: bool,
+    /// If true, copy attachments to the export directory without writing any conversation files
+    pub extract_media_only: bool,
+    /// Path to a file of notes to render beneath the messages they annotate
+    pub annotations: Option<PathBuf>,
+    /// GUID of a message whose raw and parsed `typedstream` body should be printed
+    pub dump_typedstream: Option<String>,
+    /// The `strftime` format of the timestamp at the start of each line in IRC exports
+    pub irc_timestamp_format: String,
+    /// If true, describe image attachments with their stored description or file name in HTML and TXT exports
+    pub alt_text: bool,
+    /// If true, describe the part of a message each reaction was placed on, i.e. `Loved an image`, in HTML and TXT exports
+    pub reaction_context: bool,
+    /// If true, note messages that were delivered while the recipient silenced notifications in HTML and TXT exports
+    pub quiet_delivery: bool,
+    /// If true, copy the database and its write-ahead log to a temporary directory and export from the copy
+    pub copy_db_first: bool,
+    /// If true, write each message as a list of lowercase tokens instead of its raw text in JSON exports
+    pub tokenize: bool,
 }
 
 impl Options {
@@ -84,8 +259,60 @@ impl Options {
         let no_lazy = args.get_flag(OPTION_DISABLE_LAZY_LOADING);
         let custom_name: Option<&String> = args.get_one(OPTION_CUSTOM_NAME);
         let use_caller_id = args.get_flag(OPTION_USE_CALLER_ID);
+        let show_my_number = args.get_flag(OPTION_SHOW_MY_NUMBER);
         let platform_type: Option<&String> = args.get_one(OPTION_PLATFORM);
         let ignore_disk_space = args.get_flag(OPTION_BYPASS_FREE_SPACE_CHECK);
+        let show_removed_reactions = args.get_flag(OPTION_SHOW_REMOVED_REACTIONS);
+        let limit: Option<&String> = args.get_one(OPTION_LIMIT);
+        let limit_per_chat: Option<&String> = args.get_one(OPTION_LIMIT_PER_CHAT);
+        let merge_services = args.get_flag(OPTION_MERGE_SERVICES);
+        let dayone_granularity_type: Option<&String> = args.get_one(OPTION_DAYONE_GRANULARITY);
+        let copy_retries: Option<&String> = args.get_one(OPTION_COPY_RETRIES);
+        let relative_time: Option<&String> = args.get_one(OPTION_RELATIVE_TIME);
+        let report_type: Option<&String> = args.get_one(OPTION_REPORT);
+        let txt_template: Option<&String> = args.get_one(OPTION_TXT_TEMPLATE);
+        let manifest = args.get_flag(OPTION_MANIFEST);
+        let animate_effects = args.get_flag(OPTION_ANIMATE_EFFECTS);
+        let participants_only: Option<&String> = args.get_one(OPTION_PARTICIPANTS_ONLY);
+        let exclude_orphaned = args.get_flag(OPTION_EXCLUDE_ORPHANED);
+        let only_orphaned = args.get_flag(OPTION_ONLY_ORPHANED);
+        let media_grid = args.get_flag(OPTION_MEDIA_GRID);
+        let show_receipts = args.get_flag(OPTION_SHOW_RECEIPTS);
+        let exif_sidecars = args.get_flag(OPTION_EXIF_SIDECARS);
+        let post_export_command: Option<&String> = args.get_one(OPTION_POST_EXPORT_COMMAND);
+        let mask_text = args.get_flag(OPTION_MASK_TEXT);
+        let compact_html = args.get_flag(OPTION_COMPACT_HTML);
+        let split_by_type: Option<&String> = args.get_one(OPTION_SPLIT_BY);
+        let warc = args.get_flag(OPTION_WARC);
+        let no_attachments = args.get_flag(OPTION_NO_ATTACHMENTS);
+        let collapse_reactions: Option<&String> = args.get_one(OPTION_COLLAPSE_REACTIONS);
+        let mark_failed_transfers = args.get_flag(OPTION_MARK_FAILED_TRANSFERS);
+        let message_guid: Option<&String> = args.get_one(OPTION_MESSAGE_GUID);
+        let normalize_newlines = args.get_flag(OPTION_NORMALIZE_NEWLINES);
+        let html_lang: Option<&String> = args.get_one(OPTION_HTML_LANG);
+        let css: Option<&String> = args.get_one(OPTION_CSS);
+        let directory_layout_type: Option<&String> = args.get_one(OPTION_DIRECTORY_LAYOUT);
+        let include_empty_chats = args.get_flag(OPTION_INCLUDE_EMPTY_CHATS);
+        let print_friendly = args.get_flag(OPTION_PRINT_FRIENDLY);
+        let since_last_export = args.get_flag(OPTION_SINCE_LAST_EXPORT);
+        let reset_last_export = args.get_flag(OPTION_RESET_LAST_EXPORT);
+        let only_reported = args.get_flag(OPTION_ONLY_REPORTED);
+        let number_messages = args.get_flag(OPTION_NUMBER_MESSAGES);
+        let emoji_shortcodes = args.get_flag(OPTION_EMOJI_SHORTCODES);
+        let flatten_groups = args.get_flag(OPTION_FLATTEN_GROUPS);
+        let extract_media_only = args.get_flag(OPTION_EXTRACT_MEDIA_ONLY);
+        let annotations: Option<&String> = args.get_one(OPTION_ANNOTATIONS);
+        let dump_typedstream: Option<&String> = args.get_one(OPTION_DUMP_TYPEDSTREAM);
+        let irc_timestamp_format: Option<&String> = args.get_one(OPTION_IRC_TIMESTAMP_FORMAT);
+        let alt_text = args.get_flag(OPTION_ALT_TEXT);
+        let reaction_context = args.get_flag(OPTION_REACTION_CONTEXT);
+        let quiet_delivery = args.get_flag(OPTION_QUIET_DELIVERY);
+        let copy_db_first = args.get_flag(OPTION_COPY_DB_FIRST);
+        let tokenize = args.get_flag(OPTION_TOKENIZE);
+        let mime_override_values: Vec<&String> = args
+            .get_many(OPTION_MIME_OVERRIDE)
+            .map(Iterator::collect)
+            .unwrap_or_default();
 
         // Build the export type
         let export_type: Option<ExportType> = match export_file_type {
@@ -98,22 +325,22 @@ impl Options {
         };
 
         // Ensure an export type is specified if other export options are selected
-        if attachment_manager_type.is_some() && export_file_type.is_none() {
+        if attachment_manager_type.is_some() && export_file_type.is_none() && !extract_media_only {
             return Err(RuntimeError::InvalidOptions(format!(
                 "Option {OPTION_ATTACHMENT_MANAGER} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
             )));
         }
-        if user_export_path.is_some() && export_file_type.is_none() {
+        if user_export_path.is_some() && export_file_type.is_none() && !extract_media_only {
             return Err(RuntimeError::InvalidOptions(format!(
                 "Option {OPTION_EXPORT_PATH} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
             )));
         }
-        if start_date.is_some() && export_file_type.is_none() {
+        if start_date.is_some() && export_file_type.is_none() && !extract_media_only {
             return Err(RuntimeError::InvalidOptions(format!(
                 "Option {OPTION_START_DATE} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
             )));
         }
-        if end_date.is_some() && export_file_type.is_none() {
+        if end_date.is_some() && export_file_type.is_none() && !extract_media_only {
             return Err(RuntimeError::InvalidOptions(format!(
                 "Option {OPTION_END_DATE} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
             )));
@@ -123,6 +350,259 @@ impl Options {
                 "Option {OPTION_USE_CALLER_ID} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
             )));
         }
+        if show_my_number && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_SHOW_MY_NUMBER} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if show_removed_reactions && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_SHOW_REMOVED_REACTIONS} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if limit.is_some() && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_LIMIT} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if limit_per_chat.is_some() && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_LIMIT_PER_CHAT} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if copy_retries.is_some() && attachment_manager_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_COPY_RETRIES} is enabled, which requires `--{OPTION_ATTACHMENT_MANAGER}`"
+            )));
+        }
+        if exif_sidecars && attachment_manager_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_EXIF_SIDECARS} is enabled, which requires `--{OPTION_ATTACHMENT_MANAGER}`"
+            )));
+        }
+        if relative_time.is_some() && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_RELATIVE_TIME} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if merge_services && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_MERGE_SERVICES} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if manifest && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_MANIFEST} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if participants_only.is_some() && export_file_type.is_none() && !extract_media_only {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_PARTICIPANTS_ONLY} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if exclude_orphaned && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_EXCLUDE_ORPHANED} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if only_orphaned && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_ONLY_ORPHANED} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if only_reported && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_ONLY_REPORTED} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if !mime_override_values.is_empty() && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_MIME_OVERRIDE} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if post_export_command.is_some() && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_POST_EXPORT_COMMAND} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if mask_text && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_MASK_TEXT} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+
+        if dayone_granularity_type.is_some() && export_type != Some(ExportType::DayOne) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_DAYONE_GRANULARITY} is enabled, which requires `--{OPTION_EXPORT_TYPE} dayone`"
+            )));
+        }
+
+        if normalize_newlines && export_type != Some(ExportType::Txt) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_NORMALIZE_NEWLINES} is enabled, which requires `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if emoji_shortcodes && export_type != Some(ExportType::Txt) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_EMOJI_SHORTCODES} is enabled, which requires `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if flatten_groups && export_type != Some(ExportType::Txt) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_FLATTEN_GROUPS} is enabled, which requires `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if annotations.is_some() && !matches!(export_type, Some(ExportType::Html | ExportType::Txt))
+        {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_ANNOTATIONS} is enabled, which requires `--{OPTION_EXPORT_TYPE} html` or `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if alt_text && !matches!(export_type, Some(ExportType::Html | ExportType::Txt)) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_ALT_TEXT} is enabled, which requires `--{OPTION_EXPORT_TYPE} html` or `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if reaction_context && !matches!(export_type, Some(ExportType::Html | ExportType::Txt)) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_REACTION_CONTEXT} is enabled, which requires `--{OPTION_EXPORT_TYPE} html` or `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if quiet_delivery && !matches!(export_type, Some(ExportType::Html | ExportType::Txt)) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_QUIET_DELIVERY} is enabled, which requires `--{OPTION_EXPORT_TYPE} html` or `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if txt_template.is_some() && export_type != Some(ExportType::Txt) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_TXT_TEMPLATE} is enabled, which requires `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+
+        if animate_effects && export_type != Some(ExportType::Html) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_ANIMATE_EFFECTS} is enabled, which requires `--{OPTION_EXPORT_TYPE} html`"
+            )));
+        }
+
+        if media_grid && export_type != Some(ExportType::Html) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_MEDIA_GRID} is enabled, which requires `--{OPTION_EXPORT_TYPE} html`"
+            )));
+        }
+
+        if show_receipts && export_type != Some(ExportType::Html) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_SHOW_RECEIPTS} is enabled, which requires `--{OPTION_EXPORT_TYPE} html`"
+            )));
+        }
+        if split_by_type.is_some()
+            && !matches!(export_type, Some(ExportType::Html | ExportType::Txt))
+        {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_SPLIT_BY} is enabled, which requires `--{OPTION_EXPORT_TYPE} html` or `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if include_empty_chats && !matches!(export_type, Some(ExportType::Html | ExportType::Txt)) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_INCLUDE_EMPTY_CHATS} is enabled, which requires `--{OPTION_EXPORT_TYPE} html` or `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if print_friendly && export_type != Some(ExportType::Html) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_PRINT_FRIENDLY} is enabled, which requires `--{OPTION_EXPORT_TYPE} html`"
+            )));
+        }
+        if since_last_export && !matches!(export_type, Some(ExportType::Html | ExportType::Txt)) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_SINCE_LAST_EXPORT} is enabled, which requires `--{OPTION_EXPORT_TYPE} html` or `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if reset_last_export && !since_last_export {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_RESET_LAST_EXPORT} is enabled, which requires `--{OPTION_SINCE_LAST_EXPORT}`"
+            )));
+        }
+        if number_messages && !matches!(export_type, Some(ExportType::Html | ExportType::Txt)) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_NUMBER_MESSAGES} is enabled, which requires `--{OPTION_EXPORT_TYPE} html` or `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if directory_layout_type.is_some() && split_by_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_DIRECTORY_LAYOUT} is enabled, which requires `--{OPTION_SPLIT_BY}`"
+            )));
+        }
+        if collapse_reactions.is_some()
+            && !matches!(export_type, Some(ExportType::Html | ExportType::Txt))
+        {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_COLLAPSE_REACTIONS} is enabled, which requires `--{OPTION_EXPORT_TYPE} html` or `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if mark_failed_transfers && !matches!(export_type, Some(ExportType::Html | ExportType::Txt))
+        {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_MARK_FAILED_TRANSFERS} is enabled, which requires `--{OPTION_EXPORT_TYPE} html` or `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if message_guid.is_some()
+            && !matches!(export_type, Some(ExportType::Html | ExportType::Txt))
+        {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_MESSAGE_GUID} is enabled, which requires `--{OPTION_EXPORT_TYPE} html` or `--{OPTION_EXPORT_TYPE} txt`"
+            )));
+        }
+        if message_guid.is_some() && attachment_manager_type.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_MESSAGE_GUID} is enabled; {OPTION_ATTACHMENT_MANAGER} is disallowed"
+            )));
+        }
+        if no_attachments && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_NO_ATTACHMENTS} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if irc_timestamp_format.is_some() && export_type != Some(ExportType::Irc) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_IRC_TIMESTAMP_FORMAT} is enabled, which requires `--{OPTION_EXPORT_TYPE} irc`"
+            )));
+        }
+        if tokenize && export_type != Some(ExportType::Json) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_TOKENIZE} is enabled, which requires `--{OPTION_EXPORT_TYPE} json`"
+            )));
+        }
+
+        if html_lang.is_some() && export_type != Some(ExportType::Html) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_HTML_LANG} is enabled, which requires `--{OPTION_EXPORT_TYPE} html`"
+            )));
+        }
+        if css.is_some() && export_type != Some(ExportType::Html) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_CSS} is enabled, which requires `--{OPTION_EXPORT_TYPE} html`"
+            )));
+        }
+        if warc && export_type != Some(ExportType::Html) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_WARC} is enabled, which requires `--{OPTION_EXPORT_TYPE} html`"
+            )));
+        }
+        if compact_html && export_type != Some(ExportType::Html) {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_COMPACT_HTML} is enabled, which requires `--{OPTION_EXPORT_TYPE} html`"
+            )));
+        }
+
+        // Ensure the message body is not dropped from TXT exports
+        if let Some(template) = txt_template {
+            if !template.contains(TEMPLATE_TEXT) {
+                return Err(RuntimeError::InvalidOptions(format!(
+                    "Option {OPTION_TXT_TEMPLATE} must include the `{TEMPLATE_TEXT}` placeholder"
+                )));
+            }
+        }
 
         // Warn the user if they are exporting to a file type for which lazy loading has no effect
         if no_lazy && export_file_type != Some(&"html".to_string()) {
@@ -162,6 +642,179 @@ impl Options {
                 "Diagnostics are enabled; {OPTION_USE_CALLER_ID} is disallowed"
             )));
         }
+        if diagnostic && show_my_number {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_SHOW_MY_NUMBER} is disallowed"
+            )));
+        }
+        if diagnostic && show_removed_reactions {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_SHOW_REMOVED_REACTIONS} is disallowed"
+            )));
+        }
+        if diagnostic && limit.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_LIMIT} is disallowed"
+            )));
+        }
+        if diagnostic && limit_per_chat.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_LIMIT_PER_CHAT} is disallowed"
+            )));
+        }
+        if diagnostic && merge_services {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_MERGE_SERVICES} is disallowed"
+            )));
+        }
+        if diagnostic && manifest {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_MANIFEST} is disallowed"
+            )));
+        }
+        if diagnostic && participants_only.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_PARTICIPANTS_ONLY} is disallowed"
+            )));
+        }
+        if diagnostic && exclude_orphaned {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_EXCLUDE_ORPHANED} is disallowed"
+            )));
+        }
+        if diagnostic && only_orphaned {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_ONLY_ORPHANED} is disallowed"
+            )));
+        }
+        if diagnostic && post_export_command.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_POST_EXPORT_COMMAND} is disallowed"
+            )));
+        }
+        if diagnostic && mask_text {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_MASK_TEXT} is disallowed"
+            )));
+        }
+        if diagnostic && no_attachments {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_NO_ATTACHMENTS} is disallowed"
+            )));
+        }
+
+        // Ensure attachments are not both skipped and copied
+        if no_attachments && attachment_manager_type.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_NO_ATTACHMENTS}` is enabled; `--{OPTION_ATTACHMENT_MANAGER}` is disallowed"
+            )));
+        }
+
+        // Ensure attachments are not copied when streaming records
+        if export_type == Some(ExportType::Ndjson) && attachment_manager_type.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_EXPORT_TYPE} ndjson` is enabled; `--{OPTION_ATTACHMENT_MANAGER}` is disallowed"
+            )));
+        }
+
+        // Ensure attachments are not copied when they are replaced by markers
+        if export_type == Some(ExportType::Json) && attachment_manager_type.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_EXPORT_TYPE} json` is enabled; `--{OPTION_ATTACHMENT_MANAGER}` is disallowed"
+            )));
+        }
+
+        // Ensure attachments are not both skipped and marked
+        if no_attachments && mark_failed_transfers {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_NO_ATTACHMENTS}` is enabled; `--{OPTION_MARK_FAILED_TRANSFERS}` is disallowed"
+            )));
+        }
+
+        // Ensure orphaned messages are not both excluded and exclusively exported
+        if exclude_orphaned && only_orphaned {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_EXCLUDE_ORPHANED}` is enabled; `--{OPTION_ONLY_ORPHANED}` is disallowed"
+            )));
+        }
+        if since_last_export && start_date.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_SINCE_LAST_EXPORT}` is enabled; `--{OPTION_START_DATE}` is disallowed"
+            )));
+        }
+        // A limited export stops before the newest message, which would be skipped by the next resumed export
+        if since_last_export && limit.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_SINCE_LAST_EXPORT}` is enabled; `--{OPTION_LIMIT}` is disallowed"
+            )));
+        }
+        if since_last_export && limit_per_chat.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_SINCE_LAST_EXPORT}` is enabled; `--{OPTION_LIMIT_PER_CHAT}` is disallowed"
+            )));
+        }
+        // Numbering restarts at `#1` in every run, so appended messages would repeat numbers already in the file
+        if since_last_export && number_messages {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_SINCE_LAST_EXPORT}` is enabled; `--{OPTION_NUMBER_MESSAGES}` is disallowed"
+            )));
+        }
+        if only_orphaned && include_empty_chats {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_ONLY_ORPHANED}` is enabled; `--{OPTION_INCLUDE_EMPTY_CHATS}` is disallowed"
+            )));
+        }
+        if diagnostic && report_type.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_REPORT} is disallowed"
+            )));
+        }
+
+        // Ensure that extracting media is not combined with other modes
+        if extract_media_only && export_file_type.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_EXTRACT_MEDIA_ONLY}` is enabled; `--{OPTION_EXPORT_TYPE}` is disallowed"
+            )));
+        }
+        if extract_media_only && report_type.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_EXTRACT_MEDIA_ONLY}` is enabled; `--{OPTION_REPORT}` is disallowed"
+            )));
+        }
+        if extract_media_only && diagnostic {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_EXTRACT_MEDIA_ONLY} is disallowed"
+            )));
+        }
+
+        // Ensure that dumping a typedstream is not combined with other modes
+        if dump_typedstream.is_some() && export_file_type.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_DUMP_TYPEDSTREAM}` is enabled; `--{OPTION_EXPORT_TYPE}` is disallowed"
+            )));
+        }
+        if dump_typedstream.is_some() && report_type.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_DUMP_TYPEDSTREAM}` is enabled; `--{OPTION_REPORT}` is disallowed"
+            )));
+        }
+        if dump_typedstream.is_some() && extract_media_only {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_DUMP_TYPEDSTREAM}` is enabled; `--{OPTION_EXTRACT_MEDIA_ONLY}` is disallowed"
+            )));
+        }
+        if dump_typedstream.is_some() && diagnostic {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Diagnostics are enabled; {OPTION_DUMP_TYPEDSTREAM} is disallowed"
+            )));
+        }
+
+        // Ensure that reports are not combined with exports
+        if report_type.is_some() && export_file_type.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_REPORT}` is enabled; `--{OPTION_EXPORT_TYPE}` is disallowed"
+            )));
+        }
 
         // Ensure that there are no custom name conflicts
         if custom_name.is_some() && use_caller_id {
@@ -169,6 +822,11 @@ impl Options {
                 "`--{OPTION_CUSTOM_NAME}` is enabled; `--{OPTION_USE_CALLER_ID}` is disallowed"
             )));
         }
+        if use_caller_id && show_my_number {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "`--{OPTION_USE_CALLER_ID}` is enabled; `--{OPTION_SHOW_MY_NUMBER}` is disallowed"
+            )));
+        }
 
         // Build query context
         let mut query_context = QueryContext::default();
@@ -182,7 +840,6 @@ impl Options {
                 return Err(RuntimeError::InvalidOptions(format!("{why}")));
             }
         }
-
         // We have to allocate a PathBuf here because it can be created from data owned by this function in the default state
         let db_path = match user_path {
             Some(path) => PathBuf::from(path),
@@ -208,6 +865,27 @@ impl Options {
             }
         };
 
+        // Validate that the custom stylesheet exists, if provided
+        if let Some(path) = css {
+            if !PathBuf::from(path).is_file() {
+                return Err(RuntimeError::InvalidOptions(format!(
+                    "Supplied {OPTION_CSS} `{path}` does not exist!"
+                )));
+            }
+        };
+
+        // Build the MIME type overrides
+        let mime_overrides = parse_mime_overrides(&mime_override_values)?;
+
+        // Validate that the annotations file exists, if provided
+        if let Some(path) = annotations {
+            if !PathBuf::from(path).is_file() {
+                return Err(RuntimeError::InvalidOptions(format!(
+                    "Supplied {OPTION_ANNOTATIONS} `{path}` does not exist!"
+                )));
+            }
+        };
+
         // Warn the user that custom attachment roots have no effect on iOS backups
         if attachment_root.is_some() && platform == Platform::iOS {
             eprintln!(
@@ -219,14 +897,132 @@ impl Options {
         let attachment_manager_mode = match attachment_manager_type {
             Some(manager) => {
                 AttachmentManager::from_cli(manager).ok_or(RuntimeError::InvalidOptions(format!(
-                    "{manager} is not a valid attachment manager mode! Must be one of <{SUPPORTED_ATTACHMENT_MANAGER_MODES}>"
+                    "{manager} is not a valid attachment manager mode! Must be one of <{SUPPORTED_ATTACHMENT_MANAGER_MODES}> or <{SUPPORTED_ATTACHMENT_MANAGER_ALIASES}>"
                 )))?
             }
             None => AttachmentManager::default(),
         };
 
-        // Validate the provided export path
-        let export_path = validate_path(user_export_path, &export_type.as_ref())?;
+        // Media can only be extracted by copying it
+        if extract_media_only && attachment_manager_mode == AttachmentManager::Disabled {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_EXTRACT_MEDIA_ONLY} is enabled, which requires `--{OPTION_ATTACHMENT_MANAGER}` to be `compatible` or `efficient`"
+            )));
+        }
+
+        // Validate the provided message limits
+        let limit = parse_limit(limit, OPTION_LIMIT)?;
+        let limit_per_chat = parse_limit(limit_per_chat, OPTION_LIMIT_PER_CHAT)?;
+        let collapse_reactions = parse_limit(collapse_reactions, OPTION_COLLAPSE_REACTIONS)?;
+
+        // Build the Day One granularity
+        let dayone_granularity = match dayone_granularity_type {
+            Some(granularity) => DayOneGranularity::from_cli(granularity).ok_or(
+                RuntimeError::InvalidOptions(format!(
+                    "{granularity} is not a valid {OPTION_DAYONE_GRANULARITY}! Must be one of <{SUPPORTED_DAYONE_GRANULARITIES}>"
+                )),
+            )?,
+            None => DayOneGranularity::default(),
+        };
+
+        // Build the conversation split
+        let split_by = match split_by_type {
+            Some(split) => Some(SplitBy::from_cli(split).ok_or(RuntimeError::InvalidOptions(
+                format!(
+                    "{split} is not a valid {OPTION_SPLIT_BY}! Must be one of <{SUPPORTED_SPLITS}>"
+                ),
+            ))?),
+            None => None,
+        };
+
+        // Build the directory layout
+        let directory_layout = match directory_layout_type {
+            Some(layout) => DirectoryLayout::from_cli(layout).ok_or(
+                RuntimeError::InvalidOptions(format!(
+                    "{layout} is not a valid {OPTION_DIRECTORY_LAYOUT}! Must be one of <{SUPPORTED_DIRECTORY_LAYOUTS}>"
+                )),
+            )?,
+            None => DirectoryLayout::default(),
+        };
+
+        // Validate the provided language tag
+        let html_lang = match html_lang {
+            Some(lang)
+                if !lang.is_empty()
+                    && lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') =>
+            {
+                lang.clone()
+            }
+            Some(lang) => {
+                return Err(RuntimeError::InvalidOptions(format!(
+                    "{lang} is not a valid value for {OPTION_HTML_LANG}! Must be a language tag, i.e. `en` or `pt-BR`"
+                )))
+            }
+            None => DEFAULT_HTML_LANG.to_string(),
+        };
+
+        // Validate the provided timestamp format by rendering a date with it
+        let irc_timestamp_format = match irc_timestamp_format {
+            Some(format) => {
+                let mut rendered = String::new();
+                let is_valid = get_local_time(&0, &0)
+                    .is_ok_and(|date| write!(rendered, "{}", date.format(format)).is_ok());
+                if !is_valid {
+                    return Err(RuntimeError::InvalidOptions(format!(
+                        "{format} is not a valid value for {OPTION_IRC_TIMESTAMP_FORMAT}! Must be a `strftime` format, i.e. `%H:%M:%S`"
+                    )));
+                }
+                format.clone()
+            }
+            None => DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+        };
+
+        // Validate the provided copy retry count
+        let copy_retries = match copy_retries {
+            Some(retries) => retries.parse::<u32>().map_err(|_| {
+                RuntimeError::InvalidOptions(format!(
+                    "{retries} is not a valid value for {OPTION_COPY_RETRIES}! Must be a non-negative integer"
+                ))
+            })?,
+            None => DEFAULT_COPY_RETRIES,
+        };
+
+        // Build the timestamp format
+        let timestamp_format = match relative_time {
+            Some(mode) => TimestampFormat::from_cli(mode).ok_or(RuntimeError::InvalidOptions(
+                format!(
+                    "{mode} is not a valid {OPTION_RELATIVE_TIME} mode! Must be one of <{SUPPORTED_RELATIVE_TIME_MODES}>"
+                ),
+            ))?,
+            None => TimestampFormat::default(),
+        };
+
+        // Build the report type
+        let report = match report_type {
+            Some(report) => Some(ReportType::from_cli(report).ok_or(
+                RuntimeError::InvalidOptions(format!(
+                    "{report} is not a valid report type! Must be one of <{SUPPORTED_REPORT_TYPES}>"
+                )),
+            )?),
+            None => None,
+        };
+
+        // Validate the provided export path, unless a single message is printed instead of exported
+        let resume = since_last_export && !reset_last_export;
+        let export_path = match message_guid {
+            Some(_) => validate_path(user_export_path, &None, false)?,
+            None => validate_path(user_export_path, &export_type.as_ref(), resume)?,
+        };
+
+        // Only export messages sent after the newest one written by the previous export
+        if let Some(last_export) = resume.then(|| read_last_export(&export_path)).flatten() {
+            query_context.start = Some(last_export + 1);
+        }
+
+        // Echo the resolved dates so relative phrases can be confirmed
+        if let Some(range) = query_context.readable_range() {
+            eprintln!("Filtering messages from {range}");
+        }
 
         Ok(Options {
             db_path,
@@ -236,37 +1032,114 @@ impl Options {
             export_type,
             export_path,
             query_context,
-            no_lazy,
+            // Printing requires every image to be loaded up front
+            no_lazy: no_lazy || print_friendly,
             custom_name: custom_name.cloned(),
             use_caller_id,
+            show_my_number,
             platform,
             ignore_disk_space,
+            show_removed_reactions,
+            limit,
+            limit_per_chat,
+            merge_services,
+            dayone_granularity,
+            copy_retries,
+            timestamp_format,
+            report,
+            txt_template: txt_template.cloned(),
+            manifest,
+            animate_effects,
+            participants_only: participants_only.cloned(),
+            exclude_orphaned,
+            only_orphaned,
+            media_grid,
+            show_receipts,
+            exif_sidecars,
+            post_export_command: post_export_command.cloned(),
+            mask_text,
+            compact_html,
+            split_by,
+            warc,
+            no_attachments,
+            collapse_reactions,
+            mark_failed_transfers,
+            message_guid: message_guid.cloned(),
+            normalize_newlines,
+            html_lang,
+            css: css.map(PathBuf::from),
+            directory_layout,
+            include_empty_chats,
+            print_friendly,
+            since_last_export,
+            reset_last_export,
+            only_reported,
+            number_messages,
+            emoji_shortcodes,
+            mime_overrides,
+            flatten_groups,
+            extract_media_only,
+            annotations: annotations.map(PathBuf::from),
+            dump_typedstream: dump_typedstream.cloned(),
+            irc_timestamp_format,
+            alt_text,
+            reaction_context,
+            quiet_delivery,
+            copy_db_first,
+            tokenize,
         })
     }
 
     /// Generate a path to the database based on the currently selected platform
     pub fn get_db_path(&self) -> PathBuf {
         match self.platform {
-            Platform::iOS => self.db_path.join(DEFAULT_PATH_IOS),
+            Platform::iOS => ios_backup_path(&self.db_path, DEFAULT_PATH_IOS),
             Platform::macOS => self.db_path.clone(),
         }
     }
 }
 
+/// Parse `ext=type` pairs into a map of lowercase file extensions to MIME types
+fn parse_mime_overrides(values: &[&String]) -> Result<HashMap<String, String>, RuntimeError> {
+    let mut overrides = HashMap::with_capacity(values.len());
+    for value in values {
+        match value.split_once('=') {
+            Some((extension, mime_type)) if !extension.is_empty() && mime_type.contains('/') => {
+                overrides.insert(
+                    extension.trim_start_matches('.').to_lowercase(),
+                    mime_type.to_string(),
+                );
+            }
+            _ => {
+                return Err(RuntimeError::InvalidOptions(format!(
+                    "{value} is not a valid {OPTION_MIME_OVERRIDE}! Must be in the form `ext=type`, i.e. `mov=video/quicktime`"
+                )));
+            }
+        }
+    }
+    Ok(overrides)
+}
+
 /// Ensure export path is empty or does not contain files of the existing export type
 ///
 /// We have to allocate a `PathBuf` here because it can be created from data owned by this function in the default state
 fn validate_path(
     export_path: Option<&String>,
     export_type: &Option<&ExportType>,
+    since_last_export: bool,
 ) -> Result<PathBuf, RuntimeError> {
     // Build a path from the user-provided data or the default location
-    let resolved_path =
-        PathBuf::from(export_path.unwrap_or(&format!("{}/{DEFAULT_OUTPUT_DIR}", home())));
+    let resolved_path = match export_path {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(home()).join(DEFAULT_OUTPUT_DIR),
+    };
 
     // If there is an export type selected, ensure we do not overwrite files of the same type
+    // An earlier export is expected when resuming it with `--since-last-export`
     if let Some(export_type) = export_type {
-        if resolved_path.exists() {
+        if resolved_path.exists()
+            && !(since_last_export && read_last_export(&resolved_path).is_some())
+        {
             // Get the word to use if there is a problem with the specified path
             let path_word = match export_path {
                 Some(_) => "Specified",
@@ -276,7 +1149,7 @@ fn validate_path(
             // Ensure the directory exists and does not contain files of the same export type
             match resolved_path.read_dir() {
                 Ok(files) => {
-                    let export_type_extension = export_type.to_string();
+                    let export_type_extension = export_type.extension();
                     for file in files.flatten() {
                         if file
                             .path()
@@ -301,8 +1174,21 @@ fn validate_path(
     Ok(resolved_path)
 }
 
+/// Ensure a message limit is a positive integer
+fn parse_limit(limit: Option<&String>, option: &str) -> Result<Option<u64>, RuntimeError> {
+    match limit {
+        Some(limit_str) => match limit_str.parse::<u64>() {
+            Ok(limit) if limit > 0 => Ok(Some(limit)),
+            _ => Err(RuntimeError::InvalidOptions(format!(
+                "{limit_str} is not a valid value for {option}! Must be a positive integer"
+            ))),
+        },
+        None => Ok(None),
+    }
+}
+
 /// Build the command line argument parser
-fn get_command() -> Command {
+pub(crate) fn get_command() -> Command {
     Command::new("iMessage Exporter")
         .version(crate_version!())
         .about(ABOUT)
@@ -327,7 +1213,7 @@ fn get_command() -> Command {
             Arg::new(OPTION_ATTACHMENT_MANAGER)
             .short('c')
             .long(OPTION_ATTACHMENT_MANAGER)
-            .help(format!("Specify an optional method to use when copying message attachments\nCompatible will convert HEIC files to JPEG\nEfficient will copy files without converting anything\nIf omitted, the default is `{}`\nImageMagick is required to convert images on non-macOS platforms.\n", AttachmentManager::default()))
+            .help(format!("Specify an optional method to use when copying message attachments\nCompatible (or `convert`) will copy files and convert HEIC images to JPEG\nEfficient (or `clone`/`cow`) will copy files without converting anything, cloning them on APFS volumes\nDisabled (or `none`) will not copy files; exports reference attachments in place\nIf omitted, the default is `{}`\nImageMagick is required to convert images on non-macOS platforms.\n", AttachmentManager::default()))
             .display_order(2)
             .value_name(SUPPORTED_ATTACHMENT_MANAGER_MODES),
         )
@@ -367,17 +1253,19 @@ fn get_command() -> Command {
             Arg::new(OPTION_START_DATE)
                 .short('s')
                 .long(OPTION_START_DATE)
-                .help("The start date filter\nOnly messages sent on or after this date will be included\n")
+                .visible_alias(OPTION_FROM_DATE)
+                .help("The start date filter, as `YYYY-MM-DD` or a phrase like `last week`, `january 2023`, or `6 months ago`\nOnly messages sent on or after this date will be included\n")
                 .display_order(7)
-                .value_name("YYYY-MM-DD"),
+                .value_name("DATE"),
         )
         .arg(
             Arg::new(OPTION_END_DATE)
                 .short('e')
                 .long(OPTION_END_DATE)
-                .help("The end date filter\nOnly messages sent before this date will be included\n")
+                .visible_alias(OPTION_TO_DATE)
+                .help("The end date filter, as `YYYY-MM-DD` or a phrase like `last week`, `january 2023`, or `6 months ago`\nOnly messages sent before this date will be included; phrases include the whole period they describe\n")
                 .display_order(8)
-                .value_name("YYYY-MM-DD"),
+                .value_name("DATE"),
         )
         .arg(
             Arg::new(OPTION_DISABLE_LAZY_LOADING)
@@ -410,6 +1298,383 @@ fn get_command() -> Command {
                 .action(ArgAction::SetTrue)
                 .display_order(12)
         )
+        .arg(
+            Arg::new(OPTION_SHOW_REMOVED_REACTIONS)
+                .long(OPTION_SHOW_REMOVED_REACTIONS)
+                .help("Include reactions that were later removed in exports\nBy default, only reactions that are still applied are shown\n")
+                .action(ArgAction::SetTrue)
+                .display_order(13)
+        )
+        .arg(
+            Arg::new(OPTION_LIMIT)
+                .long(OPTION_LIMIT)
+                .help("Stop after exporting this many messages\nUseful for sampling exports from large databases\n")
+                .display_order(14)
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new(OPTION_LIMIT_PER_CHAT)
+                .long(OPTION_LIMIT_PER_CHAT)
+                .help("Export at most this many messages from each conversation\n")
+                .display_order(15)
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new(OPTION_MERGE_SERVICES)
+                .long(OPTION_MERGE_SERVICES)
+                .help("Merge direct conversations with the same contact across iMessage and SMS into a single file
+Group chats are never merged
+")
+                .action(ArgAction::SetTrue)
+                .display_order(16)
+        )
+        .arg(
+            Arg::new(OPTION_DAYONE_GRANULARITY)
+                .long(OPTION_DAYONE_GRANULARITY)
+                .help(format!("Specify how messages are grouped into Day One journal entries\nIf omitted, the default is `{}`\n", DayOneGranularity::default()))
+                .display_order(17)
+                .value_name(SUPPORTED_DAYONE_GRANULARITIES),
+        )
+        .arg(
+            Arg::new(OPTION_SHOW_MY_NUMBER)
+                .long(OPTION_SHOW_MY_NUMBER)
+                .help(format!("Show the caller ID each of the database owner's messages was sent from next to \"Me\"\nUseful for accounts with more than one phone number or email\nConflicts with --{OPTION_USE_CALLER_ID}\n"))
+                .action(ArgAction::SetTrue)
+                .display_order(18)
+        )
+        .arg(
+            Arg::new(OPTION_COPY_RETRIES)
+                .long(OPTION_COPY_RETRIES)
+                .help(format!("Number of times to retry copying an attachment after a transient failure\nUseful when exporting from external or network volumes\nIf omitted, the default is {DEFAULT_COPY_RETRIES}\n"))
+                .display_order(19)
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new(OPTION_RELATIVE_TIME)
+                .long(OPTION_RELATIVE_TIME)
+                .help("Render message timestamps relative to the time of export, i.e. `3 years ago`\nPass `both` to include the absolute timestamp as well\n")
+                .num_args(0..=1)
+                .default_missing_value("relative")
+                .display_order(20)
+                .value_name(SUPPORTED_RELATIVE_TIME_MODES),
+        )
+        .arg(
+            Arg::new(OPTION_REPORT)
+                .long(OPTION_REPORT)
+                .help("Print a report on the contents of the database and exit\n`otp` lists messages containing one-time passcodes with their sender and timestamp\n`conversations` lists conversations with their participants, message count, and most recent activity\n`my-reactions` lists the reactions you sent with the messages they reacted to\n`reaction-stats` lists the reactions given and received in each conversation, with the most common reaction and most-reacted-to message\n")
+                .display_order(21)
+                .value_name(SUPPORTED_REPORT_TYPES),
+        )
+        .arg(
+            Arg::new(OPTION_TXT_TEMPLATE)
+                .long(OPTION_TXT_TEMPLATE)
+                .help(format!("Write each message in TXT exports on a single line using this format\nSupports the placeholders {TEMPLATE_TIME}, {TEMPLATE_SENDER}, and {TEMPLATE_TEXT}\nIf omitted, the timestamp, sender, and message are written on separate lines\n"))
+                .display_order(22)
+                .value_name("[{time}] {sender}: {text}"),
+        )
+        .arg(
+            Arg::new(OPTION_MANIFEST)
+                .long(OPTION_MANIFEST)
+                .help(format!("Write a {MANIFEST_FILENAME} file listing the SHA-256 of every file in the export directory\nThe whole directory is hashed, including files that were there before the export\nUse it to verify the archive later with `sha256sum -c {MANIFEST_FILENAME}`\n"))
+                .action(ArgAction::SetTrue)
+                .display_order(23),
+        )
+        .arg(
+            Arg::new(OPTION_ANIMATE_EFFECTS)
+                .long(OPTION_ANIMATE_EFFECTS)
+                .help("Play Confetti, Fireworks, and Balloons screen effects as animations when HTML exports are opened\nThe effect name is still shown below the message\n")
+                .action(ArgAction::SetTrue)
+                .display_order(24),
+        )
+        .arg(
+            Arg::new(OPTION_PARTICIPANTS_ONLY)
+                .long(OPTION_PARTICIPANTS_ONLY)
+                .help("Only export conversations that include this participant, across both direct and group chats\nPhone numbers should include the country code, i.e. `+15558675309`\n")
+                .display_order(25)
+                .value_name("handle"),
+        )
+        .arg(
+            Arg::new(OPTION_EXCLUDE_ORPHANED)
+                .long(OPTION_EXCLUDE_ORPHANED)
+                .help(format!("Skip messages that do not belong to a conversation instead of writing them to the `{ORPHANED}` file\n"))
+                .action(ArgAction::SetTrue)
+                .display_order(26),
+        )
+        .arg(
+            Arg::new(OPTION_ONLY_ORPHANED)
+                .long(OPTION_ONLY_ORPHANED)
+                .help("Export only messages that do not belong to a conversation, for debugging\n")
+                .action(ArgAction::SetTrue)
+                .display_order(27),
+        )
+        .arg(
+            Arg::new(OPTION_MEDIA_GRID)
+                .long(OPTION_MEDIA_GRID)
+                .help("Group consecutive attachment-only messages from the same sender into a grid\nOnly valid for HTML exports\n")
+                .action(ArgAction::SetTrue)
+                .display_order(28),
+        )
+        .arg(
+            Arg::new(OPTION_SHOW_RECEIPTS)
+                .long(OPTION_SHOW_RECEIPTS)
+                .help("Show Delivered and Read status under sent messages\nOnly valid for HTML exports\n")
+                .action(ArgAction::SetTrue)
+                .display_order(29),
+        )
+        .arg(
+            Arg::new(OPTION_EXIF_SIDECARS)
+                .long(OPTION_EXIF_SIDECARS)
+                .help("Write a JSON file next to each copied image with its date taken, dimensions, and location\nOnly valid when attachments are copied\n")
+                .action(ArgAction::SetTrue)
+                .display_order(30),
+        )
+        .arg(
+            Arg::new(OPTION_POST_EXPORT_COMMAND)
+                .long(OPTION_POST_EXPORT_COMMAND)
+                .help(format!("Shell command to run after a successful export, i.e. to compress or upload it\n`{EXPORT_PATH_PLACEHOLDER}` is replaced with the quoted export path\nThe command runs with your permissions; only pass commands you trust\n"))
+                .display_order(31)
+                .value_name("COMMAND"),
+        )
+        .arg(
+            Arg::new(OPTION_MASK_TEXT)
+                .long(OPTION_MASK_TEXT)
+                .help("Replace message text with `█` characters, keeping attachments, reactions, replies, and timestamps
+Useful for sharing an export's layout without its content
+")
+                .action(ArgAction::SetTrue)
+                .display_order(32),
+        )
+        .arg(
+            Arg::new(OPTION_COMPACT_HTML)
+                .long(OPTION_COMPACT_HTML)
+                .help("Omit the newlines between HTML elements to reduce file size\nOnly valid for HTML exports\n")
+                .action(ArgAction::SetTrue)
+                .display_order(33),
+        )
+        .arg(
+            Arg::new(OPTION_SPLIT_BY)
+                .long(OPTION_SPLIT_BY)
+                .help("Split each conversation into one file per day, month, or year\nOnly valid for HTML and TXT exports; splitting by day can create many files for active conversations\n")
+                .display_order(34)
+                .value_name(SUPPORTED_SPLITS),
+        )
+        .arg(
+            Arg::new(OPTION_WARC)
+                .long(OPTION_WARC)
+                .help(format!("Bundle the export into `{WARC_FILENAME}` for web archive tooling\nLink preview images are listed for a crawler to capture, but are not downloaded\nOnly valid for HTML exports\n"))
+                .action(ArgAction::SetTrue)
+                .display_order(35),
+        )
+        .arg(
+            Arg::new(OPTION_NO_ATTACHMENTS)
+                .long(OPTION_NO_ATTACHMENTS)
+                .help("Skip loading attachments entirely, rendering `[attachment]` in their place
+Faster than `--copy-method disabled` for text-only exports, which still looks up every attachment
+")
+                .action(ArgAction::SetTrue)
+                .display_order(36),
+        )
+        .arg(
+            Arg::new(OPTION_COLLAPSE_REACTIONS)
+                .long(OPTION_COLLAPSE_REACTIONS)
+                .help("Summarize the reactions to a message part instead of listing them when there are more than N
+In HTML exports, the list can be expanded from the summary
+Only valid for HTML and TXT exports
+")
+                .display_order(37)
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new(OPTION_MARK_FAILED_TRANSFERS)
+                .long(OPTION_MARK_FAILED_TRANSFERS)
+                .help("Render attachments that failed to send or download as a notice instead of a missing file
+Only valid for HTML and TXT exports
+")
+                .action(ArgAction::SetTrue)
+                .display_order(38),
+        )
+        .arg(
+            Arg::new(OPTION_MESSAGE_GUID)
+                .long(OPTION_MESSAGE_GUID)
+                .help(format!("Print a single message, including its reactions and replies, to stdout instead of exporting every message
+Useful for reproducing rendering issues; nothing is written to the export directory
+Only valid for HTML and TXT exports; cannot be used with --{OPTION_ATTACHMENT_MANAGER}
+"))
+                .display_order(39)
+                .value_name("GUID"),
+        )
+        .arg(
+            Arg::new(OPTION_NORMALIZE_NEWLINES)
+                .long(OPTION_NORMALIZE_NEWLINES)
+                .help("Convert `\\r\\n` and lone `\\r` line endings in message text to `\\n`
+By default, line endings are kept as they were sent
+Only valid for TXT exports
+")
+                .action(ArgAction::SetTrue)
+                .display_order(40),
+        )
+        .arg(
+            Arg::new(OPTION_HTML_LANG)
+                .long(OPTION_HTML_LANG)
+                .help(format!("The language of the exported messages, set on the `<html>` element for screen readers, i.e. `en` or `pt-BR`\nIf omitted, the default is `{DEFAULT_HTML_LANG}`\nOnly valid for HTML exports\n"))
+                .display_order(41)
+                .value_name("LANG"),
+        )
+        .arg(
+            Arg::new(OPTION_CSS)
+                .long(OPTION_CSS)
+                .help("Path to a stylesheet to include after the built-in styles, so its rules take precedence
+Only valid for HTML exports
+")
+                .display_order(42)
+                .value_name("path/to/style.css"),
+        )
+        .arg(
+            Arg::new(OPTION_DIRECTORY_LAYOUT)
+                .long(OPTION_DIRECTORY_LAYOUT)
+                .help(format!("Specify how split conversation files are arranged in the export directory\n`year` writes each year's files into a directory named for that year, i.e. `2023/`\nIf omitted, the default is `{}`\nRequires --{OPTION_SPLIT_BY}\n", DirectoryLayout::default()))
+                .display_order(43)
+                .value_name(SUPPORTED_DIRECTORY_LAYOUTS),
+        )
+        .arg(
+            Arg::new(OPTION_INCLUDE_EMPTY_CHATS)
+                .long(OPTION_INCLUDE_EMPTY_CHATS)
+                .help(format!("Create a file for every conversation, even if none of its messages are exported\nUseful with --{OPTION_START_DATE} or --{OPTION_END_DATE} for a complete list of conversations\nOnly valid for HTML and TXT exports\n"))
+                .action(ArgAction::SetTrue)
+                .display_order(44),
+        )
+        .arg(
+            Arg::new(OPTION_PRINT_FRIENDLY)
+                .long(OPTION_PRINT_FRIENDLY)
+                .help(format!("Style HTML exports for printing or saving as PDF\nStarts a new page for each day, hides media controls, and implies --{OPTION_DISABLE_LAZY_LOADING}\nOnly valid for HTML exports\n"))
+                .action(ArgAction::SetTrue)
+                .display_order(45),
+        )
+        .arg(
+            Arg::new(OPTION_SINCE_LAST_EXPORT)
+                .long(OPTION_SINCE_LAST_EXPORT)
+                .help(format!("Only export messages sent after the newest message in the previous export to the same directory, appending them to the existing files
+The date of the newest exported message is stored in `{LAST_EXPORT_FILENAME}` in the export directory
+Conversation headers are not rewritten, so the last activity date in an existing file is from the export that created it
+Only valid for HTML and TXT exports; cannot be used with --{OPTION_START_DATE}, --{OPTION_LIMIT}, --{OPTION_LIMIT_PER_CHAT}, or --{OPTION_NUMBER_MESSAGES}
+"))
+                .action(ArgAction::SetTrue)
+                .display_order(46),
+        )
+        .arg(
+            Arg::new(OPTION_RESET_LAST_EXPORT)
+                .long(OPTION_RESET_LAST_EXPORT)
+                .help(format!("Ignore the date stored by the previous export and export every message again
+The export directory must not contain an earlier export of the same type
+Requires --{OPTION_SINCE_LAST_EXPORT}
+"))
+                .action(ArgAction::SetTrue)
+                .display_order(47),
+        )
+        .arg(
+            Arg::new(OPTION_ONLY_REPORTED)
+                .long(OPTION_ONLY_REPORTED)
+                .help("Only export messages that were reported as junk\nUseful for auditing which messages and senders have been flagged\n")
+                .action(ArgAction::SetTrue)
+                .display_order(48),
+        )
+        .arg(
+            Arg::new(OPTION_NUMBER_MESSAGES)
+                .long(OPTION_NUMBER_MESSAGES)
+                .help(format!("Prefix each message with its number in its conversation file, i.e. `#42`, so it can be referenced\nReplies rendered in a thread are not numbered\nOnly valid for HTML and TXT exports; cannot be used with --{OPTION_SINCE_LAST_EXPORT}\n"))
+                .action(ArgAction::SetTrue)
+                .display_order(49),
+        )
+        .arg(
+            Arg::new(OPTION_EMOJI_SHORTCODES)
+                .long(OPTION_EMOJI_SHORTCODES)
+                .help("Convert emoji in message text to `:shortcode:` form, i.e. `:+1:`, for tools that do not display emoji\nEmoji without a known shortcode are kept as they are\nOnly valid for TXT exports\n")
+                .action(ArgAction::SetTrue)
+                .display_order(50),
+        )
+        .arg(
+            Arg::new(OPTION_MIME_OVERRIDE)
+                .long(OPTION_MIME_OVERRIDE)
+                .help("Use a MIME type for attachments with a file extension, i.e. `mov=video/quicktime`, when the database records the wrong one\nCan be passed more than once; affects how attachments are embedded and converted\n")
+                .action(ArgAction::Append)
+                .display_order(51)
+                .value_name("ext=type"),
+        )
+        .arg(
+            Arg::new(OPTION_FLATTEN_GROUPS)
+                .long(OPTION_FLATTEN_GROUPS)
+                .help(format!("Write the messages from every group chat to a single chronological file, `{FLATTENED_GROUPS}.txt`\nEach message is prefixed with the name of its group, i.e. `[Family] `\nOnly valid for TXT exports\n"))
+                .action(ArgAction::SetTrue)
+                .display_order(52),
+        )
+        .arg(
+            Arg::new(OPTION_EXTRACT_MEDIA_ONLY)
+                .long(OPTION_EXTRACT_MEDIA_ONLY)
+                .help(format!("Copy every attachment to the export directory without writing any conversation files\nAttachments are organized into a directory for each conversation\nRequires --{OPTION_ATTACHMENT_MANAGER}; cannot be used with --{OPTION_EXPORT_TYPE}\n"))
+                .action(ArgAction::SetTrue)
+                .display_order(53),
+        )
+        .arg(
+            Arg::new(OPTION_ANNOTATIONS)
+                .long(OPTION_ANNOTATIONS)
+                .help("Path to a file of notes to render beneath the messages they annotate
+Each line is a message GUID followed by its note, i.e. `ABCD-1234 First mention of the meeting`
+Lines starting with `#` are ignored
+Only valid for HTML and TXT exports
+")
+                .display_order(54)
+                .value_name("path/to/annotations.txt"),
+        )
+        .arg(
+            Arg::new(OPTION_DUMP_TYPEDSTREAM)
+                .long(OPTION_DUMP_TYPEDSTREAM)
+                .help("Print the raw and parsed typedstream body of the message with the given GUID\n")
+                .hide(true)
+                .display_order(55)
+                .value_name("GUID"),
+        )
+        .arg(
+            Arg::new(OPTION_IRC_TIMESTAMP_FORMAT)
+                .long(OPTION_IRC_TIMESTAMP_FORMAT)
+                .help(format!("The `strftime` format of the timestamp at the start of each line, i.e. `%Y-%m-%d %H:%M:%S`\nIf omitted, the default is `{DEFAULT_IRC_TIMESTAMP_FORMAT}`\nOnly valid for IRC exports\n"))
+                .display_order(56)
+                .value_name("FORMAT"),
+        )
+        .arg(
+            Arg::new(OPTION_ALT_TEXT)
+                .long(OPTION_ALT_TEXT)
+                .help("Describe image attachments for screen readers and search, using the description stored with the image or its file name\nIn HTML exports, the description is set as the image's `alt` text; in TXT exports, stored descriptions are written below the attachment\nOnly valid for HTML and TXT exports\n")
+                .action(ArgAction::SetTrue)
+                .display_order(57),
+        )
+        .arg(
+            Arg::new(OPTION_REACTION_CONTEXT)
+                .long(OPTION_REACTION_CONTEXT)
+                .help("Describe the part of a message each reaction was placed on, i.e. `Loved an image by Name` or `Liked \"See you soon\" by Name`\nOnly valid for HTML and TXT exports\n")
+                .action(ArgAction::SetTrue)
+                .display_order(58),
+        )
+        .arg(
+            Arg::new(OPTION_QUIET_DELIVERY)
+                .long(OPTION_QUIET_DELIVERY)
+                .help("Note messages that were delivered quietly because the recipient had notifications silenced, i.e. with a Focus\nOnly databases that record quiet delivery are annotated\nOnly valid for HTML and TXT exports\n")
+                .action(ArgAction::SetTrue)
+                .display_order(59),
+        )
+        .arg(
+            Arg::new(OPTION_COPY_DB_FIRST)
+                .long(OPTION_COPY_DB_FIRST)
+                .help("Copy the database and its `-wal` and `-shm` files to a temporary directory and export from the copy\nThis avoids reading a database that Messages is writing to; the copy is removed when the export finishes\n")
+                .action(ArgAction::SetTrue)
+                .display_order(60),
+        )
+        .arg(
+            Arg::new(OPTION_TOKENIZE)
+                .long(OPTION_TOKENIZE)
+                .help("Write each message as a list of lowercase word and punctuation tokens instead of its raw text\nOnly valid for JSON exports\n")
+                .action(ArgAction::SetTrue)
+                .display_order(61),
+        )
 }
 
 /// Parse arguments from the command line
@@ -417,53 +1682,2119 @@ pub fn from_command_line() -> ArgMatches {
     get_command().get_matches()
 }
 
-#[cfg(test)]
-mod arg_tests {
-    use std::fs;
+/// Build options with every feature disabled, for tests
+#[cfg(test)]
+pub(crate) fn fake_options() -> Options {
+    Options {
+        db_path: default_db_path(),
+        attachment_root: None,
+        attachment_manager: AttachmentManager::Disabled,
+        diagnostic: false,
+        export_type: None,
+        export_path: PathBuf::from("/tmp"),
+        query_context: QueryContext::default(),
+        no_lazy: false,
+        custom_name: None,
+        use_caller_id: false,
+        show_my_number: false,
+        platform: Platform::macOS,
+        ignore_disk_space: false,
+        show_removed_reactions: false,
+        limit: None,
+        limit_per_chat: None,
+        merge_services: false,
+        dayone_granularity: DayOneGranularity::default(),
+        copy_retries: DEFAULT_COPY_RETRIES,
+        timestamp_format: TimestampFormat::default(),
+        report: None,
+        txt_template: None,
+        manifest: false,
+        animate_effects: false,
+        participants_only: None,
+        exclude_orphaned: false,
+        only_orphaned: false,
+        media_grid: false,
+        show_receipts: false,
+        exif_sidecars: false,
+        post_export_command: None,
+        mask_text: false,
+        compact_html: false,
+        split_by: None,
+        warc: false,
+        no_attachments: false,
+        collapse_reactions: None,
+        mark_failed_transfers: false,
+        message_guid: None,
+        normalize_newlines: false,
+        html_lang: DEFAULT_HTML_LANG.to_string(),
+        css: None,
+        directory_layout: DirectoryLayout::default(),
+        include_empty_chats: false,
+        print_friendly: false,
+        since_last_export: false,
+        reset_last_export: false,
+        only_reported: false,
+        number_messages: false,
+        emoji_shortcodes: false,
+        mime_overrides: HashMap::new(),
+        flatten_groups: false,
+        extract_media_only: false,
+        annotations: None,
+        dump_typedstream: None,
+        irc_timestamp_format: DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+        alt_text: false,
+        reaction_context: false,
+        quiet_delivery: false,
+        copy_db_first: false,
+        tokenize: false,
+    }
+}
+
+#[cfg(test)]
+mod arg_tests {
+    use std::{collections::HashMap, fs, path::PathBuf};
+
+    use imessage_database::util::{
+        dirs::default_db_path, platform::Platform, query_context::QueryContext,
+    };
+
+    use crate::app::{
+        attachment_manager::{AttachmentManager, DEFAULT_COPY_RETRIES},
+        export_type::{DayOneGranularity, DirectoryLayout, ExportType, SplitBy},
+        options::{
+            get_command, validate_path, Options, DEFAULT_HTML_LANG, DEFAULT_IRC_TIMESTAMP_FORMAT,
+        },
+        report::ReportType,
+        timestamp_format::TimestampFormat,
+    };
+
+    #[test]
+    fn can_build_option_diagnostic_flag() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        // Expected data
+        let expected = Options {
+            db_path: default_db_path(),
+            attachment_root: None,
+            attachment_manager: AttachmentManager::default(),
+            diagnostic: true,
+            export_type: None,
+            export_path: validate_path(None, &None, false).unwrap(),
+            query_context: QueryContext::default(),
+            no_lazy: false,
+            custom_name: None,
+            use_caller_id: false,
+            show_my_number: false,
+            platform: Platform::default(),
+            ignore_disk_space: false,
+            show_removed_reactions: false,
+            limit: None,
+            limit_per_chat: None,
+            merge_services: false,
+            dayone_granularity: DayOneGranularity::default(),
+            copy_retries: DEFAULT_COPY_RETRIES,
+            timestamp_format: TimestampFormat::default(),
+            report: None,
+            txt_template: None,
+            manifest: false,
+            animate_effects: false,
+            participants_only: None,
+            exclude_orphaned: false,
+            only_orphaned: false,
+            media_grid: false,
+            show_receipts: false,
+            exif_sidecars: false,
+            post_export_command: None,
+            mask_text: false,
+            compact_html: false,
+            split_by: None,
+            warc: false,
+            no_attachments: false,
+            collapse_reactions: None,
+            mark_failed_transfers: false,
+            message_guid: None,
+            normalize_newlines: false,
+            html_lang: DEFAULT_HTML_LANG.to_string(),
+            css: None,
+            directory_layout: DirectoryLayout::default(),
+            include_empty_chats: false,
+            print_friendly: false,
+            since_last_export: false,
+            reset_last_export: false,
+            only_reported: false,
+            number_messages: false,
+            emoji_shortcodes: false,
+            mime_overrides: HashMap::new(),
+            flatten_groups: false,
+            extract_media_only: false,
+            annotations: None,
+            dump_typedstream: None,
+            irc_timestamp_format: DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+            alt_text: false,
+            reaction_context: false,
+            quiet_delivery: false,
+            copy_db_first: false,
+            tokenize: false,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn cant_build_option_diagnostic_flag_with_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-f", "txt"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_diagnostic_flag_with_export_path() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-o", "~/test"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_diagnostic_flag_with_attachment_manager() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-c", "compatible"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_diagnostic_flag_with_start_date() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-s", "2020-01-01"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_diagnostic_flag_with_end() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-e", "2020-01-01"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_diagnostic_flag_with_caller_id() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-i"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_export_html() {
+        // Cleanup existing temp data
+        let _ = fs::remove_file("/tmp/orphaned.html");
+
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "-o", "/tmp"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        // Expected data
+        let tmp_dir = String::from("/tmp");
+        let expected = Options {
+            db_path: default_db_path(),
+            attachment_root: None,
+            attachment_manager: AttachmentManager::default(),
+            diagnostic: false,
+            export_type: Some(ExportType::Html),
+            export_path: validate_path(Some(&tmp_dir), &None, false).unwrap(),
+            query_context: QueryContext::default(),
+            no_lazy: false,
+            custom_name: None,
+            use_caller_id: false,
+            show_my_number: false,
+            platform: Platform::default(),
+            ignore_disk_space: false,
+            show_removed_reactions: false,
+            limit: None,
+            limit_per_chat: None,
+            merge_services: false,
+            dayone_granularity: DayOneGranularity::default(),
+            copy_retries: DEFAULT_COPY_RETRIES,
+            timestamp_format: TimestampFormat::default(),
+            report: None,
+            txt_template: None,
+            manifest: false,
+            animate_effects: false,
+            participants_only: None,
+            exclude_orphaned: false,
+            only_orphaned: false,
+            media_grid: false,
+            show_receipts: false,
+            exif_sidecars: false,
+            post_export_command: None,
+            mask_text: false,
+            compact_html: false,
+            split_by: None,
+            warc: false,
+            no_attachments: false,
+            collapse_reactions: None,
+            mark_failed_transfers: false,
+            message_guid: None,
+            normalize_newlines: false,
+            html_lang: DEFAULT_HTML_LANG.to_string(),
+            css: None,
+            directory_layout: DirectoryLayout::default(),
+            include_empty_chats: false,
+            print_friendly: false,
+            since_last_export: false,
+            reset_last_export: false,
+            only_reported: false,
+            number_messages: false,
+            emoji_shortcodes: false,
+            mime_overrides: HashMap::new(),
+            flatten_groups: false,
+            extract_media_only: false,
+            annotations: None,
+            dump_typedstream: None,
+            irc_timestamp_format: DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+            alt_text: false,
+            reaction_context: false,
+            quiet_delivery: false,
+            copy_db_first: false,
+            tokenize: false,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_build_option_export_txt_no_lazy() {
+        // Cleanup existing temp data
+        let _ = fs::remove_file("/tmp/orphaned.txt");
+
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-l"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        // Expected data
+        let expected = Options {
+            db_path: default_db_path(),
+            attachment_root: None,
+            attachment_manager: AttachmentManager::default(),
+            diagnostic: false,
+            export_type: Some(ExportType::Txt),
+            export_path: validate_path(None, &None, false).unwrap(),
+            query_context: QueryContext::default(),
+            no_lazy: true,
+            custom_name: None,
+            use_caller_id: false,
+            show_my_number: false,
+            platform: Platform::default(),
+            ignore_disk_space: false,
+            show_removed_reactions: false,
+            limit: None,
+            limit_per_chat: None,
+            merge_services: false,
+            dayone_granularity: DayOneGranularity::default(),
+            copy_retries: DEFAULT_COPY_RETRIES,
+            timestamp_format: TimestampFormat::default(),
+            report: None,
+            txt_template: None,
+            manifest: false,
+            animate_effects: false,
+            participants_only: None,
+            exclude_orphaned: false,
+            only_orphaned: false,
+            media_grid: false,
+            show_receipts: false,
+            exif_sidecars: false,
+            post_export_command: None,
+            mask_text: false,
+            compact_html: false,
+            split_by: None,
+            warc: false,
+            no_attachments: false,
+            collapse_reactions: None,
+            mark_failed_transfers: false,
+            message_guid: None,
+            normalize_newlines: false,
+            html_lang: DEFAULT_HTML_LANG.to_string(),
+            css: None,
+            directory_layout: DirectoryLayout::default(),
+            include_empty_chats: false,
+            print_friendly: false,
+            since_last_export: false,
+            reset_last_export: false,
+            only_reported: false,
+            number_messages: false,
+            emoji_shortcodes: false,
+            mime_overrides: HashMap::new(),
+            flatten_groups: false,
+            extract_media_only: false,
+            annotations: None,
+            dump_typedstream: None,
+            irc_timestamp_format: DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+            alt_text: false,
+            reaction_context: false,
+            quiet_delivery: false,
+            copy_db_first: false,
+            tokenize: false,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn cant_build_option_attachment_manager_no_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-c", "compatible"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_export_path_no_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-o", "~/test"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_start_date_path_no_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-s", "2020-01-01"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_end_date_path_no_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-e", "2020-01-01"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_invalid_date() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "-e", "2020-32-32"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_invalid_platform() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-a", "iPad"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_invalid_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "pdf"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_custom_name() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-m", "Name"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        // Expected data
+        let expected = Options {
+            db_path: default_db_path(),
+            attachment_root: None,
+            attachment_manager: AttachmentManager::default(),
+            diagnostic: false,
+            export_type: Some(ExportType::Txt),
+            export_path: validate_path(None, &None, false).unwrap(),
+            query_context: QueryContext::default(),
+            no_lazy: false,
+            custom_name: Some("Name".to_string()),
+            use_caller_id: false,
+            show_my_number: false,
+            platform: Platform::default(),
+            ignore_disk_space: false,
+            show_removed_reactions: false,
+            limit: None,
+            limit_per_chat: None,
+            merge_services: false,
+            dayone_granularity: DayOneGranularity::default(),
+            copy_retries: DEFAULT_COPY_RETRIES,
+            timestamp_format: TimestampFormat::default(),
+            report: None,
+            txt_template: None,
+            manifest: false,
+            animate_effects: false,
+            participants_only: None,
+            exclude_orphaned: false,
+            only_orphaned: false,
+            media_grid: false,
+            show_receipts: false,
+            exif_sidecars: false,
+            post_export_command: None,
+            mask_text: false,
+            compact_html: false,
+            split_by: None,
+            warc: false,
+            no_attachments: false,
+            collapse_reactions: None,
+            mark_failed_transfers: false,
+            message_guid: None,
+            normalize_newlines: false,
+            html_lang: DEFAULT_HTML_LANG.to_string(),
+            css: None,
+            directory_layout: DirectoryLayout::default(),
+            include_empty_chats: false,
+            print_friendly: false,
+            since_last_export: false,
+            reset_last_export: false,
+            only_reported: false,
+            number_messages: false,
+            emoji_shortcodes: false,
+            mime_overrides: HashMap::new(),
+            flatten_groups: false,
+            extract_media_only: false,
+            annotations: None,
+            dump_typedstream: None,
+            irc_timestamp_format: DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+            alt_text: false,
+            reaction_context: false,
+            quiet_delivery: false,
+            copy_db_first: false,
+            tokenize: false,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_build_option_caller_id() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-i"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        // Expected data
+        let expected = Options {
+            db_path: default_db_path(),
+            attachment_root: None,
+            attachment_manager: AttachmentManager::default(),
+            diagnostic: false,
+            export_type: Some(ExportType::Txt),
+            export_path: validate_path(None, &None, false).unwrap(),
+            query_context: QueryContext::default(),
+            no_lazy: false,
+            custom_name: None,
+            use_caller_id: true,
+            show_my_number: false,
+            platform: Platform::default(),
+            ignore_disk_space: false,
+            show_removed_reactions: false,
+            limit: None,
+            limit_per_chat: None,
+            merge_services: false,
+            dayone_granularity: DayOneGranularity::default(),
+            copy_retries: DEFAULT_COPY_RETRIES,
+            timestamp_format: TimestampFormat::default(),
+            report: None,
+            txt_template: None,
+            manifest: false,
+            animate_effects: false,
+            participants_only: None,
+            exclude_orphaned: false,
+            only_orphaned: false,
+            media_grid: false,
+            show_receipts: false,
+            exif_sidecars: false,
+            post_export_command: None,
+            mask_text: false,
+            compact_html: false,
+            split_by: None,
+            warc: false,
+            no_attachments: false,
+            collapse_reactions: None,
+            mark_failed_transfers: false,
+            message_guid: None,
+            normalize_newlines: false,
+            html_lang: DEFAULT_HTML_LANG.to_string(),
+            css: None,
+            directory_layout: DirectoryLayout::default(),
+            include_empty_chats: false,
+            print_friendly: false,
+            since_last_export: false,
+            reset_last_export: false,
+            only_reported: false,
+            number_messages: false,
+            emoji_shortcodes: false,
+            mime_overrides: HashMap::new(),
+            flatten_groups: false,
+            extract_media_only: false,
+            annotations: None,
+            dump_typedstream: None,
+            irc_timestamp_format: DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+            alt_text: false,
+            reaction_context: false,
+            quiet_delivery: false,
+            copy_db_first: false,
+            tokenize: false,
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn cant_build_option_custom_name_and_caller_id() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-m", "Name", "-i"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_caller_id_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-m", "Name", "-i"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_show_removed_reactions() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> =
+            vec!["imessage-exporter", "-f", "txt", "--show-removed-reactions"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.show_removed_reactions);
+    }
+
+    #[test]
+    fn can_build_option_copy_retries() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "-c",
+            "efficient",
+            "--copy-retries",
+            "5",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.copy_retries, 5);
+    }
+
+    #[test]
+    fn cant_build_option_copy_retries_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "-c",
+            "efficient",
+            "--copy-retries",
+            "many",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_copy_retries_no_copy_method() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--copy-retries", "5"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_exif_sidecars() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "-c",
+            "clone",
+            "--exif-sidecars",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.exif_sidecars);
+    }
+
+    #[test]
+    fn cant_build_option_exif_sidecars_no_copy_method() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--exif-sidecars"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_post_export_command() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--post-export-command",
+            "tar -czf export.tgz {export_path}",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(
+            actual.post_export_command.as_deref(),
+            Some("tar -czf export.tgz {export_path}")
+        );
+    }
+
+    #[test]
+    fn cant_build_option_post_export_command_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--post-export-command", "echo done"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_mask_text() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--mask-text"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.mask_text);
+    }
+
+    #[test]
+    fn cant_build_option_mask_text_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--mask-text"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_compact_html() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--compact-html"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.compact_html);
+    }
+
+    #[test]
+    fn cant_build_option_compact_html_txt() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--compact-html"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_split_by() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--split-by", "day"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.split_by, Some(SplitBy::Day));
+    }
+
+    #[test]
+    fn cant_build_option_split_by_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--split-by", "week"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_split_by_dayone() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "dayone", "--split-by", "day"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_warc() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--warc"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.warc);
+    }
+
+    #[test]
+    fn cant_build_option_warc_txt() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--warc"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_no_attachments() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--no-attachments"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.no_attachments);
+    }
+
+    #[test]
+    fn cant_build_option_no_attachments_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--no-attachments"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_no_attachments_copy_method() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "-c",
+            "efficient",
+            "--no-attachments",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_collapse_reactions() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--collapse-reactions-under-threshold",
+            "5",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.collapse_reactions, Some(5));
+    }
+
+    #[test]
+    fn cant_build_option_collapse_reactions_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--collapse-reactions-under-threshold",
+            "0",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_collapse_reactions_dayone() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "dayone",
+            "--collapse-reactions-under-threshold",
+            "5",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_mark_failed_transfers() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> =
+            vec!["imessage-exporter", "-f", "html", "--mark-failed-transfers"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.mark_failed_transfers);
+    }
+
+    #[test]
+    fn cant_build_option_mark_failed_transfers_dayone() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "dayone",
+            "--mark-failed-transfers",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_mark_failed_transfers_no_attachments() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--no-attachments",
+            "--mark-failed-transfers",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_message_guid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--message-guid",
+            "ABCD-1234",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.message_guid.as_deref(), Some("ABCD-1234"));
+    }
+
+    #[test]
+    fn cant_build_option_message_guid_attachment_manager() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "-c",
+            "compatible",
+            "--message-guid",
+            "ABCD-1234",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_message_guid_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--message-guid", "ABCD-1234"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_normalize_newlines() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--normalize-newlines"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.normalize_newlines);
+    }
+
+    #[test]
+    fn cant_build_option_normalize_newlines_html() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--normalize-newlines"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_html_lang() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--html-lang", "pt-BR"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.html_lang, "pt-BR");
+    }
+
+    #[test]
+    fn cant_build_option_html_lang_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--html-lang",
+            "en\"><script>",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_html_lang_txt() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--html-lang", "en"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_css() {
+        // Get matches from sample args
+        let css = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/exporters/resources/style.css"
+        );
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--css", css];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.css, Some(PathBuf::from(css)));
+    }
+
+    #[test]
+    fn cant_build_option_css_missing() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--css",
+            "/does/not/exist.css",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_directory_layout() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--split-by",
+            "year",
+            "--directory-layout",
+            "year",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.split_by, Some(SplitBy::Year));
+        assert_eq!(actual.directory_layout, DirectoryLayout::Year);
+    }
+
+    #[test]
+    fn cant_build_option_directory_layout_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--split-by",
+            "month",
+            "--directory-layout",
+            "month",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_directory_layout_no_split() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--directory-layout",
+            "year",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_include_empty_chats() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--include-empty-chats"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.include_empty_chats);
+    }
+
+    #[test]
+    fn cant_build_option_include_empty_chats_dayone() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> =
+            vec!["imessage-exporter", "-f", "dayone", "--include-empty-chats"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_include_empty_chats_only_orphaned() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--only-orphaned",
+            "--include-empty-chats",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_print_friendly() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--print-friendly"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.print_friendly);
+        assert!(actual.no_lazy);
+    }
+
+    #[test]
+    fn cant_build_option_print_friendly_txt() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--print-friendly"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_since_last_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--since-last-export"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.since_last_export);
+        assert!(!actual.reset_last_export);
+    }
+
+    #[test]
+    fn cant_build_option_since_last_export_dayone() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "dayone", "--since-last-export"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_since_last_export_limit() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--since-last-export",
+            "--limit",
+            "10",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_since_last_export_limit_per_chat() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--since-last-export",
+            "--limit-per-chat",
+            "10",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_since_last_export_start_date() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--since-last-export",
+            "-s",
+            "2020-01-01",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_reset_last_export_without_since_last_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--reset-last-export"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_only_reported() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--only-reported"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.only_reported);
+    }
+
+    #[test]
+    fn cant_build_option_only_reported_no_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--only-reported"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_number_messages() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--number-messages"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.number_messages);
+    }
+
+    #[test]
+    fn cant_build_option_number_messages_dayone() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "dayone", "--number-messages"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_number_messages_since_last_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--number-messages",
+            "--since-last-export",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_emoji_shortcodes() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--emoji-shortcodes"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.emoji_shortcodes);
+    }
+
+    #[test]
+    fn cant_build_option_emoji_shortcodes_html() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--emoji-shortcodes"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_mime_override() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--mime-override",
+            ".MOV=video/quicktime",
+            "--mime-override",
+            "caf=audio/x-caf",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(
+            actual.mime_overrides,
+            HashMap::from([
+                ("mov".to_string(), "video/quicktime".to_string()),
+                ("caf".to_string(), "audio/x-caf".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn cant_build_option_mime_override_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--mime-override", "mov"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_mime_override_no_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "--mime-override",
+            "mov=video/quicktime",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_flatten_groups() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--flatten-groups"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.flatten_groups);
+    }
+
+    #[test]
+    fn cant_build_option_flatten_groups_html() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--flatten-groups"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_extract_media_only() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "--extract-media-only",
+            "-c",
+            "efficient",
+            "-s",
+            "2020-01-01",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.extract_media_only);
+        assert!(actual.export_type.is_none());
+        assert_eq!(actual.attachment_manager, AttachmentManager::Efficient);
+    }
+
+    #[test]
+    fn cant_build_option_extract_media_only_no_copy() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--extract-media-only"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_extract_media_only_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "--extract-media-only",
+            "-c",
+            "efficient",
+            "-f",
+            "txt",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_annotations_missing() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--annotations",
+            "does/not/exist.txt",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_alt_text() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--alt-text"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.alt_text);
+    }
+
+    #[test]
+    fn cant_build_option_alt_text_dayone() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "dayone", "--alt-text"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_quiet_delivery() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--quiet-delivery"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.quiet_delivery);
+    }
+
+    #[test]
+    fn cant_build_option_quiet_delivery_ndjson() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "ndjson", "--quiet-delivery"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_copy_db_first() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--copy-db-first"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.copy_db_first);
+    }
+
+    #[test]
+    fn can_build_option_reaction_context() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--reaction-context"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.reaction_context);
+    }
+
+    #[test]
+    fn cant_build_option_reaction_context_irc() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "irc", "--reaction-context"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_annotations_dayone() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "dayone",
+            "--annotations",
+            "Cargo.toml",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_dump_typedstream() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--dump-typedstream", "ABCD-1234"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.dump_typedstream, Some("ABCD-1234".to_string()));
+        assert!(actual.export_type.is_none());
+    }
+
+    #[test]
+    fn cant_build_option_dump_typedstream_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--dump-typedstream",
+            "ABCD-1234",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_irc_timestamp_format() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "irc",
+            "--irc-timestamp-format",
+            "%H:%M:%S",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.export_type, Some(ExportType::Irc));
+        assert_eq!(actual.irc_timestamp_format, "%H:%M:%S");
+    }
+
+    #[test]
+    fn cant_build_option_irc_timestamp_format_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "irc",
+            "--irc-timestamp-format",
+            "%H:%Q",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_irc_timestamp_format_txt() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--irc-timestamp-format",
+            "%H:%M:%S",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_ndjson_copy_method() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "ndjson", "-c", "efficient"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_json_copy_method() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "json", "-c", "efficient"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_tokenize() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "json", "--tokenize"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.export_type, Some(ExportType::Json));
+        assert!(actual.tokenize);
+    }
+
+    #[test]
+    fn cant_build_option_tokenize_txt() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--tokenize"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_relative_time() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--relative-time"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.timestamp_format, TimestampFormat::Relative);
+    }
+
+    #[test]
+    fn can_build_option_relative_time_both() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--relative-time", "both"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.timestamp_format, TimestampFormat::Both);
+    }
+
+    #[test]
+    fn cant_build_option_relative_time_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--relative-time", "ago"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_report() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--report", "otp"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.report, Some(ReportType::Otp));
+        assert!(actual.export_type.is_none());
+    }
+
+    #[test]
+    fn cant_build_option_report_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--report", "codes"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_report_with_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--report", "otp"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_report_with_diagnostic() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "--report", "otp"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
 
-    use imessage_database::util::{
-        dirs::default_db_path, platform::Platform, query_context::QueryContext,
-    };
+        // Build the Options
+        let actual = Options::from_args(&args);
 
-    use crate::app::{
-        attachment_manager::AttachmentManager,
-        export_type::ExportType,
-        options::{get_command, validate_path, Options},
-    };
+        assert!(actual.is_err());
+    }
 
     #[test]
-    fn can_build_option_diagnostic_flag() {
+    fn can_build_option_txt_template() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d"];
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--txt-template",
+            "[{time}] {sender}: {text}",
+        ];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
         let actual = Options::from_args(&args).unwrap();
 
-        // Expected data
-        let expected = Options {
-            db_path: default_db_path(),
-            attachment_root: None,
-            attachment_manager: AttachmentManager::default(),
-            diagnostic: true,
-            export_type: None,
-            export_path: validate_path(None, &None).unwrap(),
-            query_context: QueryContext::default(),
-            no_lazy: false,
-            custom_name: None,
-            use_caller_id: false,
-            platform: Platform::default(),
-            ignore_disk_space: false,
-        };
-
-        assert_eq!(actual, expected);
+        assert_eq!(
+            actual.txt_template,
+            Some("[{time}] {sender}: {text}".to_string())
+        );
     }
 
     #[test]
-    fn cant_build_option_diagnostic_flag_with_export_type() {
+    fn cant_build_option_txt_template_html() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-f", "txt"];
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--txt-template",
+            "{text}",
+        ];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
@@ -474,9 +3805,15 @@ mod arg_tests {
     }
 
     #[test]
-    fn cant_build_option_diagnostic_flag_with_export_path() {
+    fn cant_build_option_txt_template_no_text() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-o", "~/test"];
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--txt-template",
+            "[{time}] {sender}",
+        ];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
@@ -487,22 +3824,22 @@ mod arg_tests {
     }
 
     #[test]
-    fn cant_build_option_diagnostic_flag_with_attachment_manager() {
+    fn can_build_option_show_my_number() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-c", "compatible"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--show-my-number"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args).unwrap();
 
-        assert!(actual.is_err());
+        assert!(actual.show_my_number);
     }
 
     #[test]
-    fn cant_build_option_diagnostic_flag_with_start_date() {
+    fn cant_build_option_show_my_number_caller_id() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-s", "2020-01-01"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-i", "--show-my-number"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
@@ -513,22 +3850,22 @@ mod arg_tests {
     }
 
     #[test]
-    fn cant_build_option_diagnostic_flag_with_end() {
+    fn can_build_option_merge_services() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-e", "2020-01-01"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--merge-services"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args).unwrap();
 
-        assert!(actual.is_err());
+        assert!(actual.merge_services);
     }
 
     #[test]
-    fn cant_build_option_diagnostic_flag_with_caller_id() {
+    fn cant_build_option_merge_services_no_export() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-d", "-i"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--merge-services"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
@@ -539,74 +3876,48 @@ mod arg_tests {
     }
 
     #[test]
-    fn can_build_option_export_html() {
-        // Cleanup existing temp data
-        let _ = fs::remove_file("/tmp/orphaned.html");
-
+    fn can_build_option_manifest() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "-o", "/tmp"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--manifest"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
         let actual = Options::from_args(&args).unwrap();
 
-        // Expected data
-        let tmp_dir = String::from("/tmp");
-        let expected = Options {
-            db_path: default_db_path(),
-            attachment_root: None,
-            attachment_manager: AttachmentManager::default(),
-            diagnostic: false,
-            export_type: Some(ExportType::Html),
-            export_path: validate_path(Some(&tmp_dir), &None).unwrap(),
-            query_context: QueryContext::default(),
-            no_lazy: false,
-            custom_name: None,
-            use_caller_id: false,
-            platform: Platform::default(),
-            ignore_disk_space: false,
-        };
-
-        assert_eq!(actual, expected);
+        assert!(actual.manifest);
     }
 
     #[test]
-    fn can_build_option_export_txt_no_lazy() {
-        // Cleanup existing temp data
-        let _ = fs::remove_file("/tmp/orphaned.txt");
+    fn cant_build_option_manifest_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--manifest"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
 
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_animate_effects() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-l"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--animate-effects"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
         let actual = Options::from_args(&args).unwrap();
 
-        // Expected data
-        let expected = Options {
-            db_path: default_db_path(),
-            attachment_root: None,
-            attachment_manager: AttachmentManager::default(),
-            diagnostic: false,
-            export_type: Some(ExportType::Txt),
-            export_path: validate_path(None, &None).unwrap(),
-            query_context: QueryContext::default(),
-            no_lazy: true,
-            custom_name: None,
-            use_caller_id: false,
-            platform: Platform::default(),
-            ignore_disk_space: false,
-        };
-
-        assert_eq!(actual, expected);
+        assert!(actual.animate_effects);
     }
 
     #[test]
-    fn cant_build_option_attachment_manager_no_export_type() {
+    fn cant_build_option_animate_effects_txt() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-c", "compatible"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--animate-effects"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
@@ -617,22 +3928,22 @@ mod arg_tests {
     }
 
     #[test]
-    fn cant_build_option_export_path_no_export_type() {
+    fn can_build_option_media_grid() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-o", "~/test"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--media-grid"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args).unwrap();
 
-        assert!(actual.is_err());
+        assert!(actual.media_grid);
     }
 
     #[test]
-    fn cant_build_option_start_date_path_no_export_type() {
+    fn cant_build_option_media_grid_txt() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-s", "2020-01-01"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--media-grid"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
@@ -643,22 +3954,22 @@ mod arg_tests {
     }
 
     #[test]
-    fn cant_build_option_end_date_path_no_export_type() {
+    fn can_build_option_show_receipts() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-e", "2020-01-01"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--show-receipts"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args).unwrap();
 
-        assert!(actual.is_err());
+        assert!(actual.show_receipts);
     }
 
     #[test]
-    fn cant_build_option_invalid_date() {
+    fn cant_build_option_show_receipts_txt() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "-e", "2020-32-32"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--show-receipts"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
@@ -669,22 +3980,33 @@ mod arg_tests {
     }
 
     #[test]
-    fn cant_build_option_invalid_platform() {
+    fn can_build_option_date_phrases() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-a", "iPad"];
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--from-date",
+            "january 2020",
+            "--to-date",
+            "january 2020",
+        ];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args).unwrap();
 
-        assert!(actual.is_err());
+        let mut expected = QueryContext::default();
+        expected.set_start("2020-01-01").unwrap();
+        expected.set_end("2020-02-01").unwrap();
+        assert_eq!(actual.query_context, expected);
     }
 
     #[test]
-    fn cant_build_option_invalid_export_type() {
+    fn cant_build_option_invalid_date_phrase() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "pdf"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-s", "next tuesday"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
@@ -695,67 +4017,75 @@ mod arg_tests {
     }
 
     #[test]
-    fn can_build_option_custom_name() {
+    fn can_build_option_participants_only() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-m", "Name"];
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--participants-only",
+            "+15558675309",
+        ];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
         let actual = Options::from_args(&args).unwrap();
 
-        // Expected data
-        let expected = Options {
-            db_path: default_db_path(),
-            attachment_root: None,
-            attachment_manager: AttachmentManager::default(),
-            diagnostic: false,
-            export_type: Some(ExportType::Txt),
-            export_path: validate_path(None, &None).unwrap(),
-            query_context: QueryContext::default(),
-            no_lazy: false,
-            custom_name: Some("Name".to_string()),
-            use_caller_id: false,
-            platform: Platform::default(),
-            ignore_disk_space: false,
-        };
+        assert_eq!(actual.participants_only, Some("+15558675309".to_string()));
+    }
 
-        assert_eq!(actual, expected);
+    #[test]
+    fn can_build_option_exclude_orphaned() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--exclude-orphaned"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert!(actual.exclude_orphaned);
+        assert!(!actual.only_orphaned);
     }
 
     #[test]
-    fn can_build_option_caller_id() {
+    fn can_build_option_only_orphaned() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-i"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--only-orphaned"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
         let actual = Options::from_args(&args).unwrap();
 
-        // Expected data
-        let expected = Options {
-            db_path: default_db_path(),
-            attachment_root: None,
-            attachment_manager: AttachmentManager::default(),
-            diagnostic: false,
-            export_type: Some(ExportType::Txt),
-            export_path: validate_path(None, &None).unwrap(),
-            query_context: QueryContext::default(),
-            no_lazy: false,
-            custom_name: None,
-            use_caller_id: true,
-            platform: Platform::default(),
-            ignore_disk_space: false,
-        };
+        assert!(!actual.exclude_orphaned);
+        assert!(actual.only_orphaned);
+    }
 
-        assert_eq!(actual, expected);
+    #[test]
+    fn cant_build_option_exclude_and_only_orphaned() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--exclude-orphaned",
+            "--only-orphaned",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
     }
 
     #[test]
-    fn cant_build_option_custom_name_and_caller_id() {
+    fn cant_build_option_exclude_orphaned_no_export() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-m", "Name", "-i"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--exclude-orphaned"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
@@ -766,9 +4096,85 @@ mod arg_tests {
     }
 
     #[test]
-    fn cant_build_option_caller_id_no_export() {
+    fn cant_build_option_participants_only_no_export() {
         // Get matches from sample args
-        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "-m", "Name", "-i"];
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--participants-only", "+15558675309"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_show_removed_reactions_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--show-removed-reactions"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args);
+
+        assert!(actual.is_err());
+    }
+}
+
+#[cfg(test)]
+mod limit_tests {
+    use crate::app::options::{get_command, parse_limit, Options};
+
+    #[test]
+    fn can_parse_limit() {
+        let limit = String::from("10");
+        assert_eq!(parse_limit(Some(&limit), "limit").unwrap(), Some(10));
+    }
+
+    #[test]
+    fn can_parse_no_limit() {
+        assert_eq!(parse_limit(None, "limit").unwrap(), None);
+    }
+
+    #[test]
+    fn cant_parse_zero_limit() {
+        let limit = String::from("0");
+        assert!(parse_limit(Some(&limit), "limit").is_err());
+    }
+
+    #[test]
+    fn cant_parse_invalid_limit() {
+        let limit = String::from("ten");
+        assert!(parse_limit(Some(&limit), "limit").is_err());
+    }
+
+    #[test]
+    fn can_build_option_limits() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--limit",
+            "100",
+            "--limit-per-chat",
+            "5",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args).unwrap();
+
+        assert_eq!(actual.limit, Some(100));
+        assert_eq!(actual.limit_per_chat, Some(5));
+    }
+
+    #[test]
+    fn cant_build_option_limit_no_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--limit", "100"];
         let command = get_command();
         let args = command.get_matches_from(cli_args);
 
@@ -787,9 +4193,10 @@ mod path_tests {
 
     use crate::app::{
         export_type::ExportType,
-        options::{validate_path, DEFAULT_OUTPUT_DIR},
+        last_export::write_last_export,
+        options::{get_command, validate_path, Options, DEFAULT_OUTPUT_DIR},
     };
-    use imessage_database::util::dirs::home;
+    use imessage_database::util::{dirs::home, platform::Platform};
 
     #[test]
     fn can_validate_empty() {
@@ -800,7 +4207,7 @@ mod path_tests {
         let export_path = Some(&tmp);
         let export_type = Some(ExportType::Txt);
 
-        let result = validate_path(export_path, &export_type.as_ref());
+        let result = validate_path(export_path, &export_type.as_ref(), false);
 
         assert_eq!(result.unwrap(), PathBuf::from("/tmp"));
     }
@@ -814,7 +4221,7 @@ mod path_tests {
         let export_path = Some(&tmp);
         let export_type = Some(ExportType::Txt);
 
-        let result = validate_path(export_path, &export_type.as_ref());
+        let result = validate_path(export_path, &export_type.as_ref(), false);
 
         let mut tmp = PathBuf::from("/tmp");
         tmp.push("fake1.html");
@@ -834,7 +4241,7 @@ mod path_tests {
         let export_path = Some(&tmp);
         let export_type = Some(ExportType::Txt);
 
-        let result = validate_path(export_path, &export_type.as_ref());
+        let result = validate_path(export_path, &export_type.as_ref(), false);
 
         let mut tmp = PathBuf::from("/tmp");
         tmp.push("fake2.txt");
@@ -845,16 +4252,64 @@ mod path_tests {
         fs::remove_file(&tmp).unwrap();
     }
 
+    #[test]
+    fn can_validate_same_type_since_last_export() {
+        let dir = std::env::temp_dir().join("imessage-exporter-since-last-export-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::File::create(dir.join("fake.txt")).unwrap();
+        let path = dir.to_string_lossy().to_string();
+        let export_type = Some(ExportType::Txt);
+
+        // Without a recorded export, existing files are still protected
+        assert!(validate_path(Some(&path), &export_type.as_ref(), true).is_err());
+
+        write_last_export(&dir, 674526582885055488).unwrap();
+        assert_eq!(
+            validate_path(Some(&path), &export_type.as_ref(), true).unwrap(),
+            dir
+        );
+        assert!(validate_path(Some(&path), &export_type.as_ref(), false).is_err());
+    }
+
     #[test]
     fn can_validate_none() {
         let export_path = None;
         let export_type = None;
 
-        let result = validate_path(export_path, &export_type);
+        let result = validate_path(export_path, &export_type, false);
 
         assert_eq!(
             result.unwrap(),
-            PathBuf::from(&format!("{}/{DEFAULT_OUTPUT_DIR}", home()))
+            PathBuf::from(home()).join(DEFAULT_OUTPUT_DIR)
+        );
+    }
+
+    #[test]
+    fn can_get_db_path_macos() {
+        let options = Options {
+            db_path: PathBuf::from("chat.db"),
+            ..Options::from_args(&get_command().get_matches_from(["imessage-exporter", "-d"]))
+                .unwrap()
+        };
+
+        assert_eq!(options.get_db_path(), PathBuf::from("chat.db"));
+    }
+
+    #[test]
+    fn can_get_db_path_ios() {
+        let options = Options {
+            db_path: PathBuf::from("backup"),
+            platform: Platform::iOS,
+            ..Options::from_args(&get_command().get_matches_from(["imessage-exporter", "-d"]))
+                .unwrap()
+        };
+
+        assert_eq!(
+            options.get_db_path(),
+            PathBuf::from("backup")
+                .join("3d")
+                .join("3d0d7e5fb2ce288813306e4d4636395e047a3d28")
         );
     }
 }
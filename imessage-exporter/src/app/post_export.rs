@@ -0,0 +1,100 @@
+/*!
+ Runs a user-provided shell command after an export finishes, i.e. to compress or upload the export.
+
+ The command is passed to the system shell with the same privileges as `imessage-exporter`, so it
+ should only ever come from a trusted source. The export path is quoted before it is substituted
+ into the command, but the rest of the command is run exactly as written.
+*/
+
+use std::{
+    io::Error,
+    path::Path,
+    process::{Command, ExitStatus},
+};
+
+/// Placeholder in the command that is replaced with the quoted export path
+pub const EXPORT_PATH_PLACEHOLDER: &str = "{export_path}";
+
+/// Substitute the export path into the command template
+pub fn build_command(template: &str, export_path: &Path) -> String {
+    template.replace(
+        EXPORT_PATH_PLACEHOLDER,
+        &quote(&export_path.to_string_lossy()),
+    )
+}
+
+/// Quote a value so the shell treats it as a single argument
+#[cfg(not(target_family = "windows"))]
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Quote a value so the shell treats it as a single argument
+#[cfg(target_family = "windows")]
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// Run the command in the system shell, waiting for it to finish
+///
+/// The command inherits the exporter's standard input, output, and error streams.
+pub fn run(template: &str, export_path: &Path) -> Result<ExitStatus, Error> {
+    let command = build_command(template, export_path);
+
+    #[cfg(not(target_family = "windows"))]
+    let status = Command::new("sh").arg("-c").arg(&command).status();
+    #[cfg(target_family = "windows")]
+    let status = Command::new("cmd").arg("/C").arg(&command).status();
+
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::app::post_export::{build_command, run};
+
+    #[test]
+    #[cfg(not(target_family = "windows"))]
+    fn can_build_command() {
+        assert_eq!(
+            build_command(
+                "tar -czf export.tgz {export_path}",
+                Path::new("/tmp/export")
+            ),
+            "tar -czf export.tgz '/tmp/export'"
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_family = "windows"))]
+    fn can_build_command_quotes_path() {
+        assert_eq!(
+            build_command("ls {export_path}", Path::new("/tmp/it's; rm -rf ~")),
+            "ls '/tmp/it'\\''s; rm -rf ~'"
+        );
+    }
+
+    #[test]
+    fn can_build_command_without_placeholder() {
+        assert_eq!(
+            build_command("echo done", Path::new("/tmp/export")),
+            "echo done"
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_family = "windows"))]
+    fn can_run_command() {
+        let status = run("test -d {export_path}", Path::new("/tmp")).unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    #[cfg(not(target_family = "windows"))]
+    fn can_report_failed_command() {
+        let status = run("exit 3", Path::new("/tmp")).unwrap();
+        assert_eq!(status.code(), Some(3));
+    }
+}
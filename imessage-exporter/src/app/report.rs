@@ -0,0 +1,284 @@
+/*!
+ Contains data structures used to describe and render reports on database contents.
+*/
+
+use std::{collections::BTreeMap, fmt::Display};
+
+/// Column headers for the one-time passcode report
+pub const OTP_HEADERS: [&str; 3] = ["Date", "Sender", "Code"];
+/// Column headers for the conversations report
+pub const CONVERSATION_HEADERS: [&str; 5] =
+    ["ID", "Name", "Participants", "Messages", "Last Activity"];
+/// Column headers for the report of reactions sent by the database owner
+pub const MY_REACTION_HEADERS: [&str; 4] = ["Date", "Reaction", "Sender", "Message"];
+/// Column headers for the reaction statistics report
+pub const REACTION_STATS_HEADERS: [&str; 5] = [
+    "Conversation",
+    "Given",
+    "Received",
+    "Most Common",
+    "Most Reacted Message",
+];
+/// The maximum number of characters of a message to show in a report
+const SNIPPET_LENGTH: usize = 50;
+/// The character that marks where an attachment appears in message text
+const ATTACHMENT_CHAR: char = '\u{FFFC}';
+
+/// Represents the type of report to generate
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ReportType {
+    /// List messages that contain detected one-time passcodes
+    Otp,
+    /// List conversations with their participants, message count, and most recent activity
+    Conversations,
+    /// List reactions sent by the database owner with the messages they reacted to
+    MyReactions,
+    /// Summarize the reactions given and received in each conversation
+    ReactionStats,
+}
+
+impl ReportType {
+    /// Given user's input, return a variant if the input matches one
+    pub fn from_cli(report: &str) -> Option<Self> {
+        match report.to_lowercase().as_str() {
+            "otp" => Some(Self::Otp),
+            "conversations" => Some(Self::Conversations),
+            "my-reactions" => Some(Self::MyReactions),
+            "reaction-stats" => Some(Self::ReactionStats),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ReportType {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportType::Otp => write!(fmt, "otp"),
+            ReportType::Conversations => write!(fmt, "conversations"),
+            ReportType::MyReactions => write!(fmt, "my-reactions"),
+            ReportType::ReactionStats => write!(fmt, "reaction-stats"),
+        }
+    }
+}
+
+/// Tally of the reactions in a conversation
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReactionStats {
+    /// The number of reactions sent by the database owner
+    pub given: u64,
+    /// The number of reactions others sent to the database owner's messages
+    pub received: u64,
+    /// The number of times each type of reaction was sent, keyed by its name
+    pub counts: BTreeMap<String, u64>,
+    /// The number of reactions on the most-reacted-to message, with a snippet of that message
+    pub most_reacted: Option<(usize, String)>,
+}
+
+impl ReactionStats {
+    /// Count a reaction of type `reaction`, sent by the database owner if `from_me`, to the database owner if `to_me`
+    pub fn add_reaction(&mut self, reaction: String, from_me: bool, to_me: bool) {
+        if from_me {
+            self.given += 1;
+        } else if to_me {
+            self.received += 1;
+        }
+        *self.counts.entry(reaction).or_default() += 1;
+    }
+
+    /// Keep a message with `count` reactions if it has more than the current most-reacted-to message
+    ///
+    /// `snippet` is only called when the message is kept.
+    pub fn add_target(&mut self, count: usize, snippet: impl FnOnce() -> String) {
+        if self
+            .most_reacted
+            .as_ref()
+            .map_or(count > 0, |(most, _)| count > *most)
+        {
+            self.most_reacted = Some((count, snippet()));
+        }
+    }
+
+    /// Get the type of reaction sent most often, preferring the first name alphabetically in a tie
+    pub fn most_common(&self) -> Option<&str> {
+        self.counts
+            .iter()
+            .rev()
+            .max_by_key(|(_, count)| **count)
+            .map(|(reaction, _)| reaction.as_str())
+    }
+
+    /// Get the total number of reactions of any type
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+}
+
+/// Shorten message text to a single line of at most [`SNIPPET_LENGTH`] characters for display in a report
+///
+/// Attachments are shown as `placeholder`.
+pub fn format_snippet(text: &str, placeholder: &str) -> String {
+    let text = text
+        .replace(ATTACHMENT_CHAR, placeholder)
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+    if text.chars().count() > SNIPPET_LENGTH {
+        let mut snippet: String = text.chars().take(SNIPPET_LENGTH).collect();
+        snippet.push_str("...");
+        return snippet;
+    }
+    text
+}
+
+/// Render rows of data as a plain text table, padding each column to its widest cell
+pub fn format_table<const N: usize>(headers: [&str; N], rows: &[[String; N]]) -> String {
+    let mut widths = headers.map(|header| header.chars().count());
+    rows.iter().for_each(|row| {
+        row.iter()
+            .enumerate()
+            .for_each(|(idx, cell)| widths[idx] = widths[idx].max(cell.chars().count()))
+    });
+
+    let format_row = |cells: [&str; N]| {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<String>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut table = format_row(headers);
+    table.push('\n');
+    table.push_str(
+        &widths
+            .iter()
+            .map(|width| "-".repeat(*width))
+            .collect::<Vec<String>>()
+            .join("-+-"),
+    );
+    table.push('\n');
+
+    rows.iter().for_each(|row| {
+        table.push_str(&format_row(row.each_ref().map(String::as_str)));
+        table.push('\n');
+    });
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::report::{
+        format_snippet, format_table, ReactionStats, ReportType, OTP_HEADERS,
+    };
+
+    #[test]
+    fn can_parse_any_case() {
+        assert_eq!(ReportType::from_cli("otp"), Some(ReportType::Otp));
+        assert_eq!(ReportType::from_cli("OTP"), Some(ReportType::Otp));
+        assert_eq!(
+            ReportType::from_cli("Conversations"),
+            Some(ReportType::Conversations)
+        );
+        assert_eq!(
+            ReportType::from_cli("my-reactions"),
+            Some(ReportType::MyReactions)
+        );
+        assert_eq!(
+            ReportType::from_cli("reaction-stats"),
+            Some(ReportType::ReactionStats)
+        );
+    }
+
+    #[test]
+    fn cant_parse_invalid() {
+        assert!(ReportType::from_cli("codes").is_none());
+        assert!(ReportType::from_cli("").is_none());
+    }
+
+    #[test]
+    fn can_tally_reactions() {
+        let mut stats = ReactionStats::default();
+        stats.add_reaction("Loved".to_string(), true, false);
+        stats.add_reaction("Liked".to_string(), false, true);
+        stats.add_reaction("Loved".to_string(), false, true);
+        stats.add_reaction("Laughed".to_string(), false, false);
+
+        assert_eq!(stats.given, 1);
+        assert_eq!(stats.received, 2);
+        assert_eq!(stats.total(), 4);
+        assert_eq!(stats.most_common(), Some("Loved"));
+    }
+
+    #[test]
+    fn can_tally_reactions_tie() {
+        let mut stats = ReactionStats::default();
+        stats.add_reaction("Loved".to_string(), true, false);
+        stats.add_reaction("Emphasized".to_string(), true, false);
+
+        assert_eq!(stats.most_common(), Some("Emphasized"));
+        assert_eq!(ReactionStats::default().most_common(), None);
+    }
+
+    #[test]
+    fn can_track_most_reacted() {
+        let mut stats = ReactionStats::default();
+        stats.add_target(2, || "first".to_string());
+        stats.add_target(1, || unreachable!());
+        stats.add_target(2, || unreachable!());
+        assert_eq!(stats.most_reacted, Some((2, "first".to_string())));
+
+        stats.add_target(3, || "second".to_string());
+        assert_eq!(stats.most_reacted, Some((3, "second".to_string())));
+    }
+
+    #[test]
+    fn can_format_snippet() {
+        assert_eq!(
+            format_snippet("Look\n\u{FFFC} at this", "[attachment]"),
+            "Look [attachment] at this"
+        );
+    }
+
+    #[test]
+    fn can_format_snippet_truncated() {
+        let text = "a".repeat(60);
+        assert_eq!(
+            format_snippet(&text, "[attachment]"),
+            format!("{}...", "a".repeat(50))
+        );
+    }
+
+    #[test]
+    fn can_format_table() {
+        let rows = [
+            [
+                "May 17, 2022  5:29:42 PM".to_string(),
+                "12345".to_string(),
+                "000123".to_string(),
+            ],
+            [
+                "May 18, 2022  8:01:10 AM".to_string(),
+                "Example Bank".to_string(),
+                "9876, 5432".to_string(),
+            ],
+        ];
+
+        let expected = "\
+Date                     | Sender       | Code
+-------------------------+--------------+-----------
+May 17, 2022  5:29:42 PM | 12345        | 000123
+May 18, 2022  8:01:10 AM | Example Bank | 9876, 5432
+";
+        assert_eq!(format_table(OTP_HEADERS, &rows), expected);
+    }
+
+    #[test]
+    fn can_format_empty_table() {
+        let expected = "Date | Sender | Code\n-----+--------+-----\n";
+        assert_eq!(format_table(OTP_HEADERS, &[]), expected);
+    }
+}
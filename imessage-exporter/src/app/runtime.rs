@@ -1,8 +1,10 @@
 use std::{
+    borrow::Cow,
+    cell::RefCell,
     cmp::min,
-    collections::{BTreeSet, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs::create_dir_all,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use fdlimit::raise_fd_limit;
@@ -11,34 +13,90 @@ use rusqlite::Connection;
 
 use crate::{
     app::{
-        attachment_manager::AttachmentManager, converter::Converter, error::RuntimeError,
-        export_type::ExportType, options::Options, sanitizers::sanitize_filename,
+        annotations::read_annotations,
+        attachment_manager::{AttachmentManager, ATTACHMENT_PLACEHOLDER},
+        conversations::Conversation,
+        converter::Converter,
+        db_copy::DatabaseCopy,
+        error::RuntimeError,
+        export_type::{DirectoryLayout, ExportType},
+        last_export::{write_last_export, LAST_EXPORT_FILENAME},
+        manifest::{write_manifest, MANIFEST_FILENAME},
+        options::Options,
+        post_export,
+        progress::build_progress_bar_export,
+        report::{
+            format_snippet, format_table, ReactionStats, ReportType, CONVERSATION_HEADERS,
+            MY_REACTION_HEADERS, OTP_HEADERS, REACTION_STATS_HEADERS,
+        },
+        sanitizers::{mask_text, normalize_newlines, sanitize_filename},
+        timestamp_format::TimestampFormat,
+        typedstream_dump::format_typedstream_dump,
     },
-    Exporter, HTML, TXT,
+    DayOne, Exporter, HTML, IRC, JSON, NDJSON, TXT,
 };
 
 use imessage_database::{
     error::table::TableError,
+    message_types::{
+        edited::UnsentTiming,
+        sticker::StickerSource,
+        variants::{Announcement, Variant},
+    },
     tables::{
-        attachment::Attachment,
-        chat::Chat,
+        attachment::{Attachment, MediaType, TransferState},
+        chat::{Chat, ServiceMix},
         chat_handle::ChatToHandle,
         handle::Handle,
-        messages::Message,
+        messages::{models::BubbleComponent, Message},
+        schema::Schema,
         table::{
-            get_connection, get_db_size, Cacheable, Deduplicate, Diagnostic, ATTACHMENTS_DIR,
-            MAX_LENGTH, ME, ORPHANED, UNKNOWN,
+            get_connection, get_db_size, Cacheable, Deduplicate, Diagnostic, Table,
+            ATTACHMENTS_DIR, FITNESS_RECEIVER, FITNESS_SENDER, MAX_LENGTH, ME, ORPHANED, UNKNOWN,
+            YOU,
         },
     },
-    util::{dates::get_offset, size::format_file_size},
+    util::{
+        dates::{format, get_local_time, get_offset, readable_relative_to_now},
+        emoji::replace_shortcodes,
+        size::format_file_size,
+    },
 };
 
+/// The name of the file group chats are written to when using `--flatten-groups`
+pub const FLATTENED_GROUPS: &str = "groups";
+
+/// The most resolved attachment paths to keep cached before the cache is emptied
+const MAX_CACHED_ATTACHMENT_PATHS: usize = 256;
+
+/// What an exporter should do with a message it read, as decided by [`Config::filter_message`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum MessageFilter {
+    /// Export the message
+    Export,
+    /// Skip the message and read the next one
+    Skip,
+    /// Stop reading messages because `--limit` was reached
+    Stop,
+}
+
+/// Number of messages written by an export, used to enforce `--limit` and `--limit-per-chat`
+#[derive(Debug, Default)]
+pub struct ExportCounts {
+    /// Number of messages exported from every conversation
+    pub total: u64,
+    /// Map of deduplicated chat ID to the number of messages exported from it
+    pub per_chat: HashMap<Option<i32>, u64>,
+}
+
 /// Stores the application state and handles application lifecycle
 pub struct Config {
     /// Map of chatroom ID to chatroom information
     pub chatrooms: HashMap<i32, Chat>,
     // Map of chatroom ID to an internal unique chatroom ID
     pub real_chatrooms: HashMap<i32, i32>,
+    /// Map of chatroom ID to the chatroom ID used to name its merged conversation, if conversations are merged
+    pub merged_chatrooms: HashMap<i32, i32>,
     /// Map of chatroom ID to chatroom participants
     pub chatroom_participants: HashMap<i32, BTreeSet<i32>>,
     /// Map of participant ID to contact info
@@ -53,8 +111,18 @@ pub struct Config {
     pub offset: i64,
     /// The connection we use to query the database
     pub db: Connection,
+    /// The columns and tables present in `db`, detected once when the connection is opened
+    pub schema: Schema,
     /// Converter type used when converting image files
     pub converter: Option<Converter>,
+    /// Map of an attachment's raw filename to its resolved path on disk, for the most recently rendered attachments
+    pub attachment_paths: RefCell<HashMap<String, Option<String>>>,
+    /// Map of message GUID to the note passed for it with `--annotations`
+    pub annotations: HashMap<String, String>,
+    /// The temporary copy `db` reads from when `--copy-db-first` is set
+    ///
+    /// Declared after `db` so the connection is closed before the copy is removed.
+    pub db_copy: Option<DatabaseCopy>,
 }
 
 impl Config {
@@ -74,6 +142,269 @@ impl Config {
         }
     }
 
+    /// Get the deduplicated chat ID for a message, if it belongs to a known chat
+    pub fn conversation_id(&self, message: &Message) -> Option<i32> {
+        message
+            .chat_id
+            .or(message.deleted_from)
+            .and_then(|chat_id| self.real_chatrooms.get(&chat_id).copied())
+    }
+
+    /// Get the first and last message dates across every chat that shares the given chat's deduplicated ID
+    ///
+    /// Returns `None` if none of those chats contain any messages.
+    pub fn conversation_activity(&self, chatroom: &Chat) -> Option<(i64, i64)> {
+        let real_id = self.real_chatrooms.get(&chatroom.rowid)?;
+        self.real_chatrooms
+            .iter()
+            .filter(|(_, id)| *id == real_id)
+            .filter_map(|(chat_id, _)| self.chatrooms.get(chat_id))
+            .filter_map(|chat| Some((chat.first_message_date?, chat.last_message_date?)))
+            .reduce(|(first, last), (chat_first, chat_last)| {
+                (first.min(chat_first), last.max(chat_last))
+            })
+    }
+
+    /// Get which services were used across every chat that shares the given chat's deduplicated ID
+    ///
+    /// Returns `None` if none of those chats contain any iMessages or SMS.
+    pub fn conversation_service_mix(&self, chatroom: &Chat) -> Option<ServiceMix> {
+        let Some(real_id) = self.real_chatrooms.get(&chatroom.rowid) else {
+            return chatroom.service_mix();
+        };
+        let chats: Vec<&Chat> = self
+            .real_chatrooms
+            .iter()
+            .filter(|(_, id)| *id == real_id)
+            .filter_map(|(chat_id, _)| self.chatrooms.get(chat_id))
+            .collect();
+        ServiceMix::from_services(
+            chats.iter().any(|chat| chat.has_imessage),
+            chats.iter().any(|chat| chat.has_sms),
+        )
+    }
+
+    /// Get the identifiers of every chat that shares the given chat's deduplicated ID, ordered by chat ID
+    ///
+    /// Group chats with the same participants are merged into one conversation but keep distinct identifiers, i.e. `chat123456789`.
+    pub fn conversation_identifiers<'b>(&'b self, chatroom: &'b Chat) -> Vec<&'b str> {
+        let Some(real_id) = self.real_chatrooms.get(&chatroom.rowid) else {
+            return vec![&chatroom.chat_identifier];
+        };
+
+        let mut chat_ids: Vec<&i32> = self
+            .real_chatrooms
+            .iter()
+            .filter(|(_, id)| *id == real_id)
+            .map(|(chat_id, _)| chat_id)
+            .collect();
+        chat_ids.sort();
+
+        let mut identifiers: Vec<&str> = vec![];
+        for chat in chat_ids
+            .iter()
+            .filter_map(|chat_id| self.chatrooms.get(chat_id))
+        {
+            if !identifiers.contains(&chat.chat_identifier.as_str()) {
+                identifiers.push(&chat.chat_identifier);
+            }
+        }
+        if identifiers.is_empty() {
+            identifiers.push(&chatroom.chat_identifier);
+        }
+        identifiers
+    }
+
+    /// List every conversation in the database, most recently active first
+    ///
+    /// Chats that dedupe to the same conversation are combined into a single [`Conversation`].
+    pub fn conversations(&self) -> Result<Vec<Conversation>, RuntimeError> {
+        let message_counts = Chat::message_counts_with_schema(&self.db, &self.schema)
+            .map_err(RuntimeError::DatabaseError)?;
+
+        // Group each chat under its deduplicated chat ID
+        let mut grouped: BTreeMap<i32, Vec<&Chat>> = BTreeMap::new();
+        for (chat_id, real_id) in &self.real_chatrooms {
+            if let Some(chat) = self.chatrooms.get(chat_id) {
+                grouped.entry(*real_id).or_default().push(chat);
+            }
+        }
+
+        let mut conversations: Vec<Conversation> = grouped
+            .into_iter()
+            .filter_map(|(id, mut chats)| {
+                chats.sort_by_key(|chat| chat.rowid);
+                let first_chat = *chats.first()?;
+
+                let participant_ids: BTreeSet<i32> = chats
+                    .iter()
+                    .filter_map(|chat| self.chatroom_participants.get(&chat.rowid))
+                    .flatten()
+                    .copied()
+                    .collect();
+                let participants: BTreeSet<String> = participant_ids
+                    .iter()
+                    .filter_map(|handle_id| self.participants.get(handle_id))
+                    .cloned()
+                    .collect();
+
+                let display_name = match chats.iter().find_map(|chat| chat.display_name()) {
+                    Some(name) => name.to_string(),
+                    None => self.filename_from_participants(&participant_ids),
+                };
+
+                Some(Conversation {
+                    id,
+                    display_name,
+                    participants: participants.into_iter().collect(),
+                    message_count: chats
+                        .iter()
+                        .filter_map(|chat| message_counts.get(&chat.rowid))
+                        .sum(),
+                    last_activity: self.conversation_activity(first_chat).map(|(_, last)| last),
+                })
+            })
+            .collect();
+
+        conversations.sort_by(|a, b| {
+            b.last_activity
+                .cmp(&a.last_activity)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        Ok(conversations)
+    }
+
+    /// Get the IDs of every chat that includes the participant selected with `--participants-only`
+    ///
+    /// Returns `None` if no participant filter is set. Handles are compared after normalization,
+    /// so `+1 (555) 867-5309` matches `+15558675309`.
+    pub fn participant_chatrooms(&self) -> Option<HashSet<i32>> {
+        let handle = normalize_handle(self.options.participants_only.as_ref()?);
+        let handle_ids: HashSet<&i32> = self
+            .participants
+            .iter()
+            .filter(|(_, participant)| normalize_handle(participant) == handle)
+            .map(|(handle_id, _)| handle_id)
+            .collect();
+
+        let chatrooms: HashSet<i32> = self
+            .chatroom_participants
+            .iter()
+            .filter(|(_, participants)| participants.iter().any(|id| handle_ids.contains(id)))
+            .map(|(chat_id, _)| *chat_id)
+            .collect();
+
+        if chatrooms.is_empty() {
+            eprintln!("No conversations include {handle}!");
+        }
+        Some(chatrooms)
+    }
+
+    /// Determine if a message should be skipped because of `--exclude-orphaned` or `--only-orphaned`
+    ///
+    /// A message is orphaned if it does not belong to a known conversation.
+    pub fn skip_orphaned(&self, message: &Message) -> bool {
+        if !self.options.exclude_orphaned && !self.options.only_orphaned {
+            return false;
+        }
+        let is_orphaned = self.conversation(message).is_none();
+        (self.options.exclude_orphaned && is_orphaned)
+            || (self.options.only_orphaned && !is_orphaned)
+    }
+
+    /// Determine if a message should be skipped because `--only-reported` is enabled and it was not reported as junk
+    pub fn skip_unreported(&self, message: &Message) -> bool {
+        self.options.only_reported && !message.is_reported_junk()
+    }
+
+    /// Determine if a message belongs to one of the chats selected by [`Config::participant_chatrooms`]
+    pub fn in_chatrooms(message: &Message, chatrooms: &Option<HashSet<i32>>) -> bool {
+        match chatrooms {
+            Some(chatrooms) => message
+                .chat_id
+                .or(message.deleted_from)
+                .is_some_and(|chat_id| chatrooms.contains(&chat_id)),
+            None => true,
+        }
+    }
+
+    /// Decide if a message should be exported, applying the conversation filters and message limits
+    ///
+    /// Messages outside the chats selected by [`Config::participant_chatrooms`], or skipped by [`Config::skip_orphaned`] or
+    /// [`Config::skip_unreported`], are skipped. If `counted` is `true`, the message counts towards `--limit` and
+    /// `--limit-per-chat`: the export stops once `--limit` messages were exported, the message is skipped once its
+    /// conversation reached `--limit-per-chat`, and otherwise `counts` is updated.
+    pub fn filter_message(
+        &self,
+        message: &Message,
+        chatrooms: &Option<HashSet<i32>>,
+        counts: &mut ExportCounts,
+        counted: bool,
+    ) -> MessageFilter {
+        if !Config::in_chatrooms(message, chatrooms)
+            || self.skip_orphaned(message)
+            || self.skip_unreported(message)
+        {
+            return MessageFilter::Skip;
+        }
+
+        if counted {
+            if self
+                .options
+                .limit
+                .is_some_and(|limit| counts.total >= limit)
+            {
+                return MessageFilter::Stop;
+            }
+
+            let chat_count = counts
+                .per_chat
+                .entry(self.conversation_id(message))
+                .or_insert(0);
+            if self
+                .options
+                .limit_per_chat
+                .is_some_and(|limit| *chat_count >= limit)
+            {
+                return MessageFilter::Skip;
+            }
+            *chat_count += 1;
+            counts.total += 1;
+        }
+
+        MessageFilter::Export
+    }
+
+    /// Get one chat for each conversation that had no messages exported, for `--include-empty-chats`
+    ///
+    /// `exported` maps deduplicated chat IDs to the number of messages exported from them. Chats excluded by
+    /// [`Config::participant_chatrooms`] are skipped.
+    pub fn empty_chatrooms(
+        &self,
+        exported: &HashMap<Option<i32>, u64>,
+        chatrooms: &Option<HashSet<i32>>,
+    ) -> Vec<&Chat> {
+        let mut chats: Vec<&Chat> = self
+            .chatrooms
+            .values()
+            .filter(|chat| {
+                chatrooms
+                    .as_ref()
+                    .is_none_or(|chatrooms| chatrooms.contains(&chat.rowid))
+            })
+            .collect();
+        chats.sort_by_key(|chat| chat.rowid);
+
+        let mut seen = HashSet::new();
+        chats
+            .into_iter()
+            .filter(|chat| match self.real_chatrooms.get(&chat.rowid) {
+                Some(real_id) => !exported.contains_key(&Some(*real_id)) && seen.insert(*real_id),
+                None => false,
+            })
+            .collect()
+    }
+
     /// Get the attachment path for the current session
     pub fn attachment_path(&self) -> PathBuf {
         let mut path = self.options.export_path.clone();
@@ -104,22 +435,132 @@ impl Config {
                 }
                 path.display().to_string()
             }
-            None => attachment
-                .resolved_attachment_path(
-                    &self.options.platform,
-                    &self.options.db_path,
-                    self.options.attachment_root.as_deref(),
-                )
+            None => self
+                .resolved_attachment_path(attachment)
                 .unwrap_or(attachment.filename().to_string()),
         }
     }
 
+    /// Generate a file path for an attachment relative to the file its message is written to
+    ///
+    /// Copied attachments are relative to the export directory, so messages written into a `--directory-layout` subdirectory step out of it first.
+    pub fn message_attachment_link(&self, attachment: &Attachment, message: &Message) -> String {
+        let path = self.message_attachment_path(attachment);
+        let is_copied = attachment
+            .copied_path
+            .as_ref()
+            .is_some_and(|copied| copied.starts_with(&self.options.export_path));
+        if is_copied
+            && self.conversation(message).is_some()
+            && self.year_directory(&message.date).is_some()
+        {
+            return format!("../{path}");
+        }
+        path
+    }
+
+    /// Resolve the path to an attachment on disk, possibly using cached data.
+    ///
+    /// Resolving a path can be expensive (i.e. hashing on iOS), and the same attachment is resolved several times while
+    /// its message is rendered, so results are memoized by the attachment's raw filename. Filenames rarely repeat across
+    /// messages, so the cache is emptied once it holds [`MAX_CACHED_ATTACHMENT_PATHS`] entries instead of growing for the
+    /// whole export.
+    pub fn resolved_attachment_path(&self, attachment: &Attachment) -> Option<String> {
+        let key = attachment.filename.as_ref()?;
+        if let Some(path) = self.attachment_paths.borrow().get(key) {
+            return path.clone();
+        }
+
+        let path = attachment.resolved_attachment_path(
+            &self.options.platform,
+            &self.options.db_path,
+            self.options.attachment_root.as_deref(),
+        );
+        let mut cache = self.attachment_paths.borrow_mut();
+        if cache.len() >= MAX_CACHED_ATTACHMENT_PATHS {
+            cache.clear();
+        }
+        cache.insert(key.to_string(), path.clone());
+        path
+    }
+
+    /// Get the attachments for a message
+    ///
+    /// If `--no-attachments` is enabled, this returns no attachments without querying the database.
+    pub fn attachments(&self, message: &Message) -> Result<Vec<Attachment>, TableError> {
+        if self.options.no_attachments {
+            return Ok(vec![]);
+        }
+        Attachment::from_message(&self.db, message)
+    }
+
+    /// Determine if an attachment is an audio message that expired before it could be kept
+    ///
+    /// The attachment row remains after an audio message expires, but its file is removed from the disk.
+    pub fn is_expired_audio_message(&self, attachment: &Attachment) -> bool {
+        attachment.is_audio_message()
+            && self
+                .resolved_attachment_path(attachment)
+                .is_none_or(|path| !Path::new(&path).exists())
+    }
+
+    /// Get a single message by its GUID, with its text generated so it is ready to format
+    pub fn message_by_guid(&self, guid: &str) -> Result<Message, RuntimeError> {
+        let mut message = Message::from_guid_with_schema(guid, &self.db, &self.schema)
+            .map_err(RuntimeError::DatabaseError)?;
+        let _ = message.generate_text(&self.db);
+        Ok(message)
+    }
+
+    /// Describe an attachment whose transfer failed, if `--mark-failed-transfers` is enabled
+    ///
+    /// Failed transfers have no file on disk, so this notice replaces the attachment in the export.
+    pub fn failed_transfer(&self, attachment: &Attachment) -> Option<&'static str> {
+        if !self.options.mark_failed_transfers
+            || attachment.transfer_state() != TransferState::Failed
+        {
+            return None;
+        }
+        Some(if attachment.is_outgoing {
+            "Attachment failed to send"
+        } else {
+            "Attachment failed to download"
+        })
+    }
+
+    /// Determine the app a sticker was sent from, if it came from a sticker pack app
+    ///
+    /// Downloaded stickers may not have `attribution_info`, so fall back to the third-party app that sent the sticker's message.
+    pub fn sticker_source(&self, sticker: &Attachment, message: &Message) -> Option<StickerSource> {
+        if let Some(source) = sticker.get_sticker_source(&self.db) {
+            return Some(source);
+        }
+        if !sticker.is_sticker {
+            return None;
+        }
+        message
+            .parse_balloon_bundle_id()
+            .filter(|bundle_id| !bundle_id.starts_with("com.apple."))
+            .map(StickerSource::from_bundle_id)
+    }
+
     /// Get a filename for a chat, possibly using cached data.
     ///
     /// If the chat has an assigned name, use that, truncating if necessary.
     ///
     /// If it does not, first try and make a flat list of its members. Failing that, use the unique `chat_identifier` field.
     pub fn filename(&self, chatroom: &Chat) -> String {
+        if self.is_flattened(chatroom) {
+            return FLATTENED_GROUPS.to_string();
+        }
+
+        // Merged conversations share the name of a single chat
+        let chatroom = self
+            .merged_chatrooms
+            .get(&chatroom.rowid)
+            .and_then(|chat_id| self.chatrooms.get(chat_id))
+            .unwrap_or(chatroom);
+
         let filename = match &chatroom.display_name() {
             // If there is a display name, use that
             Some(name) => {
@@ -145,6 +586,76 @@ impl Config {
         sanitize_filename(&filename)
     }
 
+    /// Get the note passed for a message with `--annotations`, if any
+    pub fn annotation(&self, message: &Message) -> Option<&str> {
+        self.annotations.get(&message.guid).map(String::as_str)
+    }
+
+    /// Determine if a chat has more than one participant besides the database owner
+    pub fn is_group(&self, chatroom: &Chat) -> bool {
+        self.chatroom_participants
+            .get(&chatroom.rowid)
+            .is_some_and(|participants| participants.len() > 1)
+    }
+
+    /// Determine if a chat's messages are written to the [`FLATTENED_GROUPS`] file with `--flatten-groups`
+    pub fn is_flattened(&self, chatroom: &Chat) -> bool {
+        self.options.flatten_groups && self.is_group(chatroom)
+    }
+
+    /// Get the name of a group chat, falling back to its participants if it does not have one
+    pub fn group_name(&self, chatroom: &Chat) -> String {
+        match chatroom.display_name() {
+            Some(name) => name.to_string(),
+            None => self
+                .chatroom_participants
+                .get(&chatroom.rowid)
+                .map(|participants| self.filename_from_participants(participants))
+                .unwrap_or_else(|| chatroom.chat_identifier.clone()),
+        }
+    }
+
+    /// Get the filename a message is written to
+    ///
+    /// If conversations are split with `--split-by`, the period the message was sent in is appended to its chat's [`filename`](Config::filename).
+    /// With `--directory-layout year`, the file is placed in a directory named for the year the message was sent in.
+    pub fn message_filename(&self, chatroom: &Chat, message: &Message) -> String {
+        let filename = self.filename(chatroom);
+        match (
+            self.split_period(&message.date),
+            self.year_directory(&message.date),
+        ) {
+            (Some(period), Some(year)) => format!("{year}/{filename}-{period}"),
+            (Some(period), None) => format!("{filename}-{period}"),
+            (None, _) => filename,
+        }
+    }
+
+    /// Get the name of the file a message is written to, or `None` if it is orphaned
+    pub fn message_file(&self, message: &Message) -> Option<String> {
+        self.conversation(message)
+            .map(|(chatroom, _)| self.message_filename(chatroom, message))
+    }
+
+    /// Get the directory split files for a date are written to when using `--directory-layout year`, i.e. `2022`
+    pub fn year_directory(&self, date: &i64) -> Option<String> {
+        self.options.split_by?;
+        match self.options.directory_layout {
+            DirectoryLayout::Flat => None,
+            DirectoryLayout::Year => get_local_time(date, &self.offset)
+                .ok()
+                .map(|date| date.format("%Y").to_string()),
+        }
+    }
+
+    /// Get the period a date falls in when conversations are split with `--split-by`, i.e. `2022-05-17`
+    pub fn split_period(&self, date: &i64) -> Option<String> {
+        let split_by = self.options.split_by?;
+        get_local_time(date, &self.offset)
+            .ok()
+            .map(|date| date.format(split_by.date_format()).to_string())
+    }
+
     /// Generate a filename from a set of participants, truncating if the name is too long
     ///
     /// - All names:
@@ -160,7 +671,7 @@ impl Config {
                 if !out_s.is_empty() {
                     out_s.push_str(", ");
                 }
-                out_s.push_str(participant);
+                out_s.push_str(&participant);
                 added += 1;
             } else {
                 let extra = format!(", and {} others", participants.len() - added);
@@ -193,7 +704,18 @@ impl Config {
     /// let app = Config::new(options).unwrap();
     /// ```
     pub fn new(options: Options) -> Result<Config, RuntimeError> {
-        let conn = get_connection(&options.get_db_path()).map_err(RuntimeError::DatabaseError)?;
+        let db_copy = if options.copy_db_first {
+            eprintln!("Copying database...");
+            Some(DatabaseCopy::new(&options.get_db_path())?)
+        } else {
+            None
+        };
+        let conn = match &db_copy {
+            Some(db_copy) => get_connection(db_copy.path()),
+            None => get_connection(&options.get_db_path()),
+        }
+        .map_err(RuntimeError::DatabaseError)?;
+        let schema = Schema::detect(&conn).map_err(RuntimeError::DatabaseError)?;
         eprintln!("Building cache...");
         eprintln!("[1/4] Caching chats...");
         let chatrooms = Chat::cache(&conn).map_err(RuntimeError::DatabaseError)?;
@@ -203,7 +725,8 @@ impl Config {
         eprintln!("[3/4] Caching participants...");
         let participants = Handle::cache(&conn).map_err(RuntimeError::DatabaseError)?;
         eprintln!("[4/4] Caching reactions...");
-        let reactions = Message::cache(&conn).map_err(RuntimeError::DatabaseError)?;
+        let reactions =
+            Message::cache_with_schema(&conn, &schema).map_err(RuntimeError::DatabaseError)?;
         eprintln!("Cache built!");
 
         // Only attempt to create a converter if we need it
@@ -213,9 +736,27 @@ impl Config {
             AttachmentManager::Efficient => None,
         };
 
+        let annotations = match &options.annotations {
+            Some(path) => read_annotations(path)?,
+            None => HashMap::new(),
+        };
+
+        let mut real_chatrooms = ChatToHandle::dedupe(&chatroom_participants);
+        let merged_chatrooms = if options.merge_services {
+            Config::merge_services(
+                &chatrooms,
+                &chatroom_participants,
+                &participants,
+                &mut real_chatrooms,
+            )
+        } else {
+            HashMap::new()
+        };
+
         Ok(Config {
             chatrooms,
-            real_chatrooms: ChatToHandle::dedupe(&chatroom_participants),
+            real_chatrooms,
+            merged_chatrooms,
             chatroom_participants,
             real_participants: Handle::dedupe(&participants),
             participants,
@@ -223,10 +764,61 @@ impl Config {
             options,
             offset: get_offset(),
             db: conn,
+            schema,
             converter,
+            attachment_paths: RefCell::new(HashMap::new()),
+            annotations,
+            db_copy,
         })
     }
 
+    /// Merge direct conversations whose only participant resolves to the same normalized handle
+    ///
+    /// Chats with a display name or more than one participant are group chats and are never merged.
+    ///
+    /// This updates `real_chatrooms` so merged chats share a deduplicated chat ID and returns a map of
+    /// each merged chat ID to the lowest chat ID in its merged conversation.
+    fn merge_services(
+        chatrooms: &HashMap<i32, Chat>,
+        chatroom_participants: &HashMap<i32, BTreeSet<i32>>,
+        participants: &HashMap<i32, String>,
+        real_chatrooms: &mut HashMap<i32, i32>,
+    ) -> HashMap<i32, i32> {
+        let mut merged_chatrooms: HashMap<i32, i32> = HashMap::new();
+        let mut handle_to_chat_id: HashMap<String, i32> = HashMap::new();
+
+        // Iterate over the chats in a deterministic order
+        let mut chat_ids: Vec<&i32> = chatroom_participants.keys().collect();
+        chat_ids.sort();
+
+        for chat_id in chat_ids {
+            let is_direct = chatrooms
+                .get(chat_id)
+                .is_some_and(|chat| chat.display_name().is_none());
+            if !is_direct {
+                continue;
+            }
+
+            let handle = match chatroom_participants.get(chat_id) {
+                Some(members) if members.len() == 1 => {
+                    members.first().and_then(|id| participants.get(id))
+                }
+                _ => None,
+            };
+
+            if let Some(handle) = handle {
+                let merged_id = *handle_to_chat_id
+                    .entry(normalize_handle(handle))
+                    .or_insert(*chat_id);
+                if let Some(real_id) = real_chatrooms.get(&merged_id).copied() {
+                    real_chatrooms.insert(*chat_id, real_id);
+                    merged_chatrooms.insert(*chat_id, merged_id);
+                }
+            }
+        }
+        merged_chatrooms
+    }
+
     /// Ensure there is available disk space for the requested export
     fn ensure_free_space(&self) -> Result<(), RuntimeError> {
         // Export size is usually about 6% the size of the db; we divide by 10 to over-estimate about 10% of the total size
@@ -284,6 +876,8 @@ impl Config {
             format_file_size(total_db_size)
         );
 
+        println!("    Detected schema: {}", self.schema.version());
+
         let unique_handles: HashSet<i32> =
             HashSet::from_iter(self.real_participants.values().cloned());
         let duplicated_handles = self.participants.len() - unique_handles.len();
@@ -300,8 +894,356 @@ impl Config {
         Ok(())
     }
 
+    /// Print the raw and parsed `typedstream` body of a single message
+    fn dump_typedstream(&self, guid: &str) -> Result<(), RuntimeError> {
+        let message = Message::from_guid_with_schema(guid, &self.db, &self.schema)
+            .map_err(RuntimeError::DatabaseError)?;
+        match message.attributed_body(&self.db) {
+            Some(body) => print!("{}", format_typedstream_dump(&body)),
+            None => eprintln!("Message {guid} has no typedstream body"),
+        }
+        Ok(())
+    }
+
+    /// Print a single message, including its reactions and replies, without writing anything to the export directory
+    fn print_message(&self, guid: &str) -> Result<(), RuntimeError> {
+        match self.options.export_type {
+            Some(ExportType::Html) => println!("{}", HTML::new(self)?.format_single_message(guid)?),
+            Some(ExportType::Txt) => println!(
+                "{}",
+                self.normalize(&TXT::new(self)?.format_single_message(guid)?)
+            ),
+            // Other export types are rejected when the options are parsed
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Handles reports on the contents of the database
+    fn run_report(&self, report: &ReportType) -> Result<(), RuntimeError> {
+        match report {
+            ReportType::Otp => self.run_otp_report(),
+            ReportType::Conversations => self.run_conversations_report(),
+            ReportType::MyReactions => self.run_my_reactions_report(),
+            ReportType::ReactionStats => self.run_reaction_stats_report(),
+        }
+    }
+
+    /// List every conversation with its participants, message count, and most recent activity
+    fn run_conversations_report(&self) -> Result<(), RuntimeError> {
+        let conversations = self.conversations()?;
+        let rows: Vec<[String; 5]> = conversations
+            .iter()
+            .map(|conversation| {
+                [
+                    conversation.id.to_string(),
+                    conversation.display_name.clone(),
+                    conversation.participants.join(", "),
+                    conversation.message_count.to_string(),
+                    conversation
+                        .last_activity
+                        .map(|date| format(&get_local_time(&date, &self.offset)))
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect();
+
+        println!("\nConversations Report\n");
+        print!("{}", format_table(CONVERSATION_HEADERS, &rows));
+        println!("\nFound {} conversations", rows.len());
+
+        Ok(())
+    }
+
+    /// List the messages that contain detected one-time passcodes with their sender and timestamp
+    fn run_otp_report(&self) -> Result<(), RuntimeError> {
+        eprintln!("Scanning messages for one-time passcodes...");
+
+        // Keep track of current message ROWID
+        let mut current_message_row = -1;
+        let mut rows = vec![];
+
+        let mut statement =
+            Message::stream_rows_with_schema(&self.db, &self.schema, &self.options.query_context)
+                .map_err(RuntimeError::DatabaseError)?;
+
+        let messages = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .map_err(|err| RuntimeError::DatabaseError(TableError::Messages(err)))?;
+
+        for message in messages {
+            let mut msg = Message::extract(message).map_err(RuntimeError::DatabaseError)?;
+
+            // Skip duplicate rows for messages that belong to more than one chat
+            if msg.rowid == current_message_row {
+                continue;
+            }
+            current_message_row = msg.rowid;
+
+            let _ = msg.generate_text(&self.db);
+            let codes = msg.one_time_codes();
+            if !codes.is_empty() {
+                rows.push([
+                    format(&msg.date(&self.offset)),
+                    self.who(msg.handle_id, msg.is_from_me, &msg.destination_caller_id)
+                        .to_string(),
+                    codes.join(", "),
+                ]);
+            }
+        }
+
+        println!("\nOne-time Passcode Report\n");
+        print!("{}", format_table(OTP_HEADERS, &rows));
+        println!(
+            "\nFound {} messages containing one-time passcodes",
+            rows.len()
+        );
+
+        Ok(())
+    }
+
+    /// List the reactions sent by the database owner with the sender and a snippet of the message they reacted to
+    fn run_my_reactions_report(&self) -> Result<(), RuntimeError> {
+        eprintln!("Scanning messages for your reactions...");
+
+        // Keep track of current message ROWID
+        let mut current_message_row = -1;
+        let mut rows = vec![];
+
+        let mut statement =
+            Message::stream_rows_with_schema(&self.db, &self.schema, &self.options.query_context)
+                .map_err(RuntimeError::DatabaseError)?;
+
+        let messages = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .map_err(|err| RuntimeError::DatabaseError(TableError::Messages(err)))?;
+
+        for message in messages {
+            let msg = Message::extract(message).map_err(RuntimeError::DatabaseError)?;
+
+            // Skip duplicate rows for messages that belong to more than one chat
+            if msg.rowid == current_message_row {
+                continue;
+            }
+            current_message_row = msg.rowid;
+
+            if !msg.is_from_me() {
+                continue;
+            }
+            let Variant::Reaction(_, true, reaction) = msg.variant() else {
+                continue;
+            };
+
+            // The message that was reacted to may have since been deleted
+            let (sender, snippet) = match msg
+                .associated_target_guid()
+                .and_then(|guid| self.message_by_guid(guid).ok())
+            {
+                Some(target) => (
+                    self.who(
+                        target.handle_id,
+                        target.is_from_me(),
+                        &target.destination_caller_id,
+                    )
+                    .to_string(),
+                    format_snippet(
+                        target.text.as_deref().unwrap_or_default(),
+                        ATTACHMENT_PLACEHOLDER,
+                    ),
+                ),
+                None => (String::new(), String::from("Message not found")),
+            };
+            rows.push([
+                format(&msg.date(&self.offset)),
+                format!("{reaction:?}"),
+                sender,
+                snippet,
+            ]);
+        }
+
+        println!("\nMy Reactions Report\n");
+        print!("{}", format_table(MY_REACTION_HEADERS, &rows));
+        println!("\nFound {} reactions", rows.len());
+
+        Ok(())
+    }
+
+    /// Summarize the reactions given and received in each conversation, with the most common reaction and most-reacted-to message
+    fn run_reaction_stats_report(&self) -> Result<(), RuntimeError> {
+        eprintln!("Scanning messages for reactions...");
+
+        // Keep track of current message ROWID
+        let mut current_message_row = -1;
+        let mut total = ReactionStats::default();
+        let mut conversations: HashMap<Option<i32>, ReactionStats> = HashMap::new();
+
+        let mut statement =
+            Message::stream_rows_with_schema(&self.db, &self.schema, &self.options.query_context)
+                .map_err(RuntimeError::DatabaseError)?;
+
+        let messages = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .map_err(|err| RuntimeError::DatabaseError(TableError::Messages(err)))?;
+
+        for message in messages {
+            let mut msg = Message::extract(message).map_err(RuntimeError::DatabaseError)?;
+
+            // Skip duplicate rows for messages that belong to more than one chat
+            if msg.rowid == current_message_row {
+                continue;
+            }
+            current_message_row = msg.rowid;
+
+            let Some(parts) = self.reactions.get(&msg.guid) else {
+                continue;
+            };
+
+            let stats = conversations
+                .entry(
+                    msg.chat_id
+                        .and_then(|chat_id| self.real_chatrooms.get(&chat_id))
+                        .copied(),
+                )
+                .or_default();
+
+            // Removed reactions are not counted
+            let mut count = 0;
+            for reaction_msg in parts.values().flatten() {
+                if let Variant::Reaction(_, true, reaction) = reaction_msg.variant() {
+                    let reaction = format!("{reaction:?}");
+                    stats.add_reaction(
+                        reaction.clone(),
+                        reaction_msg.is_from_me(),
+                        msg.is_from_me(),
+                    );
+                    total.add_reaction(reaction, reaction_msg.is_from_me(), msg.is_from_me());
+                    count += 1;
+                }
+            }
+
+            stats.add_target(count, || {
+                let _ = msg.generate_text(&self.db);
+                format_snippet(
+                    msg.text.as_deref().unwrap_or_default(),
+                    ATTACHMENT_PLACEHOLDER,
+                )
+            });
+        }
+
+        let names: HashMap<i32, String> = self
+            .conversations()?
+            .into_iter()
+            .map(|conversation| (conversation.id, conversation.display_name))
+            .collect();
+
+        let mut conversations: Vec<(Option<i32>, ReactionStats)> = conversations
+            .into_iter()
+            .filter(|(_, stats)| stats.total() > 0)
+            .collect();
+        conversations
+            .sort_by(|(a_id, a), (b_id, b)| b.total().cmp(&a.total()).then_with(|| a_id.cmp(b_id)));
+
+        let rows: Vec<[String; 5]> = conversations
+            .iter()
+            .map(|(chat_id, stats)| {
+                [
+                    chat_id
+                        .and_then(|id| names.get(&id))
+                        .map_or(ORPHANED, String::as_str)
+                        .to_string(),
+                    stats.given.to_string(),
+                    stats.received.to_string(),
+                    stats.most_common().unwrap_or_default().to_string(),
+                    stats
+                        .most_reacted
+                        .as_ref()
+                        .map(|(count, snippet)| format!("{snippet} ({count})"))
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect();
+
+        println!("\nReaction Stats Report\n");
+        print!("{}", format_table(REACTION_STATS_HEADERS, &rows));
+        println!(
+            "\nFound {} reactions: {} given, {} received, most often {}",
+            total.total(),
+            total.given,
+            total.received,
+            total.most_common().unwrap_or("none")
+        );
+
+        Ok(())
+    }
+
+    /// Copy the attachments from every message without writing any conversation files, for `--extract-media-only`
+    ///
+    /// Returns the number of attachments that were copied.
+    fn extract_media(&self) -> Result<u64, RuntimeError> {
+        eprintln!(
+            "Extracting attachments to {}...",
+            self.attachment_path().display()
+        );
+
+        // Keep track of current message ROWID
+        let mut current_message_row = -1;
+        let mut extracted = 0;
+
+        // Only extract from conversations that include the selected participant, if any
+        let chatrooms = self.participant_chatrooms();
+
+        // Set up progress bar
+        let mut current_message = 0;
+        let total_messages = Message::get_count(&self.db, &self.options.query_context)
+            .map_err(RuntimeError::DatabaseError)?;
+        let pb = build_progress_bar_export(total_messages);
+
+        let mut statement =
+            Message::stream_rows_with_schema(&self.db, &self.schema, &self.options.query_context)
+                .map_err(RuntimeError::DatabaseError)?;
+
+        let messages = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .map_err(|err| RuntimeError::DatabaseError(TableError::Messages(err)))?;
+
+        for message in messages {
+            let msg = Message::extract(message).map_err(RuntimeError::DatabaseError)?;
+            current_message += 1;
+            if current_message % 99 == 0 {
+                pb.set_position(current_message);
+            }
+
+            // Skip duplicate rows for messages that belong to more than one chat
+            if msg.rowid == current_message_row {
+                continue;
+            }
+            current_message_row = msg.rowid;
+
+            if !msg.has_attachments() || !Config::in_chatrooms(&msg, &chatrooms) {
+                continue;
+            }
+
+            let mut attachments = self
+                .attachments(&msg)
+                .map_err(RuntimeError::DatabaseError)?;
+            for attachment in &mut attachments {
+                if self
+                    .options
+                    .attachment_manager
+                    .handle_attachment(&msg, attachment, self)
+                    .is_some()
+                {
+                    extracted += 1;
+                }
+            }
+        }
+        pb.finish();
+
+        Ok(extracted)
+    }
+
     /// Start the app given the provided set of options. This will either run
-    /// diagnostic tests on the database or export data to the specified file type.
+    /// diagnostic tests on the database, generate a report, or export data to the specified file type.
     ///
     // # Example:
     ///
@@ -319,6 +1261,24 @@ impl Config {
     pub fn start(&self) -> Result<(), RuntimeError> {
         if self.options.diagnostic {
             self.run_diagnostic().map_err(RuntimeError::DatabaseError)?;
+        } else if let Some(report) = &self.options.report {
+            self.run_report(report)?;
+        } else if let Some(guid) = &self.options.dump_typedstream {
+            self.dump_typedstream(guid)?;
+        } else if let Some(guid) = &self.options.message_guid {
+            self.print_message(guid)?;
+        } else if self.options.extract_media_only {
+            create_dir_all(self.attachment_path()).map_err(RuntimeError::DiskError)?;
+            if !self.options.ignore_disk_space {
+                self.ensure_free_space()?;
+            }
+            let _ = raise_fd_limit();
+
+            let extracted = self.extract_media()?;
+            eprintln!(
+                "Extracted {extracted} attachments to {}",
+                self.attachment_path().display()
+            );
         } else if let Some(export_type) = &self.options.export_type {
             // Ensure the path we want to export to exists
             create_dir_all(&self.options.export_path).map_err(RuntimeError::DiskError)?;
@@ -336,6 +1296,14 @@ impl Config {
             // Ensure we have enough file handles to export
             let _ = raise_fd_limit();
 
+            // Find the newest message before exporting, so messages that arrive during the export are not skipped next time
+            let last_export = if self.options.since_last_export {
+                Message::get_max_date(&self.db, &self.options.query_context)
+                    .map_err(RuntimeError::DatabaseError)?
+            } else {
+                None
+            };
+
             // Create exporter, pass it data we care about, then kick it off
             match export_type {
                 ExportType::Html => {
@@ -344,46 +1312,302 @@ impl Config {
                 ExportType::Txt => {
                     TXT::new(self)?.iter_messages()?;
                 }
+                ExportType::DayOne => {
+                    DayOne::new(self)?.iter_messages()?;
+                }
+                ExportType::Irc => {
+                    IRC::new(self)?.iter_messages()?;
+                }
+                ExportType::Ndjson => {
+                    NDJSON::new(self)?.iter_messages()?;
+                }
+                ExportType::Json => {
+                    JSON::new(self)?.iter_messages()?;
+                }
+            }
+
+            if let Some(date) = last_export {
+                write_last_export(&self.options.export_path, date)?;
+                eprintln!("Recorded the newest exported message in {LAST_EXPORT_FILENAME}");
+            }
+
+            // Hash the exported files once every writer has been flushed
+            if self.options.manifest {
+                eprintln!("Writing {MANIFEST_FILENAME}...");
+                let listed = write_manifest(&self.options.export_path)?;
+                eprintln!("Listed {listed} files in {MANIFEST_FILENAME}");
             }
         }
-        println!("Done!");
+        // Keep stdout clean when printing a single message or its typedstream
+        if self.options.message_guid.is_none() && self.options.dump_typedstream.is_none() {
+            println!("Done!");
+        }
         Ok(())
     }
 
-    /// Determine who sent a message
-    pub fn who<'a, 'b: 'a>(
-        &'a self,
-        handle_id: Option<i32>,
-        is_from_me: bool,
-        destination_caller_id: &'b Option<String>,
-    ) -> &'a str {
-        if is_from_me {
-            if self.options.use_caller_id {
-                return destination_caller_id.as_deref().unwrap_or(ME);
+    /// Run the command passed with `--post-export-command`, if any, and report its exit status
+    ///
+    /// This should only be called after [`Config::start()`] succeeds.
+    pub fn run_post_export_command(&self) {
+        if let Some(command) = &self.options.post_export_command {
+            eprintln!("Running post-export command...");
+            match post_export::run(command, &self.options.export_path) {
+                Ok(status) if status.success() => eprintln!("Post-export command succeeded"),
+                Ok(status) => eprintln!("Post-export command failed: {status}"),
+                Err(why) => eprintln!("Unable to run post-export command: {why}"),
             }
-            return self.options.custom_name.as_deref().unwrap_or(ME);
-        } else if let Some(handle_id) = handle_id {
-            return match self.participants.get(&handle_id) {
-                Some(contact) => contact,
-                None => UNKNOWN,
-            };
         }
-        UNKNOWN
     }
-}
 
-#[cfg(test)]
+    /// Mask message text with [`mask_text`] if `--mask-text` is enabled
+    pub fn mask<'b>(&self, text: &'b str) -> Cow<'b, str> {
+        if self.options.mask_text {
+            Cow::Owned(mask_text(text))
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Normalize line endings with [`normalize_newlines`] if `--normalize-newlines` is enabled
+    pub fn normalize<'b>(&self, text: &'b str) -> Cow<'b, str> {
+        if self.options.normalize_newlines {
+            normalize_newlines(text)
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Convert emoji to shortcodes with [`replace_shortcodes`] if `--emoji-shortcodes` is enabled
+    pub fn shortcodes<'b>(&self, text: &'b str) -> Cow<'b, str> {
+        if self.options.emoji_shortcodes {
+            replace_shortcodes(text)
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Get the [`MediaType`] of an attachment, applying any `--mime-override` for its file extension
+    pub fn media_type<'b>(&'b self, attachment: &'b Attachment) -> MediaType<'b> {
+        attachment.mime_type_with_overrides(&self.options.mime_overrides)
+    }
+
+    /// Format the date a message was sent using the requested [`TimestampFormat`]
+    ///
+    /// Relative timestamps fall back to absolute ones if the message date is in the future.
+    pub fn format_timestamp(&self, message: &Message) -> String {
+        let absolute = format(&message.date(&self.offset));
+        match self.options.timestamp_format {
+            TimestampFormat::Absolute => absolute,
+            TimestampFormat::Relative => {
+                readable_relative_to_now(message.date(&self.offset)).unwrap_or(absolute)
+            }
+            TimestampFormat::Both => match readable_relative_to_now(message.date(&self.offset)) {
+                Some(relative) => format!("{absolute} ({relative})"),
+                None => absolute,
+            },
+        }
+    }
+
+    /// Determine who sent a message
+    ///
+    /// For messages from the database owner, `destination_caller_id` is the number or email the
+    /// message was sent from, which distinguishes between identities on accounts with more than one.
+    pub fn who<'a, 'b: 'a>(
+        &'a self,
+        handle_id: Option<i32>,
+        is_from_me: bool,
+        destination_caller_id: &'b Option<String>,
+    ) -> Cow<'a, str> {
+        if is_from_me {
+            // Blank caller IDs carry no information about which identity was used
+            let caller_id = destination_caller_id
+                .as_deref()
+                .map(str::trim)
+                .filter(|caller_id| !caller_id.is_empty());
+
+            if self.options.use_caller_id {
+                return Cow::Borrowed(caller_id.unwrap_or(ME));
+            }
+
+            let name = self.options.custom_name.as_deref().unwrap_or(ME);
+            return match caller_id {
+                Some(caller_id) if self.options.show_my_number => {
+                    Cow::Owned(format!("{name} ({caller_id})"))
+                }
+                _ => Cow::Borrowed(name),
+            };
+        } else if let Some(handle_id) = handle_id {
+            return match self.participants.get(&handle_id) {
+                Some(contact) => Cow::Borrowed(contact),
+                None => Cow::Borrowed(UNKNOWN),
+            };
+        }
+        Cow::Borrowed(UNKNOWN)
+    }
+
+    /// Replace the placeholders Fitness.app messages use for their sender and receiver with the people they refer to
+    ///
+    /// The database owner is [`YOU`] on either side, so the text reads correctly whether the fitness share was sent or received.
+    pub fn fitness_text<'b>(&'b self, text: &'b str, message: &Message) -> Cow<'b, str> {
+        if !text.contains(FITNESS_RECEIVER) && !text.contains(FITNESS_SENDER) {
+            return Cow::Borrowed(text);
+        }
+        let (sender, receiver) = if message.is_from_me() {
+            (Cow::Borrowed(YOU), self.fitness_recipient(message))
+        } else {
+            (
+                self.who(message.handle_id, false, &None),
+                Cow::Borrowed(YOU),
+            )
+        };
+        Cow::Owned(
+            text.replace(FITNESS_SENDER, &sender)
+                .replace(FITNESS_RECEIVER, &receiver),
+        )
+    }
+
+    /// Describe the part of a message a reaction targets, i.e. `an image` or `"See you soon"`, if `--reaction-context` is enabled
+    ///
+    /// `parts` are the message's [`BubbleComponent`]s and `attachments` are its attachments, in the order they were sent.
+    pub fn reaction_target(
+        &self,
+        message: &Message,
+        parts: &[BubbleComponent],
+        idx: usize,
+        attachments: &[Attachment],
+    ) -> Option<String> {
+        if !self.options.reaction_context {
+            return None;
+        }
+        match parts.get(idx)? {
+            BubbleComponent::Text(text_attrs) => {
+                let text = message.text.as_deref()?;
+                // A part's text spans from the start of its first range to the end of its last range
+                let part = match (text_attrs.first(), text_attrs.last()) {
+                    (Some(first), Some(last)) => text.get(first.start..last.end)?,
+                    _ => text,
+                };
+                let snippet = format_snippet(&self.mask(part), "");
+                (!snippet.is_empty()).then(|| format!("\"{snippet}\""))
+            }
+            BubbleComponent::Attachment => {
+                // Attachments are stored in the same order as the parts that display them
+                let position = parts[..idx]
+                    .iter()
+                    .filter(|part| matches!(part, BubbleComponent::Attachment))
+                    .count();
+                Some(
+                    match attachments.get(position) {
+                        Some(attachment) if attachment.is_sticker => "a sticker",
+                        Some(attachment) if attachment.is_audio_message() => "an audio message",
+                        Some(attachment) => match self.media_type(attachment) {
+                            MediaType::Image(_) | MediaType::AnimatedImage(_) => "an image",
+                            MediaType::Video(_) => "a video",
+                            MediaType::Audio(_) => "an audio file",
+                            _ => "an attachment",
+                        },
+                        None => "an attachment",
+                    }
+                    .to_string(),
+                )
+            }
+            BubbleComponent::App => Some(String::from("an app message")),
+            BubbleComponent::Retracted => None,
+        }
+    }
+
+    /// Describe the chat event an announcement message represents, i.e. `You renamed the conversation to Family`
+    pub fn announcement_text(&self, message: &Message) -> Option<String> {
+        let mut who = self.who(
+            message.handle_id,
+            message.is_from_me(),
+            &message.destination_caller_id,
+        );
+        // Rename yourself so we render the proper grammar here
+        if who == ME {
+            who = Cow::Borrowed(self.options.custom_name.as_deref().unwrap_or(YOU));
+        }
+
+        Some(match message.get_announcement()? {
+            Announcement::NameChange(name) => format!("{who} renamed the conversation to {name}"),
+            Announcement::PhotoChange => format!("{who} changed the group photo."),
+            Announcement::Unknown(num) => format!("{who} performed unknown action {num}."),
+            Announcement::FullyUnsent => match message.unsent_timing() {
+                Some(UnsentTiming::BeforeDelivery) => {
+                    format!("{who} unsent a message before it was delivered!")
+                }
+                _ => format!("{who} unsent a message!"),
+            },
+        })
+    }
+
+    /// Name the recipient of a fitness share sent by the database owner, if the conversation has a single other participant
+    fn fitness_recipient(&self, message: &Message) -> Cow<'_, str> {
+        message
+            .chat_id
+            .and_then(|chat_id| self.chatroom_participants.get(&chat_id))
+            .filter(|participants| participants.len() == 1)
+            .and_then(|participants| participants.first())
+            .map(|handle_id| self.who(Some(*handle_id), false, &None))
+            // Group chats have more than one recipient
+            .unwrap_or(Cow::Borrowed("They"))
+    }
+
+    /// Determine if a handle, i.e. the target of a mention, is the identity the database owner used in a message's conversation
+    ///
+    /// A message's `destination_caller_id` is the database owner's number or email in the chat it was sent to.
+    pub fn is_my_handle(&self, handle: &str, message: &Message) -> bool {
+        message
+            .destination_caller_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|caller_id| !caller_id.is_empty())
+            .is_some_and(|caller_id| normalize_handle(caller_id) == normalize_handle(handle))
+    }
+}
+
+/// Normalize a handle so the same contact can be matched across services
+///
+/// Emails are compared case-insensitively and phone numbers ignore formatting characters.
+fn normalize_handle(handle: &str) -> String {
+    if handle.contains('@') {
+        return handle.to_lowercase();
+    }
+    let number: String = handle
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == '+')
+        .collect();
+    if number.chars().any(|c| c.is_ascii_digit()) {
+        number
+    } else {
+        handle.to_string()
+    }
+}
+
+#[cfg(test)]
 mod filename_tests {
-    use crate::{app::attachment_manager::AttachmentManager, Config, Options};
+    use crate::{
+        app::{
+            attachment_manager::{AttachmentManager, DEFAULT_COPY_RETRIES},
+            conversations::Conversation,
+            export_type::{DayOneGranularity, DirectoryLayout},
+            options::{DEFAULT_HTML_LANG, DEFAULT_IRC_TIMESTAMP_FORMAT},
+            runtime::{normalize_handle, FLATTENED_GROUPS},
+            timestamp_format::TimestampFormat,
+        },
+        Config, Options,
+    };
     use imessage_database::{
         tables::{
-            chat::Chat,
+            chat::{Chat, ServiceMix},
+            schema::Schema,
             table::{get_connection, MAX_LENGTH},
         },
         util::{dirs::default_db_path, platform::Platform, query_context::QueryContext},
     };
     use std::{
-        collections::{BTreeSet, HashMap},
+        cell::RefCell,
+        collections::{BTreeSet, HashMap, HashSet},
         path::PathBuf,
     };
 
@@ -399,8 +1623,57 @@ mod filename_tests {
             no_lazy: false,
             custom_name: None,
             use_caller_id: false,
+            show_my_number: false,
             platform: Platform::macOS,
             ignore_disk_space: false,
+            show_removed_reactions: false,
+            limit: None,
+            limit_per_chat: None,
+            merge_services: false,
+            dayone_granularity: DayOneGranularity::default(),
+            copy_retries: DEFAULT_COPY_RETRIES,
+            timestamp_format: TimestampFormat::default(),
+            report: None,
+            txt_template: None,
+            manifest: false,
+            animate_effects: false,
+            participants_only: None,
+            exclude_orphaned: false,
+            only_orphaned: false,
+            media_grid: false,
+            show_receipts: false,
+            exif_sidecars: false,
+            post_export_command: None,
+            mask_text: false,
+            compact_html: false,
+            split_by: None,
+            warc: false,
+            no_attachments: false,
+            collapse_reactions: None,
+            mark_failed_transfers: false,
+            message_guid: None,
+            normalize_newlines: false,
+            html_lang: DEFAULT_HTML_LANG.to_string(),
+            css: None,
+            directory_layout: DirectoryLayout::default(),
+            include_empty_chats: false,
+            print_friendly: false,
+            since_last_export: false,
+            reset_last_export: false,
+            only_reported: false,
+            number_messages: false,
+            emoji_shortcodes: false,
+            mime_overrides: HashMap::new(),
+            flatten_groups: false,
+            extract_media_only: false,
+            annotations: None,
+            dump_typedstream: None,
+            irc_timestamp_format: DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+            alt_text: false,
+            reaction_context: false,
+            quiet_delivery: false,
+            copy_db_first: false,
+            tokenize: false,
         }
     }
 
@@ -410,14 +1683,20 @@ mod filename_tests {
             chat_identifier: "Default".to_string(),
             service_name: Some(String::new()),
             display_name: None,
+            first_message_date: None,
+            last_message_date: None,
+            has_imessage: false,
+            has_sms: false,
         }
     }
 
     fn fake_app(options: Options) -> Config {
         let connection = get_connection(&options.db_path).unwrap();
+        let schema = Schema::detect(&connection).unwrap();
         Config {
             chatrooms: HashMap::new(),
             real_chatrooms: HashMap::new(),
+            merged_chatrooms: HashMap::new(),
             chatroom_participants: HashMap::new(),
             participants: HashMap::new(),
             real_participants: HashMap::new(),
@@ -425,7 +1704,11 @@ mod filename_tests {
             options,
             offset: 0,
             db: connection,
+            schema,
             converter: Some(crate::app::converter::Converter::Sips),
+            attachment_paths: RefCell::new(HashMap::new()),
+            annotations: HashMap::new(),
+            db_copy: None,
         }
     }
 
@@ -558,6 +1841,34 @@ mod filename_tests {
         assert_eq!(filename, "Test Chat Name - 0");
     }
 
+    #[test]
+    fn can_get_filename_chat_display_name_colons() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Create chat
+        let mut chat = fake_chat();
+        chat.display_name = Some("Book Club: 2024?".to_string());
+
+        // Get filename
+        let filename = app.filename(&chat);
+        assert_eq!(filename, "Book Club_ 2024_ - 0");
+    }
+
+    #[test]
+    fn can_get_filename_chat_reserved_name() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Create chat
+        let mut chat = fake_chat();
+        chat.chat_identifier = "nul".to_string();
+
+        // Get filename
+        let filename = app.filename(&chat);
+        assert_eq!(filename, "nul_");
+    }
+
     #[test]
     fn can_get_filename_chat_display_name_short() {
         let options = fake_options();
@@ -595,6 +1906,231 @@ mod filename_tests {
         assert_eq!(filename, "Person 10, Person 11");
     }
 
+    #[test]
+    fn can_create_filename_flattened_group() {
+        let mut options = fake_options();
+        options.flatten_groups = true;
+        let mut app = fake_app(options);
+
+        // Create a group chat and a direct chat
+        let group = fake_chat();
+        let mut direct = fake_chat();
+        direct.rowid = 1;
+
+        app.participants.insert(10, "Person 10".to_string());
+        app.participants.insert(11, "Person 11".to_string());
+        app.chatroom_participants
+            .insert(group.rowid, BTreeSet::from([10, 11]));
+        app.chatroom_participants
+            .insert(direct.rowid, BTreeSet::from([10]));
+
+        assert!(app.is_flattened(&group));
+        assert!(!app.is_flattened(&direct));
+        assert_eq!(app.filename(&group), FLATTENED_GROUPS);
+        assert_eq!(app.filename(&direct), "Person 10");
+        assert_eq!(app.group_name(&group), "Person 10, Person 11");
+    }
+
+    #[test]
+    fn can_get_group_name_display_name() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        let mut chat = fake_chat();
+        chat.display_name = Some("Family".to_string());
+
+        assert_eq!(app.group_name(&chat), "Family");
+    }
+
+    #[test]
+    fn can_normalize_handle() {
+        assert_eq!(normalize_handle("+1 (555) 555-0000"), "+15555550000");
+        assert_eq!(normalize_handle("Person@Example.com"), "person@example.com");
+        assert_eq!(normalize_handle("Apple"), "Apple");
+    }
+
+    #[test]
+    fn can_get_participant_chatrooms() {
+        let mut options = fake_options();
+        options.participants_only = Some("+1 (555) 555-0000".to_string());
+        let mut app = fake_app(options);
+
+        // Create a direct chat, a group chat, and an unrelated chat
+        app.participants.insert(10, "+15555550000".to_string());
+        app.participants.insert(11, "+15555550001".to_string());
+        app.participants
+            .insert(12, "person@example.com".to_string());
+        app.chatroom_participants.insert(1, BTreeSet::from([10]));
+        app.chatroom_participants
+            .insert(2, BTreeSet::from([10, 11, 12]));
+        app.chatroom_participants
+            .insert(3, BTreeSet::from([11, 12]));
+
+        assert_eq!(app.participant_chatrooms(), Some(HashSet::from([1, 2])));
+    }
+
+    #[test]
+    fn can_get_participant_chatrooms_email() {
+        let mut options = fake_options();
+        options.participants_only = Some("Person@Example.com".to_string());
+        let mut app = fake_app(options);
+
+        app.participants.insert(10, "+15555550000".to_string());
+        app.participants
+            .insert(12, "person@example.com".to_string());
+        app.chatroom_participants.insert(1, BTreeSet::from([10]));
+        app.chatroom_participants
+            .insert(2, BTreeSet::from([10, 12]));
+
+        assert_eq!(app.participant_chatrooms(), Some(HashSet::from([2])));
+    }
+
+    #[test]
+    fn can_get_participant_chatrooms_no_filter() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        app.participants.insert(10, "+15555550000".to_string());
+        app.chatroom_participants.insert(1, BTreeSet::from([10]));
+
+        assert_eq!(app.participant_chatrooms(), None);
+    }
+
+    #[test]
+    fn can_get_empty_chatrooms() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        // Chats 1 and 2 are merged into one conversation, chat 3 is its own
+        for chat_id in 1..=3 {
+            let mut chat = fake_chat();
+            chat.rowid = chat_id;
+            app.chatrooms.insert(chat_id, chat);
+        }
+        app.real_chatrooms.insert(1, 1);
+        app.real_chatrooms.insert(2, 1);
+        app.real_chatrooms.insert(3, 3);
+
+        // Nothing was exported, so each conversation is listed once
+        let exported = HashMap::new();
+        let empty: Vec<i32> = app
+            .empty_chatrooms(&exported, &None)
+            .iter()
+            .map(|chat| chat.rowid)
+            .collect();
+        assert_eq!(empty, vec![1, 3]);
+
+        // Conversations with exported messages are skipped
+        let exported = HashMap::from([(Some(1), 5)]);
+        let empty: Vec<i32> = app
+            .empty_chatrooms(&exported, &None)
+            .iter()
+            .map(|chat| chat.rowid)
+            .collect();
+        assert_eq!(empty, vec![3]);
+
+        // Chats excluded by the participant filter are skipped
+        let empty = app.empty_chatrooms(&HashMap::new(), &Some(HashSet::from([2])));
+        assert_eq!(empty.len(), 1);
+        assert_eq!(empty[0].rowid, 2);
+    }
+
+    #[test]
+    fn can_merge_services() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        // Create an iMessage chat and an SMS chat with the same contact
+        let mut imessage = fake_chat();
+        imessage.rowid = 1;
+        let mut sms = fake_chat();
+        sms.rowid = 2;
+        app.chatrooms.insert(1, imessage);
+        app.chatrooms.insert(2, sms);
+
+        app.participants.insert(10, "+15555550000".to_string());
+        app.participants.insert(11, "+1 (555) 555-0000".to_string());
+        app.chatroom_participants.insert(1, BTreeSet::from([10]));
+        app.chatroom_participants.insert(2, BTreeSet::from([11]));
+        app.real_chatrooms.insert(1, 0);
+        app.real_chatrooms.insert(2, 1);
+
+        app.merged_chatrooms = Config::merge_services(
+            &app.chatrooms,
+            &app.chatroom_participants,
+            &app.participants,
+            &mut app.real_chatrooms,
+        );
+
+        assert_eq!(app.real_chatrooms.get(&1), app.real_chatrooms.get(&2));
+        assert_eq!(app.filename(app.chatrooms.get(&2).unwrap()), "+15555550000");
+    }
+
+    #[test]
+    fn cant_merge_services_group_chats() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        // Create two group chats that share a contact
+        let mut group_a = fake_chat();
+        group_a.rowid = 1;
+        let mut group_b = fake_chat();
+        group_b.rowid = 2;
+        app.chatrooms.insert(1, group_a);
+        app.chatrooms.insert(2, group_b);
+
+        app.participants.insert(10, "+15555550000".to_string());
+        app.participants.insert(11, "+15555550000".to_string());
+        app.participants.insert(12, "+15555550001".to_string());
+        app.chatroom_participants
+            .insert(1, BTreeSet::from([10, 12]));
+        app.chatroom_participants
+            .insert(2, BTreeSet::from([11, 12]));
+        app.real_chatrooms.insert(1, 0);
+        app.real_chatrooms.insert(2, 1);
+
+        let merged = Config::merge_services(
+            &app.chatrooms,
+            &app.chatroom_participants,
+            &app.participants,
+            &mut app.real_chatrooms,
+        );
+
+        assert!(merged.is_empty());
+        assert_ne!(app.real_chatrooms.get(&1), app.real_chatrooms.get(&2));
+    }
+
+    #[test]
+    fn cant_merge_services_named_chats() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        // Create a direct chat and a named chat with the same contact
+        let mut direct = fake_chat();
+        direct.rowid = 1;
+        let mut named = fake_chat();
+        named.rowid = 2;
+        named.display_name = Some("Book Club".to_string());
+        app.chatrooms.insert(1, direct);
+        app.chatrooms.insert(2, named);
+
+        app.participants.insert(10, "+15555550000".to_string());
+        app.chatroom_participants.insert(1, BTreeSet::from([10]));
+        app.chatroom_participants.insert(2, BTreeSet::from([10]));
+        app.real_chatrooms.insert(1, 0);
+        app.real_chatrooms.insert(2, 1);
+
+        let merged = Config::merge_services(
+            &app.chatrooms,
+            &app.chatroom_participants,
+            &app.participants,
+            &mut app.real_chatrooms,
+        );
+
+        assert!(!merged.contains_key(&2));
+        assert_ne!(app.real_chatrooms.get(&1), app.real_chatrooms.get(&2));
+    }
+
     #[test]
     fn can_get_filename_chat_no_participants() {
         let options = fake_options();
@@ -607,16 +2143,228 @@ mod filename_tests {
         let filename = app.filename(&chat);
         assert_eq!(filename, "Default");
     }
+
+    #[test]
+    fn can_get_conversation_activity_merged() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        // Two chats that dedupe to the same conversation, plus one empty chat
+        let mut chat_1 = fake_chat();
+        chat_1.rowid = 1;
+        chat_1.first_message_date = Some(100);
+        chat_1.last_message_date = Some(500);
+
+        let mut chat_2 = fake_chat();
+        chat_2.rowid = 2;
+        chat_2.first_message_date = Some(50);
+        chat_2.last_message_date = Some(200);
+
+        let mut chat_3 = fake_chat();
+        chat_3.rowid = 3;
+
+        app.real_chatrooms.insert(1, 0);
+        app.real_chatrooms.insert(2, 0);
+        app.real_chatrooms.insert(3, 1);
+        app.chatrooms.insert(1, chat_1);
+        app.chatrooms.insert(2, chat_2);
+        app.chatrooms.insert(3, chat_3);
+
+        let chat = app.chatrooms.get(&1).unwrap();
+        assert_eq!(app.conversation_activity(chat), Some((50, 500)));
+
+        let chat = app.chatrooms.get(&3).unwrap();
+        assert_eq!(app.conversation_activity(chat), None);
+    }
+
+    #[test]
+    fn can_get_conversation_service_mix_merged() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        // An iMessage chat and an SMS chat that dedupe to the same conversation, plus one iMessage chat
+        let mut chat_1 = fake_chat();
+        chat_1.rowid = 1;
+        chat_1.has_imessage = true;
+
+        let mut chat_2 = fake_chat();
+        chat_2.rowid = 2;
+        chat_2.has_sms = true;
+
+        let mut chat_3 = fake_chat();
+        chat_3.rowid = 3;
+        chat_3.has_imessage = true;
+
+        app.real_chatrooms.insert(1, 0);
+        app.real_chatrooms.insert(2, 0);
+        app.real_chatrooms.insert(3, 1);
+        app.chatrooms.insert(1, chat_1);
+        app.chatrooms.insert(2, chat_2);
+        app.chatrooms.insert(3, chat_3);
+
+        let chat = app.chatrooms.get(&1).unwrap();
+        assert_eq!(app.conversation_service_mix(chat), Some(ServiceMix::Mixed));
+
+        let chat = app.chatrooms.get(&3).unwrap();
+        assert_eq!(
+            app.conversation_service_mix(chat),
+            Some(ServiceMix::iMessage)
+        );
+    }
+
+    #[test]
+    fn can_get_conversation_identifiers_merged() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        // Two group chats with the same participants that dedupe to the same conversation
+        let mut chat_1 = fake_chat();
+        chat_1.rowid = 1;
+        chat_1.chat_identifier = String::from("chat222");
+
+        let mut chat_2 = fake_chat();
+        chat_2.rowid = 2;
+        chat_2.chat_identifier = String::from("chat111");
+
+        let mut chat_3 = fake_chat();
+        chat_3.rowid = 3;
+        chat_3.chat_identifier = String::from("chat333");
+
+        app.real_chatrooms.insert(1, 0);
+        app.real_chatrooms.insert(2, 0);
+        app.real_chatrooms.insert(3, 1);
+        app.chatrooms.insert(1, chat_1);
+        app.chatrooms.insert(2, chat_2);
+        app.chatrooms.insert(3, chat_3);
+
+        let chat = app.chatrooms.get(&2).unwrap();
+        assert_eq!(
+            app.conversation_identifiers(chat),
+            vec!["chat222", "chat111"]
+        );
+
+        let chat = app.chatrooms.get(&3).unwrap();
+        assert_eq!(app.conversation_identifiers(chat), vec!["chat333"]);
+    }
+
+    #[test]
+    fn can_get_conversation_identifiers_unknown_chat() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        let mut chat = fake_chat();
+        chat.chat_identifier = String::from("chat444");
+        assert_eq!(app.conversation_identifiers(&chat), vec!["chat444"]);
+    }
+
+    #[test]
+    fn can_list_conversations() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        // Message counts are read from the chat to message join table
+        app.db = rusqlite::Connection::open_in_memory().unwrap();
+        app.db
+            .execute_batch(
+                "CREATE TABLE chat_message_join (chat_id INTEGER, message_id INTEGER);
+                 INSERT INTO chat_message_join VALUES (1, 1), (1, 2), (2, 3), (3, 4);",
+            )
+            .unwrap();
+        app.schema = Schema::detect(&app.db).unwrap();
+
+        // Two direct chats with the same participant that dedupe to the same conversation
+        let mut chat_1 = fake_chat();
+        chat_1.rowid = 1;
+        chat_1.last_message_date = Some(500);
+        chat_1.first_message_date = Some(100);
+
+        let mut chat_2 = fake_chat();
+        chat_2.rowid = 2;
+        chat_2.last_message_date = Some(200);
+        chat_2.first_message_date = Some(50);
+
+        // A named group chat
+        let mut chat_3 = fake_chat();
+        chat_3.rowid = 3;
+        chat_3.display_name = Some(String::from("Group"));
+        chat_3.last_message_date = Some(900);
+        chat_3.first_message_date = Some(300);
+
+        app.real_chatrooms.insert(1, 0);
+        app.real_chatrooms.insert(2, 0);
+        app.real_chatrooms.insert(3, 1);
+        app.chatrooms.insert(1, chat_1);
+        app.chatrooms.insert(2, chat_2);
+        app.chatrooms.insert(3, chat_3);
+
+        app.participants.insert(10, String::from("+15558675309"));
+        app.participants
+            .insert(11, String::from("person@example.com"));
+        app.chatroom_participants.insert(1, BTreeSet::from([10]));
+        app.chatroom_participants.insert(2, BTreeSet::from([10]));
+        app.chatroom_participants
+            .insert(3, BTreeSet::from([10, 11]));
+
+        // The most recently active conversation is listed first
+        assert_eq!(
+            app.conversations().unwrap(),
+            vec![
+                Conversation {
+                    id: 1,
+                    display_name: String::from("Group"),
+                    participants: vec![
+                        String::from("+15558675309"),
+                        String::from("person@example.com")
+                    ],
+                    message_count: 1,
+                    last_activity: Some(900),
+                },
+                Conversation {
+                    id: 0,
+                    display_name: String::from("+15558675309"),
+                    participants: vec![String::from("+15558675309")],
+                    message_count: 3,
+                    last_activity: Some(500),
+                },
+            ]
+        );
+    }
 }
 
 #[cfg(test)]
 mod who_tests {
-    use crate::{app::attachment_manager::AttachmentManager, Config, Options};
+    use crate::{
+        app::{
+            attachment_manager::{AttachmentManager, DEFAULT_COPY_RETRIES},
+            export_type::{DayOneGranularity, DirectoryLayout, SplitBy},
+            options::{DEFAULT_HTML_LANG, DEFAULT_IRC_TIMESTAMP_FORMAT},
+            runtime::{ExportCounts, MessageFilter},
+            timestamp_format::TimestampFormat,
+        },
+        Config, Options,
+    };
     use imessage_database::{
-        tables::{chat::Chat, messages::Message, table::get_connection},
-        util::{dirs::default_db_path, platform::Platform, query_context::QueryContext},
+        message_types::text_effects::TextEffect,
+        tables::{
+            chat::Chat,
+            messages::{
+                models::{BubbleComponent, TextAttributes},
+                Message,
+            },
+            schema::Schema,
+            table::{get_connection, FITNESS_RECEIVER, FITNESS_SENDER},
+        },
+        util::{
+            dates::get_offset, dirs::default_db_path, platform::Platform,
+            query_context::QueryContext,
+        },
+    };
+    use std::{
+        cell::RefCell,
+        collections::{BTreeSet, HashMap, HashSet},
+        env::set_var,
+        path::PathBuf,
     };
-    use std::{collections::HashMap, path::PathBuf};
 
     fn fake_options() -> Options {
         Options {
@@ -630,8 +2378,57 @@ mod who_tests {
             no_lazy: false,
             custom_name: None,
             use_caller_id: false,
+            show_my_number: false,
             platform: Platform::macOS,
             ignore_disk_space: false,
+            show_removed_reactions: false,
+            limit: None,
+            limit_per_chat: None,
+            merge_services: false,
+            dayone_granularity: DayOneGranularity::default(),
+            copy_retries: DEFAULT_COPY_RETRIES,
+            timestamp_format: TimestampFormat::default(),
+            report: None,
+            txt_template: None,
+            manifest: false,
+            animate_effects: false,
+            participants_only: None,
+            exclude_orphaned: false,
+            only_orphaned: false,
+            media_grid: false,
+            show_receipts: false,
+            exif_sidecars: false,
+            post_export_command: None,
+            mask_text: false,
+            compact_html: false,
+            split_by: None,
+            warc: false,
+            no_attachments: false,
+            collapse_reactions: None,
+            mark_failed_transfers: false,
+            message_guid: None,
+            normalize_newlines: false,
+            html_lang: DEFAULT_HTML_LANG.to_string(),
+            css: None,
+            directory_layout: DirectoryLayout::default(),
+            include_empty_chats: false,
+            print_friendly: false,
+            since_last_export: false,
+            reset_last_export: false,
+            only_reported: false,
+            number_messages: false,
+            emoji_shortcodes: false,
+            mime_overrides: HashMap::new(),
+            flatten_groups: false,
+            extract_media_only: false,
+            annotations: None,
+            dump_typedstream: None,
+            irc_timestamp_format: DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+            alt_text: false,
+            reaction_context: false,
+            quiet_delivery: false,
+            copy_db_first: false,
+            tokenize: false,
         }
     }
 
@@ -641,14 +2438,20 @@ mod who_tests {
             chat_identifier: "Default".to_string(),
             service_name: Some(String::new()),
             display_name: None,
+            first_message_date: None,
+            last_message_date: None,
+            has_imessage: false,
+            has_sms: false,
         }
     }
 
     fn fake_app(options: Options) -> Config {
         let connection = get_connection(&options.db_path).unwrap();
+        let schema = Schema::detect(&connection).unwrap();
         Config {
             chatrooms: HashMap::new(),
             real_chatrooms: HashMap::new(),
+            merged_chatrooms: HashMap::new(),
             chatroom_participants: HashMap::new(),
             participants: HashMap::new(),
             real_participants: HashMap::new(),
@@ -656,7 +2459,11 @@ mod who_tests {
             options,
             offset: 0,
             db: connection,
+            schema,
             converter: Some(crate::app::converter::Converter::Sips),
+            attachment_paths: RefCell::new(HashMap::new()),
+            annotations: HashMap::new(),
+            db_copy: None,
         }
     }
 
@@ -672,8 +2479,12 @@ mod who_tests {
             date: i64::default(),
             date_read: i64::default(),
             date_delivered: i64::default(),
+            date_played: 0,
             is_from_me: false,
             is_read: false,
+            is_forward: false,
+            is_spam: false,
+            was_delivered_quietly: false,
             item_type: 0,
             other_handle: 0,
             share_status: false,
@@ -687,6 +2498,7 @@ mod who_tests {
             thread_originator_guid: None,
             thread_originator_part: None,
             date_edited: 0,
+            schedule_type: 0,
             chat_id: None,
             num_attachments: 0,
             deleted_from: None,
@@ -775,42 +2587,260 @@ mod who_tests {
     }
 
     #[test]
-    fn can_get_who_none_them() {
-        let options = fake_options();
+    fn can_get_who_me_different_caller_ids() {
+        let mut options = fake_options();
+        options.use_caller_id = true;
+        let app = fake_app(options);
+
+        // Messages sent from different identities on the same account
+        let phone = Some("+15555550000".to_string());
+        let email = Some("me@example.com".to_string());
+        assert_eq!(app.who(Some(0), true, &phone), "+15555550000");
+        assert_eq!(app.who(Some(0), true, &email), "me@example.com");
+    }
+
+    #[test]
+    fn can_get_who_me_blank_caller_id() {
+        let mut options = fake_options();
+        options.use_caller_id = true;
         let app = fake_app(options);
 
         // Get participant name
-        let who = app.who(None, false, &None);
-        assert_eq!(who, "Unknown".to_string());
+        let caller_id = Some(" ".to_string());
+        let who = app.who(Some(0), true, &caller_id);
+        assert_eq!(who, "Me");
     }
 
     #[test]
-    fn can_get_chat_valid() {
+    fn can_get_who_me_show_my_number() {
+        let mut options = fake_options();
+        options.show_my_number = true;
+        let app = fake_app(options);
+
+        // Messages sent from different identities on the same account
+        let phone = Some("+15555550000".to_string());
+        let email = Some("me@example.com".to_string());
+        assert_eq!(app.who(Some(0), true, &phone), "Me (+15555550000)");
+        assert_eq!(app.who(Some(0), true, &email), "Me (me@example.com)");
+        assert_eq!(app.who(Some(0), true, &None), "Me");
+    }
+
+    #[test]
+    fn can_replace_fitness_received() {
         let options = fake_options();
         let mut app = fake_app(options);
+        app.participants.insert(10, "Person 10".to_string());
 
-        // Create chat
-        let chat = fake_chat();
-        app.chatrooms.insert(chat.rowid, chat);
-        app.real_chatrooms.insert(0, 0);
-
-        // Create message
         let mut message = blank();
-        message.chat_id = Some(0);
+        message.handle_id = Some(10);
+        message.chat_id = Some(1);
 
-        // Get filename
-        let (_, id) = app.conversation(&message).unwrap();
-        assert_eq!(id, &0);
+        let text = format!("{FITNESS_SENDER} challenged {FITNESS_RECEIVER} to a competition");
+        assert_eq!(
+            app.fitness_text(&text, &message),
+            "Person 10 challenged You to a competition"
+        );
     }
 
     #[test]
-    fn can_get_chat_valid_deleted() {
+    fn can_replace_fitness_sent() {
         let options = fake_options();
         let mut app = fake_app(options);
+        app.participants.insert(10, "Person 10".to_string());
+        app.chatroom_participants.insert(1, BTreeSet::from([10]));
 
-        // Create chat
-        let chat = fake_chat();
-        app.chatrooms.insert(chat.rowid, chat);
+        let mut message = blank();
+        message.is_from_me = true;
+        message.chat_id = Some(1);
+
+        let text = format!("{FITNESS_SENDER} challenged {FITNESS_RECEIVER} to a competition");
+        assert_eq!(
+            app.fitness_text(&text, &message),
+            "You challenged Person 10 to a competition"
+        );
+
+        // A group chat has no single recipient
+        app.chatroom_participants
+            .insert(1, BTreeSet::from([10, 11]));
+        assert_eq!(
+            app.fitness_text(
+                &format!("{FITNESS_RECEIVER} closed all three rings"),
+                &message
+            ),
+            "They closed all three rings"
+        );
+    }
+
+    #[test]
+    fn can_skip_fitness_plain_text() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        assert_eq!(app.fitness_text("Hello world", &blank()), "Hello world");
+    }
+
+    #[test]
+    fn can_get_reaction_target_text() {
+        let mut options = fake_options();
+        options.reaction_context = true;
+        let app = fake_app(options);
+
+        let mut message = blank();
+        message.text = Some("Hello world\u{FFFC}".to_string());
+        let parts = vec![
+            BubbleComponent::Text(vec![TextAttributes::new(0, 11, TextEffect::Default)]),
+            BubbleComponent::Attachment,
+        ];
+
+        assert_eq!(
+            app.reaction_target(&message, &parts, 0, &[]),
+            Some("\"Hello world\"".to_string())
+        );
+    }
+
+    #[test]
+    fn can_get_reaction_target_image() {
+        let mut options = fake_options();
+        options.reaction_context = true;
+        let app = fake_app(options);
+
+        let mut message = blank();
+        message.text = Some("Hello world\u{FFFC}".to_string());
+        let parts = vec![
+            BubbleComponent::Text(vec![TextAttributes::new(0, 11, TextEffect::Default)]),
+            BubbleComponent::Attachment,
+        ];
+        let attachments = vec![super::directory_tests::fake_attachment()];
+
+        assert_eq!(
+            app.reaction_target(&message, &parts, 1, &attachments),
+            Some("an image".to_string())
+        );
+    }
+
+    #[test]
+    fn cant_get_reaction_target_disabled() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        let mut message = blank();
+        message.text = Some("Hello world".to_string());
+        let parts = vec![BubbleComponent::Text(vec![TextAttributes::new(
+            0,
+            11,
+            TextEffect::Default,
+        )])];
+
+        assert_eq!(app.reaction_target(&message, &parts, 0, &[]), None);
+    }
+
+    #[test]
+    fn can_match_my_handle() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        let mut message = blank();
+        message.destination_caller_id = Some("+1 (555) 555-0000".to_string());
+        assert!(app.is_my_handle("+15555550000", &message));
+        assert!(!app.is_my_handle("+15555550001", &message));
+
+        message.destination_caller_id = Some("Me@Example.com".to_string());
+        assert!(app.is_my_handle("me@example.com", &message));
+
+        message.destination_caller_id = None;
+        assert!(!app.is_my_handle("+15555550000", &message));
+    }
+
+    #[test]
+    fn can_get_who_me_custom_show_my_number() {
+        let mut options = fake_options();
+        options.custom_name = Some("Name".to_string());
+        options.show_my_number = true;
+        let app = fake_app(options);
+
+        // Get participant name
+        let caller_id = Some("+15555550000".to_string());
+        let who = app.who(Some(0), true, &caller_id);
+        assert_eq!(who, "Name (+15555550000)");
+    }
+
+    #[test]
+    fn can_get_who_them_ignores_caller_id() {
+        let mut options = fake_options();
+        options.show_my_number = true;
+        let mut app = fake_app(options);
+
+        // Add participant
+        app.participants.insert(10, "Person 10".to_string());
+
+        // The caller ID of a received message is the owner's, not the sender's
+        let caller_id = Some("+15555550000".to_string());
+        let who = app.who(Some(10), false, &caller_id);
+        assert_eq!(who, "Person 10");
+    }
+
+    #[test]
+    fn can_get_who_none_them() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Get participant name
+        let who = app.who(None, false, &None);
+        assert_eq!(who, "Unknown".to_string());
+    }
+
+    #[test]
+    fn can_get_chat_valid() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        // Create chat
+        let chat = fake_chat();
+        app.chatrooms.insert(chat.rowid, chat);
+        app.real_chatrooms.insert(0, 0);
+
+        // Create message
+        let mut message = blank();
+        message.chat_id = Some(0);
+
+        // Get filename
+        let (_, id) = app.conversation(&message).unwrap();
+        assert_eq!(id, &0);
+    }
+
+    #[test]
+    fn can_get_conversation_id() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+        app.real_chatrooms.insert(2, 0);
+
+        // Create message
+        let mut message = blank();
+        message.deleted_from = Some(2);
+
+        assert_eq!(app.conversation_id(&message), Some(0));
+    }
+
+    #[test]
+    fn cant_get_conversation_id_missing() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Create message
+        let mut message = blank();
+        message.chat_id = Some(2);
+
+        assert_eq!(app.conversation_id(&message), None);
+    }
+
+    #[test]
+    fn can_get_chat_valid_deleted() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        // Create chat
+        let chat = fake_chat();
+        app.chatrooms.insert(chat.rowid, chat);
         app.real_chatrooms.insert(0, 0);
 
         // Create message
@@ -861,16 +2891,378 @@ mod who_tests {
         let room = app.conversation(&message);
         assert!(room.is_none());
     }
+
+    #[test]
+    fn can_skip_orphaned_excluded() {
+        let mut options = fake_options();
+        options.exclude_orphaned = true;
+        let mut app = fake_app(options);
+        app.chatrooms.insert(0, fake_chat());
+        app.real_chatrooms.insert(0, 0);
+
+        let mut message = blank();
+        assert!(app.skip_orphaned(&message));
+
+        message.chat_id = Some(0);
+        assert!(!app.skip_orphaned(&message));
+    }
+
+    #[test]
+    fn can_skip_orphaned_only() {
+        let mut options = fake_options();
+        options.only_orphaned = true;
+        let mut app = fake_app(options);
+        app.chatrooms.insert(0, fake_chat());
+        app.real_chatrooms.insert(0, 0);
+
+        let mut message = blank();
+        assert!(!app.skip_orphaned(&message));
+
+        message.chat_id = Some(0);
+        assert!(app.skip_orphaned(&message));
+    }
+
+    #[test]
+    fn cant_skip_orphaned_default() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        let message = blank();
+        assert!(!app.skip_orphaned(&message));
+    }
+
+    #[test]
+    fn can_skip_unreported() {
+        let mut options = fake_options();
+        options.only_reported = true;
+        let app = fake_app(options);
+
+        let mut message = blank();
+        assert!(app.skip_unreported(&message));
+
+        message.is_spam = true;
+        assert!(!app.skip_unreported(&message));
+    }
+
+    #[test]
+    fn cant_skip_unreported_default() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        let message = blank();
+        assert!(!app.skip_unreported(&message));
+    }
+
+    #[test]
+    fn can_filter_message_limit() {
+        let mut options = fake_options();
+        options.limit = Some(2);
+        let app = fake_app(options);
+        let mut counts = ExportCounts::default();
+
+        let message = blank();
+        for expected in [
+            MessageFilter::Export,
+            MessageFilter::Export,
+            MessageFilter::Stop,
+        ] {
+            assert_eq!(
+                app.filter_message(&message, &None, &mut counts, true),
+                expected
+            );
+        }
+        assert_eq!(counts.total, 2);
+    }
+
+    #[test]
+    fn can_filter_message_limit_per_chat() {
+        let mut options = fake_options();
+        options.limit_per_chat = Some(1);
+        let mut app = fake_app(options);
+        app.chatrooms.insert(0, fake_chat());
+        app.real_chatrooms.insert(0, 0);
+        let mut counts = ExportCounts::default();
+
+        let mut message = blank();
+        message.chat_id = Some(0);
+        assert_eq!(
+            app.filter_message(&message, &None, &mut counts, true),
+            MessageFilter::Export
+        );
+        assert_eq!(
+            app.filter_message(&message, &None, &mut counts, true),
+            MessageFilter::Skip
+        );
+
+        // Other conversations have their own limit
+        let orphaned = blank();
+        assert_eq!(
+            app.filter_message(&orphaned, &None, &mut counts, true),
+            MessageFilter::Export
+        );
+        assert_eq!(counts.total, 2);
+        assert_eq!(counts.per_chat.get(&Some(0)), Some(&1));
+        assert_eq!(counts.per_chat.get(&None), Some(&1));
+    }
+
+    #[test]
+    fn can_filter_message_uncounted() {
+        let mut options = fake_options();
+        options.limit = Some(1);
+        let app = fake_app(options);
+        let mut counts = ExportCounts::default();
+
+        let message = blank();
+        assert_eq!(
+            app.filter_message(&message, &None, &mut counts, true),
+            MessageFilter::Export
+        );
+        assert_eq!(
+            app.filter_message(&message, &None, &mut counts, true),
+            MessageFilter::Stop
+        );
+
+        // Messages that are not counted, i.e. reactions, are still exported
+        assert_eq!(
+            app.filter_message(&message, &None, &mut counts, false),
+            MessageFilter::Export
+        );
+        assert_eq!(counts.total, 1);
+    }
+
+    #[test]
+    fn can_filter_message_skipped() {
+        let mut options = fake_options();
+        options.only_reported = true;
+        options.limit = Some(1);
+        let app = fake_app(options);
+        let mut counts = ExportCounts::default();
+
+        // Skipped messages do not count towards the limits
+        let mut message = blank();
+        assert_eq!(
+            app.filter_message(&message, &None, &mut counts, true),
+            MessageFilter::Skip
+        );
+        assert_eq!(counts.total, 0);
+
+        message.is_spam = true;
+        assert_eq!(
+            app.filter_message(&message, &None, &mut counts, true),
+            MessageFilter::Export
+        );
+
+        // Messages outside the selected conversations are skipped
+        let chatrooms = Some(HashSet::from([1]));
+        assert_eq!(
+            app.filter_message(&message, &chatrooms, &mut counts, true),
+            MessageFilter::Skip
+        );
+    }
+
+    #[test]
+    fn can_get_message_filename_split() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let mut options = fake_options();
+        options.split_by = Some(SplitBy::Day);
+        let mut app = fake_app(options);
+        app.offset = get_offset();
+
+        let chat = fake_chat();
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+
+        assert_eq!(app.message_filename(&chat, &message), "Default-2022-05-17");
+    }
+
+    #[test]
+    fn can_get_message_filename_year_directory() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let mut options = fake_options();
+        options.split_by = Some(SplitBy::Month);
+        options.directory_layout = DirectoryLayout::Year;
+        let mut app = fake_app(options);
+        app.offset = get_offset();
+
+        let chat = fake_chat();
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+
+        assert_eq!(
+            app.message_filename(&chat, &message),
+            "2022/Default-2022-05"
+        );
+    }
+
+    #[test]
+    fn can_get_message_filename_year_directory_unsplit() {
+        let mut options = fake_options();
+        options.directory_layout = DirectoryLayout::Year;
+        let app = fake_app(options);
+
+        let chat = fake_chat();
+        let message = blank();
+
+        assert_eq!(app.message_filename(&chat, &message), "Default");
+    }
+
+    #[test]
+    fn can_get_message_filename_unsplit() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        let chat = fake_chat();
+        let message = blank();
+
+        assert_eq!(app.message_filename(&chat, &message), "Default");
+    }
+
+    #[test]
+    fn can_get_attachment_link_year_directory() {
+        let mut options = fake_options();
+        options.export_path = PathBuf::from("/Users/ReagentX/exports");
+        options.split_by = Some(SplitBy::Year);
+        options.directory_layout = DirectoryLayout::Year;
+        let mut app = fake_app(options);
+        app.chatrooms.insert(0, fake_chat());
+        app.real_chatrooms.insert(0, 0);
+
+        let mut attachment = super::directory_tests::fake_attachment();
+        attachment.copied_path = Some(PathBuf::from("/Users/ReagentX/exports/attachments/d.jpg"));
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.chat_id = Some(0);
+        assert_eq!(
+            app.message_attachment_link(&attachment, &message),
+            "../attachments/d.jpg"
+        );
+
+        // Orphaned messages are written to the top of the export directory
+        message.chat_id = None;
+        assert_eq!(
+            app.message_attachment_link(&attachment, &message),
+            "attachments/d.jpg"
+        );
+    }
+
+    #[test]
+    fn can_get_sticker_source_from_message() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+        // An empty database has no attachment table, so there is no `attribution_info` to read
+        app.db = rusqlite::Connection::open_in_memory().unwrap();
+
+        let mut sticker = super::directory_tests::fake_attachment();
+        sticker.is_sticker = true;
+
+        let mut message = blank();
+        message.balloon_bundle_id = Some("com.apple.messages.MSMessageExtensionBalloonPlugin:0000000000:com.example.StickerPack.MessagesExtension".to_string());
+        assert_eq!(
+            app.sticker_source(&sticker, &message).unwrap().to_string(),
+            "com.example.StickerPack.MessagesExtension"
+        );
+
+        // Built-in sticker apps are not listed as a source
+        message.balloon_bundle_id = Some("com.apple.messages.MSMessageExtensionBalloonPlugin:0000000000:com.apple.Stickers.UserGenerated.MessagesExtension".to_string());
+        assert!(app.sticker_source(&sticker, &message).is_none());
+
+        // Attachments that are not stickers have no source
+        sticker.is_sticker = false;
+        message.balloon_bundle_id = Some("com.example.StickerPack.MessagesExtension".to_string());
+        assert!(app.sticker_source(&sticker, &message).is_none());
+    }
+
+    #[test]
+    fn can_skip_attachment_queries() {
+        let mut options = fake_options();
+        options.no_attachments = true;
+        let mut app = fake_app(options);
+
+        // An empty database has no attachment table, so any attachment query fails
+        app.db = rusqlite::Connection::open_in_memory().unwrap();
+
+        let mut message = blank();
+        message.num_attachments = 1;
+
+        assert!(app.attachments(&message).unwrap().is_empty());
+    }
+
+    #[test]
+    fn can_query_attachments() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+        app.db = rusqlite::Connection::open_in_memory().unwrap();
+
+        let mut message = blank();
+        message.num_attachments = 1;
+
+        assert!(app.attachments(&message).is_err());
+    }
+
+    #[test]
+    fn can_normalize_newlines() {
+        let mut options = fake_options();
+        options.normalize_newlines = true;
+        let app = fake_app(options);
+
+        assert_eq!(app.normalize("a\r\nb\rc"), "a\nb\nc");
+    }
+
+    #[test]
+    fn cant_normalize_newlines_disabled() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        assert_eq!(app.normalize("a\r\nb\rc"), "a\r\nb\rc");
+    }
+
+    #[test]
+    fn can_get_message_by_guid() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+        app.db = rusqlite::Connection::open_in_memory().unwrap();
+        app.db
+            .execute_batch(
+                "CREATE TABLE message (ROWID INTEGER PRIMARY KEY, guid TEXT, text TEXT, date INTEGER, is_from_me INTEGER, is_read INTEGER);
+                 CREATE TABLE chat_message_join (chat_id INTEGER, message_id INTEGER);
+                 CREATE TABLE message_attachment_join (message_id INTEGER, attachment_id INTEGER);
+                 INSERT INTO message VALUES (1, 'ABCD-1234', 'Hello world', 0, 1, 1);",
+            )
+            .unwrap();
+        app.schema = Schema::detect(&app.db).unwrap();
+
+        let message = app.message_by_guid("ABCD-1234").unwrap();
+        assert_eq!(message.rowid, 1);
+        assert_eq!(message.text.as_deref(), Some("Hello world"));
+        assert!(app.message_by_guid("missing").is_err());
+    }
 }
 
 #[cfg(test)]
 mod directory_tests {
-    use crate::{app::attachment_manager::AttachmentManager, Config, Options};
+    use crate::{
+        app::{
+            attachment_manager::{AttachmentManager, DEFAULT_COPY_RETRIES},
+            export_type::{DayOneGranularity, DirectoryLayout},
+            options::{DEFAULT_HTML_LANG, DEFAULT_IRC_TIMESTAMP_FORMAT},
+            runtime::MAX_CACHED_ATTACHMENT_PATHS,
+            timestamp_format::TimestampFormat,
+        },
+        Config, Options,
+    };
     use imessage_database::{
-        tables::{attachment::Attachment, table::get_connection},
+        tables::{attachment::Attachment, schema::Schema, table::get_connection},
         util::{dirs::default_db_path, platform::Platform, query_context::QueryContext},
     };
-    use std::{collections::HashMap, path::PathBuf};
+    use std::{cell::RefCell, collections::HashMap, path::PathBuf};
 
     fn fake_options() -> Options {
         Options {
@@ -884,16 +3276,67 @@ mod directory_tests {
             no_lazy: false,
             custom_name: None,
             use_caller_id: false,
+            show_my_number: false,
             platform: Platform::macOS,
             ignore_disk_space: false,
+            show_removed_reactions: false,
+            limit: None,
+            limit_per_chat: None,
+            merge_services: false,
+            dayone_granularity: DayOneGranularity::default(),
+            copy_retries: DEFAULT_COPY_RETRIES,
+            timestamp_format: TimestampFormat::default(),
+            report: None,
+            txt_template: None,
+            manifest: false,
+            animate_effects: false,
+            participants_only: None,
+            exclude_orphaned: false,
+            only_orphaned: false,
+            media_grid: false,
+            show_receipts: false,
+            exif_sidecars: false,
+            post_export_command: None,
+            mask_text: false,
+            compact_html: false,
+            split_by: None,
+            warc: false,
+            no_attachments: false,
+            collapse_reactions: None,
+            mark_failed_transfers: false,
+            message_guid: None,
+            normalize_newlines: false,
+            html_lang: DEFAULT_HTML_LANG.to_string(),
+            css: None,
+            directory_layout: DirectoryLayout::default(),
+            include_empty_chats: false,
+            print_friendly: false,
+            since_last_export: false,
+            reset_last_export: false,
+            only_reported: false,
+            number_messages: false,
+            emoji_shortcodes: false,
+            mime_overrides: HashMap::new(),
+            flatten_groups: false,
+            extract_media_only: false,
+            annotations: None,
+            dump_typedstream: None,
+            irc_timestamp_format: DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+            alt_text: false,
+            reaction_context: false,
+            quiet_delivery: false,
+            copy_db_first: false,
+            tokenize: false,
         }
     }
 
     fn fake_app(options: Options) -> Config {
         let connection = get_connection(&options.db_path).unwrap();
+        let schema = Schema::detect(&connection).unwrap();
         Config {
             chatrooms: HashMap::new(),
             real_chatrooms: HashMap::new(),
+            merged_chatrooms: HashMap::new(),
             chatroom_participants: HashMap::new(),
             participants: HashMap::new(),
             real_participants: HashMap::new(),
@@ -901,7 +3344,11 @@ mod directory_tests {
             options,
             offset: 0,
             db: connection,
+            schema,
             converter: Some(crate::app::converter::Converter::Sips),
+            attachment_paths: RefCell::new(HashMap::new()),
+            annotations: HashMap::new(),
+            db_copy: None,
         }
     }
 
@@ -915,10 +3362,96 @@ mod directory_tests {
             total_bytes: 100,
             is_sticker: false,
             hide_attachment: 0,
+            transfer_state: 5,
+            is_outgoing: false,
             copied_path: None,
+            description: None,
         }
     }
 
+    #[test]
+    fn can_get_expired_audio_message() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Create an audio message whose file no longer exists
+        let mut attachment = fake_attachment();
+        attachment.filename = Some("a/b/c/Audio Message.caf".to_string());
+        attachment.uti = Some("com.apple.coreaudio-format".to_string());
+        attachment.mime_type = None;
+
+        assert!(app.is_expired_audio_message(&attachment));
+
+        // Audio messages without a path have also expired
+        attachment.filename = None;
+        assert!(app.is_expired_audio_message(&attachment));
+    }
+
+    #[test]
+    fn cant_get_expired_audio_message_exists() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Create an audio message whose file still exists
+        let mut attachment = fake_attachment();
+        attachment.filename = Some(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml").to_string());
+        attachment.uti = Some("com.apple.coreaudio-format".to_string());
+
+        assert!(!app.is_expired_audio_message(&attachment));
+    }
+
+    #[test]
+    fn cant_get_expired_audio_message_not_audio() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Missing attachments that are not audio messages are not expired
+        let attachment = fake_attachment();
+
+        assert!(!app.is_expired_audio_message(&attachment));
+    }
+
+    #[test]
+    fn can_get_failed_transfer() {
+        let mut options = fake_options();
+        options.mark_failed_transfers = true;
+        let app = fake_app(options);
+
+        let mut attachment = fake_attachment();
+        attachment.transfer_state = 6;
+        assert_eq!(
+            app.failed_transfer(&attachment),
+            Some("Attachment failed to download")
+        );
+
+        attachment.is_outgoing = true;
+        assert_eq!(
+            app.failed_transfer(&attachment),
+            Some("Attachment failed to send")
+        );
+    }
+
+    #[test]
+    fn cant_get_failed_transfer_unmarked() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Failed transfers are only described when requested
+        let mut attachment = fake_attachment();
+        attachment.transfer_state = 6;
+        assert_eq!(app.failed_transfer(&attachment), None);
+    }
+
+    #[test]
+    fn cant_get_failed_transfer_finished() {
+        let mut options = fake_options();
+        options.mark_failed_transfers = true;
+        let app = fake_app(options);
+
+        let attachment = fake_attachment();
+        assert_eq!(app.failed_transfer(&attachment), None);
+    }
+
     #[test]
     fn can_get_valid_attachment_sub_dir() {
         let options = fake_options();
@@ -1006,4 +3539,64 @@ mod directory_tests {
         let expected = String::from("a/b/c/d.jpg");
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn can_cache_resolved_path() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Create attachment
+        let attachment = fake_attachment();
+
+        let result = app.resolved_attachment_path(&attachment);
+        assert_eq!(result, Some(String::from("a/b/c/d.jpg")));
+        assert_eq!(
+            app.attachment_paths.borrow().get("a/b/c/d.jpg"),
+            Some(&Some(String::from("a/b/c/d.jpg")))
+        );
+
+        // A second lookup should hit the cache
+        app.attachment_paths
+            .borrow_mut()
+            .insert(String::from("a/b/c/d.jpg"), Some(String::from("cached")));
+        let result = app.resolved_attachment_path(&attachment);
+        assert_eq!(result, Some(String::from("cached")));
+    }
+
+    #[test]
+    fn can_bound_resolved_path_cache() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Fill the cache with other attachments
+        for i in 0..MAX_CACHED_ATTACHMENT_PATHS {
+            let mut attachment = fake_attachment();
+            attachment.filename = Some(format!("a/b/c/{i}.jpg"));
+            app.resolved_attachment_path(&attachment);
+        }
+        assert_eq!(
+            app.attachment_paths.borrow().len(),
+            MAX_CACHED_ATTACHMENT_PATHS
+        );
+
+        // The next new attachment empties the cache before it is added
+        let attachment = fake_attachment();
+        let result = app.resolved_attachment_path(&attachment);
+        assert_eq!(result, Some(String::from("a/b/c/d.jpg")));
+        assert_eq!(app.attachment_paths.borrow().len(), 1);
+    }
+
+    #[test]
+    fn cant_cache_missing_path() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Create attachment
+        let mut attachment = fake_attachment();
+        attachment.filename = None;
+
+        let result = app.resolved_attachment_path(&attachment);
+        assert_eq!(result, None);
+        assert!(app.attachment_paths.borrow().is_empty());
+    }
 }
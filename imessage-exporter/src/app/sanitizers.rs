@@ -33,18 +33,44 @@ static HTML_DISALLOWED_CHARS: LazyLock<HashMap<&char, &str>> = LazyLock::new(||
 /// The character to replace disallowed chars with
 const FILENAME_REPLACEMENT_CHAR: char = '_';
 
-/// Remove unsafe chars in [this list](FILENAME_DISALLOWED_CHARS).
+/// Device names that Windows reserves, with or without an extension
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Make a filename safe to use on macOS, Linux, and Windows.
+///
+/// - Replaces the chars in [this list](FILENAME_DISALLOWED_CHARS) and control characters
+/// - Trims trailing dots and spaces, which Windows strips from filenames
+/// - Appends [`FILENAME_REPLACEMENT_CHAR`] to names Windows reserves for devices, i.e. `CON` or `NUL`
 pub fn sanitize_filename(filename: &str) -> String {
-    filename
+    let mut sanitized: String = filename
         .chars()
         .map(|letter| {
-            if FILENAME_DISALLOWED_CHARS.contains(&letter) {
+            if FILENAME_DISALLOWED_CHARS.contains(&letter) || letter.is_control() {
                 FILENAME_REPLACEMENT_CHAR
             } else {
                 letter
             }
         })
-        .collect()
+        .collect();
+
+    sanitized.truncate(sanitized.trim_end_matches(['.', ' ']).len());
+    if sanitized.is_empty() {
+        sanitized.push(FILENAME_REPLACEMENT_CHAR);
+    }
+
+    // Reserved names are disallowed even when followed by an extension, i.e. `NUL.txt`
+    let stem = sanitized.split('.').next().unwrap_or_default();
+    if WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem.trim_end()))
+    {
+        sanitized.insert(stem.len(), FILENAME_REPLACEMENT_CHAR);
+    }
+
+    sanitized
 }
 
 /// Escapes HTML special characters in the input string.
@@ -64,6 +90,48 @@ pub fn sanitize_html(input: &str) -> Cow<str> {
     Cow::Borrowed(input)
 }
 
+/// Escapes a string so it can be embedded in a JSON string literal.
+pub fn sanitize_json(input: &str) -> Cow<'_, str> {
+    if !input
+        .chars()
+        .any(|c| matches!(c, '"' | '\\') || c.is_control())
+    {
+        return Cow::Borrowed(input);
+    }
+
+    let mut res = String::with_capacity(input.len() + 8);
+    input.chars().for_each(|c| match c {
+        '"' => res.push_str("\\\""),
+        '\\' => res.push_str("\\\\"),
+        '\n' => res.push_str("\\n"),
+        '\r' => res.push_str("\\r"),
+        '\t' => res.push_str("\\t"),
+        c if c.is_control() => res.push_str(&format!("\\u{:04x}", c as u32)),
+        c => res.push(c),
+    });
+    Cow::Owned(res)
+}
+
+/// The character used to mask message text
+const MASK_CHAR: char = '█';
+
+/// Replace every non-whitespace character with [`MASK_CHAR`], keeping the text's length and line breaks.
+pub fn mask_text(input: &str) -> String {
+    input
+        .chars()
+        .map(|c| if c.is_whitespace() { c } else { MASK_CHAR })
+        .collect()
+}
+
+/// Convert `\r\n` and lone `\r` line endings to `\n`, borrowing the input if it has none
+pub fn normalize_newlines(input: &str) -> Cow<'_, str> {
+    if input.contains('\r') {
+        Cow::Owned(input.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
 #[cfg(test)]
 mod test_filename {
     use crate::app::sanitizers::sanitize_filename;
@@ -83,6 +151,37 @@ mod test_filename {
         assert_eq!(sanitize_filename("ab/cd"), "ab_cd");
     }
 
+    #[test]
+    fn can_sanitize_colons() {
+        assert_eq!(sanitize_filename("Re: Plans? 10:30"), "Re_ Plans_ 10_30");
+    }
+
+    #[test]
+    fn can_sanitize_control_chars() {
+        assert_eq!(sanitize_filename("a\tb\nc"), "a_b_c");
+    }
+
+    #[test]
+    fn can_sanitize_trailing_dots_and_spaces() {
+        assert_eq!(sanitize_filename("Family... "), "Family");
+        assert_eq!(sanitize_filename(" . "), "_");
+        assert_eq!(sanitize_filename("..."), "_");
+    }
+
+    #[test]
+    fn can_sanitize_reserved_names() {
+        assert_eq!(sanitize_filename("CON"), "CON_");
+        assert_eq!(sanitize_filename("nul"), "nul_");
+        assert_eq!(sanitize_filename("Com1.txt"), "Com1_.txt");
+        assert_eq!(sanitize_filename("LPT9"), "LPT9_");
+    }
+
+    #[test]
+    fn doesnt_sanitize_reserved_prefix() {
+        assert_eq!(sanitize_filename("CONTACT"), "CONTACT");
+        assert_eq!(sanitize_filename("Aux Cable Group"), "Aux Cable Group");
+    }
+
     #[test]
     fn can_sanitize_only_bad() {
         assert_eq!(
@@ -146,3 +245,60 @@ mod tests {
         );
     }
 }
+
+#[cfg(test)]
+mod test_json {
+    use crate::app::sanitizers::sanitize_json;
+
+    #[test]
+    fn doesnt_sanitize_plain() {
+        assert_eq!(&sanitize_json("Hello world"), "Hello world");
+    }
+
+    #[test]
+    fn can_sanitize_quotes_and_slashes() {
+        assert_eq!(&sanitize_json("a \"b\" \\c"), "a \\\"b\\\" \\\\c");
+    }
+
+    #[test]
+    fn can_sanitize_control_chars() {
+        assert_eq!(&sanitize_json("a\nb\tc\u{0}"), "a\\nb\\tc\\u0000");
+    }
+}
+
+#[cfg(test)]
+mod test_mask {
+    use crate::app::sanitizers::mask_text;
+
+    #[test]
+    fn can_mask_text() {
+        assert_eq!(mask_text("Hello, world!"), "██████ ██████");
+    }
+
+    #[test]
+    fn can_mask_text_keeps_line_breaks() {
+        assert_eq!(mask_text("Hi\nthere"), "██\n█████");
+    }
+
+    #[test]
+    fn can_mask_text_multibyte() {
+        assert_eq!(mask_text("héllo 👋"), "█████ █");
+    }
+}
+
+#[cfg(test)]
+mod test_newlines {
+    use std::borrow::Cow;
+
+    use crate::app::sanitizers::normalize_newlines;
+
+    #[test]
+    fn can_normalize_newlines() {
+        assert_eq!(normalize_newlines("a\r\nb\rc\nd"), "a\nb\nc\nd");
+    }
+
+    #[test]
+    fn doesnt_normalize_newlines_none() {
+        assert!(matches!(normalize_newlines("a\nb"), Cow::Borrowed("a\nb")));
+    }
+}
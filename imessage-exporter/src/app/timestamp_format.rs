@@ -0,0 +1,66 @@
+/*!
+ Contains data structures used to describe how message timestamps are rendered.
+*/
+
+use std::fmt::Display;
+
+/// Represents how message timestamps are rendered in exports
+#[derive(PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub enum TimestampFormat {
+    /// Render the date and time a message was sent, i.e. `May 17, 2022  5:29:42 PM`
+    #[default]
+    Absolute,
+    /// Render how long ago a message was sent, i.e. `3 years ago`
+    Relative,
+    /// Render both the absolute and relative timestamps
+    Both,
+}
+
+impl TimestampFormat {
+    /// Given user's input, return a variant if the input matches one
+    pub fn from_cli(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "absolute" => Some(Self::Absolute),
+            "relative" => Some(Self::Relative),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+}
+
+impl Display for TimestampFormat {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampFormat::Absolute => write!(fmt, "absolute"),
+            TimestampFormat::Relative => write!(fmt, "relative"),
+            TimestampFormat::Both => write!(fmt, "both"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::timestamp_format::TimestampFormat;
+
+    #[test]
+    fn can_parse_any_case() {
+        assert_eq!(
+            TimestampFormat::from_cli("relative"),
+            Some(TimestampFormat::Relative)
+        );
+        assert_eq!(
+            TimestampFormat::from_cli("BOTH"),
+            Some(TimestampFormat::Both)
+        );
+        assert_eq!(
+            TimestampFormat::from_cli("Absolute"),
+            Some(TimestampFormat::Absolute)
+        );
+    }
+
+    #[test]
+    fn cant_parse_invalid() {
+        assert!(TimestampFormat::from_cli("ago").is_none());
+        assert!(TimestampFormat::from_cli("").is_none());
+    }
+}
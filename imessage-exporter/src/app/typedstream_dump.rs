@@ -0,0 +1,64 @@
+/*!
+ Renders a message's raw `typedstream` body next to its parsed form, for `--dump-typedstream`.
+*/
+
+use imessage_database::util::typedstream::parser::TypedStreamReader;
+
+/// The number of bytes shown on each line of a hex dump
+const BYTES_PER_LINE: usize = 16;
+
+/// Render `typedstream` bytes as a hex dump followed by the [`Archivable`](imessage_database::util::typedstream::models::Archivable) data parsed from them
+pub fn format_typedstream_dump(bytes: &[u8]) -> String {
+    let mut out = format!("Raw typedstream ({} bytes):\n", bytes.len());
+    out.push_str(&format_hex(bytes));
+
+    out.push_str("\nParsed typedstream:\n");
+    match TypedStreamReader::from(bytes).parse() {
+        Ok(parsed) => out.push_str(&format!("{parsed:#?}\n")),
+        Err(why) => out.push_str(&format!("Unable to parse typedstream: {why}\n")),
+    }
+    out
+}
+
+/// Render bytes as lines of hex pairs, each prefixed with the offset of its first byte
+fn format_hex(bytes: &[u8]) -> String {
+    bytes
+        .chunks(BYTES_PER_LINE)
+        .enumerate()
+        .map(|(line, chunk)| {
+            let hex = chunk
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<Vec<String>>()
+                .join(" ");
+            format!("{:08x}  {hex}\n", line * BYTES_PER_LINE)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::typedstream_dump::{format_hex, format_typedstream_dump};
+
+    #[test]
+    fn can_format_hex() {
+        let bytes: Vec<u8> = (0..18).collect();
+        let expected = "\
+00000000  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f
+00000010  10 11
+";
+        assert_eq!(format_hex(&bytes), expected);
+    }
+
+    #[test]
+    fn can_format_hex_empty() {
+        assert_eq!(format_hex(&[]), "");
+    }
+
+    #[test]
+    fn can_format_dump_invalid() {
+        let dump = format_typedstream_dump(&[0x04]);
+        assert!(dump.starts_with("Raw typedstream (1 bytes):\n00000000  04\n"));
+        assert!(dump.contains("Unable to parse typedstream: "));
+    }
+}
@@ -0,0 +1,270 @@
+/*!
+ Bundles an HTML export into a [WARC](https://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/) file for web archive tooling.
+
+ Each exported file is stored as a `resource` record. The link preview image URLs referenced by the
+ export are not fetched; they are listed as `outlink`s in a `metadata` record so a crawler can capture them.
+*/
+
+use std::{
+    collections::BTreeSet,
+    fs::{metadata, read_dir, File},
+    io::{copy, BufWriter, Error, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::app::error::RuntimeError;
+
+/// Name of the file the archive is written to in the export directory
+pub const WARC_FILENAME: &str = "export.warc";
+
+/// Characters that do not need to be percent-encoded in a `file:` URI path
+const URI_SAFE_CHARS: &[u8] = b"-._~/";
+
+/// Write a WARC file containing every file in the export directory
+///
+/// `preview_urls` are the remote link preview images referenced by the export. Returns the number of files archived.
+pub fn write_warc(
+    export_path: &Path,
+    preview_urls: &BTreeSet<String>,
+) -> Result<usize, RuntimeError> {
+    let mut files = vec![];
+    collect_files(export_path, &mut files)?;
+    files.sort();
+
+    let warc_path = export_path.join(WARC_FILENAME);
+    let warc = File::create(&warc_path)
+        .map_err(|err| RuntimeError::CreateError(err, warc_path.clone()))?;
+    let mut warc = BufWriter::new(warc);
+    let date = warc_date(SystemTime::now());
+
+    let info = format!(
+        "software: imessage-exporter/{}\r\nformat: WARC File Format 1.1\r\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    write_record(
+        &mut warc,
+        "warcinfo",
+        &date,
+        None,
+        "application/warc-fields",
+        info.len() as u64,
+        &mut info.as_bytes(),
+    )?;
+
+    let mut archived = 0;
+    for file in files {
+        if file == warc_path {
+            continue;
+        }
+
+        let relative_path = file
+            .strip_prefix(export_path)
+            .unwrap_or(&file)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        // Stream the file into the archive so large attachments are never held in memory
+        let open_error = |err| RuntimeError::CreateError(err, file.clone());
+        let length = metadata(&file).map_err(open_error)?.len();
+        let mut contents = File::open(&file).map_err(open_error)?;
+        write_record(
+            &mut warc,
+            "resource",
+            &date,
+            Some(&file_uri(&relative_path)),
+            content_type(&file),
+            length,
+            &mut contents,
+        )?;
+        archived += 1;
+    }
+
+    if !preview_urls.is_empty() {
+        let outlinks: String = preview_urls
+            .iter()
+            .map(|url| format!("outlink: {url}\r\n"))
+            .collect();
+        write_record(
+            &mut warc,
+            "metadata",
+            &date,
+            Some("file:///"),
+            "application/warc-fields",
+            outlinks.len() as u64,
+            &mut outlinks.as_bytes(),
+        )?;
+    }
+
+    warc.flush().map_err(RuntimeError::DiskError)?;
+    Ok(archived)
+}
+
+/// Write a single WARC record with a header block and a content block of `content_length` bytes read from `content`
+fn write_record(
+    warc: &mut impl Write,
+    record_type: &str,
+    date: &str,
+    target_uri: Option<&str>,
+    content_type: &str,
+    content_length: u64,
+    content: &mut impl Read,
+) -> Result<(), RuntimeError> {
+    let mut header = format!(
+        "WARC/1.1\r\nWARC-Type: {record_type}\r\nWARC-Record-ID: <urn:uuid:{}>\r\nWARC-Date: {date}\r\n",
+        Uuid::new_v4()
+    );
+    if let Some(uri) = target_uri {
+        header.push_str(&format!("WARC-Target-URI: {uri}\r\n"));
+    }
+    header.push_str(&format!(
+        "Content-Type: {content_type}\r\nContent-Length: {content_length}\r\n\r\n"
+    ));
+
+    warc.write_all(header.as_bytes())
+        .and_then(|_| copy(&mut content.take(content_length), warc))
+        .and_then(|written| {
+            // A file that shrank after its length was read would leave the record shorter than its header says
+            if written == content_length {
+                Ok(())
+            } else {
+                Err(Error::from(ErrorKind::UnexpectedEof))
+            }
+        })
+        .and_then(|_| warc.write_all(b"\r\n\r\n"))
+        .map_err(RuntimeError::DiskError)
+}
+
+/// Recursively collect the paths of all files in a directory
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) -> Result<(), RuntimeError> {
+    for entry in read_dir(directory).map_err(RuntimeError::DiskError)? {
+        let path = entry.map_err(RuntimeError::DiskError)?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Build a `file:` URI for a path relative to the export directory
+fn file_uri(relative_path: &str) -> String {
+    let mut uri = String::from("file:///");
+    for byte in relative_path.bytes() {
+        if byte.is_ascii_alphanumeric() || URI_SAFE_CHARS.contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
+
+/// Guess the MIME type of an exported file from its extension
+fn content_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase)
+        .as_deref()
+    {
+        Some("html") => "text/html; charset=utf-8",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("json") => "application/json",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("heic") => "image/heic",
+        Some("mp4") => "video/mp4",
+        Some("mov") => "video/quicktime",
+        Some("m4a") => "audio/mp4",
+        Some("caf") => "audio/x-caf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Format a time as a UTC timestamp, i.e. `2024-01-31T12:34:56Z`
+fn warc_date(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time)
+        .naive_utc()
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        env::temp_dir,
+        fs::{create_dir_all, read_to_string, remove_dir_all, write},
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use crate::app::warc::{file_uri, warc_date, write_warc, WARC_FILENAME};
+
+    #[test]
+    fn can_format_warc_date() {
+        assert_eq!(warc_date(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            warc_date(UNIX_EPOCH + Duration::from_secs(1652833782)),
+            "2022-05-18T00:29:42Z"
+        );
+        assert_eq!(
+            warc_date(UNIX_EPOCH + Duration::from_secs(951782400)),
+            "2000-02-29T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn can_build_file_uri() {
+        assert_eq!(
+            file_uri("attachments/1/IMG 01.jpg"),
+            "file:///attachments/1/IMG%2001.jpg"
+        );
+        assert_eq!(file_uri("Café.html"), "file:///Caf%C3%A9.html");
+    }
+
+    #[test]
+    fn can_write_warc() {
+        let directory = temp_dir().join("imessage-exporter-warc-write");
+        create_dir_all(directory.join("attachments")).unwrap();
+        write(directory.join("chat.html"), "<html></html>").unwrap();
+        write(directory.join("attachments").join("empty.jpg"), "").unwrap();
+
+        let preview_urls = BTreeSet::from([String::from("https://example.com/preview.jpg")]);
+        let archived = write_warc(&directory, &preview_urls).unwrap();
+        let warc = read_to_string(directory.join(WARC_FILENAME)).unwrap();
+
+        // Writing the archive again should not include the previous archive
+        let rearchived = write_warc(&directory, &preview_urls).unwrap();
+        remove_dir_all(&directory).unwrap();
+
+        assert_eq!(archived, 2);
+        assert_eq!(rearchived, 2);
+        assert!(warc.starts_with("WARC/1.1\r\nWARC-Type: warcinfo\r\n"));
+        assert!(warc.contains(
+            "WARC-Target-URI: file:///chat.html\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: 13\r\n\r\n<html></html>\r\n\r\n"
+        ));
+        assert!(warc.contains("WARC-Target-URI: file:///attachments/empty.jpg\r\n"));
+        assert!(warc.contains("outlink: https://example.com/preview.jpg\r\n"));
+    }
+
+    #[test]
+    fn can_write_warc_without_previews() {
+        let directory = temp_dir().join("imessage-exporter-warc-no-previews");
+        create_dir_all(&directory).unwrap();
+        write(directory.join("chat.html"), "<html></html>").unwrap();
+
+        write_warc(&directory, &BTreeSet::new()).unwrap();
+        let warc = read_to_string(directory.join(WARC_FILENAME)).unwrap();
+        remove_dir_all(&directory).unwrap();
+
+        assert!(!warc.contains("WARC-Type: metadata"));
+    }
+}
@@ -0,0 +1,597 @@
+use std::{
+    collections::{
+        hash_map::Entry::{Occupied, Vacant},
+        HashMap,
+    },
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use crate::{
+    app::{
+        attachment_manager::ATTACHMENT_PLACEHOLDER,
+        error::RuntimeError,
+        error_report::{ErrorReport, IssueKind},
+        export_type::DayOneGranularity,
+        progress::build_progress_bar_export,
+        runtime::{Config, ExportCounts, MessageFilter},
+        sanitizers::sanitize_json,
+    },
+    exporters::exporter::Exporter,
+};
+
+use imessage_database::{
+    error::table::TableError,
+    tables::{
+        attachment::MediaType,
+        messages::Message,
+        table::{Table, ORPHANED},
+    },
+    util::dates::format,
+};
+use uuid::Uuid;
+
+/// The version of the Day One import schema written by this exporter
+const DAY_ONE_SCHEMA_VERSION: &str = "1.0";
+
+/// A photo referenced by a [`DayOneEntry`]
+#[derive(Debug, PartialEq, Eq)]
+pub struct DayOnePhoto {
+    /// Unique identifier used to reference the photo from the entry's text
+    pub identifier: String,
+    /// The photo's file extension, i.e. `jpeg`
+    pub photo_type: String,
+    /// The path to the photo, relative to the export directory if the attachment was copied
+    pub path: String,
+}
+
+/// A single Day One journal entry
+#[derive(Debug, PartialEq, Eq)]
+pub struct DayOneEntry {
+    /// Unique identifier for the entry
+    pub uuid: String,
+    /// ISO 8601 UTC timestamp of the first message in the entry
+    pub creation_date: String,
+    /// Local calendar date of the first message in the entry, used to group messages by day
+    pub day: String,
+    /// Markdown body of the entry
+    pub text: String,
+    /// Photos referenced by the entry
+    pub photos: Vec<DayOnePhoto>,
+}
+
+impl DayOneEntry {
+    /// Serialize the entry as a JSON object in a Day One journal's `entries` array
+    fn to_json(&self) -> String {
+        let photos = self
+            .photos
+            .iter()
+            .map(|photo| {
+                format!(
+                    "        {{\"identifier\": \"{}\", \"type\": \"{}\", \"path\": \"{}\"}}",
+                    photo.identifier,
+                    sanitize_json(&photo.photo_type),
+                    sanitize_json(&photo.path)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",\n");
+
+        let photos = if photos.is_empty() {
+            String::from("[]")
+        } else {
+            format!("[\n{photos}\n      ]")
+        };
+
+        format!(
+            "    {{\n      \"uuid\": \"{}\",\n      \"creationDate\": \"{}\",\n      \"text\": \"{}\",\n      \"photos\": {photos}\n    }}",
+            self.uuid,
+            self.creation_date,
+            sanitize_json(&self.text)
+        )
+    }
+}
+
+pub struct DayOne<'a> {
+    /// Data that is setup from the application's runtime
+    pub config: &'a Config,
+    /// Handles to files we want to write journals to
+    /// Map of resolved chatroom file location to a buffered writer
+    pub files: HashMap<String, BufWriter<File>>,
+    /// Writer instance for orphaned messages
+    pub orphaned: BufWriter<File>,
+    /// Map of resolved chatroom file location to the entries in its journal, `None` for orphaned messages
+    pub entries: HashMap<Option<String>, Vec<DayOneEntry>>,
+    /// Non-fatal problems encountered during the export
+    pub errors: ErrorReport,
+}
+
+impl<'a> Exporter<'a> for DayOne<'a> {
+    fn new(config: &'a Config) -> Result<Self, RuntimeError> {
+        let mut orphaned = config.options.export_path.clone();
+        orphaned.push(ORPHANED);
+        orphaned.set_extension("json");
+
+        let file = File::options()
+            .append(true)
+            .create(true)
+            .open(&orphaned)
+            .map_err(|err| RuntimeError::CreateError(err, orphaned))?;
+
+        Ok(DayOne {
+            config,
+            files: HashMap::new(),
+            orphaned: BufWriter::new(file),
+            entries: HashMap::new(),
+            errors: ErrorReport::default(),
+        })
+    }
+
+    fn iter_messages(&mut self) -> Result<(), RuntimeError> {
+        // Tell the user what we are doing
+        eprintln!(
+            "Exporting to {} as Day One journals...",
+            self.config.options.export_path.display()
+        );
+
+        // Keep track of current message ROWID
+        let mut current_message_row = -1;
+
+        // Keep track of how many messages we exported, globally and per conversation
+        let mut counts = ExportCounts::default();
+
+        // Only export conversations that include the selected participant, if any
+        let chatrooms = self.config.participant_chatrooms();
+
+        // Set up progress bar
+        let mut current_message = 0;
+        let mut total_messages =
+            Message::get_count(&self.config.db, &self.config.options.query_context)
+                .map_err(RuntimeError::DatabaseError)?;
+        if let Some(limit) = self.config.options.limit {
+            total_messages = total_messages.min(limit);
+        }
+        let pb = build_progress_bar_export(total_messages);
+
+        let mut statement = Message::stream_rows_with_schema(
+            &self.config.db,
+            &self.config.schema,
+            &self.config.options.query_context,
+        )
+        .map_err(RuntimeError::DatabaseError)?;
+
+        let messages = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .map_err(|err| RuntimeError::DatabaseError(TableError::Messages(err)))?;
+
+        for message in messages {
+            let mut msg = Message::extract(message).map_err(RuntimeError::DatabaseError)?;
+
+            // Early escape if we try and render the same message GUID twice
+            // See https://github.com/ReagentX/imessage-exporter/issues/135 for rationale
+            if msg.rowid == current_message_row {
+                current_message += 1;
+                continue;
+            }
+            current_message_row = msg.rowid;
+
+            // Reactions are not exported, so they do not count towards the limits
+            match self
+                .config
+                .filter_message(&msg, &chatrooms, &mut counts, !msg.is_reaction())
+            {
+                MessageFilter::Export => {}
+                MessageFilter::Skip => {
+                    current_message += 1;
+                    continue;
+                }
+                MessageFilter::Stop => break,
+            }
+
+            // Generate the text of the message
+            let _ = msg.generate_text(&self.config.db);
+
+            // Journals only contain messages that were sent, not reactions or chat events
+            if !msg.is_reaction() && !msg.is_announcement() {
+                self.add_message(&msg)?;
+            }
+            current_message += 1;
+            if current_message % 99 == 0 {
+                pb.set_position(current_message);
+            }
+        }
+        pb.finish();
+
+        self.write_journals()?;
+        self.errors.write(&self.config.options.export_path)?;
+
+        Ok(())
+    }
+
+    /// Create a file for the given chat, caching it so we don't need to build it later
+    fn get_or_create_file(
+        &mut self,
+        message: &Message,
+    ) -> Result<&mut BufWriter<File>, RuntimeError> {
+        match self.config.conversation(message) {
+            Some((chatroom, _)) => {
+                let filename = self.config.filename(chatroom);
+                match self.files.entry(filename) {
+                    Occupied(entry) => Ok(entry.into_mut()),
+                    Vacant(entry) => {
+                        let mut path = self.config.options.export_path.clone();
+                        path.push(self.config.filename(chatroom));
+                        path.set_extension("json");
+
+                        let file = File::options()
+                            .append(true)
+                            .create(true)
+                            .open(&path)
+                            .map_err(|err| RuntimeError::CreateError(err, path))?;
+
+                        Ok(entry.insert(BufWriter::new(file)))
+                    }
+                }
+            }
+            None => Ok(&mut self.orphaned),
+        }
+    }
+}
+
+impl<'a> DayOne<'a> {
+    /// Add a message to its conversation's journal, either as a new entry or appended to the current day's entry
+    fn add_message(&mut self, message: &Message) -> Result<(), RuntimeError> {
+        // Messages without a valid date cannot be placed in a journal
+        let Ok(date) = message.date(&self.config.offset) else {
+            return Ok(());
+        };
+
+        // Ensure the journal file exists before any data is written to it
+        self.get_or_create_file(message)?;
+        let journal = self
+            .config
+            .conversation(message)
+            .map(|(chatroom, _)| self.config.filename(chatroom));
+
+        let (text, photos) = self.format_message(message)?;
+        let day = date.format("%Y-%m-%d").to_string();
+
+        let entries = self.entries.entry(journal).or_default();
+        match entries.last_mut() {
+            Some(entry)
+                if self.config.options.dayone_granularity == DayOneGranularity::Day
+                    && entry.day == day =>
+            {
+                entry.text.push_str("\n\n");
+                entry.text.push_str(&text);
+                entry.photos.extend(photos);
+            }
+            _ => entries.push(DayOneEntry {
+                uuid: DayOne::identifier(),
+                creation_date: date.naive_utc().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                day,
+                text,
+                photos,
+            }),
+        }
+        Ok(())
+    }
+
+    /// Build the Markdown text of a message and collect the photos it references
+    fn format_message(
+        &self,
+        message: &Message,
+    ) -> Result<(String, Vec<DayOnePhoto>), RuntimeError> {
+        let mut photos = vec![];
+        let mut text = format!(
+            "**{}** {}",
+            self.config.who(
+                message.handle_id,
+                message.is_from_me(),
+                &message.destination_caller_id
+            ),
+            format(&message.date(&self.config.offset))
+        );
+        if message.is_forwarded() {
+            text.push_str(" _(forwarded)_");
+        }
+
+        // Attachment placeholders are replaced by references to the attachments themselves
+        if let Some(body) = &message.text {
+            let body = self.config.mask(&body.replace('\u{FFFC}', "")).into_owned();
+            if !body.trim().is_empty() {
+                text.push('\n');
+                text.push_str(body.trim());
+            }
+        }
+
+        if self.config.options.no_attachments {
+            for _ in 0..message.num_attachments {
+                text.push('\n');
+                text.push_str(ATTACHMENT_PLACEHOLDER);
+            }
+        }
+
+        let mut attachments = self
+            .config
+            .attachments(message)
+            .map_err(RuntimeError::DatabaseError)?;
+        for attachment in attachments.iter_mut() {
+            // Copy the file, if requested
+            if self
+                .config
+                .options
+                .attachment_manager
+                .handle_attachment(message, attachment, self.config)
+                .is_none()
+            {
+                self.errors.record(
+                    IssueKind::UnresolvedAttachment,
+                    &message.guid,
+                    attachment.filename(),
+                );
+                continue;
+            }
+
+            let path = self.config.message_attachment_path(attachment);
+            match self.config.media_type(attachment) {
+                MediaType::Image(_) | MediaType::AnimatedImage(_) => {
+                    let identifier = DayOne::identifier();
+                    text.push_str(&format!("\n![](dayone-moment://{identifier})"));
+                    photos.push(DayOnePhoto {
+                        identifier,
+                        photo_type: attachment.extension().unwrap_or_default().to_lowercase(),
+                        path,
+                    });
+                }
+                _ => text.push_str(&format!("\nAttachment: {path}")),
+            }
+        }
+
+        Ok((text, photos))
+    }
+
+    /// Write each journal to its file
+    fn write_journals(&mut self) -> Result<(), RuntimeError> {
+        // Orphaned messages always get a journal, since the file is created up front
+        let orphaned = self.entries.remove(&None).unwrap_or_default();
+        DayOne::write_to_file(&mut self.orphaned, &DayOne::format_journal(&orphaned))?;
+
+        for (journal, entries) in self.entries.drain() {
+            if let Some(file) = journal.and_then(|journal| self.files.get_mut(&journal)) {
+                DayOne::write_to_file(file, &DayOne::format_journal(&entries))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize a set of entries as a Day One journal
+    fn format_journal(entries: &[DayOneEntry]) -> String {
+        let entries = entries
+            .iter()
+            .map(DayOneEntry::to_json)
+            .collect::<Vec<String>>()
+            .join(",\n");
+
+        let entries = if entries.is_empty() {
+            String::from("[]")
+        } else {
+            format!("[\n{entries}\n  ]")
+        };
+
+        format!(
+            "{{\n  \"metadata\": {{\n    \"version\": \"{DAY_ONE_SCHEMA_VERSION}\"\n  }},\n  \"entries\": {entries}\n}}\n"
+        )
+    }
+
+    /// Generate an identifier in the format Day One uses for entries and photos
+    fn identifier() -> String {
+        Uuid::new_v4().simple().to_string().to_uppercase()
+    }
+
+    fn write_to_file(file: &mut BufWriter<File>, text: &str) -> Result<(), RuntimeError> {
+        file.write_all(text.as_bytes())
+            .map_err(RuntimeError::DiskError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap, env::set_var};
+
+    use crate::{
+        app::{export_type::DayOneGranularity, options::fake_options},
+        exporters::dayone::{DayOne, DayOneEntry, DayOnePhoto},
+        Config, Exporter, Options,
+    };
+    use imessage_database::{
+        tables::{messages::Message, schema::Schema, table::get_connection},
+        util::dates::get_offset,
+    };
+
+    fn blank() -> Message {
+        Message {
+            rowid: i32::default(),
+            guid: String::default(),
+            text: None,
+            service: Some("iMessage".to_string()),
+            handle_id: Some(i32::default()),
+            destination_caller_id: None,
+            subject: None,
+            date: i64::default(),
+            date_read: i64::default(),
+            date_delivered: i64::default(),
+            date_played: 0,
+            is_from_me: false,
+            is_read: false,
+            is_forward: false,
+            is_spam: false,
+            was_delivered_quietly: false,
+            item_type: 0,
+            other_handle: 0,
+            share_status: false,
+            share_direction: false,
+            group_title: None,
+            group_action_type: 0,
+            associated_message_guid: None,
+            associated_message_type: Some(i32::default()),
+            balloon_bundle_id: None,
+            expressive_send_style_id: None,
+            thread_originator_guid: None,
+            thread_originator_part: None,
+            date_edited: 0,
+            schedule_type: 0,
+            chat_id: None,
+            num_attachments: 0,
+            deleted_from: None,
+            num_replies: 0,
+            components: None,
+            edited_parts: None,
+        }
+    }
+
+    fn fake_config(options: Options) -> Config {
+        let db = get_connection(&options.get_db_path()).unwrap();
+        let schema = Schema::detect(&db).unwrap();
+        Config {
+            chatrooms: HashMap::new(),
+            real_chatrooms: HashMap::new(),
+            merged_chatrooms: HashMap::new(),
+            chatroom_participants: HashMap::new(),
+            participants: HashMap::new(),
+            real_participants: HashMap::new(),
+            reactions: HashMap::new(),
+            options,
+            offset: get_offset(),
+            db,
+            schema,
+            converter: None,
+            attachment_paths: RefCell::new(HashMap::new()),
+            annotations: HashMap::new(),
+            db_copy: None,
+        }
+    }
+
+    fn fake_entry() -> DayOneEntry {
+        DayOneEntry {
+            uuid: "ABC".to_string(),
+            creation_date: "2022-05-18T00:29:42Z".to_string(),
+            day: "2022-05-17".to_string(),
+            text: "**Me** \"Hello\"".to_string(),
+            photos: vec![],
+        }
+    }
+
+    #[test]
+    fn can_create() {
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = DayOne::new(&config).unwrap();
+        assert_eq!(exporter.files.len(), 0);
+        assert!(exporter.entries.is_empty());
+    }
+
+    #[test]
+    fn can_format_entry() {
+        let entry = fake_entry();
+        let expected = "    {\n      \"uuid\": \"ABC\",\n      \"creationDate\": \"2022-05-18T00:29:42Z\",\n      \"text\": \"**Me** \\\"Hello\\\"\",\n      \"photos\": []\n    }";
+        assert_eq!(entry.to_json(), expected);
+    }
+
+    #[test]
+    fn can_format_entry_photos() {
+        let mut entry = fake_entry();
+        entry.photos.push(DayOnePhoto {
+            identifier: "DEF".to_string(),
+            photo_type: "jpeg".to_string(),
+            path: "attachments/0/d.jpeg".to_string(),
+        });
+        let expected = "    {\n      \"uuid\": \"ABC\",\n      \"creationDate\": \"2022-05-18T00:29:42Z\",\n      \"text\": \"**Me** \\\"Hello\\\"\",\n      \"photos\": [\n        {\"identifier\": \"DEF\", \"type\": \"jpeg\", \"path\": \"attachments/0/d.jpeg\"}\n      ]\n    }";
+        assert_eq!(entry.to_json(), expected);
+    }
+
+    #[test]
+    fn can_format_journal_empty() {
+        let expected =
+            "{\n  \"metadata\": {\n    \"version\": \"1.0\"\n  },\n  \"entries\": []\n}\n";
+        assert_eq!(DayOne::format_journal(&[]), expected);
+    }
+
+    #[test]
+    fn can_format_message() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = DayOne::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+
+        let (text, photos) = exporter.format_message(&message).unwrap();
+        assert_eq!(text, "**Unknown** May 17, 2022  5:29:42 PM\nHello world");
+        assert!(photos.is_empty());
+    }
+
+    #[test]
+    fn can_group_entries_by_day() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let options = fake_options();
+        let config = fake_config(options);
+        let mut exporter = DayOne::new(&config).unwrap();
+
+        let mut first = blank();
+        // May 17, 2022  8:29:42 PM
+        first.date = 674526582885055488;
+        first.text = Some("First".to_string());
+
+        let mut second = blank();
+        // May 17, 2022  9:30:31 PM
+        second.date = 674530231992568192;
+        second.text = Some("Second".to_string());
+
+        exporter.add_message(&first).unwrap();
+        exporter.add_message(&second).unwrap();
+
+        let entries = exporter.entries.get(&None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].creation_date, "2022-05-18T00:29:42Z");
+        assert_eq!(
+            entries[0].text,
+            "**Unknown** May 17, 2022  5:29:42 PM\nFirst\n\n**Unknown** May 17, 2022  6:30:31 PM\nSecond"
+        );
+    }
+
+    #[test]
+    fn can_group_entries_by_message() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let mut options = fake_options();
+        options.dayone_granularity = DayOneGranularity::Message;
+        let config = fake_config(options);
+        let mut exporter = DayOne::new(&config).unwrap();
+
+        let mut first = blank();
+        // May 17, 2022  8:29:42 PM
+        first.date = 674526582885055488;
+        first.text = Some("First".to_string());
+
+        let mut second = blank();
+        // May 17, 2022  9:30:31 PM
+        second.date = 674530231992568192;
+        second.text = Some("Second".to_string());
+
+        exporter.add_message(&first).unwrap();
+        exporter.add_message(&second).unwrap();
+
+        let entries = exporter.entries.get(&None).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_ne!(entries[0].uuid, entries[1].uuid);
+    }
+}
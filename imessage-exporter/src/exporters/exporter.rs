@@ -7,6 +7,7 @@ use imessage_database::{
         app_store::AppStoreMessage,
         collaboration::CollaborationMessage,
         edited::EditedMessage,
+        game::GameMessage,
         handwriting::HandwrittenMessage,
         music::MusicMessage,
         placemark::PlacemarkMessage,
@@ -52,8 +53,8 @@ pub(super) trait Writer<'a> {
         attachments: &mut Vec<Attachment>,
         indent: &str,
     ) -> Result<String, PlistParseError>;
-    /// Format a reaction (displayed under a message)
-    fn format_reaction(&self, msg: &Message) -> Result<String, TableError>;
+    /// Format a reaction (displayed under a message), naming the `target` part it was placed on, if provided
+    fn format_reaction(&self, msg: &Message, target: Option<&str>) -> Result<String, TableError>;
     /// Format an expressive message
     fn format_expressive(&self, msg: &'a Message) -> &'a str;
     /// Format an announcement message
@@ -89,6 +90,8 @@ pub(super) trait BalloonFormatter<T> {
     fn format_placemark(&self, balloon: &PlacemarkMessage, indent: T) -> String;
     /// Format a handwritten note message
     fn format_handwriting(&self, balloon: &HandwrittenMessage, indent: T) -> String;
+    /// Format a Digital Touch message
+    fn format_digital_touch(&self, indent: T) -> String;
     /// Format an Apple Pay message
     fn format_apple_pay(&self, balloon: &AppMessage, indent: T) -> String;
     /// Format a Fitness message
@@ -99,6 +102,10 @@ pub(super) trait BalloonFormatter<T> {
     fn format_find_my(&self, balloon: &AppMessage, indent: T) -> String;
     /// Format a Check In message
     fn format_check_in(&self, balloon: &AppMessage, indent: T) -> String;
+    /// Format a Journal suggestion message
+    fn format_journal(&self, balloon: &AppMessage, indent: T) -> String;
+    /// Format a turn in a game played in a third party app
+    fn format_game(&self, balloon: &GameMessage, indent: T) -> String;
     /// Format a generic app, generally third party
     fn format_generic_app(
         &self,
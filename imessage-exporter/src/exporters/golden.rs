@@ -0,0 +1,252 @@
+/*!
+ Golden file tests that export a small fixture database end to end and compare every file written against checked-in expected output.
+
+ The fixture database is built from `test_data/golden/fixture.sql`, and the expected output for each export type lives in
+ `test_data/golden/<export type>`. After an intentional change to the output, regenerate the expected files with:
+
+ ```sh
+ UPDATE_GOLDEN=1 cargo test -p imessage-exporter golden
+ ```
+*/
+
+use std::{
+    collections::BTreeMap,
+    env::{set_var, temp_dir, var_os},
+    fs::{create_dir_all, read_dir, read_to_string, remove_dir_all, write},
+    path::{Path, PathBuf},
+};
+
+use rusqlite::Connection;
+
+use crate::app::{
+    last_export::LAST_EXPORT_FILENAME,
+    options::{get_command, Options},
+    runtime::Config,
+};
+
+/// Set this environment variable to overwrite the expected files with the current output instead of comparing them
+const UPDATE_GOLDEN: &str = "UPDATE_GOLDEN";
+
+/// The directory holding the fixture database script and the expected output
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("test_data/golden")
+}
+
+/// Build the fixture database in a fresh directory, returning the path to the database file
+fn build_fixture(dir: &Path) -> PathBuf {
+    let _ = remove_dir_all(dir);
+    create_dir_all(dir).unwrap();
+
+    let db_path = dir.join("chat.db");
+    let script = read_to_string(golden_dir().join("fixture.sql")).unwrap();
+    Connection::open(&db_path)
+        .unwrap()
+        .execute_batch(&script)
+        .unwrap();
+    db_path
+}
+
+/// Read every file in a directory tree, keyed by its path relative to `root`
+fn read_tree(root: &Path) -> BTreeMap<String, String> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in read_dir(&dir).unwrap().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                let relative = path.strip_prefix(root).unwrap();
+                files.insert(
+                    relative.to_string_lossy().replace('\\', "/"),
+                    read_to_string(&path).unwrap(),
+                );
+            }
+        }
+    }
+    files
+}
+
+/// Describe the first line where `actual` differs from `expected`
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            (None, None) => return String::from("line endings differ"),
+            (e, a) => {
+                return format!(
+                    "line {line}\n  expected: {}\n  actual:   {}",
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>")
+                )
+            }
+        }
+    }
+}
+
+/// Run a full export of the database at `db_path` as `export_type`, passing any `extra_args` to the command line
+fn export(export_type: &str, db_path: &Path, export_path: &Path, extra_args: &[&str]) {
+    let mut args = vec![
+        String::from("imessage-exporter"),
+        String::from("--format"),
+        export_type.to_string(),
+        String::from("--db-path"),
+        db_path.to_string_lossy().to_string(),
+        String::from("--export-path"),
+        export_path.to_string_lossy().to_string(),
+        String::from("--ignore-disk-warning"),
+    ];
+    args.extend(extra_args.iter().map(ToString::to_string));
+
+    let args = get_command().get_matches_from(args);
+    let options = Options::from_args(&args).unwrap();
+    Config::new(options).unwrap().start().unwrap();
+}
+
+/// Export the fixture database as `export_type` and compare the output to the expected files
+fn assert_golden(export_type: &str) {
+    // Set timezone to PST for consistent Local time
+    set_var("TZ", "PST");
+
+    let work_dir = temp_dir().join(format!("imessage-exporter-golden-{export_type}"));
+    let db_path = build_fixture(&work_dir);
+    let export_path = work_dir.join("export");
+    export(export_type, &db_path, &export_path, &[]);
+
+    let actual = read_tree(&export_path);
+    let expected_dir = golden_dir().join(export_type);
+
+    if var_os(UPDATE_GOLDEN).is_some() {
+        let _ = remove_dir_all(&expected_dir);
+        for (file, contents) in &actual {
+            let path = expected_dir.join(file);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            write(path, contents).unwrap();
+        }
+    } else {
+        let expected = read_tree(&expected_dir);
+        assert_eq!(
+            expected.keys().collect::<Vec<_>>(),
+            actual.keys().collect::<Vec<_>>(),
+            "`{export_type}` export wrote different files than expected; run with `{UPDATE_GOLDEN}=1` to regenerate"
+        );
+        for (file, expected_contents) in &expected {
+            let actual_contents = &actual[file];
+            assert!(
+                expected_contents == actual_contents,
+                "`{export_type}` export of {file} differs from the expected output at {}\nRun with `{UPDATE_GOLDEN}=1` to regenerate",
+                first_difference(expected_contents, actual_contents)
+            );
+        }
+    }
+
+    remove_dir_all(&work_dir).unwrap();
+}
+
+#[test]
+fn golden_txt() {
+    assert_golden("txt");
+}
+
+#[test]
+fn golden_html() {
+    assert_golden("html");
+}
+
+#[test]
+fn golden_ndjson() {
+    assert_golden("ndjson");
+}
+
+#[test]
+fn golden_html_since_last_export() {
+    // Set timezone to PST for consistent Local time
+    set_var("TZ", "PST");
+
+    let work_dir = temp_dir().join("imessage-exporter-golden-html-resumed");
+    let db_path = build_fixture(&work_dir);
+    let export_path = work_dir.join("export");
+
+    // Hold back the newer messages so the first export only writes the direct conversation
+    let db = Connection::open(&db_path).unwrap();
+    db.execute_batch(
+        "CREATE TABLE later AS SELECT * FROM message WHERE ROWID >= 5;
+         DELETE FROM message WHERE ROWID >= 5;",
+    )
+    .unwrap();
+    export("html", &db_path, &export_path, &["--since-last-export"]);
+
+    // The second export appends the newer messages to the files the first one wrote
+    db.execute_batch("INSERT INTO message SELECT * FROM later; DROP TABLE later;")
+        .unwrap();
+    export("html", &db_path, &export_path, &["--since-last-export"]);
+
+    // Appending the new messages should produce the same files as exporting everything at once
+    let mut actual = read_tree(&export_path);
+    actual.remove(LAST_EXPORT_FILENAME);
+    let expected = read_tree(&golden_dir().join("html"));
+    assert_eq!(
+        expected.keys().collect::<Vec<_>>(),
+        actual.keys().collect::<Vec<_>>()
+    );
+    for (file, expected_contents) in &expected {
+        let actual_contents = &actual[file];
+        assert!(
+            expected_contents == actual_contents,
+            "resumed `html` export of {file} differs from a full export at {}",
+            first_difference(expected_contents, actual_contents)
+        );
+    }
+
+    remove_dir_all(&work_dir).unwrap();
+}
+
+#[test]
+fn message_guid_leaves_export_path_untouched() {
+    let work_dir = temp_dir().join("imessage-exporter-golden-message-guid");
+    let db_path = build_fixture(&work_dir);
+    let export_path = work_dir.join("export");
+
+    // An earlier export in the same directory should neither be rejected nor modified
+    create_dir_all(&export_path).unwrap();
+    write(export_path.join("earlier.html"), "earlier export").unwrap();
+    write(export_path.join("earlier.txt"), "earlier export").unwrap();
+    let before = read_tree(&export_path);
+
+    for export_type in ["html", "txt"] {
+        export(
+            export_type,
+            &db_path,
+            &export_path,
+            &["--message-guid", "A0000000-0000-0000-0000-000000000002"],
+        );
+    }
+    assert_eq!(before, read_tree(&export_path));
+
+    // A missing export directory is not created
+    remove_dir_all(&export_path).unwrap();
+    export(
+        "txt",
+        &db_path,
+        &export_path,
+        &["--message-guid", "A0000000-0000-0000-0000-000000000002"],
+    );
+    assert!(!export_path.exists());
+
+    remove_dir_all(&work_dir).unwrap();
+}
+
+#[test]
+fn can_find_first_difference() {
+    assert_eq!(
+        first_difference("a\nb\nc", "a\nx\nc"),
+        "line 2\n  expected: b\n  actual:   x"
+    );
+    assert_eq!(
+        first_difference("a\nb", "a"),
+        "line 2\n  expected: b\n  actual:   <end of file>"
+    );
+}
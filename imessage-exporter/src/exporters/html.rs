@@ -1,17 +1,24 @@
 use std::{
     borrow::Cow,
+    cell::RefCell,
     collections::{
         hash_map::Entry::{Occupied, Vacant},
-        HashMap,
+        BTreeSet, HashMap, HashSet,
     },
-    fs::File,
-    io::{BufWriter, Write},
+    fs::{create_dir_all, read_to_string, File},
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
 };
 
 use crate::{
     app::{
-        error::RuntimeError, progress::build_progress_bar_export, runtime::Config,
+        attachment_manager::ATTACHMENT_PLACEHOLDER,
+        error::RuntimeError,
+        error_report::{ErrorReport, IssueKind},
+        progress::build_progress_bar_export,
+        runtime::{Config, ExportCounts, MessageFilter},
         sanitizers::sanitize_html,
+        warc::{write_warc, WARC_FILENAME},
     },
     exporters::exporter::{BalloonFormatter, Exporter, TextEffectFormatter, Writer},
 };
@@ -22,19 +29,24 @@ use imessage_database::{
         app::AppMessage,
         app_store::AppStoreMessage,
         collaboration::CollaborationMessage,
-        edited::{EditStatus, EditedMessage},
+        edited::{EditStatus, EditedMessage, UnsentTiming},
         expressives::{BubbleEffect, Expressive, ScreenEffect},
+        game::GameMessage,
         handwriting::HandwrittenMessage,
         music::MusicMessage,
         placemark::PlacemarkMessage,
         text_effects::{Animation, Style, TextEffect, Unit},
         url::URLMessage,
-        variants::{Announcement, BalloonProvider, CustomBalloon, URLOverride, Variant},
+        variants::{Announcement, BalloonProvider, CustomBalloon, Reaction, URLOverride, Variant},
     },
     tables::{
         attachment::{Attachment, MediaType},
-        messages::{models::BubbleComponent, Message},
-        table::{Table, FITNESS_RECEIVER, ME, ORPHANED, YOU},
+        chat::Chat,
+        messages::{
+            models::{BubbleComponent, ReadReceiptState, WritingDirection},
+            Message,
+        },
+        table::{Table, ME, ORPHANED, YOU},
     },
     util::{
         dates::{format, get_local_time, readable_diff, TIMESTAMP_FACTOR},
@@ -42,9 +54,12 @@ use imessage_database::{
     },
 };
 
-const HEADER: &str = "<html>\n<head>\n<meta charset=\"UTF-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">";
+const HEADER: &str = "<head>\n<meta charset=\"UTF-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">";
 const FOOTER: &str = "</body></html>";
 const STYLE: &str = include_str!("resources/style.css");
+const PRINT_STYLE: &str = include_str!("resources/print.css");
+/// Consecutive messages from the same sender sent within this many seconds are grouped together
+const GROUPING_WINDOW: i64 = 300;
 
 pub struct HTML<'a> {
     /// Data that is setup from the application's runtime
@@ -52,25 +67,45 @@ pub struct HTML<'a> {
     /// Handles to files we want to write messages to
     /// Map of resolved chatroom file location to a buffered writer
     pub files: HashMap<String, BufWriter<File>>,
-    /// Writer instance for orphaned messages
-    pub orphaned: BufWriter<File>,
+    /// Writer instance for orphaned messages, opened the first time it is needed
+    pub orphaned: Option<BufWriter<File>>,
+    /// Non-fatal problems encountered during the export
+    pub errors: ErrorReport,
+    /// Map of deduplicated chat ID to the sender and date of the last message rendered in that chat
+    pub last_sender: HashMap<Option<i32>, (String, i64)>,
+    /// Map of file name, or `None` for orphaned messages, to the deduplicated chat ID of the media grid open in that file
+    pub media_grids: HashMap<Option<String>, Option<i32>>,
+    /// Map of conversation file name to the file its messages are currently written to, if conversations are split with `--split-by`
+    pub split_files: HashMap<String, String>,
+    /// Remote link preview image URLs referenced by the export, collected if `--warc` is enabled
+    pub preview_urls: RefCell<BTreeSet<String>>,
+    /// The contents of the stylesheet passed with `--css`, written after the built-in styles
+    pub custom_css: Option<String>,
+    /// Map of file name, or `None` for orphaned messages, to the day of the last message written to that file, if `--print-friendly` is enabled
+    pub last_days: HashMap<Option<String>, String>,
+    /// Map of file name, or `None` for orphaned messages, to the number of top-level messages written to that file, if `--number-messages` is enabled
+    pub message_numbers: HashMap<Option<String>, u64>,
 }
 
 impl<'a> Exporter<'a> for HTML<'a> {
     fn new(config: &'a Config) -> Result<Self, RuntimeError> {
-        let mut orphaned = config.options.export_path.clone();
-        orphaned.push(ORPHANED);
-        orphaned.set_extension("html");
-        let file = File::options()
-            .append(true)
-            .create(true)
-            .open(&orphaned)
-            .map_err(|err| RuntimeError::CreateError(err, orphaned))?;
+        let custom_css = match &config.options.css {
+            Some(path) => Some(read_to_string(path).map_err(RuntimeError::DiskError)?),
+            None => None,
+        };
 
         Ok(HTML {
             config,
             files: HashMap::new(),
-            orphaned: BufWriter::new(file),
+            orphaned: None,
+            errors: ErrorReport::default(),
+            last_sender: HashMap::new(),
+            media_grids: HashMap::new(),
+            split_files: HashMap::new(),
+            preview_urls: RefCell::new(BTreeSet::new()),
+            custom_css,
+            last_days: HashMap::new(),
+            message_numbers: HashMap::new(),
         })
     }
 
@@ -81,22 +116,34 @@ impl<'a> Exporter<'a> for HTML<'a> {
             self.config.options.export_path.display()
         );
 
-        // Write orphaned file headers
-        HTML::write_headers(&mut self.orphaned)?;
+        // Create the orphaned file even if no orphaned messages are exported
+        self.orphaned_file()?;
 
         // Keep track of current message ROWID
         let mut current_message_row = -1;
 
+        // Keep track of how many messages we exported, globally and per conversation
+        let mut counts = ExportCounts::default();
+
+        // Only export conversations that include the selected participant, if any
+        let chatrooms = self.config.participant_chatrooms();
+
         // Set up progress bar
         let mut current_message = 0;
-        let total_messages =
+        let mut total_messages =
             Message::get_count(&self.config.db, &self.config.options.query_context)
                 .map_err(RuntimeError::DatabaseError)?;
+        if let Some(limit) = self.config.options.limit {
+            total_messages = total_messages.min(limit);
+        }
         let pb = build_progress_bar_export(total_messages);
 
-        let mut statement =
-            Message::stream_rows(&self.config.db, &self.config.options.query_context)
-                .map_err(RuntimeError::DatabaseError)?;
+        let mut statement = Message::stream_rows_with_schema(
+            &self.config.db,
+            &self.config.schema,
+            &self.config.options.query_context,
+        )
+        .map_err(RuntimeError::DatabaseError)?;
 
         let messages = statement
             .query_map([], |row| Ok(Message::from_row(row)))
@@ -113,20 +160,48 @@ impl<'a> Exporter<'a> for HTML<'a> {
             }
             current_message_row = msg.rowid;
 
+            // Reactions are rendered in context, so they do not count towards the limits
+            match self
+                .config
+                .filter_message(&msg, &chatrooms, &mut counts, !msg.is_reaction())
+            {
+                MessageFilter::Export => {}
+                MessageFilter::Skip => {
+                    current_message += 1;
+                    continue;
+                }
+                MessageFilter::Stop => break,
+            }
+
             // Generate the text of the message
             let _ = msg.generate_text(&self.config.db);
 
             // Render the announcement in-line
             if msg.is_announcement() {
                 let announcement = self.format_announcement(&msg);
+                self.write_day_break(&msg)?;
+                self.write_media_grid_boundary(&msg)?;
                 HTML::write_to_file(self.get_or_create_file(&msg)?, &announcement)?;
+                // Announcements break up groups of consecutive messages
+                self.last_sender.remove(&self.config.conversation_id(&msg));
             }
             // Message replies and reactions are rendered in context, so no need to render them separately
             else if !msg.is_reaction() {
+                self.number_message(&msg);
                 let message = self
                     .format_message(&msg, 0)
                     .map_err(RuntimeError::DatabaseError)?;
+                self.write_day_break(&msg)?;
+                self.write_media_grid_boundary(&msg)?;
                 HTML::write_to_file(self.get_or_create_file(&msg)?, &message)?;
+
+                // Track the sender so the next message in this chat can be grouped with this one
+                let sender = self
+                    .config
+                    .who(msg.handle_id, msg.is_from_me(), &msg.destination_caller_id)
+                    .to_string();
+                self.last_sender
+                    .insert(self.config.conversation_id(&msg), (sender, msg.date));
             }
             current_message += 1;
             if current_message % 99 == 0 {
@@ -135,11 +210,47 @@ impl<'a> Exporter<'a> for HTML<'a> {
         }
         pb.finish();
 
+        if self.config.options.include_empty_chats {
+            self.write_empty_chats(&counts.per_chat, &chatrooms)?;
+        }
+
+        // Close any media grids that were still open when the export finished
+        let open_grids: Vec<Option<String>> =
+            self.media_grids.drain().map(|(file, _)| file).collect();
+        for file in open_grids {
+            let buf = match &file {
+                Some(filename) => self.files.get_mut(filename),
+                None => self.orphaned.as_mut(),
+            };
+            if let Some(buf) = buf {
+                HTML::write_to_file(buf, "</div>\n")?;
+            }
+        }
+
         eprintln!("Writing HTML footers...");
         for (_, buf) in self.files.iter_mut() {
             HTML::write_to_file(buf, FOOTER)?;
         }
-        HTML::write_to_file(&mut self.orphaned, FOOTER)?;
+        HTML::write_to_file(self.orphaned_file()?, FOOTER)?;
+
+        self.errors.write(&self.config.options.export_path)?;
+
+        if self.config.options.warc {
+            // Every file must be complete on disk before it is archived
+            for (_, buf) in self.files.iter_mut() {
+                buf.flush().map_err(RuntimeError::DiskError)?;
+            }
+            self.orphaned_file()?
+                .flush()
+                .map_err(RuntimeError::DiskError)?;
+
+            eprintln!("Writing {WARC_FILENAME}...");
+            let archived = write_warc(
+                &self.config.options.export_path,
+                &self.preview_urls.borrow(),
+            )?;
+            eprintln!("Archived {archived} files in {WARC_FILENAME}");
+        }
 
         Ok(())
     }
@@ -151,37 +262,27 @@ impl<'a> Exporter<'a> for HTML<'a> {
     ) -> Result<&mut BufWriter<File>, RuntimeError> {
         match self.config.conversation(message) {
             Some((chatroom, _)) => {
-                let filename = self.config.filename(chatroom);
+                let filename = self.config.message_filename(chatroom, message);
+                let header = self.conversation_header(chatroom);
+
+                if self.config.options.split_by.is_some() {
+                    self.close_previous_split(self.config.filename(chatroom), &filename)?;
+                }
 
                 return match self.files.entry(filename) {
                     Occupied(entry) => Ok(entry.into_mut()),
                     Vacant(entry) => {
-                        let mut path = self.config.options.export_path.clone();
-                        path.push(self.config.filename(chatroom));
-                        path.set_extension("html");
-
-                        // If the file already exists, don't write the headers again
-                        // This can happen if multiple chats use the same group name
-                        let file_exists = path.exists();
-
-                        let file = File::options()
-                            .append(true)
-                            .create(true)
-                            .open(&path)
-                            .map_err(|err| RuntimeError::CreateError(err, path))?;
-
-                        let mut buf = BufWriter::new(file);
-
-                        // Write headers if the file does not exist
-                        if !file_exists {
-                            let _ = HTML::write_headers(&mut buf);
-                        }
-
+                        let buf = HTML::create_file(
+                            self.config,
+                            self.custom_css.as_deref(),
+                            entry.key(),
+                            &header,
+                        )?;
                         Ok(entry.insert(buf))
                     }
                 };
             }
-            None => Ok(&mut self.orphaned),
+            None => self.orphaned_file(),
         }
     }
 }
@@ -191,39 +292,79 @@ impl<'a> Writer<'a> for HTML<'a> {
         // Data we want to write to a file
         let mut formatted_message = String::new();
 
+        // Screen effects are played over the message when animations are enabled
+        let effect_class = self
+            .effect_class(message)
+            .map(|class| format!(" {class}"))
+            .unwrap_or_default();
+
         // Message div
         if message.is_reply() && indent_size == 0 {
             // Add an ID for any top-level message so we can link to them in threads
             self.add_line(
                 &mut formatted_message,
-                &format!("<div class=\"message\", id=\"r-{}\">", message.guid),
+                &format!(
+                    "<div class=\"message{effect_class}\", id=\"r-{}\">",
+                    message.guid
+                ),
                 "",
                 "",
             );
         } else {
             // No ID needed if the message has no replies
-            self.add_line(&mut formatted_message, "<div class=\"message\">", "", "");
+            self.add_line(
+                &mut formatted_message,
+                &format!("<div class=\"message{effect_class}\">"),
+                "",
+                "",
+            );
         }
 
+        // Consecutive messages from the same sender omit the repeated timestamp and sender
+        let grouped = indent_size == 0 && self.is_grouped(message);
+        let group_class = if grouped { " grouped" } else { "" };
+
         // Start message div
         if message.is_from_me() {
             self.add_line(
                 &mut formatted_message,
-                &format!("<div class=\"sent {:?}\">", message.service()),
+                &format!("<div class=\"sent {:?}{group_class}\">", message.service()),
                 "",
                 "",
             );
         } else {
-            self.add_line(&mut formatted_message, "<div class=\"received\">", "", "");
+            self.add_line(
+                &mut formatted_message,
+                &format!("<div class=\"received{group_class}\">"),
+                "",
+                "",
+            );
         }
 
+        // Number top-level messages so they can be referenced
+        let number = self
+            .message_number(message, indent_size)
+            .map(|number| format!("<span class=\"msg-number\">#{number}</span> "))
+            .unwrap_or_default();
+
         // Add message date
-        self.add_line(
-            &mut formatted_message,
-            &self.get_time(message),
-            "<p><span class=\"timestamp\">",
-            "</span>",
-        );
+        if grouped {
+            self.add_line(&mut formatted_message, &format!("<p>{number}"), "", "");
+        } else {
+            self.add_line(
+                &mut formatted_message,
+                &self.get_time(message),
+                &format!("<p>{number}<span class=\"timestamp\">"),
+                "</span>",
+            );
+        }
+
+        // Compact output drops the newlines between elements, so inline elements in the header need a space instead
+        let inline_gap = if self.config.options.compact_html {
+            " "
+        } else {
+            ""
+        };
 
         // Add reply anchor if necessary
         if message.is_reply() {
@@ -232,7 +373,7 @@ impl<'a> Writer<'a> for HTML<'a> {
                 self.add_line(
                     &mut formatted_message,
                     &format!("<a href=\"#r-{}\">⇲</a>", message.guid),
-                    "<span class=\"reply_anchor\">",
+                    &format!("{inline_gap}<span class=\"reply_anchor\">"),
                     "</span>",
                 );
             } else {
@@ -240,23 +381,57 @@ impl<'a> Writer<'a> for HTML<'a> {
                 self.add_line(
                     &mut formatted_message,
                     &format!("<a href=\"#{}\">⇱</a>", message.guid),
-                    "<span class=\"reply_anchor\">",
+                    &format!("{inline_gap}<span class=\"reply_anchor\">"),
                     "</span>",
                 );
             }
         }
 
         // Add message sender
-        self.add_line(
-            &mut formatted_message,
-            self.config.who(
-                message.handle_id,
-                message.is_from_me(),
-                &message.destination_caller_id,
-            ),
-            "<span class=\"sender\">",
-            "</span></p>",
-        );
+        if grouped {
+            self.add_line(&mut formatted_message, "</p>", "", "");
+        } else {
+            self.add_line(
+                &mut formatted_message,
+                &self.config.who(
+                    message.handle_id,
+                    message.is_from_me(),
+                    &message.destination_caller_id,
+                ),
+                &format!("{inline_gap}<span class=\"sender\">"),
+                "</span></p>",
+            );
+        }
+
+        // If message was forwarded, annotate it
+        if message.is_forwarded() {
+            self.add_line(
+                &mut formatted_message,
+                "Forwarded",
+                "<span class=\"forwarded\">",
+                "</span></p>",
+            );
+        }
+
+        // If message was reported as junk, annotate it
+        if message.is_reported_junk() {
+            self.add_line(
+                &mut formatted_message,
+                "(reported as junk)",
+                "<span class=\"junk\">",
+                "</span></p>",
+            );
+        }
+
+        // If message was delivered while notifications were silenced, annotate it
+        if self.config.options.quiet_delivery && message.was_delivered_quietly() {
+            self.add_line(
+                &mut formatted_message,
+                "(delivered quietly)",
+                "<span class=\"quiet\">",
+                "</span></p>",
+            );
+        }
 
         // If message was deleted (not unsent), annotate it
         if message.is_deleted() {
@@ -270,8 +445,19 @@ impl<'a> Writer<'a> for HTML<'a> {
 
         // Useful message metadata
         let message_parts = message.body();
-        let mut attachments = Attachment::from_message(&self.config.db, message)?;
-        let mut replies = message.get_replies(&self.config.db)?;
+        // Messages that are only a few emoji are enlarged, like in iMessage
+        let bubble_class = if message.is_big_emoji() {
+            "bubble big-emoji"
+        } else {
+            "bubble"
+        };
+        let bubble_open = match message.writing_direction() {
+            WritingDirection::RightToLeft => format!("<span class=\"{bubble_class}\" dir=\"rtl\">"),
+            WritingDirection::LeftToRight => format!("<span class=\"{bubble_class}\" dir=\"ltr\">"),
+            WritingDirection::Natural => format!("<span class=\"{bubble_class}\">"),
+        };
+        let mut attachments = self.config.attachments(message)?;
+        let mut replies = message.get_replies_with_schema(&self.config.db, &self.config.schema)?;
 
         // Index of where we are in the attachment Vector
         let mut attachment_index: usize = 0;
@@ -309,10 +495,22 @@ impl<'a> Writer<'a> for HTML<'a> {
 
         // Generate the message body from it's components
         for (idx, message_part) in message_parts.iter().enumerate() {
+            let target = self
+                .config
+                .reaction_target(message, &message_parts, idx, &attachments);
+            let reactions = self.format_reactions(message, idx, target.as_deref())?;
+            // Reactions to an attachment are overlaid on the media they target instead of listed below it
+            let attachment_reactions =
+                matches!(message_part, BubbleComponent::Attachment) && !reactions.is_empty();
+
             // Write the part div start
             self.add_line(
                 &mut formatted_message,
-                "<hr><div class=\"message_part\">",
+                if attachment_reactions {
+                    "<hr><div class=\"message_part attachment_part\">"
+                } else {
+                    "<hr><div class=\"message_part\">"
+                },
                 "",
                 "",
             );
@@ -342,16 +540,23 @@ impl<'a> Writer<'a> for HTML<'a> {
                                 if let Some(message_content) =
                                     text.get(text_attr.start..text_attr.end)
                                 {
-                                    formatted_text.push_str(&self.format_attributed(
-                                        &sanitize_html(message_content),
-                                        &text_attr.effect,
-                                    ))
+                                    let masked = self.config.mask(message_content);
+                                    let content = sanitize_html(&masked);
+                                    formatted_text.push_str(&match &text_attr.effect {
+                                        // Mentions of the database owner are highlighted
+                                        TextEffect::Mention(mentioned)
+                                            if self.config.is_my_handle(mentioned, message) =>
+                                        {
+                                            Cow::Owned(self.format_mention_me(&content, mentioned))
+                                        }
+                                        effect => self.format_attributed(&content, effect),
+                                    })
                                 }
                             }
 
                             // If we failed to parse any text above, make sure we sanitize if before using it
                             if formatted_text.is_empty() {
-                                formatted_text.push_str(&sanitize_html(text));
+                                formatted_text.push_str(&sanitize_html(&self.config.mask(text)));
                             }
 
                             // Render the message body if the message or message part was not edited
@@ -360,24 +565,23 @@ impl<'a> Writer<'a> for HTML<'a> {
                             //     Some(edited_parts) => edited_parts.is_unedited_at(idx),
                             //     None => !message.is_edited(),
                             // } {
-                            if formatted_text.starts_with(FITNESS_RECEIVER) {
-                                self.add_line(
-                                    &mut formatted_message,
-                                    &formatted_text.replace(FITNESS_RECEIVER, YOU),
-                                    "<span class=\"bubble\">",
-                                    "</span>",
-                                );
-                            } else {
-                                self.add_line(
-                                    &mut formatted_message,
-                                    &formatted_text,
-                                    "<span class=\"bubble\">",
-                                    "</span>",
-                                );
-                            }
+                            self.add_line(
+                                &mut formatted_message,
+                                &self.config.fitness_text(&formatted_text, message),
+                                &bubble_open,
+                                "</span>",
+                            );
                         }
                     }
                 }
+                BubbleComponent::Attachment if self.config.options.no_attachments => {
+                    self.add_line(
+                        &mut formatted_message,
+                        ATTACHMENT_PLACEHOLDER,
+                        "<div class=\"attachment\">",
+                        "</div>",
+                    );
+                }
                 BubbleComponent::Attachment => {
                     match attachments.get_mut(attachment_index) {
                         Some(attachment) => {
@@ -389,6 +593,22 @@ impl<'a> Writer<'a> for HTML<'a> {
                                     "<div class=\"sticker\">",
                                     "</div>",
                                 );
+                            } else if let Some(failed) = self.config.failed_transfer(attachment) {
+                                attachment_index += 1;
+                                self.add_line(
+                                    &mut formatted_message,
+                                    failed,
+                                    "<span class=\"attachment_error\">",
+                                    "</span>",
+                                );
+                            } else if self.config.is_expired_audio_message(attachment) {
+                                attachment_index += 1;
+                                self.add_line(
+                                    &mut formatted_message,
+                                    "Audio message (expired)",
+                                    "<div class=\"attachment\">",
+                                    "</div>",
+                                );
                             } else {
                                 match self.format_attachment(attachment, message) {
                                     Ok(result) => {
@@ -401,6 +621,11 @@ impl<'a> Writer<'a> for HTML<'a> {
                                         );
                                     }
                                     Err(result) => {
+                                        self.errors.record(
+                                            IssueKind::UnresolvedAttachment,
+                                            &message.guid,
+                                            result,
+                                        );
                                         self.add_line(
                                         &mut formatted_message,
                                         result,
@@ -412,12 +637,19 @@ impl<'a> Writer<'a> for HTML<'a> {
                             }
                         }
                         // Attachment does not exist in attachments table
-                        None => self.add_line(
-                            &mut formatted_message,
-                            "Attachment does not exist!",
-                            "<span class=\"attachment_error\">",
-                            "</span>",
-                        ),
+                        None => {
+                            self.errors.record(
+                                IssueKind::MissingAttachment,
+                                &message.guid,
+                                &format!("Attachment {attachment_index} does not exist"),
+                            );
+                            self.add_line(
+                                &mut formatted_message,
+                                "Attachment does not exist!",
+                                "<span class=\"attachment_error\">",
+                                "</span>",
+                            )
+                        }
                     }
                 }
                 BubbleComponent::App => match self.format_app(message, &mut attachments, "") {
@@ -427,12 +659,19 @@ impl<'a> Writer<'a> for HTML<'a> {
                         "<div class=\"app\">",
                         "</div>",
                     ),
-                    Err(why) => self.add_line(
-                        &mut formatted_message,
-                        &format!("Unable to format {:?} message: {why}", message.variant()),
-                        "<div class=\"app_error\">",
-                        "</div>",
-                    ),
+                    Err(why) => {
+                        self.errors.record(
+                            IssueKind::UnparseablePayload,
+                            &message.guid,
+                            &why.to_string(),
+                        );
+                        self.add_line(
+                            &mut formatted_message,
+                            &format!("Unable to format {:?} message: {why}", message.variant()),
+                            "<div class=\"app_error\">",
+                            "</div>",
+                        )
+                    }
                 },
                 BubbleComponent::Retracted => {
                     if let Some(edited_parts) = &message.edited_parts {
@@ -448,50 +687,47 @@ impl<'a> Writer<'a> for HTML<'a> {
                 }
             };
 
+            if attachment_reactions {
+                self.add_line(
+                    &mut formatted_message,
+                    &reactions,
+                    "<div class=\"attachment_reactions\">",
+                    "</div>",
+                );
+            }
+
             // Write the part div end
             self.add_line(&mut formatted_message, "</div>", "", "");
 
             // Handle expressives
             if message.expressive_send_style_id.is_some() {
-                self.add_line(
-                    &mut formatted_message,
-                    self.format_expressive(message),
-                    "<span class=\"expressive\">",
-                    "</span>",
-                );
+                let expressive = self.format_expressive(message);
+                match message.expressive_state() {
+                    Some(state) => self.add_line(
+                        &mut formatted_message,
+                        &format!("{expressive} <span class=\"expressive_state\">({state})</span>"),
+                        "<span class=\"expressive\">",
+                        "</span>",
+                    ),
+                    None => self.add_line(
+                        &mut formatted_message,
+                        expressive,
+                        "<span class=\"expressive\">",
+                        "</span>",
+                    ),
+                }
             }
 
             // Handle Reactions
-            if let Some(reactions_map) = self.config.reactions.get(&message.guid) {
-                if let Some(reactions) = reactions_map.get(&idx) {
-                    let mut formatted_reactions = String::new();
-
-                    reactions
-                        .iter()
-                        .try_for_each(|reaction| -> Result<(), TableError> {
-                            let formatted = self.format_reaction(reaction)?;
-                            if !formatted.is_empty() {
-                                self.add_line(
-                                    &mut formatted_reactions,
-                                    &self.format_reaction(reaction)?,
-                                    "<div class=\"reaction\">",
-                                    "</div>",
-                                );
-                            }
-                            Ok(())
-                        })?;
-
-                    if !formatted_reactions.is_empty() {
-                        self.add_line(
-                            &mut formatted_message,
-                            "<hr><p>Reactions:</p>",
-                            "<div class=\"reactions\">",
-                            "",
-                        );
-                        self.add_line(&mut formatted_message, &formatted_reactions, "", "");
-                    }
-                    self.add_line(&mut formatted_message, "</div>", "", "");
-                }
+            if !attachment_reactions && !reactions.is_empty() {
+                self.add_line(
+                    &mut formatted_message,
+                    "<hr><p>Reactions:</p>",
+                    "<div class=\"reactions\">",
+                    "",
+                );
+                self.add_line(&mut formatted_message, &reactions, "", "");
+                self.add_line(&mut formatted_message, "</div>", "", "");
             }
 
             // Handle Replies
@@ -516,6 +752,21 @@ impl<'a> Writer<'a> for HTML<'a> {
             }
         }
 
+        // Note that the message was edited, regardless of how its history is displayed
+        if message.edit_count() > 0 {
+            self.add_line(
+                &mut formatted_message,
+                "(edited)",
+                "<span class=\"edited_indicator\">",
+                "</span>",
+            );
+        }
+
+        // Show the delivery status of sent messages
+        if let Some(receipt) = self.get_receipt(message) {
+            self.add_line(&mut formatted_message, &receipt, "", "");
+        }
+
         // Add a note if the message is a reply and not rendered in a thread
         if message.is_reply() && indent_size == 0 {
             self.add_line(
@@ -526,6 +777,16 @@ impl<'a> Writer<'a> for HTML<'a> {
             );
         }
 
+        // Render the note passed for this message, if any
+        if let Some(note) = self.config.annotation(message) {
+            self.add_line(
+                &mut formatted_message,
+                &sanitize_html(note),
+                "<aside class=\"annotation\">",
+                "</aside>",
+            );
+        }
+
         // End message type div
         self.add_line(&mut formatted_message, "</div>", "", "");
 
@@ -548,14 +809,19 @@ impl<'a> Writer<'a> for HTML<'a> {
             .ok_or(attachment.filename())?;
 
         // Build a relative filepath from the fully qualified one on the `Attachment`
-        let embed_path = self.config.message_attachment_path(attachment);
+        let embed_path = self.config.message_attachment_link(attachment, message);
 
-        return Ok(match attachment.mime_type() {
-            MediaType::Image(_) => {
+        return Ok(match self.config.media_type(attachment) {
+            MediaType::Image(_) | MediaType::AnimatedImage(_) => {
+                let alt = if self.config.options.alt_text {
+                    format!(" alt=\"{}\"", sanitize_html(attachment.alt_text()))
+                } else {
+                    String::new()
+                };
                 if self.config.options.no_lazy {
-                    format!("<img src=\"{embed_path}\">")
+                    format!("<img src=\"{embed_path}\"{alt}>")
                 } else {
-                    format!("<img src=\"{embed_path}\" loading=\"lazy\">")
+                    format!("<img src=\"{embed_path}\"{alt} loading=\"lazy\">")
                 }
             }
             MediaType::Video(media_type) => {
@@ -588,14 +854,21 @@ impl<'a> Writer<'a> for HTML<'a> {
 
     fn format_sticker(&self, sticker: &'a mut Attachment, message: &Message) -> String {
         match self.format_attachment(sticker, message) {
-            Ok(sticker_embed) => {
+            Ok(mut sticker_embed) => {
                 let sticker_effect = sticker.get_sticker_effect(
                     &self.config.options.platform,
                     &self.config.options.db_path,
                     self.config.options.attachment_root.as_deref(),
                 );
                 if let Ok(Some(sticker_effect)) = sticker_effect {
-                    return format!("{sticker_embed}\n<div class=\"sticker_effect\">Sent with {sticker_effect} effect</div>");
+                    sticker_embed.push_str(&format!(
+                        "\n<div class=\"sticker_effect\">Sent with {sticker_effect} effect</div>"
+                    ));
+                }
+                if let Some(sticker_source) = self.config.sticker_source(sticker, message) {
+                    sticker_embed.push_str(&format!(
+                        "\n<div class=\"sticker_source\">Sticker from {sticker_source}</div>"
+                    ));
                 }
                 sticker_embed
             }
@@ -617,6 +890,11 @@ impl<'a> Writer<'a> for HTML<'a> {
                 return Ok(self.format_handwriting(&HandwrittenMessage::new(), message));
             }
 
+            // Digital Touch messages use a different payload type, so handle those next
+            if matches!(balloon, CustomBalloon::DigitalTouch) {
+                return Ok(self.format_digital_touch(message));
+            }
+
             if let Some(payload) = message.payload_data(&self.config.db) {
                 let res = if message.is_url() {
                     let parsed = parse_plist(&payload)?;
@@ -644,7 +922,13 @@ impl<'a> Writer<'a> for HTML<'a> {
                             CustomBalloon::Slideshow => self.format_slideshow(&bubble, message),
                             CustomBalloon::CheckIn => self.format_check_in(&bubble, message),
                             CustomBalloon::FindMy => self.format_find_my(&bubble, message),
+                            CustomBalloon::Journal => self.format_journal(&bubble, message),
+                            CustomBalloon::Game(bundle_id) => self.format_game(
+                                &GameMessage::from_app_message(&bubble, bundle_id),
+                                message,
+                            ),
                             CustomBalloon::Handwriting => unreachable!(),
+                            CustomBalloon::DigitalTouch => unreachable!(),
                             CustomBalloon::URL => unreachable!(),
                         },
                         Err(why) => return Err(why),
@@ -679,32 +963,58 @@ impl<'a> Writer<'a> for HTML<'a> {
         }
     }
 
-    fn format_reaction(&self, msg: &Message) -> Result<String, TableError> {
+    fn format_reaction(&self, msg: &Message, target: Option<&str>) -> Result<String, TableError> {
         match msg.variant() {
-            Variant::Reaction(_, added, reaction) => {
-                if !added {
-                    return Ok(String::new());
-                }
-                Ok(format!(
-                    "<span class=\"reaction\"><b>{:?}</b> by {}</span>",
-                    reaction,
-                    self.config
-                        .who(msg.handle_id, msg.is_from_me(), &msg.destination_caller_id),
-                ))
-            }
-            Variant::Sticker(_) => {
-                let mut paths = Attachment::from_message(&self.config.db, msg)?;
+            // Sticker tapbacks carry their sticker image as an attachment, so they render like stickers
+            Variant::Reaction(_, true, Reaction::Sticker) | Variant::Sticker(_) => {
+                let mut paths = self.config.attachments(msg)?;
                 let who =
                     self.config
                         .who(msg.handle_id, msg.is_from_me(), &msg.destination_caller_id);
                 // Sticker messages have only one attachment, the sticker image
                 Ok(match paths.get_mut(0) {
                     Some(sticker) => self.format_sticker(sticker, msg),
+                    None if self.config.options.no_attachments => {
+                        format!("<span class=\"reaction\">Sticker from {who}: {ATTACHMENT_PLACEHOLDER}</span>")
+                    }
                     None => {
+                        self.errors.record(
+                            IssueKind::MissingAttachment,
+                            &msg.guid,
+                            "Sticker does not exist",
+                        );
                         format!("<span class=\"reaction\">Sticker from {who} not found!</span>")
                     }
                 })
             }
+            Variant::Reaction(_, added, reaction) => {
+                if !added {
+                    if !self.config.options.show_removed_reactions {
+                        return Ok(String::new());
+                    }
+                    return Ok(format!(
+                        "<span class=\"reaction\">{} removed their <b>{:?}</b> reaction{}</span>",
+                        self.config.who(
+                            msg.handle_id,
+                            msg.is_from_me(),
+                            &msg.destination_caller_id
+                        ),
+                        reaction,
+                        target
+                            .map(|target| format!(" to {}", sanitize_html(target)))
+                            .unwrap_or_default(),
+                    ));
+                }
+                Ok(format!(
+                    "<span class=\"reaction\"><b>{:?}</b>{} by {}</span>",
+                    reaction,
+                    target
+                        .map(|target| format!(" {}", sanitize_html(target)))
+                        .unwrap_or_default(),
+                    self.config
+                        .who(msg.handle_id, msg.is_from_me(), &msg.destination_caller_id),
+                ))
+            }
             _ => unreachable!(),
         }
     }
@@ -739,7 +1049,7 @@ impl<'a> Writer<'a> for HTML<'a> {
             .who(msg.handle_id, msg.is_from_me(), &msg.destination_caller_id);
         // Rename yourself so we render the proper grammar here
         if who == ME {
-            who = self.config.options.custom_name.as_deref().unwrap_or("You");
+            who = Cow::Borrowed(self.config.options.custom_name.as_deref().unwrap_or("You"));
         }
         let timestamp = format(&msg.date(&self.config.offset));
 
@@ -761,11 +1071,14 @@ impl<'a> Writer<'a> for HTML<'a> {
                         "\n<div class =\"announcement\"><p><span class=\"timestamp\">{timestamp}</span> {who} performed unknown action {num}</p></div>\n"
                     )
                 }
-                Announcement::FullyUnsent => {
-                    format!(
+                Announcement::FullyUnsent => match msg.unsent_timing() {
+                    Some(UnsentTiming::BeforeDelivery) => format!(
+                        "<div class =\"announcement\"><p><span class=\"timestamp\">{timestamp}</span> {who} unsent a message before it was delivered.</p></div>"
+                    ),
+                    _ => format!(
                         "<div class =\"announcement\"><p><span class=\"timestamp\">{timestamp}</span> {who} unsent a message.</p></div>"
-                    )
-                }
+                    ),
+                },
             },
             None => String::from(
                 "\n<div class =\"announcement\"><p>Unable to format announcement!</p></div>\n",
@@ -804,7 +1117,8 @@ impl<'a> Writer<'a> for HTML<'a> {
 
                     for (idx, event) in edited_message_part.edit_history.iter().enumerate() {
                         let last = idx == edited_message_part.edit_history.len() - 1;
-                        let clean_text = sanitize_html(&event.text);
+                        let masked_text = self.config.mask(&event.text);
+                        let clean_text = sanitize_html(&masked_text);
                         match previous_timestamp {
                             None => out_s.push_str(&self.edited_to_html("", &clean_text, last)),
                             Some(prev_timestamp) => {
@@ -828,21 +1142,35 @@ impl<'a> Writer<'a> for HTML<'a> {
                 }
                 EditStatus::Unsent => {
                     let who = if msg.is_from_me() {
-                        self.config.options.custom_name.as_deref().unwrap_or(YOU)
+                        Cow::Borrowed(self.config.options.custom_name.as_deref().unwrap_or(YOU))
                     } else {
                         self.config
                             .who(msg.handle_id, msg.is_from_me(), &msg.destination_caller_id)
                     };
 
+                    // Messages retracted before delivery were never seen by the recipient
+                    let before_delivery =
+                        matches!(msg.unsent_timing(), Some(UnsentTiming::BeforeDelivery));
+
                     match readable_diff(
                         msg.date(&self.config.offset),
                         msg.date_edited(&self.config.offset),
                     ) {
+                        Some(diff) if before_delivery => {
+                            out_s.push_str(&format!(
+                                "<span class=\"unsent\">{who} unsent this message part {diff} after sending, before it was delivered!</span>"
+                            ))
+                        },
                         Some(diff) => {
                             out_s.push_str(&format!(
                                 "<span class=\"unsent\">{who} unsent this message part {diff} after sending!</span>"
                             ))
                         },
+                        None if before_delivery => {
+                            out_s.push_str(&format!(
+                                "<span class=\"unsent\">{who} unsent this message part before it was delivered!</span>"
+                            ))
+                        },
                         None => {
                             out_s.push_str(&format!(
                                 "<span class=\"unsent\">{who} unsent this message part!</span>"
@@ -891,6 +1219,17 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         // Header section
         out_s.push_str("<div class=\"app_header\">");
 
+        // Remote preview images are listed in the WARC so a crawler can capture them
+        if self.config.options.warc {
+            self.preview_urls.borrow_mut().extend(
+                balloon
+                    .images
+                    .iter()
+                    .filter(|image| image.starts_with("http://") || image.starts_with("https://"))
+                    .map(|image| image.to_string()),
+            );
+        }
+
         // Add preview images
         balloon.images.iter().for_each(|image| {
             out_s.push_str("<img src=\"");
@@ -1196,6 +1535,10 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         String::from("Handwritten messages are not yet supported!")
     }
 
+    fn format_digital_touch(&self, _: &Message) -> String {
+        String::from("Digital Touch messages are not yet supported!")
+    }
+
     fn format_apple_pay(&self, balloon: &AppMessage, _: &Message) -> String {
         let mut out_s = String::new();
 
@@ -1224,11 +1567,11 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
     }
 
     fn format_fitness(&self, balloon: &AppMessage, message: &Message) -> String {
-        self.balloon_to_html(balloon, "Fitness", &mut [], message)
+        self.balloon_to_html(balloon, "Fitness", false, &mut [], message)
     }
 
     fn format_slideshow(&self, balloon: &AppMessage, message: &Message) -> String {
-        self.balloon_to_html(balloon, "Slideshow", &mut [], message)
+        self.balloon_to_html(balloon, "Slideshow", false, &mut [], message)
     }
 
     fn format_find_my(&self, balloon: &AppMessage, _: &'a Message) -> String {
@@ -1258,52 +1601,131 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s
     }
 
-    fn format_check_in(&self, balloon: &AppMessage, _: &Message) -> String {
+    fn format_journal(&self, balloon: &AppMessage, _: &'a Message) -> String {
         let mut out_s = String::new();
 
         out_s.push_str("<div class=\"app_header\">");
 
         // Name
         out_s.push_str("<div class=\"name\">");
-        out_s.push_str(balloon.app_name.unwrap_or("Check In"));
+        out_s.push_str(balloon.app_name.unwrap_or("Journal"));
         out_s.push_str("</div>");
 
-        // ldtext
-        if let Some(ldtext) = balloon.ldtext {
-            out_s.push_str("<div class=\"ldtext\">");
-            out_s.push_str(ldtext);
+        // Suggestion title
+        if let Some(title) = balloon.title {
+            out_s.push_str("<div class=\"image_title\">");
+            out_s.push_str(title);
             out_s.push_str("</div>");
         }
 
         // Header end, footer begin
         out_s.push_str("</div>");
+        out_s.push_str("<div class=\"app_footer\">");
 
-        // Only write the footer if there is data to write
-        let metadata: HashMap<&str, &str> = balloon.parse_query_string();
+        // Suggestion type
+        if let Some(suggestion_type) = balloon.subcaption {
+            out_s.push_str("<div class=\"caption\">");
+            out_s.push_str(suggestion_type);
+            out_s.push_str(" suggestion</div>");
+        }
 
-        // Before manual check-in
-        if let Some(date_str) = metadata.get("estimatedEndTime") {
-            // Parse the estimated end time from the message's query string
-            let date_stamp = date_str.parse::<f64>().unwrap_or(0.) as i64 * TIMESTAMP_FACTOR;
-            let date_time = get_local_time(&date_stamp, &0);
-            let date_string = format(&date_time);
+        // End footer
+        out_s.push_str("</div>");
 
-            out_s.push_str("<div class=\"app_footer\">");
+        out_s
+    }
 
-            out_s.push_str("<div class=\"caption\">Expected around ");
-            out_s.push_str(&date_string);
-            out_s.push_str("</div>");
+    fn format_game(&self, balloon: &GameMessage, _: &Message) -> String {
+        let mut out_s = String::new();
+
+        out_s.push_str("<div class=\"app_header\">");
+
+        // Name
+        out_s.push_str("<div class=\"name\">");
+        out_s.push_str(balloon.app_name);
+        out_s.push_str("</div>");
 
+        // Game
+        if let Some(game) = balloon.game {
+            out_s.push_str("<div class=\"image_title\">");
+            out_s.push_str(game);
             out_s.push_str("</div>");
         }
-        // Expired check-in
-        else if let Some(date_str) = metadata.get("triggerTime") {
-            // Parse the estimated end time from the message's query string
-            let date_stamp = date_str.parse::<f64>().unwrap_or(0.) as i64 * TIMESTAMP_FACTOR;
-            let date_time = get_local_time(&date_stamp, &0);
-            let date_string = format(&date_time);
-
-            out_s.push_str("<div class=\"app_footer\">");
+
+        // Header end
+        out_s.push_str("</div>");
+
+        // Only write the footer if there is data to write
+        if balloon.state.is_some() || balloon.score.is_some() {
+            out_s.push_str("<div class=\"app_footer\">");
+
+            // State
+            if let Some(state) = balloon.state {
+                out_s.push_str("<div class=\"caption\">");
+                out_s.push_str(state);
+                out_s.push_str("</div>");
+            }
+
+            // Score
+            if let Some(score) = balloon.score {
+                out_s.push_str("<div class=\"trailing_caption\">");
+                out_s.push_str(score);
+                out_s.push_str("</div>");
+            }
+
+            // End footer
+            out_s.push_str("</div>");
+        }
+
+        out_s
+    }
+
+    fn format_check_in(&self, balloon: &AppMessage, _: &Message) -> String {
+        let mut out_s = String::new();
+
+        out_s.push_str("<div class=\"app_header\">");
+
+        // Name
+        out_s.push_str("<div class=\"name\">");
+        out_s.push_str(balloon.app_name.unwrap_or("Check In"));
+        out_s.push_str("</div>");
+
+        // ldtext
+        if let Some(ldtext) = balloon.ldtext {
+            out_s.push_str("<div class=\"ldtext\">");
+            out_s.push_str(ldtext);
+            out_s.push_str("</div>");
+        }
+
+        // Header end, footer begin
+        out_s.push_str("</div>");
+
+        // Only write the footer if there is data to write
+        let metadata: HashMap<&str, &str> = balloon.parse_query_string();
+
+        // Before manual check-in
+        if let Some(date_str) = metadata.get("estimatedEndTime") {
+            // Parse the estimated end time from the message's query string
+            let date_stamp = date_str.parse::<f64>().unwrap_or(0.) as i64 * TIMESTAMP_FACTOR;
+            let date_time = get_local_time(&date_stamp, &0);
+            let date_string = format(&date_time);
+
+            out_s.push_str("<div class=\"app_footer\">");
+
+            out_s.push_str("<div class=\"caption\">Expected around ");
+            out_s.push_str(&date_string);
+            out_s.push_str("</div>");
+
+            out_s.push_str("</div>");
+        }
+        // Expired check-in
+        else if let Some(date_str) = metadata.get("triggerTime") {
+            // Parse the estimated end time from the message's query string
+            let date_stamp = date_str.parse::<f64>().unwrap_or(0.) as i64 * TIMESTAMP_FACTOR;
+            let date_time = get_local_time(&date_stamp, &0);
+            let date_string = format(&date_time);
+
+            out_s.push_str("<div class=\"app_footer\">");
 
             out_s.push_str("<div class=\"caption\">Was expected around ");
             out_s.push_str(&date_string);
@@ -1337,7 +1759,8 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         attachments: &mut Vec<Attachment>,
         message: &Message,
     ) -> String {
-        self.balloon_to_html(balloon, bundle_id, attachments, message)
+        // Unknown apps show their bundle id so the source of the balloon can be identified
+        self.balloon_to_html(balloon, bundle_id, true, attachments, message)
     }
 }
 
@@ -1347,7 +1770,8 @@ impl<'a> TextEffectFormatter for HTML<'a> {
     }
 
     fn format_link(&self, text: &str, url: &str) -> String {
-        format!("<a href=\"{url}\">{text}</a>")
+        // The target can differ from the displayed text, so it must not be able to close the attribute
+        format!("<a href=\"{}\">{text}</a>", url.replace('"', "%22"))
     }
 
     fn format_otp(&self, text: &str) -> String {
@@ -1390,38 +1814,458 @@ impl<'a> TextEffectFormatter for HTML<'a> {
 }
 
 impl<'a> HTML<'a> {
+    /// Open the file for orphaned messages, writing its headers unless a previous export already wrote them
+    fn orphaned_file(&mut self) -> Result<&mut BufWriter<File>, RuntimeError> {
+        let buf = match self.orphaned.take() {
+            Some(buf) => buf,
+            None => {
+                let mut path = self.config.options.export_path.clone();
+                path.push(ORPHANED);
+                path.set_extension("html");
+
+                let file = HTML::open_for_append(&path)?;
+                let is_empty = file.metadata().map_err(RuntimeError::DiskError)?.len() == 0;
+                let mut buf = BufWriter::new(file);
+                if is_empty {
+                    HTML::write_headers(
+                        &mut buf,
+                        &self.config.options.html_lang,
+                        self.custom_css.as_deref(),
+                        self.config.options.print_friendly,
+                    )?;
+                }
+                buf
+            }
+        };
+        Ok(self.orphaned.insert(buf))
+    }
+
+    /// Format the message with the given GUID, including its reactions and replies, without writing it to a file
+    pub(crate) fn format_single_message(&self, guid: &str) -> Result<String, RuntimeError> {
+        let msg = self.config.message_by_guid(guid)?;
+        if msg.is_announcement() {
+            return Ok(self.format_announcement(&msg));
+        }
+        self.format_message(&msg, 0)
+            .map_err(RuntimeError::DatabaseError)
+    }
+
+    /// Render the reactions to a single part of a message, or an empty string if there are none
+    ///
+    /// If there are more reactions than the `--collapse-reactions-under-threshold` value, they are collapsed under an expandable summary.
+    fn format_reactions(
+        &self,
+        message: &Message,
+        idx: usize,
+        target: Option<&str>,
+    ) -> Result<String, TableError> {
+        let mut formatted_reactions = String::new();
+        let mut count = 0;
+        if let Some(reactions) = self
+            .config
+            .reactions
+            .get(&message.guid)
+            .and_then(|reactions_map| reactions_map.get(&idx))
+        {
+            for reaction in reactions {
+                let formatted = self.format_reaction(reaction, target)?;
+                if !formatted.is_empty() {
+                    count += 1;
+                    self.add_line(
+                        &mut formatted_reactions,
+                        &formatted,
+                        "<div class=\"reaction\">",
+                        "</div>",
+                    );
+                }
+            }
+        }
+
+        if self
+            .config
+            .options
+            .collapse_reactions
+            .is_some_and(|threshold| count > threshold)
+        {
+            let mut collapsed = String::new();
+            self.add_line(
+                &mut collapsed,
+                &format!("+{count} reactions"),
+                "<details><summary>",
+                "</summary>",
+            );
+            collapsed.push_str(&formatted_reactions);
+            collapsed.push_str("</details>");
+            return Ok(collapsed);
+        }
+        Ok(formatted_reactions)
+    }
+
+    /// Build the header written at the top of a conversation's file, showing its chat identifiers and when it was created and last active
+    fn conversation_header(&self, chatroom: &Chat) -> String {
+        let identifiers = self.config.conversation_identifiers(chatroom).join(", ");
+        let mut header = format!(
+            "<div class=\"conversation-header\">\n<span class=\"chat-id\">Chat ID {}</span>\n",
+            sanitize_html(&identifiers)
+        );
+        if let Some((first, last)) = self.config.conversation_activity(chatroom) {
+            let created = format(&get_local_time(&first, &self.config.offset));
+            let last_activity = format(&get_local_time(&last, &self.config.offset));
+            header.push_str(&format!(
+                "<span class=\"created\">Created {created}</span>\n<span class=\"last-activity\">Last activity {last_activity}</span>\n"
+            ));
+        }
+        if let Some(service_mix) = self.config.conversation_service_mix(chatroom) {
+            header.push_str(&format!(
+                "<span class=\"services\">Sent with {service_mix}</span>\n"
+            ));
+        }
+        header.push_str("</div>\n");
+        header
+    }
+
     fn get_time(&self, message: &Message) -> String {
-        let mut date = format(&message.date(&self.config.offset));
-        let read_after = message.time_until_read(&self.config.offset);
-        if let Some(time) = read_after {
-            if !time.is_empty() {
-                let who = if message.is_from_me() {
-                    "them"
-                } else {
-                    self.config.options.custom_name.as_deref().unwrap_or("you")
-                };
-                date.push_str(&format!(" (Read by {who} after {time})"));
+        let mut date = self.config.format_timestamp(message);
+        // Scheduled messages are dated with the time they were scheduled to send
+        if message.is_scheduled() {
+            date = format!("Scheduled for {date}");
+        }
+        match message.read_receipt_state() {
+            ReadReceiptState::Read => {
+                if let Some(time) = message.time_until_read(&self.config.offset) {
+                    if !time.is_empty() {
+                        let who = if message.is_from_me() {
+                            "them"
+                        } else {
+                            self.config.options.custom_name.as_deref().unwrap_or("you")
+                        };
+                        date.push_str(&format!(" (Read by {who} after {time})"));
+                    }
+                }
+            }
+            // Without a read receipt, we only know when the message was delivered
+            ReadReceiptState::Delivered => {
+                if let Some(time) = message.time_until_delivered(&self.config.offset) {
+                    if !time.is_empty() {
+                        date.push_str(&format!(" (Delivered after {time})"));
+                    }
+                }
             }
+            ReadReceiptState::Unread | ReadReceiptState::Unknown => {}
         }
         date
     }
 
+    /// Get the Delivered or Read status indicator for a sent message, if `--show-receipts` is enabled
+    ///
+    /// The time the status was reached is shown when hovering over the indicator.
+    fn get_receipt(&self, message: &Message) -> Option<String> {
+        if !self.config.options.show_receipts || !message.is_from_me() {
+            return None;
+        }
+        let (status, date) = match message.read_receipt_state() {
+            ReadReceiptState::Read => ("Read", message.date_read(&self.config.offset)),
+            ReadReceiptState::Delivered => {
+                ("Delivered", message.date_delivered(&self.config.offset))
+            }
+            ReadReceiptState::Unread | ReadReceiptState::Unknown => return None,
+        };
+        Some(format!(
+            "<span class=\"receipt\" title=\"{}\">{status}</span>",
+            format(&date)
+        ))
+    }
+
+    /// Get the CSS class that animates a message's screen effect, if `--animate-effects` is enabled
+    fn effect_class(&self, message: &Message) -> Option<&'static str> {
+        if !self.config.options.animate_effects {
+            return None;
+        }
+        match message.get_expressive() {
+            Expressive::Screen(ScreenEffect::Confetti) => Some("effect_confetti"),
+            Expressive::Screen(ScreenEffect::Fireworks) => Some("effect_fireworks"),
+            Expressive::Screen(ScreenEffect::Balloons) => Some("effect_balloons"),
+            _ => None,
+        }
+    }
+
+    /// Determine if a message only contains attachments, and can be rendered in a media grid
+    fn is_media_only(message: &Message) -> bool {
+        message.has_attachments()
+            && !message.has_replies()
+            && message
+                .body()
+                .iter()
+                .all(|component| matches!(component, BubbleComponent::Attachment))
+    }
+
+    /// Update the media grid state for the file a message is written to
+    ///
+    /// Returns a tuple of `(close, open)`, where `close` is `true` if the grid open in that file
+    /// ends before this message and `open` is `true` if this message starts a new grid.
+    fn update_media_grid(&mut self, message: &Message, file: Option<String>) -> (bool, bool) {
+        let conversation_id = self.config.conversation_id(message);
+        let is_media = self.config.options.media_grid && HTML::is_media_only(message);
+
+        // Consecutive attachment-only messages in the same group share a grid
+        let open_grid = self.media_grids.get(&file).copied();
+        if is_media && open_grid == Some(conversation_id) && self.is_grouped(message) {
+            return (false, false);
+        }
+
+        let close = self.media_grids.remove(&file).is_some();
+        if is_media {
+            self.media_grids.insert(file, conversation_id);
+        }
+        (close, is_media)
+    }
+
+    /// Close or open a media grid in the file a message is written to, before the message is written
+    fn write_media_grid_boundary(&mut self, message: &Message) -> Result<(), RuntimeError> {
+        if !self.config.options.media_grid {
+            return Ok(());
+        }
+
+        let file = self.config.message_file(message);
+        let (close, open) = self.update_media_grid(message, file);
+
+        let buf = self.get_or_create_file(message)?;
+        if close {
+            HTML::write_to_file(buf, "</div>\n")?;
+        }
+        if open {
+            HTML::write_to_file(buf, "<div class=\"media-grid\">\n")?;
+        }
+        Ok(())
+    }
+
+    /// Track the day of the last message written to a file
+    ///
+    /// Returns `true` if the message is from a different day than the message before it in the same file.
+    fn is_new_day(&mut self, message: &Message, file: Option<String>) -> bool {
+        let day = match get_local_time(&message.date, &self.config.offset) {
+            Ok(date) => date.format("%Y-%m-%d").to_string(),
+            Err(_) => return false,
+        };
+        self.last_days
+            .insert(file, day.clone())
+            .is_some_and(|previous| previous != day)
+    }
+
+    /// Start a new printed page in the file a message is written to when the day changes, for `--print-friendly`
+    fn write_day_break(&mut self, message: &Message) -> Result<(), RuntimeError> {
+        if !self.config.options.print_friendly {
+            return Ok(());
+        }
+
+        let file = self.config.message_file(message);
+        if !self.is_new_day(message, file.clone()) {
+            return Ok(());
+        }
+
+        // Media grids cannot span a page break
+        let close = self.media_grids.remove(&file).is_some();
+
+        let buf = self.get_or_create_file(message)?;
+        if close {
+            HTML::write_to_file(buf, "</div>\n")?;
+        }
+        HTML::write_to_file(buf, "<div class=\"day_break\"></div>\n")?;
+        Ok(())
+    }
+
+    /// Count a top-level message in the file it is written to, for `--number-messages`
+    fn number_message(&mut self, message: &Message) {
+        if self.config.options.number_messages {
+            *self
+                .message_numbers
+                .entry(self.config.message_file(message))
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Get the number of a message in the file it is written to, if it was counted by [`HTML::number_message`]
+    ///
+    /// Replies rendered in a thread under another message are not numbered.
+    fn message_number(&self, message: &Message, indent_size: usize) -> Option<u64> {
+        if indent_size > 0 {
+            return None;
+        }
+        self.message_numbers
+            .get(&self.config.message_file(message))
+            .copied()
+    }
+
+    /// Format a [`Mention`](imessage_database::message_types::text_effects::TextEffect::Mention) of the database owner
+    fn format_mention_me(&self, text: &str, mentioned: &str) -> String {
+        format!("<span class=\"mention-me\" title=\"{mentioned}\"><b>{text}</b></span>")
+    }
+
+    /// Open the file for a conversation, writing its headers if it does not exist yet
+    fn create_file(
+        config: &Config,
+        custom_css: Option<&str>,
+        filename: &str,
+        header: &str,
+    ) -> Result<BufWriter<File>, RuntimeError> {
+        let mut path = config.options.export_path.clone();
+        path.push(filename);
+        path.set_extension("html");
+
+        // Files can be nested in a directory when using `--directory-layout`
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)
+                .map_err(|err| RuntimeError::CreateError(err, parent.to_path_buf()))?;
+        }
+
+        let file = HTML::open_for_append(&path)?;
+
+        // If the file already has contents, don't write the headers again
+        // This can happen if multiple chats use the same group name, or when resuming with `--since-last-export`
+        let is_empty = file.metadata().map_err(RuntimeError::DiskError)?.len() == 0;
+
+        let mut buf = BufWriter::new(file);
+
+        // Write headers if the file is new
+        if is_empty {
+            HTML::write_headers(
+                &mut buf,
+                &config.options.html_lang,
+                custom_css,
+                config.options.print_friendly,
+            )?;
+            HTML::write_to_file(&mut buf, header)?;
+        }
+
+        Ok(buf)
+    }
+
+    /// Open a file to append messages to, creating it if it does not exist
+    ///
+    /// A file written by a previous `--since-last-export` run already ends with [`FOOTER`], so it is removed
+    /// to keep new messages inside the document; the footer is written again when the export finishes.
+    fn open_for_append(path: &Path) -> Result<File, RuntimeError> {
+        let create_error = |err| RuntimeError::CreateError(err, path.to_path_buf());
+
+        if path.exists() {
+            let mut file = File::options()
+                .read(true)
+                .write(true)
+                .open(path)
+                .map_err(create_error)?;
+            let footer_len = FOOTER.len() as u64;
+            let len = file.metadata().map_err(create_error)?.len();
+            if len >= footer_len {
+                let mut tail = vec![0; FOOTER.len()];
+                file.seek(SeekFrom::Start(len - footer_len))
+                    .and_then(|_| file.read_exact(&mut tail))
+                    .map_err(create_error)?;
+                if tail == FOOTER.as_bytes() {
+                    file.set_len(len - footer_len).map_err(create_error)?;
+                }
+            }
+        }
+
+        File::options()
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(create_error)
+    }
+
+    /// Create a file for each conversation that had no messages exported, for `--include-empty-chats`
+    fn write_empty_chats(
+        &mut self,
+        exported: &HashMap<Option<i32>, u64>,
+        chatrooms: &Option<HashSet<i32>>,
+    ) -> Result<(), RuntimeError> {
+        for chatroom in self.config.empty_chatrooms(exported, chatrooms) {
+            let filename = self.config.filename(chatroom);
+            if self.files.contains_key(&filename) {
+                continue;
+            }
+            let header = self.conversation_header(chatroom);
+            let buf =
+                HTML::create_file(self.config, self.custom_css.as_deref(), &filename, &header)?;
+            self.files.insert(filename, buf);
+        }
+        Ok(())
+    }
+
+    /// Finish the file a split conversation was previously written to once its messages move to a new file
+    ///
+    /// Messages are exported in date order, so a conversation never returns to an earlier period. Closing
+    /// those files keeps the number of open files low when active conversations are split by day.
+    fn close_previous_split(
+        &mut self,
+        conversation: String,
+        filename: &str,
+    ) -> Result<(), RuntimeError> {
+        match self.split_files.insert(conversation, filename.to_string()) {
+            Some(previous) if previous != filename => {
+                if let Some(mut buf) = self.files.remove(&previous) {
+                    if self.media_grids.remove(&Some(previous)).is_some() {
+                        HTML::write_to_file(&mut buf, "</div>\n")?;
+                    }
+                    HTML::write_to_file(&mut buf, FOOTER)?;
+                    buf.flush().map_err(RuntimeError::DiskError)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Determine if a message was sent by the same sender as the previous message in its conversation
+    fn is_grouped(&self, message: &Message) -> bool {
+        match self.last_sender.get(&self.config.conversation_id(message)) {
+            Some((sender, date)) => {
+                (message.date - date) / TIMESTAMP_FACTOR <= GROUPING_WINDOW
+                    // Split files each start with a full message header
+                    && self.config.split_period(date) == self.config.split_period(&message.date)
+                    && *sender
+                        == self.config.who(
+                            message.handle_id,
+                            message.is_from_me(),
+                            &message.destination_caller_id,
+                        )
+            }
+            None => false,
+        }
+    }
+
     fn add_line(&self, string: &mut String, part: &str, pre: &str, post: &str) {
         if !part.is_empty() {
             string.push_str(pre);
             string.push_str(part);
             string.push_str(post);
-            string.push('\n');
+            if !self.config.options.compact_html {
+                string.push('\n');
+            }
         }
     }
 
-    fn write_headers(file: &mut BufWriter<File>) -> Result<(), RuntimeError> {
+    fn write_headers(
+        file: &mut BufWriter<File>,
+        lang: &str,
+        custom_css: Option<&str>,
+        print_friendly: bool,
+    ) -> Result<(), RuntimeError> {
         // Write file header
+        HTML::write_to_file(file, &format!("<html lang=\"{lang}\">\n"))?;
         HTML::write_to_file(file, HEADER)?;
 
         // Write CSS
         HTML::write_to_file(file, "<style>\n")?;
         HTML::write_to_file(file, STYLE)?;
+        if print_friendly {
+            HTML::write_to_file(file, "\n")?;
+            HTML::write_to_file(file, PRINT_STYLE)?;
+        }
+        if let Some(css) = custom_css {
+            HTML::write_to_file(file, "\n")?;
+            HTML::write_to_file(file, css)?;
+        }
         HTML::write_to_file(file, "\n</style>")?;
         HTML::write_to_file(file, "\n</head>\n<body>\n")?;
         Ok(())
@@ -1436,6 +2280,7 @@ impl<'a> HTML<'a> {
         &self,
         balloon: &AppMessage,
         bundle_id: &str,
+        show_bundle_id: bool,
         attachments: &mut [Attachment],
         message: &Message,
     ) -> String {
@@ -1465,6 +2310,13 @@ impl<'a> HTML<'a> {
         out_s.push_str(balloon.app_name.unwrap_or(bundle_id));
         out_s.push_str("</div>");
 
+        // Bundle ID, if the name does not already show it
+        if show_bundle_id && balloon.app_name.is_some() {
+            out_s.push_str("<div class=\"bundle_id\">");
+            out_s.push_str(bundle_id);
+            out_s.push_str("</div>");
+        }
+
         // Title
         if let Some(title) = balloon.title {
             out_s.push_str("<div class=\"image_title\">");
@@ -1537,19 +2389,34 @@ impl<'a> HTML<'a> {
 #[cfg(test)]
 mod tests {
     use std::{
+        cell::RefCell,
         collections::HashMap,
-        env::{current_dir, set_var},
+        env::{current_dir, set_var, temp_dir},
+        fs::{create_dir_all, read_to_string, remove_dir_all, File},
+        io::BufWriter,
         path::PathBuf,
     };
 
+    #[cfg(target_os = "linux")]
+    use std::os::unix::fs::symlink;
+
     use crate::{
-        app::attachment_manager::AttachmentManager, exporters::exporter::Writer, Config, Exporter,
-        Options, HTML,
+        app::{
+            attachment_manager::{AttachmentManager, DEFAULT_COPY_RETRIES},
+            error::RuntimeError,
+            export_type::{DayOneGranularity, DirectoryLayout, SplitBy},
+            options::{DEFAULT_HTML_LANG, DEFAULT_IRC_TIMESTAMP_FORMAT},
+            timestamp_format::TimestampFormat,
+        },
+        exporters::{exporter::Writer, html::FOOTER},
+        Config, Exporter, Options, HTML,
     };
     use imessage_database::{
         tables::{
             attachment::Attachment,
+            chat::Chat,
             messages::Message,
+            schema::Schema,
             table::{get_connection, ME},
         },
         util::{
@@ -1570,8 +2437,12 @@ mod tests {
             date: i64::default(),
             date_read: i64::default(),
             date_delivered: i64::default(),
+            date_played: 0,
             is_from_me: false,
             is_read: false,
+            is_forward: false,
+            is_spam: false,
+            was_delivered_quietly: false,
             item_type: 0,
             other_handle: 0,
             share_status: false,
@@ -1585,6 +2456,7 @@ mod tests {
             thread_originator_guid: None,
             thread_originator_part: None,
             date_edited: 0,
+            schedule_type: 0,
             chat_id: None,
             num_attachments: 0,
             deleted_from: None,
@@ -1606,16 +2478,67 @@ mod tests {
             no_lazy: false,
             custom_name: None,
             use_caller_id: false,
+            show_my_number: false,
             platform: Platform::macOS,
             ignore_disk_space: false,
+            show_removed_reactions: false,
+            limit: None,
+            limit_per_chat: None,
+            merge_services: false,
+            dayone_granularity: DayOneGranularity::default(),
+            copy_retries: DEFAULT_COPY_RETRIES,
+            timestamp_format: TimestampFormat::default(),
+            report: None,
+            txt_template: None,
+            manifest: false,
+            animate_effects: false,
+            participants_only: None,
+            exclude_orphaned: false,
+            only_orphaned: false,
+            media_grid: false,
+            show_receipts: false,
+            exif_sidecars: false,
+            post_export_command: None,
+            mask_text: false,
+            compact_html: false,
+            split_by: None,
+            warc: false,
+            no_attachments: false,
+            collapse_reactions: None,
+            mark_failed_transfers: false,
+            message_guid: None,
+            normalize_newlines: false,
+            html_lang: DEFAULT_HTML_LANG.to_string(),
+            css: None,
+            directory_layout: DirectoryLayout::default(),
+            include_empty_chats: false,
+            print_friendly: false,
+            since_last_export: false,
+            reset_last_export: false,
+            only_reported: false,
+            number_messages: false,
+            emoji_shortcodes: false,
+            mime_overrides: HashMap::new(),
+            flatten_groups: false,
+            extract_media_only: false,
+            annotations: None,
+            dump_typedstream: None,
+            irc_timestamp_format: DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+            alt_text: false,
+            reaction_context: false,
+            quiet_delivery: false,
+            copy_db_first: false,
+            tokenize: false,
         }
     }
 
     pub(super) fn fake_config(options: Options) -> Config {
         let db = get_connection(&options.get_db_path()).unwrap();
+        let schema = Schema::detect(&db).unwrap();
         Config {
             chatrooms: HashMap::new(),
             real_chatrooms: HashMap::new(),
+            merged_chatrooms: HashMap::new(),
             chatroom_participants: HashMap::new(),
             participants: HashMap::new(),
             real_participants: HashMap::new(),
@@ -1623,7 +2546,11 @@ mod tests {
             options,
             offset: get_offset(),
             db,
+            schema,
             converter: None,
+            attachment_paths: RefCell::new(HashMap::new()),
+            annotations: HashMap::new(),
+            db_copy: None,
         }
     }
 
@@ -1637,7 +2564,10 @@ mod tests {
             total_bytes: 100,
             is_sticker: false,
             hide_attachment: 0,
+            transfer_state: 5,
+            is_outgoing: false,
             copied_path: None,
+            description: None,
         }
     }
 
@@ -1649,6 +2579,48 @@ mod tests {
         assert_eq!(exporter.files.len(), 0);
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn cant_write_headers_to_full_disk() {
+        let mut options = fake_options();
+        options.export_path = temp_dir().join("imessage-exporter-html-full-disk");
+        let _ = remove_dir_all(&options.export_path);
+        create_dir_all(&options.export_path).unwrap();
+        let mut config = fake_config(options);
+
+        let chat = Chat {
+            rowid: 1,
+            chat_identifier: String::from("+15558675309"),
+            service_name: Some(String::from("iMessage")),
+            display_name: Some(String::from("Full Disk")),
+            first_message_date: None,
+            last_message_date: None,
+            has_imessage: true,
+            has_sms: false,
+        };
+        config.chatrooms.insert(1, chat);
+        config.real_chatrooms.insert(1, 1);
+
+        let mut message = blank();
+        message.chat_id = Some(1);
+
+        // Every write to the conversation file fails as if the disk were full
+        let path = config
+            .options
+            .export_path
+            .join(config.message_filename(&config.chatrooms[&1], &message))
+            .with_extension("html");
+        symlink("/dev/full", &path).unwrap();
+
+        // A stylesheet larger than the write buffer makes the headers reach the file as they are written
+        let mut exporter = HTML::new(&config).unwrap();
+        exporter.custom_css = Some(".message { color: red; }\n".repeat(1000));
+        let result = exporter.get_or_create_file(&message).map(|_| ());
+        remove_dir_all(&config.options.export_path).unwrap();
+
+        assert!(matches!(result, Err(RuntimeError::DiskError(_))));
+    }
+
     #[test]
     fn can_get_time_valid() {
         // Set timezone to PST for consistent Local time
@@ -1676,93 +2648,825 @@ mod tests {
     }
 
     #[test]
-    fn can_get_time_invalid() {
+    fn can_get_receipt_read() {
         // Set timezone to PST for consistent Local time
         set_var("TZ", "PST");
 
         // Create exporter
-        let options = fake_options();
+        let mut options = fake_options();
+        options.show_receipts = true;
         let config = fake_config(options);
         let exporter = HTML::new(&config).unwrap();
 
         // Create fake message
         let mut message = blank();
+        message.is_from_me = true;
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        // May 17, 2022  8:29:42 PM
+        message.date_delivered = 674526582885055488;
         // May 17, 2022  9:30:31 PM
-        message.date = 674530231992568192;
-        // May 17, 2022  9:30:31 PM
-        message.date_delivered = 674530231992568192;
-        // Wed May 18 2022 02:36:24 GMT+0000
-        message.date_read = 674526582885055488;
-        assert_eq!(exporter.get_time(&message), "May 17, 2022  6:30:31 PM");
+        message.date_read = 674530231992568192;
+
+        assert_eq!(
+            exporter.get_receipt(&message),
+            Some(
+                "<span class=\"receipt\" title=\"May 17, 2022  6:30:31 PM\">Read</span>"
+                    .to_string()
+            )
+        );
     }
 
     #[test]
-    fn can_add_line_no_indent() {
+    fn can_get_receipt_delivered() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
         // Create exporter
-        let options = fake_options();
+        let mut options = fake_options();
+        options.show_receipts = true;
         let config = fake_config(options);
         let exporter = HTML::new(&config).unwrap();
 
-        // Create sample data
-        let mut s = String::new();
-        exporter.add_line(&mut s, "hello world", "", "");
+        // Create fake message
+        let mut message = blank();
+        message.is_from_me = true;
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        // May 17, 2022  8:29:42 PM
+        message.date_delivered = 674526582885055488;
 
-        assert_eq!(s, "hello world\n".to_string());
+        assert_eq!(
+            exporter.get_receipt(&message),
+            Some(
+                "<span class=\"receipt\" title=\"May 17, 2022  5:29:42 PM\">Delivered</span>"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn cant_get_receipt_received() {
+        // Create exporter
+        let mut options = fake_options();
+        options.show_receipts = true;
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        // Create fake message
+        let mut message = blank();
+        // May 17, 2022  9:30:31 PM
+        message.date_read = 674530231992568192;
+
+        assert_eq!(exporter.get_receipt(&message), None);
+    }
+
+    #[test]
+    fn cant_get_receipt_disabled() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        // Create fake message
+        let mut message = blank();
+        message.is_from_me = true;
+        // May 17, 2022  9:30:31 PM
+        message.date_read = 674530231992568192;
+
+        assert_eq!(exporter.get_receipt(&message), None);
+    }
+
+    #[test]
+    fn can_get_time_scheduled() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        // Create fake message
+        let mut message = blank();
+        // May 17, 2022  9:30:31 PM
+        message.date = 674530231992568192;
+        message.schedule_type = 2;
+        assert_eq!(
+            exporter.get_time(&message),
+            "Scheduled for May 17, 2022  6:30:31 PM"
+        );
+    }
+
+    #[test]
+    fn can_get_time_invalid() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        // Create fake message
+        let mut message = blank();
+        // May 17, 2022  9:30:31 PM
+        message.date = 674530231992568192;
+        // May 17, 2022  9:30:31 PM
+        message.date_delivered = 674530231992568192;
+        // Wed May 18 2022 02:36:24 GMT+0000
+        message.date_read = 674526582885055488;
+        assert_eq!(exporter.get_time(&message), "May 17, 2022  6:30:31 PM");
+    }
+
+    #[test]
+    fn can_get_time_relative() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.timestamp_format = TimestampFormat::Relative;
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        // Create fake message
+        let mut message = blank();
+        // May 17, 2022  9:30:31 PM
+        message.date = 674530231992568192;
+
+        let time = exporter.get_time(&message);
+        assert!(time.ends_with(" years ago"));
+        assert!(!time.contains("May 17, 2022"));
+    }
+
+    #[test]
+    fn can_get_time_relative_both() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.timestamp_format = TimestampFormat::Both;
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        // Create fake message
+        let mut message = blank();
+        // May 17, 2022  9:30:31 PM
+        message.date = 674530231992568192;
+
+        let time = exporter.get_time(&message);
+        assert!(time.starts_with("May 17, 2022  6:30:31 PM ("));
+        assert!(time.ends_with(" years ago)"));
+    }
+
+    #[test]
+    fn can_add_line_no_indent() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        // Create sample data
+        let mut s = String::new();
+        exporter.add_line(&mut s, "hello world", "", "");
+
+        assert_eq!(s, "hello world\n".to_string());
+    }
+
+    #[test]
+    fn can_add_line() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        // Create sample data
+        let mut s = String::new();
+        exporter.add_line(&mut s, "hello world", "  ", "");
+
+        assert_eq!(s, "  hello world\n".to_string());
+    }
+
+    #[test]
+    fn can_add_line_pre_post() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        // Create sample data
+        let mut s = String::new();
+        exporter.add_line(&mut s, "hello world", "<div>", "</div>");
+
+        assert_eq!(s, "<div>hello world</div>\n".to_string());
+    }
+
+    #[test]
+    fn can_format_html_invisible_ink_revealed() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+        message.expressive_send_style_id =
+            Some("com.apple.MobileSMS.expressivesend.invisibleink".to_string());
+        message.date_played = 674526582885055488;
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n<span class=\"expressive\">Sent with Invisible Ink <span class=\"expressive_state\">(revealed)</span></span>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_annotation() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config.annotations.insert(
+            "annotated".to_string(),
+            "Confirms the <meeting> time".to_string(),
+        );
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.text = Some("Hello world".to_string());
+        message.guid = "annotated".to_string();
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        assert!(actual
+            .contains("<aside class=\"annotation\">Confirms the &lt;meeting&gt; time</aside>"));
+
+        // Messages without a note are not annotated
+        message.guid = "other".to_string();
+        let actual = exporter.format_message(&message, 0).unwrap();
+        assert!(!actual.contains("annotation"));
+    }
+
+    #[test]
+    fn can_format_html_from_me_normal() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_from_me_masked() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.mask_text = true;
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">█████ █████</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_from_me_compact() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.compact_html = true;
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\"><div class=\"sent iMessage\"><p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span> <span class=\"sender\">Me</span></p><hr><div class=\"message_part\"><span class=\"bubble\">Hello world</span></div></div></div>";
+
+        assert_eq!(actual, expected);
+        assert!(!actual.contains('\n'));
+    }
+
+    #[test]
+    fn can_format_html_message_with_html() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("<table></table>".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">&lt;table&gt;&lt;/table&gt;</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_message_big_emoji() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("🎉👍🏽".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble big-emoji\">🎉👍🏽</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_message_grouped() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let mut exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+
+        // The previous message was sent by the same sender a minute earlier
+        exporter
+            .last_sender
+            .insert(None, (String::from("Me"), 674526522885055488));
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage grouped\">\n<p>\n</p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_message_not_grouped_other_sender() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let mut exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+
+        // The previous message was sent by someone else a minute earlier
+        exporter
+            .last_sender
+            .insert(None, (String::from("Sample Contact"), 674526522885055488));
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_message_not_grouped_outside_window() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let mut exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+
+        // The previous message was sent by the same sender an hour earlier
+        exporter
+            .last_sender
+            .insert(None, (String::from("Me"), 674522982885055488));
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_group_media_grid() {
+        // Create exporter
+        let mut options = fake_options();
+        options.media_grid = true;
+        let config = fake_config(options);
+        let mut exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("\u{FFFC}".to_string());
+        message.num_attachments = 1;
+        message.is_from_me = true;
+
+        // The first attachment-only message opens a grid
+        assert_eq!(exporter.update_media_grid(&message, None), (false, true));
+        exporter
+            .last_sender
+            .insert(None, (String::from("Me"), message.date));
+
+        // The next attachment-only message from the same sender joins it
+        message.date = 674526612885055488;
+        assert_eq!(exporter.update_media_grid(&message, None), (false, false));
+
+        // A text message closes it
+        message.text = Some("Hello world".to_string());
+        message.num_attachments = 0;
+        assert_eq!(exporter.update_media_grid(&message, None), (true, false));
+        assert!(exporter.media_grids.is_empty());
+    }
+
+    #[test]
+    fn can_split_media_grid_other_sender() {
+        // Create exporter
+        let mut options = fake_options();
+        options.media_grid = true;
+        let config = fake_config(options);
+        let mut exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("\u{FFFC}".to_string());
+        message.num_attachments = 1;
+        message.is_from_me = true;
+
+        // The previous attachment-only message was sent by someone else
+        exporter.media_grids.insert(None, None);
+        exporter
+            .last_sender
+            .insert(None, (String::from("Sample Contact"), 674526522885055488));
+
+        assert_eq!(exporter.update_media_grid(&message, None), (true, true));
+    }
+
+    #[test]
+    fn can_write_headers_lang() {
+        let dir = temp_dir().join("imessage-exporter-lang-test");
+        create_dir_all(&dir).unwrap();
+        let path = dir.join("lang.html");
+        let mut buf = BufWriter::new(File::create(&path).unwrap());
+
+        HTML::write_headers(&mut buf, "pt-BR", None, false).unwrap();
+        drop(buf);
+
+        assert!(read_to_string(&path)
+            .unwrap()
+            .starts_with("<html lang=\"pt-BR\">\n<head>\n"));
+    }
+
+    #[test]
+    fn can_write_headers_custom_css() {
+        let dir = temp_dir().join("imessage-exporter-css-test");
+        create_dir_all(&dir).unwrap();
+        let path = dir.join("css.html");
+        let mut buf = BufWriter::new(File::create(&path).unwrap());
+
+        HTML::write_headers(&mut buf, "en", Some("body { color: red; }"), false).unwrap();
+        drop(buf);
+
+        let written = read_to_string(&path).unwrap();
+        assert!(written.ends_with("\nbody { color: red; }\n</style>\n</head>\n<body>\n"));
+    }
+
+    #[test]
+    fn can_write_headers_print_friendly() {
+        let dir = temp_dir().join("imessage-exporter-print-test");
+        create_dir_all(&dir).unwrap();
+        let path = dir.join("print.html");
+        let mut buf = BufWriter::new(File::create(&path).unwrap());
+
+        HTML::write_headers(&mut buf, "en", Some("body { color: red; }"), true).unwrap();
+        drop(buf);
+
+        // Custom styles still come last so they can override the print styles
+        let written = read_to_string(&path).unwrap();
+        assert!(written.contains("@media print"));
+        assert!(written.find("@media print") < written.find("body { color: red; }"));
+    }
+
+    #[test]
+    fn can_detect_new_day() {
+        let options = fake_options();
+        let config = fake_config(options);
+        let mut exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+
+        // The first message in a file does not start a new page
+        assert!(!exporter.is_new_day(&message, None));
+
+        // A message later the same day does not either
+        message.date = 674526612885055488;
+        assert!(!exporter.is_new_day(&message, None));
+
+        // A message the next day does
+        message.date = 674612982885055488;
+        assert!(exporter.is_new_day(&message, None));
+
+        // Each file tracks its own day
+        assert!(!exporter.is_new_day(&message, Some("other".to_string())));
+    }
+
+    #[test]
+    fn can_close_previous_split() {
+        let mut options = fake_options();
+        options.split_by = Some(SplitBy::Day);
+        let config = fake_config(options);
+        let mut exporter = HTML::new(&config).unwrap();
+
+        let dir = temp_dir().join("imessage-exporter-split-test");
+        create_dir_all(&dir).unwrap();
+        let path = dir.join("Chat-2022-05-17.html");
+        let file = File::create(&path).unwrap();
+        exporter
+            .files
+            .insert("Chat-2022-05-17".to_string(), BufWriter::new(file));
+
+        // Moving to the same file keeps it open
+        exporter
+            .close_previous_split("Chat".to_string(), "Chat-2022-05-17")
+            .unwrap();
+        exporter
+            .close_previous_split("Chat".to_string(), "Chat-2022-05-17")
+            .unwrap();
+        assert!(exporter.files.contains_key("Chat-2022-05-17"));
+
+        // Moving to the next day finishes the previous file
+        exporter
+            .close_previous_split("Chat".to_string(), "Chat-2022-05-18")
+            .unwrap();
+        assert!(!exporter.files.contains_key("Chat-2022-05-17"));
+        assert_eq!(read_to_string(&path).unwrap(), FOOTER);
+        assert_eq!(
+            exporter.split_files.get("Chat").map(String::as_str),
+            Some("Chat-2022-05-18")
+        );
+    }
+
+    #[test]
+    fn cant_group_across_split() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let mut options = fake_options();
+        options.split_by = Some(SplitBy::Day);
+        let config = fake_config(options);
+        let mut exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.is_from_me = true;
+        // May 17, 2022  11:59:42 PM
+        message.date = 674526582885055488 + 23400000000000;
+        exporter
+            .last_sender
+            .insert(None, (String::from("Me"), message.date));
+
+        // A minute later, but on the next day
+        message.date += 60000000000;
+        assert!(!exporter.is_grouped(&message));
+    }
+
+    #[test]
+    fn can_skip_media_grid_disabled() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let mut exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.text = Some("\u{FFFC}".to_string());
+        message.num_attachments = 1;
+
+        assert_eq!(exporter.update_media_grid(&message, None), (false, false));
+        assert!(exporter.media_grids.is_empty());
+    }
+
+    #[test]
+    fn can_format_html_from_me_normal_forwarded() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+        message.is_forward = true;
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<span class=\"forwarded\">Forwarded</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_numbered() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.number_messages = true;
+        let config = fake_config(options);
+        let mut exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+
+        exporter.number_message(&message);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        assert!(actual.contains(
+            "<p><span class=\"msg-number\">#1</span> <span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n"
+        ));
+
+        // Replies rendered in a thread are not numbered
+        let actual = exporter.format_message(&message, 1).unwrap();
+        assert!(!actual.contains("msg-number"));
+    }
+
+    #[test]
+    fn can_format_html_reported_junk() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("You won a prize!".to_string());
+        message.is_spam = true;
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        assert!(actual.contains("<span class=\"junk\">(reported as junk)</span></p>\n"));
+    }
+
+    #[test]
+    fn can_format_html_delivered_quietly() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.quiet_delivery = true;
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Are you asleep?".to_string());
+        message.was_delivered_quietly = true;
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        assert!(actual.contains("<span class=\"quiet\">(delivered quietly)</span></p>\n"));
     }
 
     #[test]
-    fn can_add_line() {
+    fn can_format_html_animated_effect() {
         // Create exporter
-        let options = fake_options();
+        let mut options = fake_options();
+        options.animate_effects = true;
         let config = fake_config(options);
         let exporter = HTML::new(&config).unwrap();
 
-        // Create sample data
-        let mut s = String::new();
-        exporter.add_line(&mut s, "hello world", "  ", "");
+        let mut message = blank();
+        message.text = Some("Hello world".to_string());
+        message.expressive_send_style_id =
+            Some("com.apple.messages.effect.CKConfettiEffect".to_string());
 
-        assert_eq!(s, "  hello world\n".to_string());
+        let actual = exporter.format_message(&message, 0).unwrap();
+
+        assert!(actual.starts_with("<div class=\"message effect_confetti\">\n"));
+        // The text label is kept as a fallback
+        assert!(actual.contains("<span class=\"expressive\">Sent with Confetti</span>"));
     }
 
     #[test]
-    fn can_add_line_pre_post() {
+    fn can_format_html_effect_not_animated() {
         // Create exporter
         let options = fake_options();
         let config = fake_config(options);
         let exporter = HTML::new(&config).unwrap();
 
-        // Create sample data
-        let mut s = String::new();
-        exporter.add_line(&mut s, "hello world", "<div>", "</div>");
+        let mut message = blank();
+        message.text = Some("Hello world".to_string());
+        message.expressive_send_style_id =
+            Some("com.apple.messages.effect.CKConfettiEffect".to_string());
 
-        assert_eq!(s, "<div>hello world</div>\n".to_string());
+        let actual = exporter.format_message(&message, 0).unwrap();
+
+        assert!(actual.starts_with("<div class=\"message\">\n"));
+        assert!(actual.contains("<span class=\"expressive\">Sent with Confetti</span>"));
     }
 
     #[test]
-    fn can_format_html_from_me_normal() {
-        // Set timezone to PST for consistent Local time
-        set_var("TZ", "PST");
-
+    fn can_format_html_animated_effect_unsupported() {
         // Create exporter
-        let options = fake_options();
+        let mut options = fake_options();
+        options.animate_effects = true;
         let config = fake_config(options);
         let exporter = HTML::new(&config).unwrap();
 
         let mut message = blank();
-        // May 17, 2022  8:29:42 PM
-        message.date = 674526582885055488;
         message.text = Some("Hello world".to_string());
-        message.is_from_me = true;
-        message.chat_id = Some(0);
+        message.expressive_send_style_id =
+            Some("com.apple.messages.effect.CKEchoEffect".to_string());
 
         let actual = exporter.format_message(&message, 0).unwrap();
-        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
 
-        assert_eq!(actual, expected);
+        assert!(actual.starts_with("<div class=\"message\">\n"));
     }
 
     #[test]
-    fn can_format_html_message_with_html() {
+    fn can_format_html_from_me_normal_deleted() {
         // Set timezone to PST for consistent Local time
         set_var("TZ", "PST");
 
@@ -1773,19 +3477,19 @@ mod tests {
 
         let mut message = blank();
         // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
         message.date = 674526582885055488;
-        message.text = Some("<table></table>".to_string());
         message.is_from_me = true;
-        message.chat_id = Some(0);
+        message.deleted_from = Some(0);
 
         let actual = exporter.format_message(&message, 0).unwrap();
-        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">&lt;table&gt;&lt;/table&gt;</span>\n</div>\n</div>\n</div>\n";
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<span class=\"deleted\">This message was deleted from the conversation!</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
     }
 
     #[test]
-    fn can_format_html_from_me_normal_deleted() {
+    fn can_format_html_from_me_normal_read() {
         // Set timezone to PST for consistent Local time
         set_var("TZ", "PST");
 
@@ -1795,20 +3499,24 @@ mod tests {
         let exporter = HTML::new(&config).unwrap();
 
         let mut message = blank();
-        // May 17, 2022  8:29:42 PM
         message.text = Some("Hello world".to_string());
+        // May 17, 2022  8:29:42 PM
         message.date = 674526582885055488;
+        // May 17, 2022  8:29:42 PM
+        message.date_delivered = 674526582885055488;
+        // May 17, 2022  9:30:31 PM
+        message.date_read = 674530231992568192;
         message.is_from_me = true;
-        message.deleted_from = Some(0);
 
         let actual = exporter.format_message(&message, 0).unwrap();
-        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<span class=\"deleted\">This message was deleted from the conversation!</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+        let expected =
+            "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM (Read by them after 1 hour, 49 seconds)</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
     }
 
     #[test]
-    fn can_format_html_from_me_normal_read() {
+    fn can_format_html_from_me_normal_delivered() {
         // Set timezone to PST for consistent Local time
         set_var("TZ", "PST");
 
@@ -1827,7 +3535,7 @@ mod tests {
 
         let actual = exporter.format_message(&message, 0).unwrap();
         let expected =
-            "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM (Read by them after 1 hour, 49 seconds)</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+            "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM (Delivered after 1 hour, 49 seconds)</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
     }
@@ -2007,12 +3715,177 @@ mod tests {
         message.associated_message_type = Some(2000);
         message.associated_message_guid = Some("fake_guid".to_string());
 
-        let actual = exporter.format_reaction(&message).unwrap();
+        let actual = exporter.format_reaction(&message, None).unwrap();
         let expected = "<span class=\"reaction\"><b>Loved</b> by Me</span>";
 
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn can_format_html_reaction_target() {
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.associated_message_type = Some(2001);
+        message.associated_message_guid = Some("p:0/fake_guid".to_string());
+
+        let actual = exporter.format_reaction(&message, Some("\"Hi\"")).unwrap();
+        let expected = "<span class=\"reaction\"><b>Liked</b> &quot;Hi&quot; by Me</span>";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_reaction_on_image() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter, skipping the attachment lookup since the test database has no attachments
+        let mut options = fake_options();
+        options.no_attachments = true;
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
+        // An image message with a single attachment part
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.guid = "image_guid".to_string();
+        message.text = Some("\u{FFFC}".to_string());
+        message.num_attachments = 1;
+
+        // A reaction to the image part
+        let mut reaction = blank();
+        reaction.date = 674526612885055488;
+        reaction.associated_message_type = Some(2000);
+        reaction.associated_message_guid = Some("p:0/image_guid".to_string());
+
+        config.reactions.insert(
+            "image_guid".to_string(),
+            HashMap::from([(0, vec![reaction])]),
+        );
+        let exporter = HTML::new(&config).unwrap();
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+
+        // The reaction is overlaid on the attachment inside its message part
+        assert!(actual.contains("<hr><div class=\"message_part attachment_part\">"));
+        assert!(actual.contains("<div class=\"attachment_reactions\"><div class=\"reaction\"><span class=\"reaction\"><b>Loved</b> by Me</span></div>\n</div>\n</div>\n"));
+        assert!(!actual.contains("Reactions:"));
+    }
+
+    #[test]
+    fn can_format_html_reaction_on_text() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.guid = "text_guid".to_string();
+        message.text = Some("Hello world".to_string());
+
+        let mut reaction = blank();
+        reaction.date = 674526612885055488;
+        reaction.associated_message_type = Some(2000);
+        reaction.associated_message_guid = Some("p:0/text_guid".to_string());
+
+        config.reactions.insert(
+            "text_guid".to_string(),
+            HashMap::from([(0, vec![reaction])]),
+        );
+        let exporter = HTML::new(&config).unwrap();
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+
+        // Reactions to text are listed below the message part
+        assert!(actual.contains("</div>\n<div class=\"reactions\"><hr><p>Reactions:</p>\n<div class=\"reaction\"><span class=\"reaction\"><b>Loved</b> by Me</span></div>\n\n</div>\n"));
+        assert!(!actual.contains("attachment_reactions"));
+    }
+
+    #[test]
+    fn can_format_html_reactions_collapsed() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.collapse_reactions = Some(1);
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.guid = "text_guid".to_string();
+        message.text = Some("Hello world".to_string());
+
+        let mut loved = blank();
+        loved.date = 674526612885055488;
+        loved.associated_message_type = Some(2000);
+        loved.associated_message_guid = Some("p:0/text_guid".to_string());
+
+        let mut liked = blank();
+        liked.date = 674526642885055488;
+        liked.associated_message_type = Some(2001);
+        liked.associated_message_guid = Some("p:0/text_guid".to_string());
+
+        config.reactions.insert(
+            "text_guid".to_string(),
+            HashMap::from([(0, vec![loved, liked])]),
+        );
+        let exporter = HTML::new(&config).unwrap();
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+
+        // More reactions than the threshold are collapsed under a summary
+        assert!(actual.contains("<div class=\"reactions\"><hr><p>Reactions:</p>\n<details><summary>+2 reactions</summary>\n<div class=\"reaction\"><span class=\"reaction\"><b>Loved</b> by Me</span></div>\n<div class=\"reaction\"><span class=\"reaction\"><b>Liked</b> by Me</span></div>\n</details>\n</div>\n"));
+    }
+
+    #[test]
+    fn can_format_html_reactions_under_threshold() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.collapse_reactions = Some(1);
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.guid = "text_guid".to_string();
+        message.text = Some("Hello world".to_string());
+
+        let mut reaction = blank();
+        reaction.date = 674526612885055488;
+        reaction.associated_message_type = Some(2000);
+        reaction.associated_message_guid = Some("p:0/text_guid".to_string());
+
+        config.reactions.insert(
+            "text_guid".to_string(),
+            HashMap::from([(0, vec![reaction])]),
+        );
+        let exporter = HTML::new(&config).unwrap();
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+
+        assert!(!actual.contains("<details>"));
+        assert!(actual.contains("<b>Loved</b> by Me"));
+    }
+
     #[test]
     fn can_format_html_reaction_them() {
         // Set timezone to PST for consistent Local time
@@ -2033,12 +3906,92 @@ mod tests {
         message.associated_message_guid = Some("fake_guid".to_string());
         message.handle_id = Some(999999);
 
-        let actual = exporter.format_reaction(&message).unwrap();
+        let actual = exporter.format_reaction(&message, None).unwrap();
         let expected = "<span class=\"reaction\"><b>Loved</b> by Sample Contact</span>";
 
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn can_format_html_digital_touch() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.item_type = 0;
+        message.balloon_bundle_id = Some("com.apple.DigitalTouchBalloonProvider".to_string());
+
+        let actual = exporter.format_app(&message, &mut vec![], "").unwrap();
+        let expected = "Digital Touch messages are not yet supported!";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_removed_reaction_hidden() {
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config
+            .participants
+            .insert(999999, "Sample Contact".to_string());
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.associated_message_type = Some(3000);
+        message.associated_message_guid = Some("fake_guid".to_string());
+        message.handle_id = Some(999999);
+
+        let actual = exporter.format_reaction(&message, None).unwrap();
+        let expected = "";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_removed_reaction_shown() {
+        // Create exporter
+        let mut options = fake_options();
+        options.show_removed_reactions = true;
+        let mut config = fake_config(options);
+        config
+            .participants
+            .insert(999999, "Sample Contact".to_string());
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.associated_message_type = Some(3000);
+        message.associated_message_guid = Some("fake_guid".to_string());
+        message.handle_id = Some(999999);
+
+        let actual = exporter.format_reaction(&message, None).unwrap();
+        let expected =
+            "<span class=\"reaction\">Sample Contact removed their <b>Loved</b> reaction</span>";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_sticker_tapback() {
+        // Create exporter
+        let mut options = fake_options();
+        options.no_attachments = true;
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.associated_message_type = Some(2007);
+        message.associated_message_guid = Some("p:0/fake_guid".to_string());
+
+        let actual = exporter.format_reaction(&message, None).unwrap();
+        let expected = "<span class=\"reaction\">Sticker from Me: [attachment]</span>";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_html_started_sharing_location_me() {
         // Set timezone to PST for consistent Local time
@@ -2151,6 +4104,51 @@ mod tests {
         assert_eq!(actual, "<img src=\"a/b/c/d.jpg\" loading=\"lazy\">");
     }
 
+    #[test]
+    fn can_format_html_attachment_alt_text() {
+        // Create exporter
+        let mut options = fake_options();
+        options.alt_text = true;
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let message = blank();
+
+        let mut attachment = fake_attachment();
+        attachment.description = Some("Cat".to_string());
+
+        let actual = exporter
+            .format_attachment(&mut attachment, &message)
+            .unwrap();
+
+        assert_eq!(
+            actual,
+            "<img src=\"a/b/c/d.jpg\" alt=\"Cat\" loading=\"lazy\">"
+        );
+    }
+
+    #[test]
+    fn can_format_html_attachment_alt_text_filename() {
+        // Create exporter
+        let mut options = fake_options();
+        options.alt_text = true;
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let message = blank();
+
+        let mut attachment = fake_attachment();
+
+        let actual = exporter
+            .format_attachment(&mut attachment, &message)
+            .unwrap();
+
+        assert_eq!(
+            actual,
+            "<img src=\"a/b/c/d.jpg\" alt=\"d.jpg\" loading=\"lazy\">"
+        );
+    }
+
     #[test]
     fn can_format_html_attachment_macos_invalid() {
         // Create exporter
@@ -2228,16 +4226,8 @@ mod tests {
         attachment.copied_path = Some(PathBuf::from(sticker_path.to_string_lossy().to_string()));
 
         let actual = exporter.format_sticker(&mut attachment, &message);
-
-        assert_eq!(actual, "<img src=\"imessage-database/test_data/stickers/outline.heic\" loading=\"lazy\">\n<div class=\"sticker_effect\">Sent with Outline effect</div>");
-
-        // Remove the file created by the constructor for this test
-        let orphaned_path = current_dir()
-            .unwrap()
-            .parent()
-            .unwrap()
-            .join("orphaned.html");
-        std::fs::remove_file(orphaned_path).unwrap();
+
+        assert_eq!(actual, "<img src=\"imessage-database/test_data/stickers/outline.heic\" loading=\"lazy\">\n<div class=\"sticker_effect\">Sent with Outline effect</div>");
     }
 }
 
@@ -2251,6 +4241,7 @@ mod balloon_format_tests {
         app::AppMessage,
         app_store::AppStoreMessage,
         collaboration::CollaborationMessage,
+        game::GameMessage,
         music::MusicMessage,
         placemark::{Placemark, PlacemarkMessage},
         url::URLMessage,
@@ -2281,6 +4272,82 @@ mod balloon_format_tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn can_collect_warc_preview_urls() {
+        // Create exporter
+        let mut options = fake_options();
+        options.warc = true;
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let balloon = URLMessage {
+            title: Some("title"),
+            summary: Some("summary"),
+            url: Some("https://example.com"),
+            original_url: Some("https://example.com"),
+            item_type: Some("item_type"),
+            images: vec!["https://example.com/preview.jpg", "local.jpg"],
+            icons: vec!["icons"],
+            site_name: Some("site_name"),
+            placeholder: false,
+        };
+
+        exporter.format_url(&balloon, &blank());
+
+        assert_eq!(
+            exporter.preview_urls.borrow().iter().collect::<Vec<_>>(),
+            vec!["https://example.com/preview.jpg"]
+        );
+    }
+
+    #[test]
+    fn can_format_conversation_header_identifier() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let chat = imessage_database::tables::chat::Chat {
+            rowid: -1,
+            chat_identifier: String::from("chat<123>"),
+            service_name: None,
+            display_name: None,
+            first_message_date: None,
+            last_message_date: None,
+            has_imessage: false,
+            has_sms: false,
+        };
+
+        assert_eq!(
+            exporter.conversation_header(&chat),
+            "<div class=\"conversation-header\">\n<span class=\"chat-id\">Chat ID chat&lt;123&gt;</span>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn can_format_conversation_header_services() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let chat = imessage_database::tables::chat::Chat {
+            rowid: -1,
+            chat_identifier: String::from("chat123"),
+            service_name: None,
+            display_name: None,
+            first_message_date: None,
+            last_message_date: None,
+            has_imessage: true,
+            has_sms: true,
+        };
+
+        assert_eq!(
+            exporter.conversation_header(&chat),
+            "<div class=\"conversation-header\">\n<span class=\"chat-id\">Chat ID chat123</span>\n<span class=\"services\">Sent with iMessage and SMS</span>\n</div>\n"
+        );
+    }
+
     #[test]
     fn can_format_html_url_no_lazy() {
         // Create exporter
@@ -2454,6 +4521,52 @@ mod balloon_format_tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn can_format_html_journal() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let balloon = AppMessage {
+            image: None,
+            url: Some("?suggestionType=workout&suggestionID=FAKE_SUGGESTION_ID"),
+            title: Some("Morning Walk"),
+            subtitle: None,
+            caption: Some("Journal Suggestion"),
+            subcaption: Some("Workout"),
+            trailing_caption: None,
+            trailing_subcaption: None,
+            app_name: Some("Journal"),
+            ldtext: Some("Shared a Journal suggestion"),
+        };
+
+        let expected = exporter.format_journal(&balloon, &blank());
+        let actual = "<div class=\"app_header\"><div class=\"name\">Journal</div><div class=\"image_title\">Morning Walk</div></div><div class=\"app_footer\"><div class=\"caption\">Workout suggestion</div></div>";
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn can_format_html_game() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let balloon = GameMessage {
+            app_name: "GamePigeon",
+            game: Some("Dots & Boxes"),
+            state: Some("Your move."),
+            score: None,
+        };
+
+        let expected = exporter.format_game(&balloon, &blank());
+        let actual = "<div class=\"app_header\"><div class=\"name\">GamePigeon</div><div class=\"image_title\">Dots & Boxes</div></div><div class=\"app_footer\"><div class=\"caption\">Your move.</div></div>";
+
+        assert_eq!(expected, actual);
+    }
+
     #[test]
     fn can_format_html_check_in_timer() {
         // Set timezone to PST for consistent Local time
@@ -2615,7 +4728,38 @@ mod balloon_format_tests {
         };
 
         let expected = exporter.format_generic_app(&balloon, "bundle_id", &mut vec![], &blank());
-        let actual = "<a href=\"url\"><div class=\"app_header\"><img src=\"image\"><div class=\"name\">app_name</div><div class=\"image_title\">title</div><div class=\"image_subtitle\">subtitle</div><div class=\"ldtext\">ldtext</div></div><div class=\"app_footer\"><div class=\"caption\">caption</div><div class=\"subcaption\">subcaption</div><div class=\"trailing_caption\">trailing_caption</div><div class=\"trailing_subcaption\">trailing_subcaption</div></div></a>";
+        let actual = "<a href=\"url\"><div class=\"app_header\"><img src=\"image\"><div class=\"name\">app_name</div><div class=\"bundle_id\">bundle_id</div><div class=\"image_title\">title</div><div class=\"image_subtitle\">subtitle</div><div class=\"ldtext\">ldtext</div></div><div class=\"app_footer\"><div class=\"caption\">caption</div><div class=\"subcaption\">subcaption</div><div class=\"trailing_caption\">trailing_caption</div><div class=\"trailing_subcaption\">trailing_subcaption</div></div></a>";
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn can_format_html_generic_app_unknown_bundle_id() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let balloon = AppMessage {
+            image: None,
+            url: None,
+            title: Some("title"),
+            subtitle: None,
+            caption: None,
+            subcaption: None,
+            trailing_caption: None,
+            trailing_subcaption: None,
+            app_name: None,
+            ldtext: None,
+        };
+
+        let expected = exporter.format_generic_app(
+            &balloon,
+            "com.example.UnknownApp.MessagesExtension",
+            &mut vec![],
+            &blank(),
+        );
+        let actual = "<div class=\"app_header\"><div class=\"name\">com.example.UnknownApp.MessagesExtension</div><div class=\"image_title\">title</div></div>";
 
         assert_eq!(expected, actual);
     }
@@ -2630,7 +4774,10 @@ mod text_effect_tests {
     };
     use imessage_database::{
         message_types::text_effects::{Style, TextEffect, Unit},
-        util::typedstream::parser::TypedStreamReader,
+        util::typedstream::{
+            models::{Archivable, OutputData},
+            parser::TypedStreamReader,
+        },
     };
     use std::{
         env::{current_dir, set_var},
@@ -2664,6 +4811,23 @@ mod text_effect_tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn can_format_html_mention_me() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.destination_caller_id = Some("+15558675309".to_string());
+        assert!(config.is_my_handle("+15558675309", &message));
+
+        let expected = exporter.format_mention_me("Chris", "+15558675309");
+        let actual = "<span class=\"mention-me\" title=\"+15558675309\"><b>Chris</b></span>";
+
+        assert_eq!(expected, actual);
+    }
+
     #[test]
     fn can_format_html_link() {
         // Create exporter
@@ -2677,6 +4841,19 @@ mod text_effect_tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn can_format_html_link_escape_target() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let expected = exporter.format_link("Example", "https://example.com/\"onclick=\"");
+        let actual = "<a href=\"https://example.com/%22onclick=%22\">Example</a>";
+
+        assert_eq!(expected, actual);
+    }
+
     #[test]
     fn can_format_html_otp() {
         // Create exporter
@@ -2785,6 +4962,47 @@ mod text_effect_tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn can_format_html_rtl_end_to_end() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Test 3".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("imessage-database/test_data/typedstream/AttributedBodyTextOnly2");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        let mut components = parser.parse().unwrap();
+
+        // Change the writing direction from natural (-1) to right-to-left (1)
+        if let Some(Archivable::Object(_, data)) = components.get_mut(4) {
+            data[0] = OutputData::SignedInteger(1);
+        }
+        message.components = Some(components);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\" dir=\"rtl\">Test 3</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_html_otp_end_to_end() {
         // Set timezone to PST for consistent Local time
@@ -2855,6 +5073,51 @@ mod text_effect_tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn can_format_html_link_different_target_end_to_end() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("https://twitter.com/xxxxxxxxx/status/0000223300009216128".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("imessage-database/test_data/typedstream/URLMessage");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        let mut components = parser.parse().unwrap();
+
+        // Point the link attribute somewhere other than the displayed text
+        let url_idx = components
+            .iter()
+            .position(|item| matches!(item, Archivable::Object(class, _) if class.name == "NSURL"))
+            .unwrap();
+        if let Some(Archivable::Object(_, data)) = components.get_mut(url_idx + 1) {
+            data[0] = OutputData::String("https://example.com/target".to_string());
+        }
+        message.components = Some(components);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\"><a href=\"https://example.com/target\">https://twitter.com/xxxxxxxxx/status/0000223300009216128</a></span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_html_conversion_end_to_end() {
         // Set timezone to PST for consistent Local time
@@ -2901,9 +5164,9 @@ mod edited_tests {
 
     use super::tests::{blank, fake_config, fake_options};
 
-    use crate::{exporters::exporter::Writer, Exporter, HTML};
+    use crate::{app::error_report::IssueKind, exporters::exporter::Writer, Exporter, HTML};
     use imessage_database::{
-        message_types::edited::{EditStatus, EditedMessage, EditedMessagePart},
+        message_types::edited::{EditStatus, EditedEvent, EditedMessage, EditedMessagePart},
         util::typedstream::parser::TypedStreamReader,
     };
 
@@ -2920,6 +5183,7 @@ mod edited_tests {
         let mut message = blank();
         // May 17, 2022  8:29:42 PM
         message.date = 674526582885055488;
+        message.date_delivered = 674526582885055488;
         message.date_edited = 674530231992568192;
         message.text = Some(
             "From arbitrary byte stream:\r\u{FFFC}To native Rust data structures:\r".to_string(),
@@ -2965,6 +5229,47 @@ mod edited_tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn can_format_html_edited_indicator() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.date_edited = 674526592885055488;
+        message.text = Some("Edited message".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+        message.edited_parts = Some(EditedMessage {
+            parts: vec![EditedMessagePart {
+                status: EditStatus::Edited,
+                edit_history: vec![
+                    EditedEvent {
+                        date: 674526582885055488,
+                        text: "First message".to_string(),
+                        guid: None,
+                    },
+                    EditedEvent {
+                        date: 674526592885055488,
+                        text: "Edited message".to_string(),
+                        guid: None,
+                    },
+                ],
+            }],
+        });
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<div class=\"edited\"><table><tbody><tr><td><span class=\"timestamp\"></span></td><td>First message</td></tr></tbody><tfoot><tr><td><span class=\"timestamp\">Edited 10 seconds later</span></td><td>Edited message</td></tr></tfoot></table></div>\n</div>\n<span class=\"edited_indicator\">(edited)</span>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_html_conversion_no_edits() {
         // Set timezone to PST for consistent Local time
@@ -3000,6 +5305,13 @@ mod edited_tests {
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">From arbitrary byte stream:\r</span>\n</div>\n<hr><div class=\"message_part\">\n<span class=\"attachment_error\">Attachment does not exist!</span>\n</div>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">To native Rust data structures:\r</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
+
+        // The missing attachment is recorded in the error report
+        assert_eq!(exporter.errors.len(), 1);
+        assert_eq!(
+            exporter.errors.counts().get(&IssueKind::MissingAttachment),
+            Some(&1)
+        );
     }
 
     #[test]
@@ -3015,6 +5327,7 @@ mod edited_tests {
         let mut message = blank();
         // May 17, 2022  8:29:42 PM
         message.date = 674526582885055488;
+        message.date_delivered = 674526582885055488;
         message.date_edited = 674530231992568192;
         message.text = None;
         message.is_from_me = true;
@@ -3043,4 +5356,46 @@ mod edited_tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn can_format_html_conversion_fully_unsent_before_delivery() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.date_edited = 674530231992568192;
+        message.text = None;
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+        message.edited_parts = Some(EditedMessage {
+            parts: vec![EditedMessagePart {
+                status: EditStatus::Unsent,
+                edit_history: vec![],
+            }],
+        });
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("imessage-database/test_data/typedstream/Blank");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        message.components = parser.parse().ok();
+
+        let actual = exporter.format_announcement(&message);
+        let expected = "<div class =\"announcement\"><p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span> You unsent a message before it was delivered.</p></div>";
+
+        assert_eq!(actual, expected);
+    }
 }
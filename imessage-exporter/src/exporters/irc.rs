@@ -0,0 +1,429 @@
+use std::{
+    collections::{
+        hash_map::Entry::{Occupied, Vacant},
+        HashMap,
+    },
+    fmt::Write as _,
+    fs::{create_dir_all, File},
+    io::{BufWriter, Write},
+};
+
+use crate::{
+    app::{
+        attachment_manager::ATTACHMENT_PLACEHOLDER,
+        error::RuntimeError,
+        error_report::{ErrorReport, IssueKind},
+        progress::build_progress_bar_export,
+        runtime::{Config, ExportCounts, MessageFilter},
+    },
+    exporters::exporter::Exporter,
+};
+
+use imessage_database::{
+    error::table::TableError,
+    tables::{
+        messages::Message,
+        table::{Table, ORPHANED},
+    },
+};
+
+pub struct IRC<'a> {
+    /// Data that is setup from the application's runtime
+    pub config: &'a Config,
+    /// Handles to files we want to write logs to
+    /// Map of resolved chatroom file location to a buffered writer
+    pub files: HashMap<String, BufWriter<File>>,
+    /// Writer instance for orphaned messages
+    pub orphaned: BufWriter<File>,
+    /// Non-fatal problems encountered during the export
+    pub errors: ErrorReport,
+}
+
+impl<'a> Exporter<'a> for IRC<'a> {
+    fn new(config: &'a Config) -> Result<Self, RuntimeError> {
+        let mut orphaned = config.options.export_path.clone();
+        orphaned.push(ORPHANED);
+        orphaned.set_extension("log");
+
+        let file = File::options()
+            .append(true)
+            .create(true)
+            .open(&orphaned)
+            .map_err(|err| RuntimeError::CreateError(err, orphaned))?;
+
+        Ok(IRC {
+            config,
+            files: HashMap::new(),
+            orphaned: BufWriter::new(file),
+            errors: ErrorReport::default(),
+        })
+    }
+
+    fn iter_messages(&mut self) -> Result<(), RuntimeError> {
+        // Tell the user what we are doing
+        eprintln!(
+            "Exporting to {} as IRC logs...",
+            self.config.options.export_path.display()
+        );
+
+        // Keep track of current message ROWID
+        let mut current_message_row = -1;
+
+        // Keep track of how many messages we exported, globally and per conversation
+        let mut counts = ExportCounts::default();
+
+        // Only export conversations that include the selected participant, if any
+        let chatrooms = self.config.participant_chatrooms();
+
+        // Set up progress bar
+        let mut current_message = 0;
+        let mut total_messages =
+            Message::get_count(&self.config.db, &self.config.options.query_context)
+                .map_err(RuntimeError::DatabaseError)?;
+        if let Some(limit) = self.config.options.limit {
+            total_messages = total_messages.min(limit);
+        }
+        let pb = build_progress_bar_export(total_messages);
+
+        let mut statement = Message::stream_rows_with_schema(
+            &self.config.db,
+            &self.config.schema,
+            &self.config.options.query_context,
+        )
+        .map_err(RuntimeError::DatabaseError)?;
+
+        let messages = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .map_err(|err| RuntimeError::DatabaseError(TableError::Messages(err)))?;
+
+        for message in messages {
+            let mut msg = Message::extract(message).map_err(RuntimeError::DatabaseError)?;
+
+            // Early escape if we try and render the same message GUID twice
+            // See https://github.com/ReagentX/imessage-exporter/issues/135 for rationale
+            if msg.rowid == current_message_row {
+                current_message += 1;
+                continue;
+            }
+            current_message_row = msg.rowid;
+
+            // Reactions are not exported, so they do not count towards the limits
+            match self
+                .config
+                .filter_message(&msg, &chatrooms, &mut counts, !msg.is_reaction())
+            {
+                MessageFilter::Export => {}
+                MessageFilter::Skip => {
+                    current_message += 1;
+                    continue;
+                }
+                MessageFilter::Stop => break,
+            }
+
+            // Generate the text of the message
+            let _ = msg.generate_text(&self.config.db);
+
+            // Chat events are rendered as action lines
+            if msg.is_announcement() {
+                let action = self.format_action(&msg);
+                IRC::write_to_file(self.get_or_create_file(&msg)?, &action)?;
+            }
+            // IRC has no way to attach a reaction to a message, so reactions are skipped
+            else if !msg.is_reaction() {
+                let lines = self.format_message(&msg)?;
+                IRC::write_to_file(self.get_or_create_file(&msg)?, &lines)?;
+            }
+            current_message += 1;
+            if current_message % 99 == 0 {
+                pb.set_position(current_message);
+            }
+        }
+        pb.finish();
+
+        self.errors.write(&self.config.options.export_path)?;
+
+        Ok(())
+    }
+
+    /// Create a file for the given chat, caching it so we don't need to build it later
+    fn get_or_create_file(
+        &mut self,
+        message: &Message,
+    ) -> Result<&mut BufWriter<File>, RuntimeError> {
+        match self.config.conversation(message) {
+            Some((chatroom, _)) => {
+                let filename = self.config.filename(chatroom);
+                match self.files.entry(filename) {
+                    Occupied(entry) => Ok(entry.into_mut()),
+                    Vacant(entry) => {
+                        let mut path = self.config.options.export_path.clone();
+                        path.push(entry.key());
+                        path.set_extension("log");
+
+                        // Files can be nested in a directory when using `--directory-layout`
+                        if let Some(parent) = path.parent() {
+                            create_dir_all(parent).map_err(|err| {
+                                RuntimeError::CreateError(err, parent.to_path_buf())
+                            })?;
+                        }
+
+                        let file = File::options()
+                            .append(true)
+                            .create(true)
+                            .open(&path)
+                            .map_err(|err| RuntimeError::CreateError(err, path))?;
+
+                        Ok(entry.insert(BufWriter::new(file)))
+                    }
+                }
+            }
+            None => Ok(&mut self.orphaned),
+        }
+    }
+}
+
+impl<'a> IRC<'a> {
+    /// Render a message as `HH:MM <sender> text` lines, one for each line of text and each attachment
+    fn format_message(&self, message: &Message) -> Result<String, RuntimeError> {
+        let mut lines = vec![];
+
+        // Attachment placeholders are replaced by references to the attachments themselves
+        if let Some(text) = &message.text {
+            let text = self.config.mask(&text.replace('\u{FFFC}', "")).into_owned();
+            lines.extend(
+                text.lines()
+                    .map(str::trim_end)
+                    .filter(|line| !line.trim().is_empty())
+                    .map(String::from),
+            );
+        }
+
+        if self.config.options.no_attachments {
+            for _ in 0..message.num_attachments {
+                lines.push(ATTACHMENT_PLACEHOLDER.to_string());
+            }
+        }
+
+        let mut attachments = self
+            .config
+            .attachments(message)
+            .map_err(RuntimeError::DatabaseError)?;
+        for attachment in attachments.iter_mut() {
+            // Copy the file, if requested
+            if self
+                .config
+                .options
+                .attachment_manager
+                .handle_attachment(message, attachment, self.config)
+                .is_none()
+            {
+                self.errors.record(
+                    IssueKind::UnresolvedAttachment,
+                    &message.guid,
+                    attachment.filename(),
+                );
+                continue;
+            }
+            lines.push(format!(
+                "Attachment: {}",
+                self.config.message_attachment_path(attachment)
+            ));
+        }
+
+        let timestamp = self.timestamp(message);
+        let sender = self.config.who(
+            message.handle_id,
+            message.is_from_me(),
+            &message.destination_caller_id,
+        );
+        Ok(lines
+            .iter()
+            .map(|line| format!("{timestamp} <{sender}> {line}\n"))
+            .collect())
+    }
+
+    /// Render a chat event, such as a rename, as a `HH:MM * ...` action line
+    fn format_action(&self, message: &Message) -> String {
+        let action = self
+            .config
+            .announcement_text(message)
+            .unwrap_or_else(|| String::from("Unable to format announcement!"));
+        format!("{} * {action}\n", self.timestamp(message))
+    }
+
+    /// Render the time a message was sent with the `--irc-timestamp-format`
+    fn timestamp(&self, message: &Message) -> String {
+        let mut timestamp = String::new();
+        if let Ok(date) = message.date(&self.config.offset) {
+            // The format is validated when the options are parsed, so this cannot fail
+            let _ = write!(
+                timestamp,
+                "{}",
+                date.format(&self.config.options.irc_timestamp_format)
+            );
+        }
+        timestamp
+    }
+
+    fn write_to_file(file: &mut BufWriter<File>, text: &str) -> Result<(), RuntimeError> {
+        file.write_all(text.as_bytes())
+            .map_err(RuntimeError::DiskError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap, env::set_var};
+
+    use crate::{app::options::fake_options, exporters::irc::IRC, Config, Exporter, Options};
+    use imessage_database::{
+        tables::{messages::Message, schema::Schema, table::get_connection},
+        util::dates::get_offset,
+    };
+
+    fn blank() -> Message {
+        Message {
+            rowid: i32::default(),
+            guid: String::default(),
+            text: None,
+            service: Some("iMessage".to_string()),
+            handle_id: Some(i32::default()),
+            destination_caller_id: None,
+            subject: None,
+            date: i64::default(),
+            date_read: i64::default(),
+            date_delivered: i64::default(),
+            date_played: 0,
+            is_from_me: false,
+            is_read: false,
+            is_forward: false,
+            is_spam: false,
+            was_delivered_quietly: false,
+            item_type: 0,
+            other_handle: 0,
+            share_status: false,
+            share_direction: false,
+            group_title: None,
+            group_action_type: 0,
+            associated_message_guid: None,
+            associated_message_type: Some(i32::default()),
+            balloon_bundle_id: None,
+            expressive_send_style_id: None,
+            thread_originator_guid: None,
+            thread_originator_part: None,
+            date_edited: 0,
+            schedule_type: 0,
+            chat_id: None,
+            num_attachments: 0,
+            deleted_from: None,
+            num_replies: 0,
+            components: None,
+            edited_parts: None,
+        }
+    }
+
+    fn fake_config(options: Options) -> Config {
+        let db = get_connection(&options.get_db_path()).unwrap();
+        let schema = Schema::detect(&db).unwrap();
+        Config {
+            chatrooms: HashMap::new(),
+            real_chatrooms: HashMap::new(),
+            merged_chatrooms: HashMap::new(),
+            chatroom_participants: HashMap::new(),
+            participants: HashMap::new(),
+            real_participants: HashMap::new(),
+            reactions: HashMap::new(),
+            options,
+            offset: get_offset(),
+            db,
+            schema,
+            converter: None,
+            attachment_paths: RefCell::new(HashMap::new()),
+            annotations: HashMap::new(),
+            db_copy: None,
+        }
+    }
+
+    #[test]
+    fn can_create() {
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = IRC::new(&config).unwrap();
+        assert_eq!(exporter.files.len(), 0);
+    }
+
+    #[test]
+    fn can_format_message() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = IRC::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+
+        let actual = exporter.format_message(&message).unwrap();
+        assert_eq!(actual, "17:29 <Unknown> Hello world\n");
+    }
+
+    #[test]
+    fn can_format_message_multiline() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = IRC::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+        message.text = Some("First line\n\nSecond line".to_string());
+
+        let actual = exporter.format_message(&message).unwrap();
+        assert_eq!(actual, "17:29 <Me> First line\n17:29 <Me> Second line\n");
+    }
+
+    #[test]
+    fn can_format_message_custom_timestamp() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let mut options = fake_options();
+        options.irc_timestamp_format = "[%Y-%m-%d %H:%M:%S]".to_string();
+        let config = fake_config(options);
+        let exporter = IRC::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+
+        let actual = exporter.format_message(&message).unwrap();
+        assert_eq!(actual, "[2022-05-17 17:29:42] <Unknown> Hello world\n");
+    }
+
+    #[test]
+    fn can_format_action() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = IRC::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+        message.group_title = Some("Family".to_string());
+
+        let actual = exporter.format_action(&message);
+        assert_eq!(actual, "17:29 * You renamed the conversation to Family\n");
+    }
+}
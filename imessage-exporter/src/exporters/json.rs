@@ -0,0 +1,538 @@
+use std::{
+    collections::{
+        hash_map::Entry::{Occupied, Vacant},
+        HashMap,
+    },
+    fs::{create_dir_all, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    app::{
+        attachment_manager::ATTACHMENT_PLACEHOLDER,
+        error::RuntimeError,
+        progress::build_progress_bar_export,
+        runtime::{Config, ExportCounts, MessageFilter},
+        sanitizers::sanitize_json,
+    },
+    exporters::exporter::Exporter,
+};
+
+use imessage_database::{
+    error::table::TableError,
+    tables::{
+        attachment::{Attachment, MediaType},
+        messages::Message,
+        table::{Table, ORPHANED},
+    },
+};
+
+/// A piece of a message body: either text, or the marker that stands in for an attachment
+#[derive(Debug, PartialEq, Eq)]
+enum BodyPart {
+    Text(String),
+    Attachment(&'static str),
+}
+
+/// Writes one compact JSON summary for each conversation, with the text, sender, and timestamp of each message
+///
+/// Unlike the NDJSON export, messages are grouped by conversation, reactions and chat events are dropped,
+/// and attachments are replaced by markers of their type, so the output can be fed to NLP tooling as is.
+pub struct JSON<'a> {
+    /// Data that is setup from the application's runtime
+    pub config: &'a Config,
+    /// Handles to files we want to write summaries to
+    /// Map of resolved chatroom file location to a buffered writer
+    pub files: HashMap<String, BufWriter<File>>,
+    /// Writer instance for orphaned messages
+    pub orphaned: BufWriter<File>,
+    /// Map of resolved chatroom file location to the number of messages written to it, `None` for orphaned messages
+    pub written: HashMap<Option<String>, u64>,
+}
+
+impl<'a> Exporter<'a> for JSON<'a> {
+    fn new(config: &'a Config) -> Result<Self, RuntimeError> {
+        let mut orphaned = config.options.export_path.clone();
+        orphaned.push(ORPHANED);
+        orphaned.set_extension("json");
+
+        let mut file = JSON::create_file(&orphaned)?;
+        JSON::write_to_file(&mut file, &JSON::format_header(ORPHANED))?;
+
+        Ok(JSON {
+            config,
+            files: HashMap::new(),
+            orphaned: file,
+            written: HashMap::new(),
+        })
+    }
+
+    fn iter_messages(&mut self) -> Result<(), RuntimeError> {
+        // Tell the user what we are doing
+        eprintln!(
+            "Exporting to {} as JSON summaries...",
+            self.config.options.export_path.display()
+        );
+
+        // Keep track of current message ROWID
+        let mut current_message_row = -1;
+
+        // Keep track of how many messages we exported, globally and per conversation
+        let mut counts = ExportCounts::default();
+
+        // Only export conversations that include the selected participant, if any
+        let chatrooms = self.config.participant_chatrooms();
+
+        // Set up progress bar
+        let mut current_message = 0;
+        let mut total_messages =
+            Message::get_count(&self.config.db, &self.config.options.query_context)
+                .map_err(RuntimeError::DatabaseError)?;
+        if let Some(limit) = self.config.options.limit {
+            total_messages = total_messages.min(limit);
+        }
+        let pb = build_progress_bar_export(total_messages);
+
+        let mut statement = Message::stream_rows_with_schema(
+            &self.config.db,
+            &self.config.schema,
+            &self.config.options.query_context,
+        )
+        .map_err(RuntimeError::DatabaseError)?;
+
+        let messages = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .map_err(|err| RuntimeError::DatabaseError(TableError::Messages(err)))?;
+
+        for message in messages {
+            let mut msg = Message::extract(message).map_err(RuntimeError::DatabaseError)?;
+
+            // Early escape if we try and render the same message GUID twice
+            // See https://github.com/ReagentX/imessage-exporter/issues/135 for rationale
+            if msg.rowid == current_message_row {
+                current_message += 1;
+                continue;
+            }
+            current_message_row = msg.rowid;
+
+            // Reactions are not exported, so they do not count towards the limits
+            match self
+                .config
+                .filter_message(&msg, &chatrooms, &mut counts, !msg.is_reaction())
+            {
+                MessageFilter::Export => {}
+                MessageFilter::Skip => {
+                    current_message += 1;
+                    continue;
+                }
+                MessageFilter::Stop => break,
+            }
+
+            // Generate the text of the message
+            let _ = msg.generate_text(&self.config.db);
+
+            // Summaries only contain messages that were sent, not reactions or chat events
+            if !msg.is_reaction() && !msg.is_announcement() {
+                self.add_message(&msg)?;
+            }
+            current_message += 1;
+            if current_message % 99 == 0 {
+                pb.set_position(current_message);
+            }
+        }
+        pb.finish();
+
+        self.close_files()?;
+
+        Ok(())
+    }
+
+    /// Create a file for the given chat, caching it so we don't need to build it later
+    fn get_or_create_file(
+        &mut self,
+        message: &Message,
+    ) -> Result<&mut BufWriter<File>, RuntimeError> {
+        match self.config.conversation(message) {
+            Some((chatroom, _)) => {
+                let filename = self.config.filename(chatroom);
+                match self.files.entry(filename) {
+                    Occupied(entry) => Ok(entry.into_mut()),
+                    Vacant(entry) => {
+                        let mut path = self.config.options.export_path.clone();
+                        path.push(entry.key());
+                        path.set_extension("json");
+
+                        // Files can be nested in a directory when using `--directory-layout`
+                        if let Some(parent) = path.parent() {
+                            create_dir_all(parent).map_err(|err| {
+                                RuntimeError::CreateError(err, parent.to_path_buf())
+                            })?;
+                        }
+
+                        let mut file = JSON::create_file(&path)?;
+                        JSON::write_to_file(&mut file, &JSON::format_header(entry.key()))?;
+
+                        Ok(entry.insert(file))
+                    }
+                }
+            }
+            None => Ok(&mut self.orphaned),
+        }
+    }
+}
+
+impl<'a> JSON<'a> {
+    /// Write a message to its conversation's summary, unless it has no text or attachments
+    fn add_message(&mut self, message: &Message) -> Result<(), RuntimeError> {
+        let parts = self.body_parts(message)?;
+        if parts.is_empty() {
+            return Ok(());
+        }
+        let record = self.format_record(message, &parts);
+
+        let summary = self
+            .config
+            .conversation(message)
+            .map(|(chatroom, _)| self.config.filename(chatroom));
+        let written = self.written.entry(summary).or_insert(0);
+        let separator = if *written == 0 { "\n" } else { ",\n" };
+        *written += 1;
+
+        let file = self.get_or_create_file(message)?;
+        JSON::write_to_file(file, separator)?;
+        JSON::write_to_file(file, &record)
+    }
+
+    /// Split a message into its text and attachment markers, in the order they were sent
+    ///
+    /// Each attachment takes the place of the object replacement character that marks it in the text;
+    /// attachments without a marker are appended after the text.
+    fn body_parts(&self, message: &Message) -> Result<Vec<BodyPart>, RuntimeError> {
+        let attachments = self
+            .config
+            .attachments(message)
+            .map_err(RuntimeError::DatabaseError)?;
+        let mut markers = attachments.iter().map(|attachment| self.marker(attachment));
+
+        let mut parts = vec![];
+        if let Some(text) = &message.text {
+            let text = self.config.mask(text).into_owned();
+            for (idx, segment) in text.split('\u{FFFC}').enumerate() {
+                if idx > 0 {
+                    parts.push(BodyPart::Attachment(
+                        markers.next().unwrap_or(ATTACHMENT_PLACEHOLDER),
+                    ));
+                }
+                let segment = segment.trim();
+                if !segment.is_empty() {
+                    parts.push(BodyPart::Text(segment.to_string()));
+                }
+            }
+        }
+        parts.extend(markers.map(BodyPart::Attachment));
+
+        Ok(parts)
+    }
+
+    /// The marker that replaces an attachment of a given type
+    fn marker(&self, attachment: &Attachment) -> &'static str {
+        match self.config.media_type(attachment) {
+            MediaType::Image(_) | MediaType::AnimatedImage(_) => "[image]",
+            MediaType::Video(_) => "[video]",
+            MediaType::Audio(_) => "[audio]",
+            _ => ATTACHMENT_PLACEHOLDER,
+        }
+    }
+
+    /// Serialize a message as a single line JSON object, with either its raw text or its tokens
+    fn format_record(&self, message: &Message, parts: &[BodyPart]) -> String {
+        let sender = self.config.who(
+            message.handle_id,
+            message.is_from_me(),
+            &message.destination_caller_id,
+        );
+        let date = match message.date(&self.config.offset) {
+            Ok(date) => format!("\"{}\"", date.naive_utc().format("%Y-%m-%dT%H:%M:%SZ")),
+            Err(_) => String::from("null"),
+        };
+
+        let body = if self.config.options.tokenize {
+            let tokens = tokenize(parts)
+                .iter()
+                .map(|token| format!("\"{}\"", sanitize_json(token)))
+                .collect::<Vec<String>>()
+                .join(",");
+            format!("\"tokens\":[{tokens}]")
+        } else {
+            let text = parts
+                .iter()
+                .map(|part| match part {
+                    BodyPart::Text(text) => text.as_str(),
+                    BodyPart::Attachment(marker) => marker,
+                })
+                .collect::<Vec<&str>>()
+                .join(" ");
+            format!("\"text\":\"{}\"", sanitize_json(&text))
+        };
+
+        format!(
+            "{{\"sender\":\"{}\",\"is_from_me\":{},\"date\":{date},{body}}}",
+            sanitize_json(&sender),
+            message.is_from_me()
+        )
+    }
+
+    /// The start of a conversation's summary, up to the opening of its `messages` array
+    fn format_header(conversation: &str) -> String {
+        format!(
+            "{{\"conversation\":\"{}\",\"messages\":[",
+            sanitize_json(conversation)
+        )
+    }
+
+    /// Close the `messages` array and object of every summary
+    fn close_files(&mut self) -> Result<(), RuntimeError> {
+        let footer = |written: Option<&u64>| match written {
+            Some(_) => "\n]}\n",
+            None => "]}\n",
+        };
+
+        JSON::write_to_file(&mut self.orphaned, footer(self.written.get(&None)))?;
+        for (filename, file) in self.files.iter_mut() {
+            JSON::write_to_file(file, footer(self.written.get(&Some(filename.clone()))))?;
+        }
+        Ok(())
+    }
+
+    /// Summaries are single JSON documents, so they are replaced instead of appended to
+    fn create_file(path: &Path) -> Result<BufWriter<File>, RuntimeError> {
+        File::create(path)
+            .map(BufWriter::new)
+            .map_err(|err| RuntimeError::CreateError(err, path.to_path_buf()))
+    }
+
+    fn write_to_file(file: &mut BufWriter<File>, text: &str) -> Result<(), RuntimeError> {
+        file.write_all(text.as_bytes())
+            .map_err(RuntimeError::DiskError)
+    }
+}
+
+/// Split a message body into lowercase tokens
+///
+/// Words are runs of letters and numbers, including contractions, every other non-whitespace character is its own token,
+/// and attachment markers are kept whole.
+fn tokenize(parts: &[BodyPart]) -> Vec<String> {
+    let mut tokens = vec![];
+    for part in parts {
+        match part {
+            BodyPart::Attachment(marker) => tokens.push(marker.to_string()),
+            BodyPart::Text(text) => {
+                let mut word = String::new();
+                let mut chars = text.chars().peekable();
+                while let Some(c) = chars.next() {
+                    // Apostrophes only belong to a word when they join two parts of it, i.e. `don't`
+                    let is_contraction = c == '\''
+                        && !word.is_empty()
+                        && chars.peek().is_some_and(|next| next.is_alphanumeric());
+                    if c.is_alphanumeric() || is_contraction {
+                        word.extend(c.to_lowercase());
+                        continue;
+                    }
+                    if !word.is_empty() {
+                        tokens.push(std::mem::take(&mut word));
+                    }
+                    if !c.is_whitespace() {
+                        tokens.push(c.to_string());
+                    }
+                }
+                if !word.is_empty() {
+                    tokens.push(word);
+                }
+            }
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap, env::set_var};
+
+    use crate::{
+        app::options::fake_options,
+        exporters::json::{tokenize, BodyPart, JSON},
+        Config, Exporter, Options,
+    };
+    use imessage_database::{
+        tables::{messages::Message, schema::Schema, table::get_connection},
+        util::dates::get_offset,
+    };
+
+    fn blank() -> Message {
+        Message {
+            rowid: i32::default(),
+            guid: String::default(),
+            text: None,
+            service: Some("iMessage".to_string()),
+            handle_id: Some(i32::default()),
+            destination_caller_id: None,
+            subject: None,
+            date: i64::default(),
+            date_read: i64::default(),
+            date_delivered: i64::default(),
+            date_played: 0,
+            is_from_me: false,
+            is_read: false,
+            is_forward: false,
+            is_spam: false,
+            was_delivered_quietly: false,
+            item_type: 0,
+            other_handle: 0,
+            share_status: false,
+            share_direction: false,
+            group_title: None,
+            group_action_type: 0,
+            associated_message_guid: None,
+            associated_message_type: Some(i32::default()),
+            balloon_bundle_id: None,
+            expressive_send_style_id: None,
+            thread_originator_guid: None,
+            thread_originator_part: None,
+            date_edited: 0,
+            schedule_type: 0,
+            chat_id: None,
+            num_attachments: 0,
+            deleted_from: None,
+            num_replies: 0,
+            components: None,
+            edited_parts: None,
+        }
+    }
+
+    fn fake_config(options: Options) -> Config {
+        let db = get_connection(&options.get_db_path()).unwrap();
+        let schema = Schema::detect(&db).unwrap();
+        Config {
+            chatrooms: HashMap::new(),
+            real_chatrooms: HashMap::new(),
+            merged_chatrooms: HashMap::new(),
+            chatroom_participants: HashMap::new(),
+            participants: HashMap::new(),
+            real_participants: HashMap::new(),
+            reactions: HashMap::new(),
+            options,
+            offset: get_offset(),
+            db,
+            schema,
+            converter: None,
+            attachment_paths: RefCell::new(HashMap::new()),
+            annotations: HashMap::new(),
+            db_copy: None,
+        }
+    }
+
+    #[test]
+    fn can_create() {
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = JSON::new(&config).unwrap();
+        assert_eq!(exporter.files.len(), 0);
+    }
+
+    #[test]
+    fn can_format_record() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = JSON::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello \"world\"".to_string());
+
+        let parts = exporter.body_parts(&message).unwrap();
+        let actual = exporter.format_record(&message, &parts);
+        assert_eq!(
+            actual,
+            "{\"sender\":\"Unknown\",\"is_from_me\":false,\"date\":\"2022-05-18T00:29:42Z\",\"text\":\"Hello \\\"world\\\"\"}"
+        );
+    }
+
+    #[test]
+    fn can_format_record_tokenized() {
+        let mut options = fake_options();
+        options.tokenize = true;
+        let config = fake_config(options);
+        let exporter = JSON::new(&config).unwrap();
+
+        let mut message = blank();
+        message.is_from_me = true;
+        message.text = Some("Don't forget, 2 PM!".to_string());
+
+        let parts = exporter.body_parts(&message).unwrap();
+        let actual = exporter.format_record(&message, &parts);
+        assert!(actual.starts_with("{\"sender\":\"Me\",\"is_from_me\":true,"));
+        assert!(actual.ends_with("\"tokens\":[\"don't\",\"forget\",\",\",\"2\",\"pm\",\"!\"]}"));
+    }
+
+    #[test]
+    fn can_get_body_parts_attachments_skipped() {
+        let mut options = fake_options();
+        options.no_attachments = true;
+        let config = fake_config(options);
+        let exporter = JSON::new(&config).unwrap();
+
+        let mut message = blank();
+        message.text = Some("\u{FFFC}Look at this \u{FFFC}".to_string());
+        message.num_attachments = 2;
+
+        assert_eq!(
+            exporter.body_parts(&message).unwrap(),
+            vec![
+                BodyPart::Attachment("[attachment]"),
+                BodyPart::Text("Look at this".to_string()),
+                BodyPart::Attachment("[attachment]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_get_body_parts_empty() {
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = JSON::new(&config).unwrap();
+
+        let mut message = blank();
+        message.text = Some("   ".to_string());
+
+        assert!(exporter.body_parts(&message).unwrap().is_empty());
+    }
+
+    #[test]
+    fn can_tokenize() {
+        let parts = vec![
+            BodyPart::Text("It's SO good...".to_string()),
+            BodyPart::Attachment("[image]"),
+            BodyPart::Text("'quoted'".to_string()),
+        ];
+        assert_eq!(
+            tokenize(&parts),
+            vec!["it's", "so", "good", ".", ".", ".", "[image]", "'", "quoted", "'"]
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn can_format_header() {
+        assert_eq!(
+            JSON::format_header("Family \"Chat\""),
+            "{\"conversation\":\"Family \\\"Chat\\\"\",\"messages\":["
+        );
+    }
+}
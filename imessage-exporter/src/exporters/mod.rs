@@ -1,3 +1,9 @@
+pub mod dayone;
 pub mod exporter;
+#[cfg(test)]
+mod golden;
 pub mod html;
+pub mod irc;
+pub mod json;
+pub mod ndjson;
 pub mod txt;
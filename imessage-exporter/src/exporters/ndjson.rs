@@ -0,0 +1,324 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use crate::{
+    app::{
+        error::RuntimeError,
+        progress::build_progress_bar_export,
+        runtime::{Config, ExportCounts, MessageFilter},
+        sanitizers::sanitize_json,
+    },
+    exporters::exporter::Exporter,
+};
+
+use imessage_database::{
+    error::table::TableError,
+    tables::{messages::Message, table::Table},
+};
+
+/// The name of the file every record is written to
+pub const NDJSON_FILENAME: &str = "messages.ndjson";
+
+/// Streams every row of the messages table, including reactions and replies, to a single file as one JSON record per line
+///
+/// Records are written in date order and nothing is rendered in context,
+/// so no message is held in memory after it is written. A message that belongs to more than one chat
+/// is written once for each chat.
+pub struct NDJSON<'a> {
+    /// Data that is setup from the application's runtime
+    pub config: &'a Config,
+    /// Writer instance for every record
+    pub file: BufWriter<File>,
+}
+
+impl<'a> Exporter<'a> for NDJSON<'a> {
+    fn new(config: &'a Config) -> Result<Self, RuntimeError> {
+        let mut path = config.options.export_path.clone();
+        path.push(NDJSON_FILENAME);
+
+        let file = File::options()
+            .append(true)
+            .create(true)
+            .open(&path)
+            .map_err(|err| RuntimeError::CreateError(err, path))?;
+
+        Ok(NDJSON {
+            config,
+            file: BufWriter::new(file),
+        })
+    }
+
+    fn iter_messages(&mut self) -> Result<(), RuntimeError> {
+        // Tell the user what we are doing
+        eprintln!(
+            "Exporting to {} as ndjson...",
+            self.config.options.export_path.display()
+        );
+
+        // Keep track of how many messages we exported, globally and per conversation
+        let mut counts = ExportCounts::default();
+
+        // Only export conversations that include the selected participant, if any
+        let chatrooms = self.config.participant_chatrooms();
+
+        // Set up progress bar
+        let mut current_message = 0;
+        let mut total_messages =
+            Message::get_count(&self.config.db, &self.config.options.query_context)
+                .map_err(RuntimeError::DatabaseError)?;
+        if let Some(limit) = self.config.options.limit {
+            total_messages = total_messages.min(limit);
+        }
+        let pb = build_progress_bar_export(total_messages);
+
+        let mut statement = Message::stream_rows_with_schema(
+            &self.config.db,
+            &self.config.schema,
+            &self.config.options.query_context,
+        )
+        .map_err(RuntimeError::DatabaseError)?;
+
+        let messages = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .map_err(|err| RuntimeError::DatabaseError(TableError::Messages(err)))?;
+
+        for message in messages {
+            let mut msg = Message::extract(message).map_err(RuntimeError::DatabaseError)?;
+
+            // Every row is its own record, so reactions count towards the limits
+            match self
+                .config
+                .filter_message(&msg, &chatrooms, &mut counts, true)
+            {
+                MessageFilter::Export => {}
+                MessageFilter::Skip => {
+                    current_message += 1;
+                    continue;
+                }
+                MessageFilter::Stop => break,
+            }
+
+            // Generate the text of the message
+            let _ = msg.generate_text(&self.config.db);
+
+            let record = self.format_record(&msg);
+            let file = self.get_or_create_file(&msg)?;
+            NDJSON::write_to_file(file, &record)?;
+
+            current_message += 1;
+            if current_message % 99 == 0 {
+                pb.set_position(current_message);
+            }
+        }
+        pb.finish();
+
+        Ok(())
+    }
+
+    /// Every record is written to the same file
+    fn get_or_create_file(
+        &mut self,
+        _message: &Message,
+    ) -> Result<&mut BufWriter<File>, RuntimeError> {
+        Ok(&mut self.file)
+    }
+}
+
+impl<'a> NDJSON<'a> {
+    /// Serialize a message as a single line JSON object
+    fn format_record(&self, message: &Message) -> String {
+        let date = message
+            .date(&self.config.offset)
+            .ok()
+            .map(|date| date.naive_utc().format("%Y-%m-%dT%H:%M:%SZ").to_string());
+        let sender = self.config.who(
+            message.handle_id,
+            message.is_from_me(),
+            &message.destination_caller_id,
+        );
+        let text = message
+            .text
+            .as_deref()
+            .map(|text| self.config.mask(text).into_owned());
+
+        // Quiet delivery is only recorded by some schemas, so the field is omitted unless it is set
+        let delivered_quietly = if message.was_delivered_quietly() {
+            ", \"delivered_quietly\": true"
+        } else {
+            ""
+        };
+
+        format!(
+            "{{\"rowid\": {}, \"guid\": {}, \"chat_id\": {}, \"date\": {}, \"is_from_me\": {}, \"sender\": {}, \"service\": {}, \"text\": {}, \"associated_message_guid\": {}, \"associated_message_type\": {}, \"thread_originator_guid\": {}, \"num_attachments\": {}{}}}\n",
+            message.rowid,
+            NDJSON::string(Some(&message.guid)),
+            NDJSON::number(message.chat_id),
+            NDJSON::string(date.as_deref()),
+            message.is_from_me(),
+            NDJSON::string(Some(&sender)),
+            NDJSON::string(message.service.as_deref()),
+            NDJSON::string(text.as_deref()),
+            NDJSON::string(message.associated_message_guid.as_deref()),
+            NDJSON::number(message.associated_message_type),
+            NDJSON::string(message.thread_originator_guid.as_deref()),
+            message.num_attachments,
+            delivered_quietly
+        )
+    }
+
+    /// Render an optional string as a JSON string or `null`
+    fn string(value: Option<&str>) -> String {
+        match value {
+            Some(value) => format!("\"{}\"", sanitize_json(value)),
+            None => String::from("null"),
+        }
+    }
+
+    /// Render an optional number as a JSON number or `null`
+    fn number(value: Option<i32>) -> String {
+        match value {
+            Some(value) => value.to_string(),
+            None => String::from("null"),
+        }
+    }
+
+    fn write_to_file(file: &mut BufWriter<File>, text: &str) -> Result<(), RuntimeError> {
+        file.write_all(text.as_bytes())
+            .map_err(RuntimeError::DiskError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use crate::{app::options::fake_options, exporters::ndjson::NDJSON, Config, Exporter, Options};
+    use imessage_database::{
+        tables::{messages::Message, schema::Schema, table::get_connection},
+        util::dates::get_offset,
+    };
+
+    fn blank() -> Message {
+        Message {
+            rowid: i32::default(),
+            guid: String::default(),
+            text: None,
+            service: Some("iMessage".to_string()),
+            handle_id: Some(i32::default()),
+            destination_caller_id: None,
+            subject: None,
+            date: i64::default(),
+            date_read: i64::default(),
+            date_delivered: i64::default(),
+            date_played: 0,
+            is_from_me: false,
+            is_read: false,
+            is_forward: false,
+            is_spam: false,
+            was_delivered_quietly: false,
+            item_type: 0,
+            other_handle: 0,
+            share_status: false,
+            share_direction: false,
+            group_title: None,
+            group_action_type: 0,
+            associated_message_guid: None,
+            associated_message_type: Some(i32::default()),
+            balloon_bundle_id: None,
+            expressive_send_style_id: None,
+            thread_originator_guid: None,
+            thread_originator_part: None,
+            date_edited: 0,
+            schedule_type: 0,
+            chat_id: None,
+            num_attachments: 0,
+            deleted_from: None,
+            num_replies: 0,
+            components: None,
+            edited_parts: None,
+        }
+    }
+
+    fn fake_config(options: Options) -> Config {
+        let db = get_connection(&options.get_db_path()).unwrap();
+        let schema = Schema::detect(&db).unwrap();
+        Config {
+            chatrooms: HashMap::new(),
+            real_chatrooms: HashMap::new(),
+            merged_chatrooms: HashMap::new(),
+            chatroom_participants: HashMap::new(),
+            participants: HashMap::new(),
+            real_participants: HashMap::new(),
+            reactions: HashMap::new(),
+            options,
+            offset: get_offset(),
+            db,
+            schema,
+            converter: None,
+            attachment_paths: RefCell::new(HashMap::new()),
+            annotations: HashMap::new(),
+            db_copy: None,
+        }
+    }
+
+    #[test]
+    fn can_create() {
+        let options = fake_options();
+        let config = fake_config(options);
+        assert!(NDJSON::new(&config).is_ok());
+    }
+
+    #[test]
+    fn can_format_record() {
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = NDJSON::new(&config).unwrap();
+
+        let mut message = blank();
+        message.rowid = 1;
+        message.guid = "ABC".to_string();
+        message.chat_id = Some(2);
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello \"world\"".to_string());
+
+        let expected = "{\"rowid\": 1, \"guid\": \"ABC\", \"chat_id\": 2, \"date\": \"2022-05-18T00:29:42Z\", \"is_from_me\": false, \"sender\": \"Unknown\", \"service\": \"iMessage\", \"text\": \"Hello \\\"world\\\"\", \"associated_message_guid\": null, \"associated_message_type\": 0, \"thread_originator_guid\": null, \"num_attachments\": 0}\n";
+        assert_eq!(exporter.format_record(&message), expected);
+    }
+
+    #[test]
+    fn can_format_record_reaction() {
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = NDJSON::new(&config).unwrap();
+
+        let mut message = blank();
+        message.is_from_me = true;
+        message.associated_message_guid = Some("p:0/ABC".to_string());
+        message.associated_message_type = Some(2000);
+
+        let record = exporter.format_record(&message);
+        assert!(record.contains("\"is_from_me\": true, \"sender\": \"Me\""));
+        assert!(record.contains("\"text\": null"));
+        assert!(record.contains(
+            "\"associated_message_guid\": \"p:0/ABC\", \"associated_message_type\": 2000"
+        ));
+        assert!(record.ends_with("}\n"));
+        assert_eq!(record.lines().count(), 1);
+    }
+
+    #[test]
+    fn can_format_record_delivered_quietly() {
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = NDJSON::new(&config).unwrap();
+
+        let mut message = blank();
+        message.was_delivered_quietly = true;
+
+        let record = exporter.format_record(&message);
+        assert!(record.ends_with("\"num_attachments\": 0, \"delivered_quietly\": true}\n"));
+    }
+}
@@ -2,14 +2,20 @@ use std::{
     borrow::Cow,
     collections::{
         hash_map::Entry::{Occupied, Vacant},
-        HashMap,
+        HashMap, HashSet,
     },
-    fs::File,
+    fs::{create_dir_all, File},
     io::{BufWriter, Write},
 };
 
 use crate::{
-    app::{error::RuntimeError, progress::build_progress_bar_export, runtime::Config},
+    app::{
+        attachment_manager::ATTACHMENT_PLACEHOLDER,
+        error::RuntimeError,
+        error_report::{ErrorReport, IssueKind},
+        progress::build_progress_bar_export,
+        runtime::{Config, ExportCounts, MessageFilter},
+    },
     exporters::exporter::{BalloonFormatter, Exporter, Writer},
 };
 
@@ -19,19 +25,24 @@ use imessage_database::{
         app::AppMessage,
         app_store::AppStoreMessage,
         collaboration::CollaborationMessage,
-        edited::{EditStatus, EditedMessage},
+        edited::{EditStatus, EditedMessage, UnsentTiming},
         expressives::{BubbleEffect, Expressive, ScreenEffect},
+        game::GameMessage,
         handwriting::HandwrittenMessage,
         music::MusicMessage,
         placemark::PlacemarkMessage,
         text_effects::TextEffect,
         url::URLMessage,
-        variants::{Announcement, BalloonProvider, CustomBalloon, URLOverride, Variant},
+        variants::{BalloonProvider, CustomBalloon, Reaction, URLOverride, Variant},
     },
     tables::{
         attachment::Attachment,
-        messages::{models::BubbleComponent, Message},
-        table::{Table, FITNESS_RECEIVER, ME, ORPHANED, YOU},
+        chat::Chat,
+        messages::{
+            models::{BubbleComponent, ReadReceiptState},
+            Message,
+        },
+        table::{Table, ORPHANED, YOU},
     },
     util::{
         dates::{format, get_local_time, readable_diff, TIMESTAMP_FACTOR},
@@ -39,32 +50,38 @@ use imessage_database::{
     },
 };
 
+/// Placeholder for the message timestamp in a `--txt-template`
+pub const TEMPLATE_TIME: &str = "{time}";
+/// Placeholder for the message sender in a `--txt-template`
+pub const TEMPLATE_SENDER: &str = "{sender}";
+/// Placeholder for the first line of the message body in a `--txt-template`
+pub const TEMPLATE_TEXT: &str = "{text}";
+
 pub struct TXT<'a> {
     /// Data that is setup from the application's runtime
     pub config: &'a Config,
     /// Handles to files we want to write messages to
     /// Map of resolved chatroom file location to a buffered writer
     pub files: HashMap<String, BufWriter<File>>,
-    /// Writer instance for orphaned messages
-    pub orphaned: BufWriter<File>,
+    /// Writer instance for orphaned messages, opened the first time it is needed
+    pub orphaned: Option<BufWriter<File>>,
+    /// Non-fatal problems encountered during the export
+    pub errors: ErrorReport,
+    /// Map of conversation file name to the file its messages are currently written to, if conversations are split with `--split-by`
+    pub split_files: HashMap<String, String>,
+    /// Map of file name, or `None` for orphaned messages, to the number of top-level messages written to that file, if `--number-messages` is enabled
+    pub message_numbers: HashMap<Option<String>, u64>,
 }
 
 impl<'a> Exporter<'a> for TXT<'a> {
     fn new(config: &'a Config) -> Result<Self, RuntimeError> {
-        let mut orphaned = config.options.export_path.clone();
-        orphaned.push(ORPHANED);
-        orphaned.set_extension("txt");
-
-        let file = File::options()
-            .append(true)
-            .create(true)
-            .open(&orphaned)
-            .map_err(|err| RuntimeError::CreateError(err, orphaned))?;
-
         Ok(TXT {
             config,
             files: HashMap::new(),
-            orphaned: BufWriter::new(file),
+            orphaned: None,
+            errors: ErrorReport::default(),
+            split_files: HashMap::new(),
+            message_numbers: HashMap::new(),
         })
     }
 
@@ -75,19 +92,34 @@ impl<'a> Exporter<'a> for TXT<'a> {
             self.config.options.export_path.display()
         );
 
+        // Create the orphaned file even if no orphaned messages are exported
+        self.orphaned_file()?;
+
         // Keep track of current message ROWID
         let mut current_message_row = -1;
 
+        // Keep track of how many messages we exported, globally and per conversation
+        let mut counts = ExportCounts::default();
+
+        // Only export conversations that include the selected participant, if any
+        let chatrooms = self.config.participant_chatrooms();
+
         // Set up progress bar
         let mut current_message = 0;
-        let total_messages =
+        let mut total_messages =
             Message::get_count(&self.config.db, &self.config.options.query_context)
                 .map_err(RuntimeError::DatabaseError)?;
+        if let Some(limit) = self.config.options.limit {
+            total_messages = total_messages.min(limit);
+        }
         let pb = build_progress_bar_export(total_messages);
 
-        let mut statement =
-            Message::stream_rows(&self.config.db, &self.config.options.query_context)
-                .map_err(RuntimeError::DatabaseError)?;
+        let mut statement = Message::stream_rows_with_schema(
+            &self.config.db,
+            &self.config.schema,
+            &self.config.options.query_context,
+        )
+        .map_err(RuntimeError::DatabaseError)?;
 
         let messages = statement
             .query_map([], |row| Ok(Message::from_row(row)))
@@ -104,19 +136,35 @@ impl<'a> Exporter<'a> for TXT<'a> {
             }
             current_message_row = msg.rowid;
 
+            // Reactions are rendered in context, so they do not count towards the limits
+            match self
+                .config
+                .filter_message(&msg, &chatrooms, &mut counts, !msg.is_reaction())
+            {
+                MessageFilter::Export => {}
+                MessageFilter::Skip => {
+                    current_message += 1;
+                    continue;
+                }
+                MessageFilter::Stop => break,
+            }
+
             // Generate the text of the message
             let _ = msg.generate_text(&self.config.db);
 
             // Render the announcement in-line
             if msg.is_announcement() {
                 let announcement = self.format_announcement(&msg);
+                let announcement = self.config.normalize(&announcement);
                 TXT::write_to_file(self.get_or_create_file(&msg)?, &announcement)?;
             }
             // Message replies and reactions are rendered in context, so no need to render them separately
             else if !msg.is_reaction() {
+                self.number_message(&msg);
                 let message = self
                     .format_message(&msg, 0)
                     .map_err(RuntimeError::DatabaseError)?;
+                let message = self.config.normalize(&message);
                 TXT::write_to_file(self.get_or_create_file(&msg)?, &message)?;
             }
             current_message += 1;
@@ -125,6 +173,13 @@ impl<'a> Exporter<'a> for TXT<'a> {
             }
         }
         pb.finish();
+
+        if self.config.options.include_empty_chats {
+            self.write_empty_chats(&counts.per_chat, &chatrooms)?;
+        }
+
+        self.errors.write(&self.config.options.export_path)?;
+
         Ok(())
     }
 
@@ -135,25 +190,26 @@ impl<'a> Exporter<'a> for TXT<'a> {
     ) -> Result<&mut BufWriter<File>, RuntimeError> {
         match self.config.conversation(message) {
             Some((chatroom, _)) => {
-                let filename = self.config.filename(chatroom);
+                let filename = self.config.message_filename(chatroom, message);
+                let header = if self.config.is_flattened(chatroom) {
+                    String::from("Group chats\n\n")
+                } else {
+                    self.conversation_header(chatroom)
+                };
+
+                if self.config.options.split_by.is_some() {
+                    self.close_previous_split(self.config.filename(chatroom), &filename)?;
+                }
+
                 return match self.files.entry(filename) {
                     Occupied(entry) => Ok(entry.into_mut()),
                     Vacant(entry) => {
-                        let mut path = self.config.options.export_path.clone();
-                        path.push(self.config.filename(chatroom));
-                        path.set_extension("txt");
-
-                        let file = File::options()
-                            .append(true)
-                            .create(true)
-                            .open(&path)
-                            .map_err(|err| RuntimeError::CreateError(err, path))?;
-
-                        Ok(entry.insert(BufWriter::new(file)))
+                        let buf = TXT::create_file(self.config, entry.key(), &header)?;
+                        Ok(entry.insert(buf))
                     }
                 };
             }
-            None => Ok(&mut self.orphaned),
+            None => self.orphaned_file(),
         }
     }
 }
@@ -164,19 +220,38 @@ impl<'a> Writer<'a> for TXT<'a> {
         // Data we want to write to a file
         let mut formatted_message = String::new();
 
-        // Add message date
-        self.add_line(&mut formatted_message, &self.get_time(message), &indent);
+        // If there is a template, the date and sender are added to the first line of the body once it is rendered
+        let template = self.config.options.txt_template.as_deref();
+        if template.is_none() {
+            // Add message date
+            self.add_line(&mut formatted_message, &self.get_time(message), &indent);
 
-        // Add message sender
-        self.add_line(
-            &mut formatted_message,
-            self.config.who(
-                message.handle_id,
-                message.is_from_me(),
-                &message.destination_caller_id,
-            ),
-            &indent,
-        );
+            // Add message sender
+            self.add_line(
+                &mut formatted_message,
+                &self.config.who(
+                    message.handle_id,
+                    message.is_from_me(),
+                    &message.destination_caller_id,
+                ),
+                &indent,
+            );
+        }
+
+        // If message was forwarded, annotate it
+        if message.is_forwarded() {
+            self.add_line(&mut formatted_message, "Forwarded", &indent);
+        }
+
+        // If message was reported as junk, annotate it
+        if message.is_reported_junk() {
+            self.add_line(&mut formatted_message, "(reported as junk)", &indent);
+        }
+
+        // If message was delivered while notifications were silenced, annotate it
+        if self.config.options.quiet_delivery && message.was_delivered_quietly() {
+            self.add_line(&mut formatted_message, "(delivered quietly)", &indent);
+        }
 
         // If message was deleted, annotate it
         if message.is_deleted() {
@@ -189,8 +264,8 @@ impl<'a> Writer<'a> for TXT<'a> {
 
         // Useful message metadata
         let message_parts = message.body();
-        let mut attachments = Attachment::from_message(&self.config.db, message)?;
-        let mut replies = message.get_replies(&self.config.db)?;
+        let mut attachments = self.config.attachments(message)?;
+        let mut replies = message.get_replies_with_schema(&self.config.db, &self.config.schema)?;
 
         // Index of where we are in the attachment Vector
         let mut attachment_index: usize = 0;
@@ -217,7 +292,7 @@ impl<'a> Writer<'a> for TXT<'a> {
         // Generate the message body from it's components
         for (idx, message_part) in message_parts.iter().enumerate() {
             match message_part {
-                // Fitness messages have a prefix that we need to replace with the opposite if who sent the message
+                // Fitness messages have placeholders for their sender and receiver that depend on who sent the message
                 BubbleComponent::Text(text_attrs) => {
                     if let Some(text) = &message.text {
                         // Render edited message content, if applicable
@@ -236,57 +311,95 @@ impl<'a> Writer<'a> for TXT<'a> {
                                 if let Some(message_content) =
                                     text.get(text_attr.start..text_attr.end)
                                 {
-                                    formatted_text.push_str(
-                                        &self.format_attributed(message_content, &text_attr.effect),
-                                    )
+                                    formatted_text.push_str(&self.format_attributed(
+                                        &self.config.shortcodes(&self.config.mask(message_content)),
+                                        &text_attr.effect,
+                                    ))
                                 }
                             }
 
                             // If we failed to parse any text above, use the original text
                             if formatted_text.is_empty() {
-                                formatted_text.push_str(text);
+                                formatted_text
+                                    .push_str(&self.config.shortcodes(&self.config.mask(text)));
                             }
 
-                            if formatted_text.starts_with(FITNESS_RECEIVER) {
-                                self.add_line(
-                                    &mut formatted_message,
-                                    &formatted_text.replace(FITNESS_RECEIVER, YOU),
-                                    &indent,
-                                );
-                            } else {
-                                self.add_line(&mut formatted_message, &formatted_text, &indent);
-                            }
+                            self.add_line(
+                                &mut formatted_message,
+                                &self.config.fitness_text(&formatted_text, message),
+                                &indent,
+                            );
                         }
                     }
                 }
+                BubbleComponent::Attachment if self.config.options.no_attachments => {
+                    self.add_line(&mut formatted_message, ATTACHMENT_PLACEHOLDER, &indent);
+                }
                 BubbleComponent::Attachment => match attachments.get_mut(attachment_index) {
                     Some(attachment) => {
                         if attachment.is_sticker {
                             let result = self.format_sticker(attachment, message);
                             self.add_line(&mut formatted_message, &result, &indent);
+                        } else if let Some(failed) = self.config.failed_transfer(attachment) {
+                            attachment_index += 1;
+                            self.add_line(&mut formatted_message, failed, &indent);
+                        } else if self.config.is_expired_audio_message(attachment) {
+                            attachment_index += 1;
+                            self.add_line(
+                                &mut formatted_message,
+                                "Audio message (expired)",
+                                &indent,
+                            );
                         } else {
                             match self.format_attachment(attachment, message) {
                                 Ok(result) => {
                                     attachment_index += 1;
                                     self.add_line(&mut formatted_message, &result, &indent);
+                                    // The file name is already part of the attachment's path
+                                    if let Some(description) = self.description(attachment) {
+                                        self.add_line(
+                                            &mut formatted_message,
+                                            &format!("Description: {description}"),
+                                            &indent,
+                                        );
+                                    }
                                 }
                                 Err(result) => {
+                                    self.errors.record(
+                                        IssueKind::UnresolvedAttachment,
+                                        &message.guid,
+                                        result,
+                                    );
                                     self.add_line(&mut formatted_message, result, &indent);
                                 }
                             }
                         }
                     }
                     // Attachment does not exist in attachments table
-                    None => self.add_line(&mut formatted_message, "Attachment missing!", &indent),
+                    None => {
+                        self.errors.record(
+                            IssueKind::MissingAttachment,
+                            &message.guid,
+                            &format!("Attachment {attachment_index} does not exist"),
+                        );
+                        self.add_line(&mut formatted_message, "Attachment missing!", &indent)
+                    }
                 },
                 BubbleComponent::App => match self.format_app(message, &mut attachments, &indent) {
                     // We use an empty indent here because `format_app` handles building the entire message
                     Ok(ok_bubble) => self.add_line(&mut formatted_message, &ok_bubble, &indent),
-                    Err(why) => self.add_line(
-                        &mut formatted_message,
-                        &format!("Unable to format app message: {why}"),
-                        &indent,
-                    ),
+                    Err(why) => {
+                        self.errors.record(
+                            IssueKind::UnparseablePayload,
+                            &message.guid,
+                            &why.to_string(),
+                        );
+                        self.add_line(
+                            &mut formatted_message,
+                            &format!("Unable to format app message: {why}"),
+                            &indent,
+                        )
+                    }
                 },
                 BubbleComponent::Retracted => {
                     if let Some(edited_parts) = &message.edited_parts {
@@ -301,32 +414,48 @@ impl<'a> Writer<'a> for TXT<'a> {
 
             // Handle expressives
             if message.expressive_send_style_id.is_some() {
-                self.add_line(
-                    &mut formatted_message,
-                    self.format_expressive(message),
-                    &indent,
-                );
+                let expressive = self.format_expressive(message);
+                match message.expressive_state() {
+                    Some(state) => self.add_line(
+                        &mut formatted_message,
+                        &format!("{expressive} ({state})"),
+                        &indent,
+                    ),
+                    None => self.add_line(&mut formatted_message, expressive, &indent),
+                }
             }
 
             // Handle Reactions
             if let Some(reactions_map) = self.config.reactions.get(&message.guid) {
                 if let Some(reactions) = reactions_map.get(&idx) {
                     let mut formatted_reactions = String::new();
+                    let mut count = 0;
+                    let target =
+                        self.config
+                            .reaction_target(message, &message_parts, idx, &attachments);
                     reactions
                         .iter()
                         .try_for_each(|reaction| -> Result<(), TableError> {
-                            let formatted = self.format_reaction(reaction)?;
+                            let formatted = self.format_reaction(reaction, target.as_deref())?;
                             if !formatted.is_empty() {
-                                self.add_line(
-                                    &mut formatted_reactions,
-                                    &self.format_reaction(reaction)?,
-                                    &indent,
-                                );
+                                count += 1;
+                                self.add_line(&mut formatted_reactions, &formatted, &indent);
                             }
                             Ok(())
                         })?;
 
-                    if !formatted_reactions.is_empty() {
+                    if self
+                        .config
+                        .options
+                        .collapse_reactions
+                        .is_some_and(|threshold| count > threshold)
+                    {
+                        self.add_line(
+                            &mut formatted_message,
+                            &format!("Reactions: {count} reactions"),
+                            &indent,
+                        );
+                    } else if !formatted_reactions.is_empty() {
                         self.add_line(&mut formatted_message, "Reactions:", &indent);
                         self.add_line(&mut formatted_message, &formatted_reactions, &indent);
                     }
@@ -351,6 +480,11 @@ impl<'a> Writer<'a> for TXT<'a> {
             }
         }
 
+        // Note that the message was edited, regardless of how its history is displayed
+        if message.edit_count() > 0 {
+            self.add_line(&mut formatted_message, "(edited)", &indent);
+        }
+
         // Add a note if the message is a reply
         if message.is_reply() && indent.is_empty() {
             self.add_line(
@@ -360,6 +494,29 @@ impl<'a> Writer<'a> for TXT<'a> {
             );
         }
 
+        // Render the note passed for this message, if any
+        if let Some(note) = self.config.annotation(message) {
+            self.add_line(
+                &mut formatted_message,
+                &format!("Note: {note}"),
+                &format!("{indent}    "),
+            );
+        }
+
+        if let Some(template) = template {
+            formatted_message = self.apply_template(template, message, &formatted_message, &indent);
+        }
+
+        // Label top-level messages with their group when group chats share a file
+        if let Some(group) = self.group_label(message, indent_size) {
+            formatted_message.insert_str(0, &format!("[{group}] "));
+        }
+
+        // Number top-level messages so they can be referenced
+        if let Some(number) = self.message_number(message, indent_size) {
+            formatted_message.insert_str(0, &format!("#{number} "));
+        }
+
         if indent.is_empty() {
             // Add a newline for top-level messages
             formatted_message.push('\n');
@@ -381,7 +538,7 @@ impl<'a> Writer<'a> for TXT<'a> {
             .ok_or(attachment.filename())?;
 
         // Build a relative filepath from the fully qualified one on the `Attachment`
-        Ok(self.config.message_attachment_path(attachment))
+        Ok(self.config.message_attachment_link(attachment, message))
     }
 
     fn format_sticker(&self, sticker: &'a mut Attachment, message: &Message) -> String {
@@ -397,10 +554,16 @@ impl<'a> Writer<'a> for TXT<'a> {
                     &self.config.options.db_path,
                     self.config.options.attachment_root.as_deref(),
                 );
-                if let Ok(Some(sticker_effect)) = sticker_effect {
-                    return format!("{sticker_effect} Sticker from {who}: {path_to_sticker}");
+                let mut sticker_line = match sticker_effect {
+                    Ok(Some(sticker_effect)) => {
+                        format!("{sticker_effect} Sticker from {who}: {path_to_sticker}")
+                    }
+                    _ => format!("Sticker from {who}: {path_to_sticker}"),
+                };
+                if let Some(sticker_source) = self.config.sticker_source(sticker, message) {
+                    sticker_line.push_str(&format!("\nSticker from {sticker_source}"));
                 }
-                format!("Sticker from {who}: {path_to_sticker}")
+                sticker_line
             }
             Err(path) => format!("Sticker from {who}: {path}"),
         }
@@ -420,6 +583,11 @@ impl<'a> Writer<'a> for TXT<'a> {
                 return Ok(self.format_handwriting(&HandwrittenMessage::new(), indent));
             }
 
+            // Digital Touch messages use a different payload type, so handle those next
+            if matches!(balloon, CustomBalloon::DigitalTouch) {
+                return Ok(self.format_digital_touch(indent));
+            }
+
             if let Some(payload) = message.payload_data(&self.config.db) {
                 // Handle URL messages separately since they are a special case
                 let res = if message.is_url() {
@@ -450,7 +618,13 @@ impl<'a> Writer<'a> for TXT<'a> {
                             CustomBalloon::Slideshow => self.format_slideshow(&bubble, indent),
                             CustomBalloon::CheckIn => self.format_check_in(&bubble, indent),
                             CustomBalloon::FindMy => self.format_find_my(&bubble, indent),
+                            CustomBalloon::Journal => self.format_journal(&bubble, indent),
+                            CustomBalloon::Game(bundle_id) => self.format_game(
+                                &GameMessage::from_app_message(&bubble, bundle_id),
+                                indent,
+                            ),
                             CustomBalloon::Handwriting => unreachable!(),
+                            CustomBalloon::DigitalTouch => unreachable!(),
                             CustomBalloon::URL => unreachable!(),
                         },
                         Err(why) => return Err(why),
@@ -472,31 +646,56 @@ impl<'a> Writer<'a> for TXT<'a> {
         }
     }
 
-    fn format_reaction(&self, msg: &Message) -> Result<String, TableError> {
+    fn format_reaction(&self, msg: &Message, target: Option<&str>) -> Result<String, TableError> {
         match msg.variant() {
-            Variant::Reaction(_, added, reaction) => {
-                if !added {
-                    return Ok(String::new());
-                }
-                Ok(format!(
-                    "{:?} by {}",
-                    reaction,
-                    self.config
-                        .who(msg.handle_id, msg.is_from_me(), &msg.destination_caller_id),
-                ))
-            }
-            Variant::Sticker(_) => {
-                let mut paths = Attachment::from_message(&self.config.db, msg)?;
+            // Sticker tapbacks carry their sticker image as an attachment, so they render like stickers
+            Variant::Reaction(_, true, Reaction::Sticker) | Variant::Sticker(_) => {
+                let mut paths = self.config.attachments(msg)?;
                 let who =
                     self.config
                         .who(msg.handle_id, msg.is_from_me(), &msg.destination_caller_id);
                 // Sticker messages have only one attachment, the sticker image
                 Ok(if let Some(sticker) = paths.get_mut(0) {
                     self.format_sticker(sticker, msg)
+                } else if self.config.options.no_attachments {
+                    format!("Sticker from {who}: {ATTACHMENT_PLACEHOLDER}")
                 } else {
+                    self.errors.record(
+                        IssueKind::MissingAttachment,
+                        &msg.guid,
+                        "Sticker does not exist",
+                    );
                     format!("Sticker from {who} not found!")
                 })
             }
+            Variant::Reaction(_, added, reaction) => {
+                if !added {
+                    if !self.config.options.show_removed_reactions {
+                        return Ok(String::new());
+                    }
+                    return Ok(format!(
+                        "{} removed their {:?} reaction{}",
+                        self.config.who(
+                            msg.handle_id,
+                            msg.is_from_me(),
+                            &msg.destination_caller_id
+                        ),
+                        reaction,
+                        target
+                            .map(|target| format!(" to {target}"))
+                            .unwrap_or_default(),
+                    ));
+                }
+                Ok(format!(
+                    "{:?}{} by {}",
+                    reaction,
+                    target
+                        .map(|target| format!(" {target}"))
+                        .unwrap_or_default(),
+                    self.config
+                        .who(msg.handle_id, msg.is_from_me(), &msg.destination_caller_id),
+                ))
+            }
             _ => unreachable!(),
         }
     }
@@ -526,31 +725,12 @@ impl<'a> Writer<'a> for TXT<'a> {
     }
 
     fn format_announcement(&self, msg: &'a Message) -> String {
-        let mut who = self
-            .config
-            .who(msg.handle_id, msg.is_from_me(), &msg.destination_caller_id);
-        // Rename yourself so we render the proper grammar here
-        if who == ME {
-            who = self.config.options.custom_name.as_deref().unwrap_or(YOU);
-        }
-
         let timestamp = format(&msg.date(&self.config.offset));
 
-        return match msg.get_announcement() {
-            Some(announcement) => match announcement {
-                Announcement::NameChange(name) => {
-                    format!("{timestamp} {who} renamed the conversation to {name}\n\n")
-                }
-                Announcement::PhotoChange => {
-                    format!("{timestamp} {who} changed the group photo.\n\n")
-                }
-                Announcement::Unknown(num) => {
-                    format!("{timestamp} {who} performed unknown action {num}.\n\n")
-                }
-                Announcement::FullyUnsent => format!("{timestamp} {who} unsent a message!\n\n"),
-            },
+        match self.config.announcement_text(msg) {
+            Some(announcement) => format!("{timestamp} {announcement}\n\n"),
             None => String::from("Unable to format announcement!\n\n"),
-        };
+        }
     }
 
     fn format_shareplay(&self) -> &str {
@@ -606,7 +786,11 @@ impl<'a> Writer<'a> for TXT<'a> {
                         previous_timestamp = Some(&event.date);
 
                         // Render the message text
-                        self.add_line(&mut out_s, &event.text, indent);
+                        self.add_line(
+                            &mut out_s,
+                            &self.config.shortcodes(&self.config.mask(&event.text)),
+                            indent,
+                        );
                     }
                 }
                 EditStatus::Unsent => {
@@ -616,21 +800,27 @@ impl<'a> Writer<'a> for TXT<'a> {
                         "They"
                     };
 
-                    match readable_diff(
+                    // Messages retracted before delivery were never seen by the recipient
+                    let before_delivery =
+                        matches!(msg.unsent_timing(), Some(UnsentTiming::BeforeDelivery));
+
+                    out_s.push_str(who);
+                    out_s.push_str(" unsent this message part");
+                    if let Some(diff) = readable_diff(
                         msg.date(&self.config.offset),
                         msg.date_edited(&self.config.offset),
                     ) {
-                        Some(diff) => {
-                            out_s.push_str(who);
-                            out_s.push_str(" unsent this message part ");
-                            out_s.push_str(&diff);
-                            out_s.push_str(" after sending!");
-                        }
-                        None => {
-                            out_s.push_str(who);
-                            out_s.push_str(" unsent this message part!");
+                        out_s.push(' ');
+                        out_s.push_str(&diff);
+                        out_s.push_str(" after sending");
+                        if before_delivery {
+                            out_s.push(',');
                         }
                     }
+                    if before_delivery {
+                        out_s.push_str(" before it was delivered");
+                    }
+                    out_s.push('!');
                 }
                 EditStatus::Original => {
                     return None;
@@ -642,9 +832,15 @@ impl<'a> Writer<'a> for TXT<'a> {
         None
     }
 
-    fn format_attributed(&'a self, msg: &'a str, _: &'a TextEffect) -> Cow<str> {
-        // There isn't really a way to represent formatted text in a plain text export
-        Cow::Borrowed(msg)
+    fn format_attributed(&'a self, msg: &'a str, effect: &'a TextEffect) -> Cow<str> {
+        match effect {
+            // Keep the target of web links whose text does not show where they lead
+            TextEffect::Link(url) if url.starts_with("http") && !url.contains(msg.trim()) => {
+                Cow::Owned(format!("{msg} ({url})"))
+            }
+            // There isn't really a way to represent formatted text in a plain text export
+            _ => Cow::Borrowed(msg),
+        }
     }
 
     fn write_to_file(file: &mut BufWriter<File>, text: &str) -> Result<(), RuntimeError> {
@@ -806,6 +1002,10 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
         format!("{indent}Handwritten messages are not yet supported!")
     }
 
+    fn format_digital_touch(&self, indent: &str) -> String {
+        format!("{indent}Digital Touch messages are not yet supported!")
+    }
+
     fn format_apple_pay(&self, balloon: &AppMessage, indent: &str) -> String {
         let mut out_s = String::from(indent);
         if let Some(caption) = balloon.caption {
@@ -907,6 +1107,49 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
         out_s
     }
 
+    fn format_journal(&self, balloon: &AppMessage, indent: &'a str) -> String {
+        let mut out_s = String::from(indent);
+        out_s.push_str(balloon.app_name.unwrap_or("Journal"));
+        out_s.push_str(" suggestion");
+
+        if let Some(suggestion_type) = balloon.subcaption {
+            out_s.push_str(" (");
+            out_s.push_str(suggestion_type);
+            out_s.push(')');
+        }
+
+        if let Some(title) = balloon.title {
+            out_s.push_str(": ");
+            out_s.push_str(title);
+        }
+
+        out_s
+    }
+
+    fn format_game(&self, balloon: &GameMessage, indent: &'a str) -> String {
+        let mut out_s = String::from(indent);
+        out_s.push_str(balloon.app_name);
+        out_s.push_str(" game");
+
+        if let Some(game) = balloon.game {
+            out_s.push_str(": ");
+            out_s.push_str(game);
+        }
+
+        if let Some(state) = balloon.state {
+            out_s.push_str(" - ");
+            out_s.push_str(state);
+        }
+
+        if let Some(score) = balloon.score {
+            out_s.push_str(" (");
+            out_s.push_str(score);
+            out_s.push(')');
+        }
+
+        out_s
+    }
+
     fn format_generic_app(
         &self,
         balloon: &AppMessage,
@@ -916,8 +1159,12 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
     ) -> String {
         let mut out_s = String::from(indent);
 
+        // Unknown apps show their bundle id so the source of the message can be identified
         if let Some(name) = balloon.app_name {
             out_s.push_str(name);
+            out_s.push_str(" (");
+            out_s.push_str(bundle_id);
+            out_s.push(')');
         } else {
             out_s.push_str(bundle_id);
         }
@@ -956,18 +1203,231 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
 }
 
 impl<'a> TXT<'a> {
+    /// Open the file for orphaned messages
+    fn orphaned_file(&mut self) -> Result<&mut BufWriter<File>, RuntimeError> {
+        let buf = match self.orphaned.take() {
+            Some(buf) => buf,
+            None => {
+                let mut path = self.config.options.export_path.clone();
+                path.push(ORPHANED);
+                path.set_extension("txt");
+
+                let file = File::options()
+                    .append(true)
+                    .create(true)
+                    .open(&path)
+                    .map_err(|err| RuntimeError::CreateError(err, path))?;
+                BufWriter::new(file)
+            }
+        };
+        Ok(self.orphaned.insert(buf))
+    }
+
+    /// Format the message with the given GUID, including its reactions and replies, without writing it to a file
+    pub(crate) fn format_single_message(&self, guid: &str) -> Result<String, RuntimeError> {
+        let msg = self.config.message_by_guid(guid)?;
+        if msg.is_announcement() {
+            return Ok(self.format_announcement(&msg));
+        }
+        self.format_message(&msg, 0)
+            .map_err(RuntimeError::DatabaseError)
+    }
+
+    /// Open the file for a conversation, writing its header if it does not exist yet
+    fn create_file(
+        config: &Config,
+        filename: &str,
+        header: &str,
+    ) -> Result<BufWriter<File>, RuntimeError> {
+        let mut path = config.options.export_path.clone();
+        path.push(filename);
+        path.set_extension("txt");
+
+        // Files can be nested in a directory when using `--directory-layout`
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)
+                .map_err(|err| RuntimeError::CreateError(err, parent.to_path_buf()))?;
+        }
+
+        // If the file already exists, don't write the header again
+        let file_exists = path.exists();
+
+        let file = File::options()
+            .append(true)
+            .create(true)
+            .open(&path)
+            .map_err(|err| RuntimeError::CreateError(err, path))?;
+
+        let mut buf = BufWriter::new(file);
+
+        if !file_exists {
+            TXT::write_to_file(&mut buf, header)?;
+        }
+
+        Ok(buf)
+    }
+
+    /// Create a file for each conversation that had no messages exported, for `--include-empty-chats`
+    fn write_empty_chats(
+        &mut self,
+        exported: &HashMap<Option<i32>, u64>,
+        chatrooms: &Option<HashSet<i32>>,
+    ) -> Result<(), RuntimeError> {
+        for chatroom in self.config.empty_chatrooms(exported, chatrooms) {
+            // Group chats without messages add nothing to the flattened file
+            if self.config.is_flattened(chatroom) {
+                continue;
+            }
+            let filename = self.config.filename(chatroom);
+            if self.files.contains_key(&filename) {
+                continue;
+            }
+            let header = self.conversation_header(chatroom);
+            let buf = TXT::create_file(self.config, &filename, &header)?;
+            self.files.insert(filename, buf);
+        }
+        Ok(())
+    }
+
+    /// Get the description stored with an attachment, if `--alt-text` is enabled
+    fn description<'b>(&self, attachment: &'b Attachment) -> Option<&'b str> {
+        if !self.config.options.alt_text {
+            return None;
+        }
+        attachment
+            .description
+            .as_deref()
+            .filter(|description| !description.trim().is_empty())
+    }
+
+    /// Count a top-level message in the file it is written to, for `--number-messages`
+    fn number_message(&mut self, message: &Message) {
+        if self.config.options.number_messages {
+            *self
+                .message_numbers
+                .entry(self.config.message_file(message))
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Get the number of a message in the file it is written to, if it was counted by [`TXT::number_message`]
+    ///
+    /// Replies rendered in a thread under another message are not numbered.
+    fn message_number(&self, message: &Message, indent_size: usize) -> Option<u64> {
+        if indent_size > 0 {
+            return None;
+        }
+        self.message_numbers
+            .get(&self.config.message_file(message))
+            .copied()
+    }
+
+    /// Get the name of the group a top-level message was sent to, if group chats are written to one file with `--flatten-groups`
+    fn group_label(&self, message: &Message, indent_size: usize) -> Option<String> {
+        if indent_size > 0 {
+            return None;
+        }
+        self.config
+            .conversation(message)
+            .filter(|(chatroom, _)| self.config.is_flattened(chatroom))
+            .map(|(chatroom, _)| self.config.group_name(chatroom))
+    }
+
+    /// Finish the file a split conversation was previously written to once its messages move to a new file
+    ///
+    /// Messages are exported in date order, so a conversation never returns to an earlier period. Closing
+    /// those files keeps the number of open files low when active conversations are split by day.
+    fn close_previous_split(
+        &mut self,
+        conversation: String,
+        filename: &str,
+    ) -> Result<(), RuntimeError> {
+        match self.split_files.insert(conversation, filename.to_string()) {
+            Some(previous) if previous != filename => {
+                if let Some(mut buf) = self.files.remove(&previous) {
+                    buf.flush().map_err(RuntimeError::DiskError)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Build the header written at the top of a conversation's file, showing its chat identifiers and when it was created and last active
+    fn conversation_header(&self, chatroom: &Chat) -> String {
+        let mut header = format!(
+            "Chat ID: {}\n",
+            self.config.conversation_identifiers(chatroom).join(", ")
+        );
+        if let Some((first, last)) = self.config.conversation_activity(chatroom) {
+            let created = format(&get_local_time(&first, &self.config.offset));
+            let last_activity = format(&get_local_time(&last, &self.config.offset));
+            header.push_str(&format!(
+                "Created: {created}\nLast activity: {last_activity}\n"
+            ));
+        }
+        header.push('\n');
+        header
+    }
+
+    /// Render the first line of a message body using the `--txt-template` format, keeping any remaining lines below it
+    fn apply_template(
+        &self,
+        template: &str,
+        message: &Message,
+        body: &str,
+        indent: &str,
+    ) -> String {
+        let (first_line, rest) = body.split_once('\n').unwrap_or((body, ""));
+        let text = first_line.strip_prefix(indent).unwrap_or(first_line);
+
+        let mut formatted_message = String::with_capacity(template.len() + body.len());
+        self.add_line(
+            &mut formatted_message,
+            &render_template(
+                template,
+                &self.get_time(message),
+                &self.config.who(
+                    message.handle_id,
+                    message.is_from_me(),
+                    &message.destination_caller_id,
+                ),
+                text,
+            ),
+            indent,
+        );
+        formatted_message.push_str(rest);
+        formatted_message
+    }
+
     fn get_time(&self, message: &Message) -> String {
-        let mut date = format(&message.date(&self.config.offset));
-        let read_after = message.time_until_read(&self.config.offset);
-        if let Some(time) = read_after {
-            if !time.is_empty() {
-                let who = if message.is_from_me() {
-                    "them"
-                } else {
-                    self.config.options.custom_name.as_deref().unwrap_or("you")
-                };
-                date.push_str(&format!(" (Read by {who} after {time})"));
+        let mut date = self.config.format_timestamp(message);
+        // Scheduled messages are dated with the time they were scheduled to send
+        if message.is_scheduled() {
+            date = format!("Scheduled for {date}");
+        }
+        match message.read_receipt_state() {
+            ReadReceiptState::Read => {
+                if let Some(time) = message.time_until_read(&self.config.offset) {
+                    if !time.is_empty() {
+                        let who = if message.is_from_me() {
+                            "them"
+                        } else {
+                            self.config.options.custom_name.as_deref().unwrap_or("you")
+                        };
+                        date.push_str(&format!(" (Read by {who} after {time})"));
+                    }
+                }
+            }
+            // Without a read receipt, we only know when the message was delivered
+            ReadReceiptState::Delivered => {
+                if let Some(time) = message.time_until_delivered(&self.config.offset) {
+                    if !time.is_empty() {
+                        date.push_str(&format!(" (Delivered after {time})"));
+                    }
+                }
             }
+            ReadReceiptState::Unread | ReadReceiptState::Unknown => {}
         }
         date
     }
@@ -981,22 +1441,62 @@ impl<'a> TXT<'a> {
     }
 }
 
+/// Fill in the placeholders in a `--txt-template` format string
+///
+/// Placeholders are substituted in a single pass, so braces in the substituted values are never expanded.
+fn render_template(template: &str, time: &str, sender: &str, text: &str) -> String {
+    let mut rendered =
+        String::with_capacity(template.len() + time.len() + sender.len() + text.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let (value, placeholder_len) = if rest.starts_with(TEMPLATE_TIME) {
+            (time, TEMPLATE_TIME.len())
+        } else if rest.starts_with(TEMPLATE_SENDER) {
+            (sender, TEMPLATE_SENDER.len())
+        } else if rest.starts_with(TEMPLATE_TEXT) {
+            (text, TEMPLATE_TEXT.len())
+        } else {
+            ("{", 1)
+        };
+
+        rendered.push_str(value);
+        rest = &rest[placeholder_len..];
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
 #[cfg(test)]
 mod tests {
     use std::{
-        collections::HashMap,
+        cell::RefCell,
+        collections::{BTreeSet, HashMap},
         env::{current_dir, set_var},
         path::PathBuf,
     };
 
     use crate::{
-        app::attachment_manager::AttachmentManager, exporters::exporter::Writer, Config, Exporter,
-        Options, TXT,
+        app::{
+            attachment_manager::{AttachmentManager, DEFAULT_COPY_RETRIES},
+            export_type::{DayOneGranularity, DirectoryLayout},
+            options::{DEFAULT_HTML_LANG, DEFAULT_IRC_TIMESTAMP_FORMAT},
+            timestamp_format::TimestampFormat,
+        },
+        exporters::{exporter::Writer, txt::render_template},
+        Config, Exporter, Options, TXT,
     };
     use imessage_database::{
+        message_types::text_effects::TextEffect,
         tables::{
             attachment::Attachment,
+            chat::Chat,
             messages::Message,
+            schema::Schema,
             table::{get_connection, ME},
         },
         util::{
@@ -1017,8 +1517,12 @@ mod tests {
             date: i64::default(),
             date_read: i64::default(),
             date_delivered: i64::default(),
+            date_played: 0,
             is_from_me: false,
             is_read: false,
+            is_forward: false,
+            is_spam: false,
+            was_delivered_quietly: false,
             item_type: 0,
             other_handle: 0,
             share_status: false,
@@ -1032,6 +1536,7 @@ mod tests {
             thread_originator_guid: None,
             thread_originator_part: None,
             date_edited: 0,
+            schedule_type: 0,
             chat_id: None,
             num_attachments: 0,
             deleted_from: None,
@@ -1053,16 +1558,67 @@ mod tests {
             no_lazy: false,
             custom_name: None,
             use_caller_id: false,
+            show_my_number: false,
             platform: Platform::macOS,
             ignore_disk_space: false,
+            show_removed_reactions: false,
+            limit: None,
+            limit_per_chat: None,
+            merge_services: false,
+            dayone_granularity: DayOneGranularity::default(),
+            copy_retries: DEFAULT_COPY_RETRIES,
+            timestamp_format: TimestampFormat::default(),
+            report: None,
+            txt_template: None,
+            manifest: false,
+            animate_effects: false,
+            participants_only: None,
+            exclude_orphaned: false,
+            only_orphaned: false,
+            media_grid: false,
+            show_receipts: false,
+            exif_sidecars: false,
+            post_export_command: None,
+            mask_text: false,
+            compact_html: false,
+            split_by: None,
+            warc: false,
+            no_attachments: false,
+            collapse_reactions: None,
+            mark_failed_transfers: false,
+            message_guid: None,
+            normalize_newlines: false,
+            html_lang: DEFAULT_HTML_LANG.to_string(),
+            css: None,
+            directory_layout: DirectoryLayout::default(),
+            include_empty_chats: false,
+            print_friendly: false,
+            since_last_export: false,
+            reset_last_export: false,
+            only_reported: false,
+            number_messages: false,
+            emoji_shortcodes: false,
+            mime_overrides: HashMap::new(),
+            flatten_groups: false,
+            extract_media_only: false,
+            annotations: None,
+            dump_typedstream: None,
+            irc_timestamp_format: DEFAULT_IRC_TIMESTAMP_FORMAT.to_string(),
+            alt_text: false,
+            reaction_context: false,
+            quiet_delivery: false,
+            copy_db_first: false,
+            tokenize: false,
         }
     }
 
     pub(super) fn fake_config(options: Options) -> Config {
         let db = get_connection(&options.get_db_path()).unwrap();
+        let schema = Schema::detect(&db).unwrap();
         Config {
             chatrooms: HashMap::new(),
             real_chatrooms: HashMap::new(),
+            merged_chatrooms: HashMap::new(),
             chatroom_participants: HashMap::new(),
             participants: HashMap::new(),
             real_participants: HashMap::new(),
@@ -1070,7 +1626,11 @@ mod tests {
             options,
             offset: get_offset(),
             db,
+            schema,
             converter: None,
+            attachment_paths: RefCell::new(HashMap::new()),
+            annotations: HashMap::new(),
+            db_copy: None,
         }
     }
 
@@ -1084,7 +1644,10 @@ mod tests {
             total_bytes: 100,
             is_sticker: false,
             hide_attachment: 0,
+            transfer_state: 5,
+            is_outgoing: false,
             copied_path: None,
+            description: None,
         }
     }
 
@@ -1121,6 +1684,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_get_time_scheduled() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        // Create fake message
+        let mut message = blank();
+        // May 17, 2022  9:30:31 PM
+        message.date = 674530231992568192;
+        message.schedule_type = 2;
+        assert_eq!(
+            exporter.get_time(&message),
+            "Scheduled for May 17, 2022  6:30:31 PM"
+        );
+    }
+
     #[test]
     fn can_get_time_invalid() {
         // Set timezone to PST for consistent Local time
@@ -1143,33 +1727,102 @@ mod tests {
     }
 
     #[test]
-    fn can_add_line_no_indent() {
+    fn can_get_time_relative() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
         // Create exporter
-        let options = fake_options();
+        let mut options = fake_options();
+        options.timestamp_format = TimestampFormat::Relative;
         let config = fake_config(options);
         let exporter = TXT::new(&config).unwrap();
 
-        // Create sample data
-        let mut s = String::new();
-        exporter.add_line(&mut s, "hello world", "");
+        // Create fake message
+        let mut message = blank();
+        // May 17, 2022  9:30:31 PM
+        message.date = 674530231992568192;
 
-        assert_eq!(s, "hello world\n".to_string());
+        let time = exporter.get_time(&message);
+        assert!(time.ends_with(" years ago"));
+        assert!(!time.contains("May 17, 2022"));
     }
 
     #[test]
-    fn can_add_line_indent() {
+    fn can_get_time_relative_both() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
         // Create exporter
-        let options = fake_options();
+        let mut options = fake_options();
+        options.timestamp_format = TimestampFormat::Both;
         let config = fake_config(options);
         let exporter = TXT::new(&config).unwrap();
 
-        // Create sample data
+        // Create fake message
+        let mut message = blank();
+        // May 17, 2022  9:30:31 PM
+        message.date = 674530231992568192;
+
+        let time = exporter.get_time(&message);
+        assert!(time.starts_with("May 17, 2022  6:30:31 PM ("));
+        assert!(time.ends_with(" years ago)"));
+    }
+
+    #[test]
+    fn can_add_line_no_indent() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        // Create sample data
+        let mut s = String::new();
+        exporter.add_line(&mut s, "hello world", "");
+
+        assert_eq!(s, "hello world\n".to_string());
+    }
+
+    #[test]
+    fn can_add_line_indent() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        // Create sample data
         let mut s = String::new();
         exporter.add_line(&mut s, "hello world", "  ");
 
         assert_eq!(s, "  hello world\n".to_string());
     }
 
+    #[test]
+    fn can_format_txt_invisible_ink_revealed() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+        message.expressive_send_style_id =
+            Some("com.apple.MobileSMS.expressivesend.invisibleink".to_string());
+        message.date_played = 674526582885055488;
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected =
+            "May 17, 2022  5:29:42 PM\nMe\nHello world\nSent with Invisible Ink (revealed)\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_txt_from_me_normal() {
         // Set timezone to PST for consistent Local time
@@ -1193,6 +1846,376 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn can_format_txt_reactions_collapsed() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.collapse_reactions = Some(1);
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.guid = "text_guid".to_string();
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+
+        let mut loved = blank();
+        loved.date = 674526612885055488;
+        loved.associated_message_type = Some(2000);
+        loved.associated_message_guid = Some("p:0/text_guid".to_string());
+
+        let mut liked = blank();
+        liked.date = 674526642885055488;
+        liked.associated_message_type = Some(2001);
+        liked.associated_message_guid = Some("p:0/text_guid".to_string());
+
+        config.reactions.insert(
+            "text_guid".to_string(),
+            HashMap::from([(0, vec![loved, liked])]),
+        );
+        let exporter = TXT::new(&config).unwrap();
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "May 17, 2022  5:29:42 PM\nMe\nHello world\nReactions: 2 reactions\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_link_different_target() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let actual =
+            exporter.format_attributed("Example", &TextEffect::Link("https://example.com/target"));
+        assert_eq!(actual, "Example (https://example.com/target)");
+    }
+
+    #[test]
+    fn can_format_txt_link_same_target() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        // Links whose text already shows the target, including detected phone numbers, are unchanged
+        let actual =
+            exporter.format_attributed("example.com", &TextEffect::Link("https://example.com"));
+        assert_eq!(actual, "example.com");
+
+        let actual =
+            exporter.format_attributed("(000) 000-0000", &TextEffect::Link("tel:0000000000"));
+        assert_eq!(actual, "(000) 000-0000");
+    }
+
+    #[test]
+    fn can_format_txt_from_me_masked() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.mask_text = true;
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "May 17, 2022  5:29:42 PM\nMe\n█████ █████\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_template() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.txt_template = Some("[{time}] {sender}: {text}".to_string());
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "[May 17, 2022  5:29:42 PM] Me: Hello world\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_template_indent() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.txt_template = Some("{sender}: {text}".to_string());
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+
+        let actual = exporter.format_message(&message, 4).unwrap();
+        let expected = "    Me: Hello world\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_render_template() {
+        assert_eq!(
+            render_template("[{time}] {sender}: {text}", "now", "Me", "Hi"),
+            "[now] Me: Hi"
+        );
+    }
+
+    #[test]
+    fn can_render_template_without_expanding_values() {
+        assert_eq!(
+            render_template("{sender} {unknown} {text", "now", "{text}", "Hi"),
+            "{text} {unknown} {text"
+        );
+    }
+
+    #[test]
+    fn can_format_txt_from_me_normal_forwarded() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+        message.is_forward = true;
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "May 17, 2022  5:29:42 PM\nMe\nForwarded\nHello world\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_numbered() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.number_messages = true;
+        let config = fake_config(options);
+        let mut exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+
+        exporter.number_message(&message);
+        exporter.number_message(&message);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "#2 May 17, 2022  5:29:42 PM\nMe\nHello world\n\n";
+        assert_eq!(actual, expected);
+
+        // Replies rendered in a thread are not numbered
+        let actual = exporter.format_message(&message, 4).unwrap();
+        assert!(!actual.contains('#'));
+    }
+
+    #[test]
+    fn can_format_txt_flattened_group() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.flatten_groups = true;
+        let mut config = fake_config(options);
+        config.chatrooms.insert(
+            1,
+            Chat {
+                rowid: 1,
+                chat_identifier: "chat123".to_string(),
+                service_name: Some("iMessage".to_string()),
+                display_name: Some("Family".to_string()),
+                first_message_date: None,
+                last_message_date: None,
+                has_imessage: true,
+                has_sms: false,
+            },
+        );
+        config.real_chatrooms.insert(1, 1);
+        config
+            .chatroom_participants
+            .insert(1, BTreeSet::from([10, 11]));
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+        message.chat_id = Some(1);
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "[Family] May 17, 2022  5:29:42 PM\nMe\nHello world\n\n";
+        assert_eq!(actual, expected);
+
+        // Replies rendered in a thread are not labeled
+        let actual = exporter.format_message(&message, 4).unwrap();
+        assert!(!actual.contains("[Family]"));
+    }
+
+    #[test]
+    fn can_format_txt_annotation() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config
+            .annotations
+            .insert("annotated".to_string(), "Confirms the time".to_string());
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+        message.guid = "annotated".to_string();
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "May 17, 2022  5:29:42 PM\nMe\nHello world\n    Note: Confirms the time\n\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_emoji_shortcodes() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.emoji_shortcodes = true;
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Sounds good 👍".to_string());
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "May 17, 2022  5:29:42 PM\nMe\nSounds good :+1:\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_reported_junk() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("You won a prize!".to_string());
+        message.date = 674526582885055488;
+        message.is_spam = true;
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected =
+            "May 17, 2022  5:29:42 PM\nUnknown\n(reported as junk)\nYou won a prize!\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_delivered_quietly() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.quiet_delivery = true;
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Are you asleep?".to_string());
+        message.date = 674526582885055488;
+        message.was_delivered_quietly = true;
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected =
+            "May 17, 2022  5:29:42 PM\nUnknown\n(delivered quietly)\nAre you asleep?\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_delivered_quietly_disabled() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Are you asleep?".to_string());
+        message.date = 674526582885055488;
+        message.was_delivered_quietly = true;
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "May 17, 2022  5:29:42 PM\nUnknown\nAre you asleep?\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_txt_from_me_normal_deleted() {
         // Set timezone to PST for consistent Local time
@@ -1231,8 +2254,10 @@ mod tests {
         message.text = Some("Hello world".to_string());
         // May 17, 2022  8:29:42 PM
         message.date = 674526582885055488;
+        // May 17, 2022  8:29:42 PM
+        message.date_delivered = 674526582885055488;
         // May 17, 2022  9:30:31 PM
-        message.date_delivered = 674530231992568192;
+        message.date_read = 674530231992568192;
         message.is_from_me = true;
 
         let actual = exporter.format_message(&message, 0).unwrap();
@@ -1242,6 +2267,31 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn can_format_txt_from_me_normal_delivered() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        message.text = Some("Hello world".to_string());
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        // May 17, 2022  9:30:31 PM
+        message.date_delivered = 674530231992568192;
+        message.is_from_me = true;
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected =
+            "May 17, 2022  5:29:42 PM (Delivered after 1 hour, 49 seconds)\nMe\nHello world\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_txt_from_them_normal() {
         // Set timezone to PST for consistent Local time
@@ -1352,84 +2402,208 @@ mod tests {
     }
 
     #[test]
-    fn can_format_txt_announcement() {
-        // Set timezone to PST for consistent Local time
-        set_var("TZ", "PST");
-
+    fn can_format_txt_announcement() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.group_title = Some("Hello world".to_string());
+        message.is_from_me = true;
+
+        let actual = exporter.format_announcement(&message);
+        let expected = "May 17, 2022  5:29:42 PM You renamed the conversation to Hello world\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_announcement_custom_name() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.custom_name = Some("Name".to_string());
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.group_title = Some("Hello world".to_string());
+
+        let actual = exporter.format_announcement(&message);
+        let expected = "May 17, 2022  5:29:42 PM Name renamed the conversation to Hello world\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_reaction_me() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.associated_message_type = Some(2000);
+        message.associated_message_guid = Some("fake_guid".to_string());
+
+        let actual = exporter.format_reaction(&message, None).unwrap();
+        let expected = "Loved by Me";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_reaction_target() {
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        message.associated_message_type = Some(2000);
+        message.associated_message_guid = Some("p:1/fake_guid".to_string());
+
+        let actual = exporter
+            .format_reaction(&message, Some("an image"))
+            .unwrap();
+        let expected = "Loved an image by Me";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_reaction_them() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config
+            .participants
+            .insert(999999, "Sample Contact".to_string());
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.associated_message_type = Some(2000);
+        message.associated_message_guid = Some("fake_guid".to_string());
+        message.handle_id = Some(999999);
+
+        let actual = exporter.format_reaction(&message, None).unwrap();
+        let expected = "Loved by Sample Contact";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_digital_touch() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        message.item_type = 0;
+        message.balloon_bundle_id = Some("com.apple.DigitalTouchBalloonProvider".to_string());
+
+        let actual = exporter.format_app(&message, &mut vec![], "").unwrap();
+        let expected = "Digital Touch messages are not yet supported!";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_removed_reaction_hidden() {
         // Create exporter
         let options = fake_options();
         let mut config = fake_config(options);
-        config.participants.insert(0, ME.to_string());
-
+        config
+            .participants
+            .insert(999999, "Sample Contact".to_string());
         let exporter = TXT::new(&config).unwrap();
 
         let mut message = blank();
-        // May 17, 2022  8:29:42 PM
-        message.date = 674526582885055488;
-        message.group_title = Some("Hello world".to_string());
-        message.is_from_me = true;
+        message.associated_message_type = Some(3000);
+        message.associated_message_guid = Some("fake_guid".to_string());
+        message.handle_id = Some(999999);
 
-        let actual = exporter.format_announcement(&message);
-        let expected = "May 17, 2022  5:29:42 PM You renamed the conversation to Hello world\n\n";
+        let actual = exporter.format_reaction(&message, None).unwrap();
+        let expected = "";
 
         assert_eq!(actual, expected);
     }
 
     #[test]
-    fn can_format_txt_announcement_custom_name() {
-        // Set timezone to PST for consistent Local time
-        set_var("TZ", "PST");
-
+    fn can_format_txt_removed_reaction_shown() {
         // Create exporter
         let mut options = fake_options();
-        options.custom_name = Some("Name".to_string());
+        options.show_removed_reactions = true;
         let mut config = fake_config(options);
-        config.participants.insert(0, ME.to_string());
-
+        config
+            .participants
+            .insert(999999, "Sample Contact".to_string());
         let exporter = TXT::new(&config).unwrap();
 
         let mut message = blank();
-        // May 17, 2022  8:29:42 PM
-        message.date = 674526582885055488;
-        message.group_title = Some("Hello world".to_string());
+        message.associated_message_type = Some(3000);
+        message.associated_message_guid = Some("fake_guid".to_string());
+        message.handle_id = Some(999999);
 
-        let actual = exporter.format_announcement(&message);
-        let expected = "May 17, 2022  5:29:42 PM Name renamed the conversation to Hello world\n\n";
+        let actual = exporter.format_reaction(&message, None).unwrap();
+        let expected = "Sample Contact removed their Loved reaction";
 
         assert_eq!(actual, expected);
     }
 
     #[test]
-    fn can_format_txt_reaction_me() {
-        // Set timezone to PST for consistent Local time
-        set_var("TZ", "PST");
-
+    fn can_format_txt_sticker_tapback() {
         // Create exporter
-        let options = fake_options();
+        let mut options = fake_options();
+        options.no_attachments = true;
         let mut config = fake_config(options);
         config.participants.insert(0, ME.to_string());
-
         let exporter = TXT::new(&config).unwrap();
 
         let mut message = blank();
-        // May 17, 2022  8:29:42 PM
-        message.date = 674526582885055488;
-        message.associated_message_type = Some(2000);
-        message.associated_message_guid = Some("fake_guid".to_string());
+        message.associated_message_type = Some(2007);
+        message.associated_message_guid = Some("p:0/fake_guid".to_string());
 
-        let actual = exporter.format_reaction(&message).unwrap();
-        let expected = "Loved by Me";
+        let actual = exporter.format_reaction(&message, None).unwrap();
+        let expected = "Sticker from Me: [attachment]";
 
         assert_eq!(actual, expected);
     }
 
     #[test]
-    fn can_format_txt_reaction_them() {
-        // Set timezone to PST for consistent Local time
-        set_var("TZ", "PST");
-
+    fn can_format_txt_removed_sticker_tapback() {
         // Create exporter
-        let options = fake_options();
+        let mut options = fake_options();
+        options.show_removed_reactions = true;
         let mut config = fake_config(options);
         config
             .participants
@@ -1437,14 +2611,12 @@ mod tests {
         let exporter = TXT::new(&config).unwrap();
 
         let mut message = blank();
-        // May 17, 2022  8:29:42 PM
-        message.date = 674526582885055488;
-        message.associated_message_type = Some(2000);
-        message.associated_message_guid = Some("fake_guid".to_string());
+        message.associated_message_type = Some(3007);
+        message.associated_message_guid = Some("p:0/fake_guid".to_string());
         message.handle_id = Some(999999);
 
-        let actual = exporter.format_reaction(&message).unwrap();
-        let expected = "Loved by Sample Contact";
+        let actual = exporter.format_reaction(&message, None).unwrap();
+        let expected = "Sample Contact removed their Sticker reaction";
 
         assert_eq!(actual, expected);
     }
@@ -1561,6 +2733,36 @@ mod tests {
         assert_eq!(actual, "a/b/c/d.jpg");
     }
 
+    #[test]
+    fn can_get_attachment_description() {
+        // Create exporter
+        let mut options = fake_options();
+        options.alt_text = true;
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut attachment = fake_attachment();
+        assert_eq!(exporter.description(&attachment), None);
+
+        attachment.description = Some("Cat wearing a party hat".to_string());
+        assert_eq!(
+            exporter.description(&attachment),
+            Some("Cat wearing a party hat")
+        );
+    }
+
+    #[test]
+    fn cant_get_attachment_description_disabled() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut attachment = fake_attachment();
+        attachment.description = Some("Cat wearing a party hat".to_string());
+        assert_eq!(exporter.description(&attachment), None);
+    }
+
     #[test]
     fn can_format_txt_attachment_macos_invalid() {
         // Create exporter
@@ -1647,14 +2849,6 @@ mod tests {
             actual,
             "Outline Sticker from Me: imessage-database/test_data/stickers/outline.heic"
         );
-
-        // Remove the file created by the constructor for this test
-        let orphaned_path = current_dir()
-            .unwrap()
-            .parent()
-            .unwrap()
-            .join("orphaned.txt");
-        std::fs::remove_file(orphaned_path).unwrap();
     }
 }
 
@@ -1668,6 +2862,7 @@ mod balloon_format_tests {
         app::AppMessage,
         app_store::AppStoreMessage,
         collaboration::CollaborationMessage,
+        game::GameMessage,
         music::MusicMessage,
         placemark::{Placemark, PlacemarkMessage},
         url::URLMessage,
@@ -1845,6 +3040,52 @@ mod balloon_format_tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn can_format_txt_journal() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let balloon = AppMessage {
+            image: None,
+            url: Some("?suggestionType=workout&suggestionID=FAKE_SUGGESTION_ID"),
+            title: Some("Morning Walk"),
+            subtitle: None,
+            caption: Some("Journal Suggestion"),
+            subcaption: Some("Workout"),
+            trailing_caption: None,
+            trailing_subcaption: None,
+            app_name: Some("Journal"),
+            ldtext: Some("Shared a Journal suggestion"),
+        };
+
+        let expected = exporter.format_journal(&balloon, "");
+        let actual = "Journal suggestion (Workout): Morning Walk";
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn can_format_txt_game() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let balloon = GameMessage {
+            app_name: "GamePigeon",
+            game: Some("8 Ball"),
+            state: Some("Your turn"),
+            score: Some("3 - 5"),
+        };
+
+        let expected = exporter.format_game(&balloon, "");
+        let actual = "GamePigeon game: 8 Ball - Your turn (3 - 5)";
+
+        assert_eq!(expected, actual);
+    }
+
     #[test]
     fn can_format_txt_check_in_timer() {
         // Set timezone to PST for consistent Local time
@@ -2006,7 +3247,38 @@ mod balloon_format_tests {
         };
 
         let expected = exporter.format_generic_app(&balloon, "bundle_id", &mut vec![], "");
-        let actual = "app_name message:\ntitle\nsubtitle\ncaption\nsubcaption\ntrailing_caption\ntrailing_subcaption";
+        let actual = "app_name (bundle_id) message:\ntitle\nsubtitle\ncaption\nsubcaption\ntrailing_caption\ntrailing_subcaption";
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn can_format_txt_generic_app_unknown_bundle_id() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let balloon = AppMessage {
+            image: None,
+            url: None,
+            title: Some("title"),
+            subtitle: None,
+            caption: None,
+            subcaption: None,
+            trailing_caption: None,
+            trailing_subcaption: None,
+            app_name: None,
+            ldtext: None,
+        };
+
+        let expected = exporter.format_generic_app(
+            &balloon,
+            "com.example.UnknownApp.MessagesExtension",
+            &mut vec![],
+            "",
+        );
+        let actual = "com.example.UnknownApp.MessagesExtension message:\ntitle";
 
         assert_eq!(expected, actual);
     }
@@ -2022,9 +3294,9 @@ mod edited_tests {
 
     use super::tests::{blank, fake_config, fake_options};
 
-    use crate::{exporters::exporter::Writer, Exporter, TXT};
+    use crate::{app::error_report::IssueKind, exporters::exporter::Writer, Exporter, TXT};
     use imessage_database::{
-        message_types::edited::{EditStatus, EditedMessage, EditedMessagePart},
+        message_types::edited::{EditStatus, EditedEvent, EditedMessage, EditedMessagePart},
         util::typedstream::parser::TypedStreamReader,
     };
 
@@ -2041,6 +3313,7 @@ mod edited_tests {
         let mut message = blank();
         // May 17, 2022  8:29:42 PM
         message.date = 674526582885055488;
+        message.date_delivered = 674526582885055488;
         message.date_edited = 674530231992568192;
         message.text = Some(
             "From arbitrary byte stream:\r\u{FFFC}To native Rust data structures:\r".to_string(),
@@ -2086,6 +3359,105 @@ mod edited_tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn can_format_txt_conversion_final_unsent_before_delivery() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.date_edited = 674530231992568192;
+        message.text = Some(
+            "From arbitrary byte stream:\r\u{FFFC}To native Rust data structures:\r".to_string(),
+        );
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+        message.edited_parts = Some(EditedMessage {
+            parts: vec![
+                EditedMessagePart {
+                    status: EditStatus::Original,
+                    edit_history: vec![],
+                },
+                EditedMessagePart {
+                    status: EditStatus::Original,
+                    edit_history: vec![],
+                },
+                EditedMessagePart {
+                    status: EditStatus::Original,
+                    edit_history: vec![],
+                },
+                EditedMessagePart {
+                    status: EditStatus::Unsent,
+                    edit_history: vec![],
+                },
+            ],
+        });
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("imessage-database/test_data/typedstream/MultiPartWithDeleted");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        message.components = parser.parse().ok();
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "May 17, 2022  5:29:42 PM\nMe\nFrom arbitrary byte stream:\r\nAttachment missing!\nTo native Rust data structures:\r\nYou unsent this message part 1 hour, 49 seconds after sending, before it was delivered!\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_edited_indicator() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.date_edited = 674526592885055488;
+        message.text = Some("Edited message".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+        message.edited_parts = Some(EditedMessage {
+            parts: vec![EditedMessagePart {
+                status: EditStatus::Edited,
+                edit_history: vec![
+                    EditedEvent {
+                        date: 674526582885055488,
+                        text: "First message".to_string(),
+                        guid: None,
+                    },
+                    EditedEvent {
+                        date: 674526592885055488,
+                        text: "Edited message".to_string(),
+                        guid: None,
+                    },
+                ],
+            }],
+        });
+
+        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = "May 17, 2022  5:29:42 PM\nMe\nMay 17, 2022  5:29:42 PM First message\nEdited 10 seconds later: Edited message\n\n(edited)\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_txt_conversion_no_edits() {
         // Set timezone to PST for consistent Local time
@@ -2121,6 +3493,13 @@ mod edited_tests {
         let expected = "May 17, 2022  5:29:42 PM\nMe\nFrom arbitrary byte stream:\r\nAttachment missing!\nTo native Rust data structures:\r\n\n";
 
         assert_eq!(actual, expected);
+
+        // The missing attachment is recorded in the error report
+        assert_eq!(exporter.errors.len(), 1);
+        assert_eq!(
+            exporter.errors.counts().get(&IssueKind::MissingAttachment),
+            Some(&1)
+        );
     }
 
     #[test]
@@ -2136,6 +3515,7 @@ mod edited_tests {
         let mut message = blank();
         // May 17, 2022  8:29:42 PM
         message.date = 674526582885055488;
+        message.date_delivered = 674526582885055488;
         message.date_edited = 674530231992568192;
         message.text = None;
         message.is_from_me = true;
@@ -2164,4 +3544,46 @@ mod edited_tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn can_format_txt_conversion_fully_unsent_before_delivery() {
+        // Set timezone to PST for consistent Local time
+        set_var("TZ", "PST");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.date_edited = 674530231992568192;
+        message.text = None;
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+        message.edited_parts = Some(EditedMessage {
+            parts: vec![EditedMessagePart {
+                status: EditStatus::Unsent,
+                edit_history: vec![],
+            }],
+        });
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("imessage-database/test_data/typedstream/Blank");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        message.components = parser.parse().ok();
+
+        let actual = exporter.format_announcement(&message);
+        let expected = "May 17, 2022  5:29:42 PM You unsent a message before it was delivered!\n\n";
+
+        assert_eq!(actual, expected);
+    }
 }
@@ -3,7 +3,9 @@
 mod app;
 mod exporters;
 
-pub use exporters::{exporter::Exporter, html::HTML, txt::TXT};
+pub use exporters::{
+    dayone::DayOne, exporter::Exporter, html::HTML, irc::IRC, json::JSON, ndjson::NDJSON, txt::TXT,
+};
 
 use app::{
     options::{from_command_line, Options},
@@ -22,11 +24,10 @@ fn main() {
     } else {
         match options {
             Ok(options) => match Config::new(options) {
-                Ok(app) => {
-                    if let Err(why) = app.start() {
-                        eprintln!("Unable to export: {why}");
-                    }
-                }
+                Ok(app) => match app.start() {
+                    Ok(()) => app.run_post_export_command(),
+                    Err(why) => eprintln!("Unable to export: {why}"),
+                },
                 Err(why) => {
                     eprintln!("Invalid configuration: {why}");
                 }
@@ -0,0 +1,84 @@
+-- Fixture database exported by the golden file tests in `src/exporters/golden.rs`
+--
+-- Dates are nanoseconds since 2001-01-01 UTC; 674526582885055488 is 2022-05-18 00:29:42 UTC.
+
+CREATE TABLE handle (ROWID INTEGER PRIMARY KEY, id TEXT NOT NULL, service TEXT, person_centric_id TEXT);
+CREATE TABLE chat (ROWID INTEGER PRIMARY KEY, guid TEXT, chat_identifier TEXT, service_name TEXT, display_name TEXT);
+CREATE TABLE chat_handle_join (chat_id INTEGER, handle_id INTEGER);
+CREATE TABLE message (
+    ROWID INTEGER PRIMARY KEY,
+    guid TEXT UNIQUE NOT NULL,
+    text TEXT,
+    service TEXT,
+    handle_id INTEGER DEFAULT 0,
+    destination_caller_id TEXT,
+    subject TEXT,
+    date INTEGER,
+    date_read INTEGER DEFAULT 0,
+    date_delivered INTEGER DEFAULT 0,
+    date_played INTEGER DEFAULT 0,
+    is_from_me INTEGER DEFAULT 0,
+    is_read INTEGER DEFAULT 0,
+    is_forward INTEGER DEFAULT 0,
+    item_type INTEGER DEFAULT 0,
+    other_handle INTEGER DEFAULT 0,
+    share_status INTEGER DEFAULT 0,
+    share_direction INTEGER DEFAULT 0,
+    group_title TEXT,
+    group_action_type INTEGER DEFAULT 0,
+    associated_message_guid TEXT,
+    associated_message_type INTEGER DEFAULT 0,
+    balloon_bundle_id TEXT,
+    expressive_send_style_id TEXT,
+    thread_originator_guid TEXT,
+    thread_originator_part TEXT,
+    date_edited INTEGER DEFAULT 0,
+    attributedBody BLOB,
+    message_summary_info BLOB,
+    payload_data BLOB
+);
+CREATE TABLE chat_message_join (chat_id INTEGER, message_id INTEGER);
+CREATE TABLE attachment (ROWID INTEGER PRIMARY KEY, filename TEXT, uti TEXT, mime_type TEXT, transfer_name TEXT, total_bytes INTEGER, is_sticker INTEGER, hide_attachment INTEGER);
+CREATE TABLE message_attachment_join (message_id INTEGER, attachment_id INTEGER);
+
+INSERT INTO handle (ROWID, id, service) VALUES
+    (1, '+15558675309', 'iMessage'),
+    (2, 'friend@example.com', 'iMessage');
+
+INSERT INTO chat (ROWID, guid, chat_identifier, service_name, display_name) VALUES
+    (1, 'iMessage;-;+15558675309', '+15558675309', 'iMessage', NULL),
+    (2, 'iMessage;+;chat100', 'chat100', 'iMessage', 'Weekend Plans');
+
+INSERT INTO chat_handle_join (chat_id, handle_id) VALUES
+    (1, 1),
+    (2, 1),
+    (2, 2);
+
+-- A direct conversation with a reaction and a threaded reply
+INSERT INTO message (ROWID, guid, text, service, handle_id, date, is_from_me, is_read) VALUES
+    (1, 'A0000000-0000-0000-0000-000000000001', 'Hey, are we still on for lunch?', 'iMessage', 1, 674526582885055488, 0, 1),
+    (2, 'A0000000-0000-0000-0000-000000000002', 'Yes! See you at noon "sharp"', 'iMessage', 1, 674526642885055488, 1, 1);
+INSERT INTO message (ROWID, guid, text, service, handle_id, date, is_from_me, associated_message_guid, associated_message_type) VALUES
+    (3, 'A0000000-0000-0000-0000-000000000003', 'Loved “Yes! See you at noon "sharp"”', 'iMessage', 1, 674526702885055488, 0, 'p:0/A0000000-0000-0000-0000-000000000002', 2000);
+INSERT INTO message (ROWID, guid, text, service, handle_id, date, is_from_me, thread_originator_guid, thread_originator_part) VALUES
+    (4, 'A0000000-0000-0000-0000-000000000004', 'Running 5 minutes late', 'iMessage', 1, 674526762885055488, 0, 'A0000000-0000-0000-0000-000000000002', '0:0:28');
+
+-- A named group conversation that is renamed
+INSERT INTO message (ROWID, guid, text, service, handle_id, date, is_from_me, is_read) VALUES
+    (5, 'A0000000-0000-0000-0000-000000000005', 'Who is bringing snacks?', 'iMessage', 2, 674613042885055488, 0, 1),
+    (6, 'A0000000-0000-0000-0000-000000000006', 'I can <bring> chips & dip', 'iMessage', 0, 674613102885055488, 1, 1);
+INSERT INTO message (ROWID, guid, service, handle_id, date, is_from_me, item_type, group_title) VALUES
+    (7, 'A0000000-0000-0000-0000-000000000007', 'iMessage', 1, 674613162885055488, 0, 2, 'Beach Trip');
+
+-- A message that does not belong to any conversation
+INSERT INTO message (ROWID, guid, text, service, handle_id, date, is_from_me, is_read) VALUES
+    (8, 'A0000000-0000-0000-0000-000000000008', 'Orphaned message', 'SMS', 2, 674699442885055488, 0, 1);
+
+INSERT INTO chat_message_join (chat_id, message_id) VALUES
+    (1, 1),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 5),
+    (2, 6),
+    (2, 7);